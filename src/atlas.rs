@@ -0,0 +1,220 @@
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/**
+ * Layout of the coverage values in a glyph mask.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskFormat {
+    /// One coverage value per pixel.
+    Alpha,
+    /// Separate red, green and blue coverage per pixel, for subpixel
+    /// antialiasing on horizontal RGB panels.
+    Subpixel
+}
+
+impl MaskFormat {
+    pub fn bytes_per_pixel(&self) -> usize {
+        match *self {
+            MaskFormat::Alpha => 1,
+            MaskFormat::Subpixel => 3
+        }
+    }
+}
+
+/**
+ * A rectangular coverage mask, usually a region of an atlas page. Pages are
+ * shared between threads, so that text can be drawn from render workers.
+ **/
+#[derive(Clone)]
+pub struct Mask {
+    page: Arc<RwLock<Vec<u8>>>,
+    offset: usize,
+    stride: usize,
+    format: MaskFormat
+}
+
+impl Mask {
+    /**
+     * Wraps coverage data of the given width that is not part of an atlas.
+     **/
+    pub fn new(data: Vec<u8>, width: u32, format: MaskFormat) -> Mask {
+        Mask {
+            page: Arc::new(RwLock::new(data)),
+            offset: 0,
+            stride: width as usize * format.bytes_per_pixel(),
+            format
+        }
+    }
+
+    pub fn format(&self) -> MaskFormat {
+        self.format
+    }
+
+    /**
+     * Calls `f` with the mask data, starting at the first pixel, and the
+     * number of bytes between two rows.
+     **/
+    pub fn read<R, F: FnOnce(&[u8], usize) -> R>(&self, f: F) -> R {
+        let page = self.page.read().unwrap();
+        f(&page[self.offset..], self.stride)
+    }
+
+    /**
+     * Returns the coverage at `(x, y)` as red, green and blue values.
+     **/
+    pub fn coverage(&self, x: u32, y: u32) -> [u8; 3] {
+        self.read(|data, stride| {
+            let i = y as usize * stride + x as usize * self.format.bytes_per_pixel();
+            match self.format {
+                MaskFormat::Alpha => [data[i]; 3],
+                MaskFormat::Subpixel => [data[i], data[i + 1], data[i + 2]]
+            }
+        })
+    }
+}
+
+impl Mask {
+    /**
+     * Whether both masks are the same region of the same page. Cheaper than
+     * `==`, which compares the coverage data.
+     **/
+    pub fn ptr_eq(&self, other: &Mask) -> bool {
+        Arc::ptr_eq(&self.page, &other.page)
+            && self.offset == other.offset
+            && self.stride == other.stride
+            && self.format == other.format
+    }
+}
+
+impl PartialEq for Mask {
+    fn eq(&self, other: &Mask) -> bool {
+        if self.ptr_eq(other) {
+            return true;
+        }
+        self.offset == other.offset
+            && self.stride == other.stride
+            && self.format == other.format
+            && *self.page.read().unwrap() == *other.page.read().unwrap()
+    }
+}
+
+impl Eq for Mask {}
+
+impl fmt::Debug for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Mask")
+            .field("offset", &self.offset)
+            .field("stride", &self.stride)
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+/**
+ * Packs glyph masks into large pages, filling them shelf by shelf. When a
+ * page is full, a new one is started; glyphs on the old page keep it alive.
+ **/
+pub struct Atlas {
+    size: u32,
+    format: MaskFormat,
+    page: Arc<RwLock<Vec<u8>>>,
+    x: u32,
+    y: u32,
+    shelf_height: u32
+}
+
+impl fmt::Debug for Atlas {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Atlas")
+            .field("size", &self.size)
+            .field("format", &self.format)
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .finish()
+    }
+}
+
+impl Atlas {
+    pub fn new(size: u32, format: MaskFormat) -> Atlas {
+        Atlas {
+            size,
+            format,
+            page: Atlas::new_page(size, format),
+            x: 0,
+            y: 0,
+            shelf_height: 0
+        }
+    }
+
+    fn new_page(size: u32, format: MaskFormat) -> Arc<RwLock<Vec<u8>>> {
+        Arc::new(RwLock::new(vec![0; (size * size) as usize * format.bytes_per_pixel()]))
+    }
+
+    pub fn format(&self) -> MaskFormat {
+        self.format
+    }
+
+    /**
+     * Copies a `width` x `height` mask into the atlas.
+     **/
+    pub fn insert(&mut self, width: u32, height: u32, data: &[u8]) -> Mask {
+        if width > self.size || height > self.size {
+            return Mask::new(data.to_vec(), width, self.format);
+        }
+
+        if self.x + width > self.size {
+            self.y += self.shelf_height;
+            self.x = 0;
+            self.shelf_height = 0;
+        }
+        if self.y + height > self.size {
+            self.page = Atlas::new_page(self.size, self.format);
+            self.x = 0;
+            self.y = 0;
+            self.shelf_height = 0;
+        }
+
+        let bpp = self.format.bytes_per_pixel();
+        let stride = self.size as usize * bpp;
+        let offset = self.y as usize * stride + self.x as usize * bpp;
+        let row_len = width as usize * bpp;
+        {
+            let mut page = self.page.write().unwrap();
+            for row in 0..height as usize {
+                let start = offset + row * stride;
+                page[start..start + row_len].copy_from_slice(&data[row * row_len..(row + 1) * row_len]);
+            }
+        }
+
+        self.x += width;
+        self.shelf_height = self.shelf_height.max(height);
+        Mask {
+            page: self.page.clone(),
+            offset,
+            stride,
+            format: self.format
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_shelves_and_pages() {
+        let mut atlas = Atlas::new(4, MaskFormat::Alpha);
+        let a = atlas.insert(3, 2, &[1, 2, 3, 4, 5, 6]);
+        let b = atlas.insert(2, 1, &[7, 8]);
+        let c = atlas.insert(4, 3, &[9; 12]);
+
+        assert_eq!(a.coverage(2, 1), [6; 3]);
+        assert_eq!(b.coverage(1, 0), [8; 3]);
+        assert_eq!(c.coverage(3, 2), [9; 3]);
+        // b went to a new shelf, c to a new page
+        assert_eq!(b.offset, 8);
+        assert_eq!(c.offset, 0);
+        assert_eq!(a.coverage(0, 0), [1; 3]);
+    }
+}
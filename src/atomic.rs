@@ -0,0 +1,224 @@
+use std::fs::File;
+use std::io;
+use std::mem;
+
+use super::drm::{drm_ioctl, Pointer};
+use super::drm_const::*;
+use super::ffi::*;
+
+impl Pointer for drm_set_client_cap {}
+impl Pointer for drm_mode_obj_get_properties {}
+impl Pointer for drm_mode_get_property {}
+impl Pointer for drm_mode_get_plane_res {}
+impl Pointer for drm_mode_get_plane {}
+impl Pointer for drm_mode_atomic {}
+impl Pointer for drm_mode_create_blob {}
+impl Pointer for drm_mode_destroy_blob {}
+
+pub fn set_client_cap(fd: &File, capability: u32, value: u64) -> io::Result<()> {
+    let mut cap = drm_set_client_cap {
+        capability: capability as u64,
+        value
+    };
+    drm_ioctl(fd, DRM_IOCTL_SET_CLIENT_CAP, &mut cap)
+}
+
+/**
+ * Returns the `(property id, value)` pairs attached to a mode object.
+ **/
+pub fn object_properties(fd: &File, obj_id: u32, obj_type: u32) -> io::Result<Vec<(u32, u64)>> {
+    let mut props = drm_mode_obj_get_properties { obj_id, obj_type, ..Default::default() };
+    drm_ioctl(fd, DRM_IOCTL_MODE_OBJ_GETPROPERTIES, &mut props)?;
+
+    let mut ids: Vec<u32> = vec![0; props.count_props as usize];
+    let mut values: Vec<u64> = vec![0; props.count_props as usize];
+    props.props_ptr = ids.as_mut_ptr() as u64;
+    props.prop_values_ptr = values.as_mut_ptr() as u64;
+    drm_ioctl(fd, DRM_IOCTL_MODE_OBJ_GETPROPERTIES, &mut props)?;
+
+    ids.truncate(props.count_props as usize);
+    Ok(ids.into_iter().zip(values).collect())
+}
+
+pub fn property_name(fd: &File, prop_id: u32) -> io::Result<String> {
+    let mut prop = drm_mode_get_property { prop_id, ..Default::default() };
+    drm_ioctl(fd, DRM_IOCTL_MODE_GETPROPERTY, &mut prop)?;
+
+    Ok(prop.name
+        .iter()
+        .take_while(|&&c| c != 0)
+        .map(|&c| c as u8 as char)
+        .collect())
+}
+
+/**
+ * Returns the value of the enum property `prop_id` called `name`.
+ **/
+pub fn enum_value(fd: &File, prop_id: u32, name: &str) -> io::Result<Option<u64>> {
+    let mut prop = drm_mode_get_property { prop_id, ..Default::default() };
+    drm_ioctl(fd, DRM_IOCTL_MODE_GETPROPERTY, &mut prop)?;
+
+    let mut enums = vec![drm_mode_property_enum::default(); prop.count_enum_blobs as usize];
+    let mut values: Vec<u64> = vec![0; prop.count_values as usize];
+    prop.enum_blob_ptr = enums.as_mut_ptr() as u64;
+    prop.values_ptr = values.as_mut_ptr() as u64;
+    drm_ioctl(fd, DRM_IOCTL_MODE_GETPROPERTY, &mut prop)?;
+
+    enums.truncate(prop.count_enum_blobs as usize);
+    Ok(enums.iter().find(|e| {
+        e.name.iter().take_while(|&&c| c != 0).map(|&c| c as u8 as char).eq(name.chars())
+    }).map(|e| e.value))
+}
+
+/**
+ * Looks up a property of a mode object by name and returns its id and
+ * current value.
+ **/
+pub fn find_property(fd: &File, obj_id: u32, obj_type: u32, name: &str) -> io::Result<Option<(u32, u64)>> {
+    for (id, value) in object_properties(fd, obj_id, obj_type)? {
+        if property_name(fd, id)? == name {
+            return Ok(Some((id, value)));
+        }
+    }
+    Ok(None)
+}
+
+pub fn plane_ids(fd: &File) -> io::Result<Vec<u32>> {
+    let mut res = drm_mode_get_plane_res::default();
+    drm_ioctl(fd, DRM_IOCTL_MODE_GETPLANERESOURCES, &mut res)?;
+
+    let mut ids: Vec<u32> = vec![0; res.count_planes as usize];
+    res.plane_id_ptr = ids.as_mut_ptr() as u64;
+    drm_ioctl(fd, DRM_IOCTL_MODE_GETPLANERESOURCES, &mut res)?;
+
+    ids.truncate(res.count_planes as usize);
+    Ok(ids)
+}
+
+pub fn get_plane(fd: &File, plane_id: u32) -> io::Result<drm_mode_get_plane> {
+    let mut plane = drm_mode_get_plane { plane_id, ..Default::default() };
+    drm_ioctl(fd, DRM_IOCTL_MODE_GETPLANE, &mut plane)?;
+    Ok(plane)
+}
+
+/**
+ * Returns the fourcc codes of the pixel formats a plane can scan out.
+ **/
+pub fn plane_formats(fd: &File, plane_id: u32) -> io::Result<Vec<u32>> {
+    let mut plane = get_plane(fd, plane_id)?;
+    let mut formats: Vec<u32> = vec![0; plane.count_format_types as usize];
+    plane.format_type_ptr = formats.as_mut_ptr() as u64;
+    drm_ioctl(fd, DRM_IOCTL_MODE_GETPLANE, &mut plane)?;
+
+    formats.truncate(plane.count_format_types as usize);
+    Ok(formats)
+}
+
+/**
+ * Returns the planes of the given type that can be attached to the crtc at
+ * `crtc_index` in the resource list. Requires universal planes.
+ **/
+pub fn find_planes(fd: &File, crtc_index: usize, plane_type: u64) -> io::Result<Vec<u32>> {
+    let mut planes = Vec::new();
+    for plane_id in plane_ids(fd)? {
+        let plane = get_plane(fd, plane_id)?;
+        if plane.possible_crtcs & (1 << crtc_index) == 0 {
+            continue;
+        }
+        let ty = find_property(fd, plane_id, DRM_MODE_OBJECT_PLANE, "type")?;
+        if ty.map(|(_, value)| value) == Some(plane_type) {
+            planes.push(plane_id);
+        }
+    }
+    Ok(planes)
+}
+
+/**
+ * A property blob that is destroyed when dropped.
+ **/
+pub struct Blob<'a> {
+    fd: &'a File,
+    id: u32
+}
+
+impl<'a> Blob<'a> {
+    pub fn new<T>(fd: &'a File, data: &[T]) -> io::Result<Blob<'a>> {
+        let mut blob = drm_mode_create_blob {
+            data: data.as_ptr() as u64,
+            length: mem::size_of_val(data) as u32,
+            blob_id: 0
+        };
+        drm_ioctl(fd, DRM_IOCTL_MODE_CREATEPROPBLOB, &mut blob)?;
+        Ok(Blob { fd, id: blob.blob_id })
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl<'a> Drop for Blob<'a> {
+    fn drop(&mut self) {
+        let mut blob = drm_mode_destroy_blob { blob_id: self.id };
+        let _ = drm_ioctl(self.fd, DRM_IOCTL_MODE_DESTROYPROPBLOB, &mut blob);
+    }
+}
+
+/**
+ * Collects property updates and commits them in a single atomic ioctl.
+ **/
+#[derive(Debug, Default)]
+pub struct AtomicRequest {
+    objs: Vec<u32>,
+    count_props: Vec<u32>,
+    props: Vec<u32>,
+    values: Vec<u64>
+}
+
+impl AtomicRequest {
+    pub fn new() -> AtomicRequest {
+        AtomicRequest::default()
+    }
+
+    pub fn add(&mut self, obj_id: u32, prop_id: u32, value: u64) {
+        // the kernel expects the properties to be grouped by object
+        let mut start = 0;
+        for (i, &obj) in self.objs.iter().enumerate() {
+            let count = self.count_props[i] as usize;
+            if obj == obj_id {
+                self.props.insert(start + count, prop_id);
+                self.values.insert(start + count, value);
+                self.count_props[i] += 1;
+                return;
+            }
+            start += count;
+        }
+
+        self.objs.push(obj_id);
+        self.count_props.push(1);
+        self.props.push(prop_id);
+        self.values.push(value);
+    }
+
+    pub fn commit(&mut self, fd: &File, flags: u32) -> io::Result<()> {
+        self.commit_with_event(fd, flags, 0)
+    }
+
+    /**
+     * Like `commit`, with `user_data` handed back in the page flip event
+     * requested by `DRM_MODE_PAGE_FLIP_EVENT`.
+     **/
+    pub fn commit_with_event(&mut self, fd: &File, flags: u32, user_data: u64) -> io::Result<()> {
+        let mut atomic = drm_mode_atomic {
+            flags,
+            count_objs: self.objs.len() as u32,
+            objs_ptr: self.objs.as_mut_ptr() as u64,
+            count_props_ptr: self.count_props.as_mut_ptr() as u64,
+            props_ptr: self.props.as_mut_ptr() as u64,
+            prop_values_ptr: self.values.as_mut_ptr() as u64,
+            reserved: 0,
+            user_data
+        };
+        drm_ioctl(fd, DRM_IOCTL_MODE_ATOMIC, &mut atomic)
+    }
+}
@@ -0,0 +1,160 @@
+use std::io;
+use std::slice;
+
+use super::drm::Rect;
+use super::format::Format;
+use super::frame_clock::FrameClock;
+use super::input::InputEvent;
+use super::yuv::YuvImage;
+
+/**
+ * A pixel buffer GFX can draw into, together with the means of getting it
+ * onto a display.
+ **/
+pub trait Backend {
+    fn width(&self) -> u32;
+
+    fn height(&self) -> u32;
+
+    fn format(&self) -> Format;
+
+    /**
+     * Number of bytes between the start of two rows.
+     **/
+    fn stride(&self) -> u32;
+
+    /**
+     * The part of the screen that `pixels` holds, starting with its top
+     * left pixel. All of it, except for the tiles of the tiled renderer.
+     **/
+    fn area(&self) -> Rect {
+        Rect::new(0, 0, self.width(), self.height())
+    }
+
+    fn pixels(&self) -> &[u8];
+
+    fn pixels_mut(&mut self) -> &mut [u8];
+
+    /**
+     * Marks a region as modified since the last flush.
+     **/
+    fn damage(&mut self, _rect: Rect) {}
+
+    /**
+     * Makes the damaged regions visible.
+     **/
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /**
+     * Width and height of the picture on the display in millimeters, when
+     * the display reports them.
+     **/
+    fn size_mm(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    /**
+     * Shows a video frame scaled to `dst` on a hardware overlay, above
+     * everything in `pixels`. Returns `false` if the display has no overlay
+     * for the frame's format, in which case the frame has to be drawn.
+     **/
+    fn show_video(&mut self, _frame: &YuvImage, _dst: Rect) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    fn hide_video(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn frame_clock(&self) -> io::Result<FrameClock> {
+        Err(io::Error::other("backend has no frame clock"))
+    }
+
+    /**
+     * Returns the input events that arrived through the backend itself, as
+     * with nested backends that receive input from their host window.
+     **/
+    fn input_events(&mut self) -> Vec<InputEvent> {
+        Vec::new()
+    }
+}
+
+/**
+ * Renders into memory only. Used on machines without a display and in tests.
+ **/
+#[derive(Debug, Clone)]
+pub struct MemoryBackend {
+    width: u32,
+    height: u32,
+    stride: u32,
+    format: Format,
+    // stored as words so that rows can be filled with aligned u32 writes
+    pixels: Vec<u32>,
+    damage: Rect
+}
+
+impl MemoryBackend {
+    pub fn new(width: u32, height: u32, format: Format) -> io::Result<MemoryBackend> {
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "output is too large");
+        let stride = width.checked_mul(format.bytes_per_pixel()).ok_or_else(too_large)?;
+        let len = (stride as usize).checked_mul(height as usize).ok_or_else(too_large)?;
+        Ok(MemoryBackend {
+            width,
+            height,
+            stride,
+            format,
+            pixels: vec![0; len.div_ceil(4)],
+            damage: Rect::default()
+        })
+    }
+
+    /**
+     * Bounding box of everything drawn since the last flush.
+     **/
+    pub fn damaged(&self) -> Rect {
+        self.damage
+    }
+
+    fn len(&self) -> usize {
+        self.stride as usize * self.height as usize
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        self.format
+    }
+
+    fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    fn pixels(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.pixels.as_ptr() as *const u8, self.len()) }
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        let len = self.len();
+        unsafe { slice::from_raw_parts_mut(self.pixels.as_mut_ptr() as *mut u8, len) }
+    }
+
+    fn damage(&mut self, rect: Rect) {
+        let bounds = Rect::new(0, 0, self.width, self.height);
+        self.damage = self.damage.union(&rect.intersect(&bounds));
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.damage = Rect::default();
+        Ok(())
+    }
+}
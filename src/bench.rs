@@ -0,0 +1,117 @@
+use std::time::{Duration, Instant};
+
+use super::drm::Color;
+use super::format::Format;
+use super::compositor::Compositor;
+use super::gfx::GFX;
+use super::image::Image;
+use super::simd::{self, Level};
+
+const ITERATIONS: u32 = 20;
+
+/**
+ * Fills the whole framebuffer one pixel at a time, the way GFX used to.
+ * Kept as the baseline for `run`.
+ **/
+fn fill_per_pixel(g: &mut GFX, c: &Color) {
+    let width = g.width();
+    let height = g.height();
+    let backend = g.backend_mut();
+    let format = backend.format();
+    let bpp = format.bytes_per_pixel();
+    let stride = backend.stride();
+    for x in 0..width {
+        for y in 0..height {
+            let i = y * stride + x * bpp;
+            format.write(&mut backend.pixels_mut()[i as usize..], c);
+        }
+    }
+}
+
+fn measure<F: FnMut(&mut GFX)>(g: &mut GFX, mut f: F) -> Duration {
+    f(g);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f(g);
+    }
+    start.elapsed() / ITERATIONS
+}
+
+/**
+ * Runs `f` on every row of a full HD buffer and returns the average time
+ * for the whole buffer.
+ **/
+fn measure_rows<F: FnMut(&mut [u8], &[u8])>(mut f: F) -> Duration {
+    let (width, height) = (1920, 1080);
+    let src: Vec<u8> = (0..width * 4).map(|i| (i * 7) as u8).collect();
+    let mut dst = vec![0u8; width * height * 4];
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for row in dst.chunks_mut(width * 4) {
+            f(row, &src);
+        }
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn report(name: &str, pixels: u64, time: Duration) {
+    let secs = time.as_secs() as f64 + time.subsec_nanos() as f64 * 1e-9;
+    println!(
+        "  {:<14} {:>9.3} ms  {:>9.1} Mpx/s",
+        name,
+        secs * 1e3,
+        pixels as f64 / secs / 1e6
+    );
+}
+
+/**
+ * Compares the throughput of full-screen clears and fills. Run with
+ * `cargo run --release -- bench`.
+ **/
+pub fn run() {
+    let c = Color::new(10, 20, 30, 255);
+    for &(width, height) in [(1920, 1080), (3840, 2160)].iter() {
+        for &format in [Format::Xrgb8888, Format::Rgb565].iter() {
+            let mut g = GFX::headless(width, height, format).unwrap();
+            let pixels = width as u64 * height as u64;
+            println!("{}x{} {:?}", width, height, format);
+
+            report("per pixel", pixels, measure(&mut g, |g| fill_per_pixel(g, &c)));
+            report("clear", pixels, measure(&mut g, |g| g.clear()));
+            report("rectangle", pixels, measure(&mut g, |g| {
+                g.rectangle(1, 1, width - 2, height - 2, &c)
+            }));
+        }
+    }
+
+    let pixels = 1920 * 1080;
+    println!("1920x1080 compositor frame");
+    for &threads in [1, 2, 4, 8].iter() {
+        let mut c = Compositor::with_gfx(GFX::headless(1920, 1080, Format::Xrgb8888).unwrap());
+        c.set_render_threads(threads);
+        let mut wallpaper = Image::new(1280, 720, Format::Xrgb8888).unwrap();
+        wallpaper.fill(&Color::new(30, 60, 90, 255));
+        c.set_wallpaper(Some(wallpaper));
+        for _ in 0..4 {
+            c.add_window();
+        }
+        c.set_status(Some(String::from("bench")));
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            c.render().unwrap();
+        }
+        report(&format!("{} threads", threads), pixels, start.elapsed() / ITERATIONS);
+    }
+
+    println!("1920x1080 row kernels");
+    for &level in Level::available().iter() {
+        report(&format!("fill {:?}", level), pixels, measure_rows(|row, _| simd::fill_with(level, row, 0xff102030)));
+        report(&format!("copy {:?}", level), pixels, measure_rows(|row, src| simd::copy_with(level, row, src)));
+        report(&format!("over {:?}", level), pixels, measure_rows(|row, src| {
+            simd::over_with(level, row, Format::Xrgb8888, src)
+        }));
+        report(&format!("convert {:?}", level), pixels, measure_rows(|row, src| {
+            simd::convert_with(level, row, Format::Xrgb8888, src, Format::Abgr8888)
+        }));
+    }
+}
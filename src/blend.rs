@@ -0,0 +1,213 @@
+use super::drm::Color;
+use super::format::Format;
+
+/**
+ * Porter-Duff compositing operators. All of them work on premultiplied
+ * colors: the pixels in a framebuffer are premultiplied, and colors passed
+ * to GFX are premultiplied before blending.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    /// Replaces the destination.
+    Source,
+    /// Draws the source on top of the destination.
+    Over,
+    /// Adds source and destination, saturating at full intensity.
+    Add,
+    /// Multiplies source and destination where both are covered.
+    Multiply
+}
+
+/**
+ * Computes `a * b / 255`, rounded.
+ **/
+pub fn mul_div255(a: u8, b: u8) -> u8 {
+    let t = a as u32 * b as u32 + 128;
+    ((t + (t >> 8)) >> 8) as u8
+}
+
+impl Color {
+    /**
+     * Scales the color channels by alpha.
+     **/
+    pub fn premultiply(&self) -> Color {
+        Color::new(
+            mul_div255(self.r, self.a),
+            mul_div255(self.g, self.a),
+            mul_div255(self.b, self.a),
+            self.a
+        )
+    }
+
+    /**
+     * Inverse of `premultiply`, up to rounding.
+     **/
+    pub fn unpremultiply(&self) -> Color {
+        if self.a == 0 {
+            return Color::new(0, 0, 0, 0);
+        }
+        let div = |c: u8| ((c as u32 * 255 + self.a as u32 / 2) / self.a as u32).min(255) as u8;
+        Color::new(div(self.r), div(self.g), div(self.b), self.a)
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        self.a == 255
+    }
+}
+
+impl Operator {
+    /**
+     * Combines a premultiplied source with a premultiplied destination.
+     **/
+    pub fn blend(&self, s: &Color, d: &Color) -> Color {
+        match *self {
+            Operator::Source => *s,
+            Operator::Over => {
+                let inv = 255 - s.a;
+                Color::new(
+                    s.r.saturating_add(mul_div255(d.r, inv)),
+                    s.g.saturating_add(mul_div255(d.g, inv)),
+                    s.b.saturating_add(mul_div255(d.b, inv)),
+                    s.a.saturating_add(mul_div255(d.a, inv))
+                )
+            }
+            Operator::Add => Color::new(
+                s.r.saturating_add(d.r),
+                s.g.saturating_add(d.g),
+                s.b.saturating_add(d.b),
+                s.a.saturating_add(d.a)
+            ),
+            Operator::Multiply => {
+                let inv_sa = 255 - s.a;
+                let inv_da = 255 - d.a;
+                let channel = |s: u8, d: u8| {
+                    let sum = mul_div255(s, d) as u32
+                        + mul_div255(s, inv_da) as u32
+                        + mul_div255(d, inv_sa) as u32;
+                    sum.min(255) as u8
+                };
+                Color::new(
+                    channel(s.r, d.r),
+                    channel(s.g, d.g),
+                    channel(s.b, d.b),
+                    s.a + mul_div255(d.a, inv_sa)
+                )
+            }
+        }
+    }
+
+    /**
+     * Whether blending `src` gives the same result as writing it, which
+     * allows using plain fills and copies.
+     **/
+    pub fn is_copy(&self, src: &Color) -> bool {
+        match *self {
+            Operator::Source => true,
+            Operator::Over => src.is_opaque(),
+            _ => false
+        }
+    }
+}
+
+/**
+ * Blends a single premultiplied color onto every pixel of `row`.
+ **/
+pub fn blend_fill(row: &mut [u8], format: Format, src: &Color, op: Operator) {
+    let bpp = format.bytes_per_pixel() as usize;
+    for pixel in row.chunks_mut(bpp) {
+        let d = format.read(pixel);
+        format.write(pixel, &op.blend(src, &d));
+    }
+}
+
+/**
+ * Blends a row of premultiplied colors onto `row`.
+ **/
+pub fn blend_span(row: &mut [u8], format: Format, src: &[Color], op: Operator) {
+    let bpp = format.bytes_per_pixel() as usize;
+    for (pixel, s) in row.chunks_mut(bpp).zip(src) {
+        let d = format.read(pixel);
+        format.write(pixel, &op.blend(s, &d));
+    }
+}
+
+/**
+ * Combines the premultiplied color `src` with `row` using separate red,
+ * green and blue coverage per pixel, as produced by subpixel antialiasing.
+ * Each channel is blended as if the whole pixel had that channel's coverage.
+ **/
+pub fn blend_component_span(row: &mut [u8], format: Format, src: &Color, coverage: &[[u8; 3]], op: Operator) {
+    let bpp = format.bytes_per_pixel() as usize;
+    for (pixel, cov) in row.chunks_mut(bpp).zip(coverage) {
+        let d = format.read(pixel);
+        let covered = |coverage: u8| {
+            let s = Color::new(
+                mul_div255(src.r, coverage),
+                mul_div255(src.g, coverage),
+                mul_div255(src.b, coverage),
+                mul_div255(src.a, coverage)
+            );
+            op.blend(&s, &d)
+        };
+        let a = cov[0].max(cov[1]).max(cov[2]);
+        format.write(pixel, &Color::new(
+            covered(cov[0]).r,
+            covered(cov[1]).g,
+            covered(cov[2]).b,
+            covered(a).a
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn over() {
+        let s = Color::new(255, 0, 0, 128).premultiply();
+        let d = Color::new(0, 0, 255, 255);
+        assert_eq!(Operator::Over.blend(&s, &d), Color::new(128, 0, 127, 255));
+        assert_eq!(Operator::Over.blend(&Color::new(0, 0, 0, 0), &d), d);
+        // colors that aren't premultiplied must not wrap around
+        let s = Color::new(255, 255, 255, 128);
+        assert_eq!(Operator::Over.blend(&s, &d), Color::new(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn add_saturates() {
+        let s = Color::new(200, 100, 0, 255);
+        let d = Color::new(100, 100, 0, 255);
+        assert_eq!(Operator::Add.blend(&s, &d), Color::new(255, 200, 0, 255));
+    }
+
+    #[test]
+    fn multiply() {
+        let s = Color::new(128, 255, 0, 255);
+        let d = Color::new(255, 128, 128, 255);
+        assert_eq!(Operator::Multiply.blend(&s, &d), Color::new(128, 128, 0, 255));
+        // a transparent destination leaves the source untouched
+        assert_eq!(Operator::Multiply.blend(&s, &Color::new(0, 0, 0, 0)), s);
+    }
+
+    #[test]
+    fn premultiply_roundtrip() {
+        let c = Color::new(200, 100, 50, 128);
+        let p = c.premultiply();
+        assert_eq!(p, Color::new(100, 50, 25, 128));
+        assert_eq!(p.unpremultiply(), Color::new(199, 100, 50, 128));
+    }
+
+    #[test]
+    fn component_alpha() {
+        let mut row = vec![255; 4];
+        let black = Color::new(0, 0, 0, 255);
+        blend_component_span(&mut row, Format::Xrgb8888, &black, &[[255, 128, 0]], Operator::Over);
+        assert_eq!(Format::Xrgb8888.read(&row), Color::new(0, 127, 255, 255));
+
+        let mut row = vec![0, 0, 100, 255];
+        let gray = Color::new(100, 100, 100, 255);
+        blend_component_span(&mut row, Format::Xrgb8888, &gray, &[[255, 0, 255]], Operator::Add);
+        assert_eq!(Format::Xrgb8888.read(&row), Color::new(200, 0, 100, 255));
+    }
+}
@@ -0,0 +1,206 @@
+use std::io;
+
+use super::drm::Color;
+use super::format::Format;
+use super::image::Image;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+const CORE_HEADER_SIZE: usize = 12;
+const INFO_HEADER_SIZE: usize = 40;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> io::Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| b[0] as u16 | (b[1] as u16) << 8)
+        .ok_or_else(|| invalid("truncated BMP"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> io::Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+        .ok_or_else(|| invalid("truncated BMP"))
+}
+
+/**
+ * Extracts the channel selected by `mask` from a pixel and scales it to
+ * 8 bits.
+ **/
+fn channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let value = (pixel & mask) >> mask.trailing_zeros();
+    let max = mask >> mask.trailing_zeros();
+    ((value as u64 * 255 + max as u64 / 2) / max as u64) as u8
+}
+
+/**
+ * Decodes an uncompressed or bitfield BMP file with 1 to 32 bits per pixel.
+ **/
+pub fn decode(data: &[u8]) -> io::Result<Image> {
+    if !data.starts_with(b"BM") {
+        return Err(invalid("not a BMP file"));
+    }
+    let pixels_offset = read_u32(data, 10)? as usize;
+    let header_size = read_u32(data, 14)? as usize;
+
+    let (width, height, bpp, compression) = if header_size == CORE_HEADER_SIZE {
+        (read_u16(data, 18)? as i32, read_u16(data, 20)? as i32, read_u16(data, 24)?, BI_RGB)
+    } else if header_size >= INFO_HEADER_SIZE {
+        (read_u32(data, 18)? as i32, read_u32(data, 22)? as i32, read_u16(data, 28)?, read_u32(data, 30)?)
+    } else {
+        return Err(invalid("unsupported BMP header"));
+    };
+    // positive heights are stored bottom-up
+    let top_down = height < 0;
+    let (width, height) = (width as u32, height.unsigned_abs());
+    if width == 0 || height == 0 || width > 1 << 16 || height > 1 << 16 {
+        return Err(invalid("invalid BMP dimensions"));
+    }
+
+    let (masks, has_alpha) = match compression {
+        BI_RGB => match bpp {
+            16 => ([0x7c00, 0x03e0, 0x001f, 0], false),
+            24 | 32 => ([0xff0000, 0x00ff00, 0x0000ff, 0], false),
+            _ => ([0; 4], false)
+        },
+        BI_BITFIELDS | BI_ALPHABITFIELDS if bpp == 16 || bpp == 32 => {
+            // the masks follow a plain info header, or are part of a larger one
+            let offset = 14 + INFO_HEADER_SIZE;
+            let alpha = if compression == BI_ALPHABITFIELDS || header_size >= 56 {
+                read_u32(data, offset + 12)?
+            } else {
+                0
+            };
+            ([read_u32(data, offset)?, read_u32(data, offset + 4)?, read_u32(data, offset + 8)?, alpha], alpha != 0)
+        }
+        _ => return Err(invalid("unsupported BMP compression"))
+    };
+
+    let palette = if bpp <= 8 {
+        let entry_size = if header_size == CORE_HEADER_SIZE { 3 } else { 4 };
+        let used = if header_size >= INFO_HEADER_SIZE { read_u32(data, 46)? as usize } else { 0 };
+        let count = if used == 0 { 1 << bpp } else { used.min(256) };
+        let start = 14 + header_size;
+        let mut palette = Vec::with_capacity(count);
+        for i in 0..count {
+            let entry = data.get(start + i * entry_size..start + i * entry_size + 3).ok_or_else(|| invalid("truncated BMP palette"))?;
+            palette.push(Color::new(entry[2], entry[1], entry[0], 255));
+        }
+        palette
+    } else {
+        Vec::new()
+    };
+
+    let row_len = (width as usize * bpp as usize).div_ceil(32) * 4;
+    let format = if has_alpha { Format::Argb8888 } else { Format::Xrgb8888 };
+    let mut image = Image::new(width, height, format).map_err(|_| invalid("BMP image is too large"))?;
+    for row in 0..height {
+        let start = pixels_offset + row as usize * row_len;
+        let src = data.get(start..start + row_len).ok_or_else(|| invalid("truncated BMP pixels"))?;
+        let y = if top_down { row } else { height - 1 - row };
+
+        for x in 0..width {
+            let i = x as usize;
+            let c = match bpp {
+                1 | 2 | 4 | 8 => {
+                    let bit = i * bpp as usize;
+                    let index = (src[bit / 8] >> (8 - bpp as usize - bit % 8)) as usize & ((1 << bpp) - 1);
+                    palette.get(index).cloned().unwrap_or(Color::new(0, 0, 0, 255))
+                }
+                24 => Color::new(src[i * 3 + 2], src[i * 3 + 1], src[i * 3], 255),
+                16 | 32 => {
+                    let pixel = if bpp == 16 {
+                        src[i * 2] as u32 | (src[i * 2 + 1] as u32) << 8
+                    } else {
+                        read_u32(src, i * 4)?
+                    };
+                    let a = if has_alpha { channel(pixel, masks[3]) } else { 255 };
+                    Color::new(channel(pixel, masks[0]), channel(pixel, masks[1]), channel(pixel, masks[2]), a)
+                }
+                _ => return Err(invalid("unsupported BMP bit depth"))
+            };
+            image.set(x, y, &c.premultiply());
+        }
+    }
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Builds a BMP with a 40 byte info header, followed by `extra` (masks
+     * or palette) and the given rows.
+     **/
+    fn bmp(width: i32, height: i32, bpp: u16, compression: u32, extra: &[u8], rows: &[u8]) -> Vec<u8> {
+        let offset = 14 + 40 + extra.len() as u32;
+        let mut data = Vec::new();
+        data.extend_from_slice(b"BM");
+        data.extend_from_slice(&(offset + rows.len() as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&40u32.to_le_bytes());
+        data.extend_from_slice(&width.to_le_bytes());
+        data.extend_from_slice(&height.to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&bpp.to_le_bytes());
+        data.extend_from_slice(&compression.to_le_bytes());
+        data.extend_from_slice(&[0; 20]);
+        data.extend_from_slice(extra);
+        data.extend_from_slice(rows);
+        data
+    }
+
+    #[test]
+    fn decodes_bottom_up_24_bit() {
+        // rows are padded to 4 bytes, the bottom row comes first
+        let rows = [
+            0, 0, 255, 0, 255, 0, 0, 0,
+            255, 0, 0, 255, 255, 255, 0, 0
+        ];
+        let image = decode(&bmp(2, 2, 24, BI_RGB, &[], &rows)).unwrap();
+        assert_eq!(image.format(), Format::Xrgb8888);
+        assert_eq!(image.get(0, 1), Color::new(255, 0, 0, 255));
+        assert_eq!(image.get(1, 1), Color::new(0, 255, 0, 255));
+        assert_eq!(image.get(0, 0), Color::new(0, 0, 255, 255));
+        assert_eq!(image.get(1, 0), Color::new(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn decodes_top_down_bitfields_with_alpha() {
+        let mut masks = Vec::new();
+        for mask in [0x00ff0000u32, 0x0000ff00, 0x000000ff, 0xff000000].iter() {
+            masks.extend_from_slice(&mask.to_le_bytes());
+        }
+        let rows = [0, 0, 255, 128, 10, 20, 30, 255];
+        let image = decode(&bmp(1, -2, 32, BI_ALPHABITFIELDS, &masks, &rows)).unwrap();
+        assert_eq!(image.format(), Format::Argb8888);
+        assert_eq!(image.get(0, 0), Color::new(128, 0, 0, 128));
+        assert_eq!(image.get(0, 1), Color::new(30, 20, 10, 255));
+    }
+
+    #[test]
+    fn decodes_palettes() {
+        let palette = [0, 0, 0, 0, 255, 255, 255, 0];
+        // 1 bit per pixel: 1 0 1, padded to 4 bytes
+        let image = decode(&bmp(3, 1, 1, BI_RGB, &palette, &[0b1010_0000, 0, 0, 0])).unwrap();
+        assert_eq!(image.get(0, 0), Color::new(255, 255, 255, 255));
+        assert_eq!(image.get(1, 0), Color::new(0, 0, 0, 255));
+        assert_eq!(image.get(2, 0), Color::new(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn decodes_rgb555() {
+        // pure red in 5:5:5
+        let image = decode(&bmp(1, 1, 16, BI_RGB, &[], &[0x00, 0x7c, 0, 0])).unwrap();
+        assert_eq!(image.get(0, 0), Color::new(255, 0, 0, 255));
+    }
+}
@@ -0,0 +1,610 @@
+use std::collections::HashMap;
+use std::io;
+
+use super::outline::Outline;
+use super::truetype::{read_u16, read_u32, read_u8};
+
+const TOP_CHAR_STRINGS: u16 = 17;
+const TOP_PRIVATE: u16 = 18;
+const TOP_CHARSTRING_TYPE: u16 = 1206;
+const TOP_FD_ARRAY: u16 = 1236;
+const TOP_FD_SELECT: u16 = 1237;
+const PRIVATE_SUBRS: u16 = 19;
+
+/**
+ * Limits from the Type 2 charstring specification.
+ **/
+const MAX_STACK: usize = 48;
+const MAX_SUBR_DEPTH: u32 = 10;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/**
+ * A CFF INDEX: an array of variable sized objects.
+ **/
+#[derive(Debug, Clone, Copy, Default)]
+struct Index {
+    count: usize,
+    off_size: usize,
+    offsets: usize,
+    data: usize
+}
+
+impl Index {
+    /**
+     * Parses the INDEX at `start`, returning it and the offset just past it.
+     **/
+    fn parse(data: &[u8], start: usize) -> io::Result<(Index, usize)> {
+        let count = read_u16(data, start)? as usize;
+        if count == 0 {
+            return Ok((Index::default(), start + 2));
+        }
+        let off_size = read_u8(data, start + 2)? as usize;
+        if !(1..=4).contains(&off_size) {
+            return Err(invalid("invalid INDEX offset size"));
+        }
+        let index = Index {
+            count,
+            off_size,
+            offsets: start + 3,
+            data: start + 3 + (count + 1) * off_size - 1
+        };
+        let end = index.data + index.offset(data, count)?;
+        Ok((index, end))
+    }
+
+    fn offset(&self, data: &[u8], i: usize) -> io::Result<usize> {
+        let mut value = 0;
+        for k in 0..self.off_size {
+            value = value << 8 | read_u8(data, self.offsets + i * self.off_size + k)? as usize;
+        }
+        Ok(value)
+    }
+
+    fn get<'a>(&self, data: &'a [u8], i: usize) -> io::Result<&'a [u8]> {
+        if i >= self.count {
+            return Err(invalid("INDEX entry out of range"));
+        }
+        let start = self.data + self.offset(data, i)?;
+        let end = self.data + self.offset(data, i + 1)?;
+        data.get(start..end).ok_or_else(|| invalid("truncated INDEX"))
+    }
+
+    /**
+     * The number added to subroutine numbers in charstrings.
+     **/
+    fn bias(&self) -> i32 {
+        if self.count < 1240 {
+            107
+        } else if self.count < 33900 {
+            1131
+        } else {
+            32768
+        }
+    }
+}
+
+/**
+ * Parses a DICT into operator keys (escaped operators as `1200 + b`) and
+ * their operands.
+ **/
+fn parse_dict(dict: &[u8]) -> io::Result<HashMap<u16, Vec<f64>>> {
+    let mut entries = HashMap::new();
+    let mut operands = Vec::new();
+    let mut i = 0;
+    while i < dict.len() {
+        let b0 = dict[i];
+        i += 1;
+        match b0 {
+            0..=21 => {
+                let key = if b0 == 12 {
+                    i += 1;
+                    1200 + *dict.get(i - 1).ok_or_else(|| invalid("truncated DICT"))? as u16
+                } else {
+                    b0 as u16
+                };
+                entries.insert(key, operands.split_off(0));
+            }
+            28 => {
+                operands.push(read_u16(dict, i)? as i16 as f64);
+                i += 2;
+            }
+            29 => {
+                operands.push(read_u32(dict, i)? as i32 as f64);
+                i += 4;
+            }
+            30 => {
+                let mut text = String::new();
+                'nibbles: while i < dict.len() {
+                    let byte = dict[i];
+                    i += 1;
+                    for &nibble in [byte >> 4, byte & 0xf].iter() {
+                        match nibble {
+                            0..=9 => text.push((b'0' + nibble) as char),
+                            0xa => text.push('.'),
+                            0xb => text.push('E'),
+                            0xc => text.push_str("E-"),
+                            0xe => text.push('-'),
+                            0xf => break 'nibbles,
+                            _ => {}
+                        }
+                    }
+                }
+                operands.push(text.parse().map_err(|_| invalid("invalid real number"))?);
+            }
+            32..=246 => operands.push(b0 as f64 - 139.0),
+            247..=250 => {
+                operands.push((b0 as f64 - 247.0) * 256.0 + read_u8(dict, i)? as f64 + 108.0);
+                i += 1;
+            }
+            251..=254 => {
+                operands.push(-(b0 as f64 - 251.0) * 256.0 - read_u8(dict, i)? as f64 - 108.0);
+                i += 1;
+            }
+            _ => return Err(invalid("invalid DICT operand"))
+        }
+    }
+    Ok(entries)
+}
+
+/**
+ * Outlines from the Compact Font Format table of an OpenType font.
+ * Offsets are absolute within the font file.
+ **/
+#[derive(Debug)]
+pub struct Cff {
+    char_strings: Index,
+    global_subrs: Index,
+    /// Local subroutines of each font DICT; a single one for fonts that
+    /// are not CID-keyed.
+    local_subrs: Vec<Index>,
+    fd_select: Option<usize>
+}
+
+impl Cff {
+    /**
+     * Parses the CFF table at `table` in the font `data`.
+     **/
+    pub fn parse(data: &[u8], table: usize) -> io::Result<Cff> {
+        let header_size = read_u8(data, table + 2)? as usize;
+        let (_names, end) = Index::parse(data, table + header_size)?;
+        let (top_dicts, end) = Index::parse(data, end)?;
+        let (_strings, end) = Index::parse(data, end)?;
+        let (global_subrs, _) = Index::parse(data, end)?;
+
+        let top = parse_dict(top_dicts.get(data, 0)?)?;
+        let offset = |dict: &HashMap<u16, Vec<f64>>, key: u16| {
+            dict.get(&key).and_then(|operands| operands.last()).map(|&v| table + v as usize)
+        };
+        if top.get(&TOP_CHARSTRING_TYPE).is_some_and(|t| t != &[2.0]) {
+            return Err(invalid("unsupported charstring type"));
+        }
+        let char_strings = offset(&top, TOP_CHAR_STRINGS).ok_or_else(|| invalid("missing CharStrings"))?;
+        let (char_strings, _) = Index::parse(data, char_strings)?;
+
+        let private_subrs = |dict: &HashMap<u16, Vec<f64>>| -> io::Result<Index> {
+            let (size, offset) = match dict.get(&TOP_PRIVATE).map(|v| v.as_slice()) {
+                Some(&[size, offset]) => (size as usize, table + offset as usize),
+                _ => return Ok(Index::default())
+            };
+            let private = parse_dict(data.get(offset..offset + size).ok_or_else(|| invalid("truncated Private DICT"))?)?;
+            match private.get(&PRIVATE_SUBRS).and_then(|v| v.last()) {
+                Some(&subrs) => Ok(Index::parse(data, offset + subrs as usize)?.0),
+                None => Ok(Index::default())
+            }
+        };
+
+        let (local_subrs, fd_select) = match offset(&top, TOP_FD_ARRAY) {
+            Some(fd_array) => {
+                let (fd_array, _) = Index::parse(data, fd_array)?;
+                let mut subrs = Vec::with_capacity(fd_array.count);
+                for i in 0..fd_array.count {
+                    subrs.push(private_subrs(&parse_dict(fd_array.get(data, i)?)?)?);
+                }
+                let fd_select = offset(&top, TOP_FD_SELECT).ok_or_else(|| invalid("missing FDSelect"))?;
+                (subrs, Some(fd_select))
+            }
+            None => (vec![private_subrs(&top)?], None)
+        };
+
+        Ok(Cff {
+            char_strings,
+            global_subrs,
+            local_subrs,
+            fd_select
+        })
+    }
+
+    /**
+     * Finds the font DICT a glyph of a CID-keyed font belongs to.
+     **/
+    fn font_dict(&self, data: &[u8], glyph: u16) -> io::Result<usize> {
+        let fd_select = match self.fd_select {
+            Some(fd_select) => fd_select,
+            None => return Ok(0)
+        };
+        match read_u8(data, fd_select)? {
+            0 => Ok(read_u8(data, fd_select + 1 + glyph as usize)? as usize),
+            3 => {
+                let ranges = read_u16(data, fd_select + 1)? as usize;
+                for i in 0..ranges {
+                    let range = fd_select + 3 + i * 3;
+                    let next = read_u16(data, range + 3)?;
+                    if glyph >= read_u16(data, range)? && glyph < next {
+                        return Ok(read_u8(data, range + 2)? as usize);
+                    }
+                }
+                Err(invalid("glyph not in FDSelect"))
+            }
+            _ => Err(invalid("unsupported FDSelect format"))
+        }
+    }
+
+    /**
+     * Runs the charstring of a glyph to build its outline.
+     **/
+    pub fn outline(&self, data: &[u8], glyph: u16) -> io::Result<Outline> {
+        let local = self.local_subrs.get(self.font_dict(data, glyph)?).cloned().unwrap_or_default();
+        let mut interpreter = Interpreter {
+            data,
+            global: self.global_subrs,
+            local,
+            stack: Vec::with_capacity(MAX_STACK),
+            x: 0.0,
+            y: 0.0,
+            stems: 0,
+            width_parsed: false,
+            open: false,
+            outline: Outline::new()
+        };
+        interpreter.run(self.char_strings.get(data, glyph as usize)?, 0)?;
+        interpreter.close();
+        Ok(interpreter.outline)
+    }
+}
+
+/**
+ * State of a Type 2 charstring program.
+ **/
+struct Interpreter<'a> {
+    data: &'a [u8],
+    global: Index,
+    local: Index,
+    stack: Vec<f32>,
+    x: f32,
+    y: f32,
+    stems: usize,
+    width_parsed: bool,
+    open: bool,
+    outline: Outline
+}
+
+impl<'a> Interpreter<'a> {
+    /**
+     * The first stack clearing operator may carry the advance width as an
+     * extra leading operand; it is not needed since hmtx has it too.
+     **/
+    fn skip_width(&mut self, expected_even: bool) {
+        if !self.width_parsed {
+            self.width_parsed = true;
+            let odd = self.stack.len() % 2 == 1;
+            if odd == expected_even && !self.stack.is_empty() {
+                self.stack.remove(0);
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        if self.open {
+            self.outline.close();
+            self.open = false;
+        }
+    }
+
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        self.close();
+        self.x += dx;
+        self.y += dy;
+        self.outline.move_to(self.x, self.y);
+        self.open = true;
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+        self.outline.line_to(self.x, self.y);
+    }
+
+    fn curve_to(&mut self, dx1: f32, dy1: f32, dx2: f32, dy2: f32, dx3: f32, dy3: f32) {
+        let (c1x, c1y) = (self.x + dx1, self.y + dy1);
+        let (c2x, c2y) = (c1x + dx2, c1y + dy2);
+        self.x = c2x + dx3;
+        self.y = c2y + dy3;
+        self.outline.cubic_to(c1x, c1y, c2x, c2y, self.x, self.y);
+    }
+
+    /**
+     * Executes `code`, returning whether it ended the glyph.
+     **/
+    fn run(&mut self, code: &[u8], depth: u32) -> io::Result<bool> {
+        if depth > MAX_SUBR_DEPTH {
+            return Err(invalid("subroutines nested too deeply"));
+        }
+        let mut i = 0;
+        while i < code.len() {
+            let op = code[i];
+            i += 1;
+            if op >= 32 || op == 28 {
+                let value = match op {
+                    28 => {
+                        i += 2;
+                        read_u16(code, i - 2)? as i16 as f32
+                    }
+                    32..=246 => op as f32 - 139.0,
+                    247..=250 => {
+                        i += 1;
+                        (op as f32 - 247.0) * 256.0 + read_u8(code, i - 1)? as f32 + 108.0
+                    }
+                    251..=254 => {
+                        i += 1;
+                        -(op as f32 - 251.0) * 256.0 - read_u8(code, i - 1)? as f32 - 108.0
+                    }
+                    _ => {
+                        i += 4;
+                        read_u32(code, i - 4)? as i32 as f32 / 65536.0
+                    }
+                };
+                if self.stack.len() >= MAX_STACK {
+                    return Err(invalid("charstring stack overflow"));
+                }
+                self.stack.push(value);
+                continue;
+            }
+
+            let s = self.stack.clone();
+            match op {
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    self.skip_width(true);
+                    self.stems += self.stack.len() / 2;
+                }
+                // hintmask, cntrmask
+                19 | 20 => {
+                    self.skip_width(true);
+                    self.stems += self.stack.len() / 2;
+                    i += self.stems.div_ceil(8);
+                }
+                // rmoveto
+                21 => {
+                    self.skip_width(true);
+                    let s = &self.stack;
+                    let (dx, dy) = (arg(s, 0)?, arg(s, 1)?);
+                    self.move_to(dx, dy);
+                }
+                // hmoveto
+                22 => {
+                    self.skip_width(false);
+                    let dx = arg(&self.stack, 0)?;
+                    self.move_to(dx, 0.0);
+                }
+                // vmoveto
+                4 => {
+                    self.skip_width(false);
+                    let dy = arg(&self.stack, 0)?;
+                    self.move_to(0.0, dy);
+                }
+                // rlineto
+                5 => {
+                    for pair in s.chunks(2).filter(|p| p.len() == 2) {
+                        self.line_to(pair[0], pair[1]);
+                    }
+                }
+                // hlineto, vlineto
+                6 | 7 => {
+                    for (k, &d) in s.iter().enumerate() {
+                        if (k % 2 == 0) == (op == 6) {
+                            self.line_to(d, 0.0);
+                        } else {
+                            self.line_to(0.0, d);
+                        }
+                    }
+                }
+                // rrcurveto
+                8 => {
+                    for c in s.chunks(6).filter(|c| c.len() == 6) {
+                        self.curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                    }
+                }
+                // rcurveline
+                24 => {
+                    let curves = s.len().saturating_sub(2) / 6;
+                    for c in s[..curves * 6].chunks(6) {
+                        self.curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                    }
+                    self.line_to(arg(&s, curves * 6)?, arg(&s, curves * 6 + 1)?);
+                }
+                // rlinecurve
+                25 => {
+                    let lines = s.len().saturating_sub(6) / 2;
+                    for pair in s[..lines * 2].chunks(2) {
+                        self.line_to(pair[0], pair[1]);
+                    }
+                    let c = s.get(lines * 2..lines * 2 + 6).ok_or_else(|| invalid("charstring stack underflow"))?;
+                    self.curve_to(c[0], c[1], c[2], c[3], c[4], c[5]);
+                }
+                // vvcurveto
+                26 => {
+                    let (mut dx1, rest) = if s.len() % 2 == 1 { (s[0], &s[1..]) } else { (0.0, &s[..]) };
+                    for c in rest.chunks(4).filter(|c| c.len() == 4) {
+                        self.curve_to(dx1, c[0], c[1], c[2], 0.0, c[3]);
+                        dx1 = 0.0;
+                    }
+                }
+                // hhcurveto
+                27 => {
+                    let (mut dy1, rest) = if s.len() % 2 == 1 { (s[0], &s[1..]) } else { (0.0, &s[..]) };
+                    for c in rest.chunks(4).filter(|c| c.len() == 4) {
+                        self.curve_to(c[0], dy1, c[1], c[2], c[3], 0.0);
+                        dy1 = 0.0;
+                    }
+                }
+                // vhcurveto, hvcurveto
+                30 | 31 => {
+                    let mut horizontal = op == 31;
+                    let groups = s.len() / 4;
+                    for g in 0..groups {
+                        let c = &s[g * 4..g * 4 + 4];
+                        let last = if g == groups - 1 && s.len() % 4 == 1 { s[s.len() - 1] } else { 0.0 };
+                        if horizontal {
+                            self.curve_to(c[0], 0.0, c[1], c[2], last, c[3]);
+                        } else {
+                            self.curve_to(0.0, c[0], c[1], c[2], c[3], last);
+                        }
+                        horizontal = !horizontal;
+                    }
+                }
+                // callsubr, callgsubr
+                10 | 29 => {
+                    let number = self.stack.pop().ok_or_else(|| invalid("charstring stack underflow"))?;
+                    let subrs = if op == 10 { self.local } else { self.global };
+                    let index = number as i32 + subrs.bias();
+                    if index < 0 {
+                        return Err(invalid("invalid subroutine"));
+                    }
+                    let subr = subrs.get(self.data, index as usize)?;
+                    if self.run(subr, depth + 1)? {
+                        return Ok(true);
+                    }
+                    continue;
+                }
+                // return
+                11 => return Ok(false),
+                // endchar
+                14 => {
+                    self.skip_width(true);
+                    self.close();
+                    return Ok(true);
+                }
+                12 => {
+                    let escape = read_u8(code, i)?;
+                    i += 1;
+                    match escape {
+                        // hflex
+                        34 if s.len() >= 7 => {
+                            self.curve_to(s[0], 0.0, s[1], s[2], s[3], 0.0);
+                            self.curve_to(s[4], 0.0, s[5], -s[2], s[6], 0.0);
+                        }
+                        // flex
+                        35 if s.len() >= 12 => {
+                            self.curve_to(s[0], s[1], s[2], s[3], s[4], s[5]);
+                            self.curve_to(s[6], s[7], s[8], s[9], s[10], s[11]);
+                        }
+                        // hflex1
+                        36 if s.len() >= 9 => {
+                            self.curve_to(s[0], s[1], s[2], s[3], s[4], 0.0);
+                            self.curve_to(s[5], 0.0, s[6], s[7], s[8], -(s[1] + s[3] + s[7]));
+                        }
+                        // flex1: the last point returns to the starting
+                        // level along the axis the flex mostly moved on
+                        37 if s.len() >= 11 => {
+                            let dx: f32 = s[..10].iter().step_by(2).sum();
+                            let dy: f32 = s[1..10].iter().step_by(2).sum();
+                            self.curve_to(s[0], s[1], s[2], s[3], s[4], s[5]);
+                            if dx.abs() > dy.abs() {
+                                self.curve_to(s[6], s[7], s[8], s[9], s[10], -dy);
+                            } else {
+                                self.curve_to(s[6], s[7], s[8], s[9], -dx, s[10]);
+                            }
+                        }
+                        // arithmetic and storage operators are not used by
+                        // fonts in practice
+                        _ => {}
+                    }
+                }
+                _ => return Err(invalid("invalid charstring operator"))
+            }
+            self.stack.clear();
+        }
+        Ok(false)
+    }
+}
+
+fn arg(stack: &[f32], i: usize) -> io::Result<f32> {
+    stack.get(i).cloned().ok_or_else(|| invalid("charstring stack underflow"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::outline::PathCommand;
+
+    /**
+     * Encodes a small integer operand.
+     **/
+    fn int(v: i32) -> u8 {
+        (v + 139) as u8
+    }
+
+    fn run(code: &[u8]) -> Outline {
+        let mut interpreter = Interpreter {
+            data: &[],
+            global: Index::default(),
+            local: Index::default(),
+            stack: Vec::new(),
+            x: 0.0,
+            y: 0.0,
+            stems: 0,
+            width_parsed: false,
+            open: false,
+            outline: Outline::new()
+        };
+        assert!(interpreter.run(code, 0).unwrap());
+        interpreter.outline
+    }
+
+    #[test]
+    fn parses_dicts() {
+        // 1000 CharStrings, 24 100 Private, -2.5 (a real) under an escape
+        let dict = [0xfa, 0x7c, 17, int(24), int(100), 18, 30, 0xe2, 0xa5, 0xff, 12, 3];
+        let entries = parse_dict(&dict).unwrap();
+        assert_eq!(entries[&17], vec![1000.0]);
+        assert_eq!(entries[&18], vec![24.0, 100.0]);
+        assert_eq!(entries[&1203], vec![-2.5]);
+    }
+
+    #[test]
+    fn runs_charstrings() {
+        // width 50, then a box drawn with hintmask, h/vlineto and endchar
+        let code = [
+            int(50), int(10), int(20), 1,
+            19, 0x80,
+            int(10), int(20), 21,
+            int(30), int(40), int(-30), 6,
+            14
+        ];
+        let outline = run(&code);
+        assert_eq!(outline.commands(), &[
+            PathCommand::MoveTo(10.0, 20.0),
+            PathCommand::LineTo(40.0, 20.0),
+            PathCommand::LineTo(40.0, 60.0),
+            PathCommand::LineTo(10.0, 60.0),
+            PathCommand::Close
+        ][..]);
+    }
+
+    #[test]
+    fn curve_shorthands() {
+        // hvcurveto with a final odd operand, then vvcurveto with a leading dx
+        let code = [
+            int(0), int(0), 21,
+            int(10), int(5), int(5), int(10), int(3), 31,
+            int(1), int(2), int(3), int(4), int(5), 26,
+            14
+        ];
+        let outline = run(&code);
+        assert_eq!(outline.commands()[1], PathCommand::CubicTo(10.0, 0.0, 15.0, 5.0, 18.0, 15.0));
+        assert_eq!(outline.commands()[2], PathCommand::CubicTo(19.0, 17.0, 22.0, 21.0, 22.0, 26.0));
+    }
+}
@@ -1,57 +1,874 @@
-use super::drm::Color;
+use std::io;
+use std::path::Path;
+
+use super::backend::Backend;
+use super::drm::{Color, Rect};
+use super::effects::Shadow;
 use super::gfx::GFX;
+use super::image::Image;
+use super::scale::Scale;
+use super::screenshot::{self, Capture};
+use super::frame_clock::FrameClock;
+use super::thread_pool::ThreadPool;
+use super::tiles::{self, TILE_HEIGHT};
+use super::yuv::YuvImage;
+
+/**
+ * The arrow shown until a cursor theme is loaded.
+ **/
+const DEFAULT_CURSOR: &[u8] = include_bytes!("cursors/arrow.pam");
+
+const ICON_SIZE: u32 = 16;
+
+const TITLE_HEIGHT: u32 = 25;
+
+fn window_shadow(scale: Scale) -> Shadow {
+    let (x, y) = scale.point(0, 4);
+    Shadow::new(x, y, scale.length(12), Color::new(0, 0, 0, 110))
+}
+
+/**
+ * A pointer image and the point in it that marks the pointer position.
+ **/
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    pub image: Image,
+    pub hotspot_x: i32,
+    pub hotspot_y: i32
+}
+
+impl Cursor {
+    pub fn new(image: Image, hotspot_x: i32, hotspot_y: i32) -> Cursor {
+        Cursor {
+            image,
+            hotspot_x,
+            hotspot_y
+        }
+    }
+
+    pub fn default_cursor() -> Cursor {
+        Cursor::new(Image::load(DEFAULT_CURSOR).unwrap(), 0, 0)
+    }
+}
 
 pub struct Compositor {
-    gfx: GFX,
+    /// The screens, side by side from left to right.
+    outputs: Vec<Output>,
+    scene: Scene,
+    pool: Option<ThreadPool>,
+    /// The output and window of a video shown on an overlay plane.
+    video: Option<(usize, usize)>
+}
+
+struct Output {
+    gfx: GFX<'static>,
+    scale: Scale
+}
+
+impl Output {
+    fn logical_size(&self) -> (u32, u32) {
+        self.scale.logical_size(self.gfx.width(), self.gfx.height())
+    }
+}
+
+/**
+ * Everything the compositor draws. Kept apart from the GFX so that render
+ * threads can share it.
+ **/
+struct Scene {
     windows: Vec<Window>,
+    status: Option<String>,
+    wallpaper: Option<Image>,
+    cursor: Option<Cursor>,
+    pointer: (i32, i32),
+    /// Scale and logical position of the output being drawn.
+    scale: Scale,
+    origin: (i32, i32)
+}
+
+/**
+ * Something drawn by `Compositor::render`, from bottom to top.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    /// Plain color under everything when there is no wallpaper, so that
+    /// nothing of the previous frame shows through.
+    Background,
+    Wallpaper,
+    /// The blurred background behind a window.
+    Backdrop(usize),
+    Window(usize),
+    StatusBar,
+    Cursor
 }
 
 impl Compositor {
     pub fn new() -> Compositor {
+        Compositor::with_gfx(GFX::new())
+    }
+
+    pub fn with_gfx(gfx: GFX<'static>) -> Compositor {
+        Compositor::with_outputs(vec![gfx])
+    }
+
+    /**
+     * Shows the windows across several outputs, placed side by side in
+     * the given order. Panics without any output.
+     **/
+    pub fn with_outputs(outputs: Vec<GFX<'static>>) -> Compositor {
+        assert!(!outputs.is_empty(), "a compositor needs an output");
         Compositor {
-            gfx: GFX::new(),
-            windows: Vec::new()
+            outputs: outputs.into_iter().map(|gfx| Output { gfx, scale: Scale::default() }).collect(),
+            scene: Scene {
+                windows: Vec::new(),
+                status: None,
+                wallpaper: None,
+                cursor: None,
+                pointer: (0, 0),
+                scale: Scale::default(),
+                origin: (0, 0)
+            },
+            pool: None,
+            video: None
         }
     }
 
+    /**
+     * The leftmost output, which also paces the rendering.
+     **/
+    pub fn gfx(&self) -> &GFX<'static> {
+        &self.outputs[0].gfx
+    }
+
+    pub fn gfx_mut(&mut self) -> &mut GFX<'static> {
+        &mut self.outputs[0].gfx
+    }
+
+    pub fn output_count(&self) -> usize {
+        self.outputs.len()
+    }
+
+    pub fn output(&self, output: usize) -> &GFX<'static> {
+        &self.outputs[output].gfx
+    }
+
+    pub fn output_mut(&mut self, output: usize) -> &mut GFX<'static> {
+        &mut self.outputs[output].gfx
+    }
+
+    /**
+     * Sets how many pixels of an output make up a pixel of the windows,
+     * pointer and bar. Everything is drawn at the output's resolution; its
+     * font should be loaded at its size times the scale.
+     **/
+    pub fn set_scale(&mut self, output: usize, scale: Scale) {
+        self.outputs[output].scale = scale;
+    }
+
+    pub fn scale(&self, output: usize) -> Scale {
+        self.outputs[output].scale
+    }
+
+    /**
+     * Size of all outputs together in the coordinates windows and the
+     * pointer use.
+     **/
+    pub fn logical_size(&self) -> (u32, u32) {
+        self.outputs.iter()
+            .map(Output::logical_size)
+            .fold((0, 0), |(width, height), (w, h)| (width + w, height.max(h)))
+    }
+
     pub fn add_window(&mut self) {
-        self.windows.push(Window {
+        // the overlay would cover the new window
+        if let Some((_, window)) = self.video {
+            self.hide_video(window);
+        }
+        self.scene.windows.push(Window {
             x: 20,
             y: 20,
             width: 500,
-            height: 300
+            height: 300,
+            title: String::from("Window"),
+            icon: None,
+            content: None,
+            backdrop_blur: 0.0
         });
     }
 
-    pub fn render(&mut self) {
-        for window in self.windows.iter_mut() {
-            window.render(&mut self.gfx);
+    pub fn set_title(&mut self, window: usize, title: &str) {
+        self.scene.windows[window].title = String::from(title);
+    }
+
+    /**
+     * Shows an icon left of the window's title.
+     **/
+    pub fn set_icon(&mut self, window: usize, icon: Option<Image>) {
+        self.scene.windows[window].icon = icon;
+    }
+
+    /**
+     * Blurs what is behind a window, with a Gaussian of standard deviation
+     * `sigma`, for frosted glass effects with translucent content. 0 turns
+     * it off.
+     **/
+    pub fn set_backdrop_blur(&mut self, window: usize, sigma: f32) {
+        self.scene.windows[window].backdrop_blur = sigma;
+    }
+
+    /**
+     * Sets the picture drawn behind all windows. It is scaled to cover the
+     * whole screen, cropping what doesn't fit.
+     **/
+    pub fn set_wallpaper(&mut self, wallpaper: Option<Image>) {
+        self.scene.wallpaper = wallpaper;
+    }
+
+    /**
+     * Sets the image drawn at the pointer position. `None` hides it.
+     **/
+    pub fn set_cursor(&mut self, cursor: Option<Cursor>) {
+        self.scene.cursor = cursor;
+    }
+
+    /**
+     * Moves the pointer to a point in logical coordinates.
+     **/
+    pub fn move_pointer(&mut self, x: i32, y: i32) {
+        self.scene.pointer = (x, y);
+    }
+
+    /**
+     * Shows a line of text in a bar at the bottom of the screen, e.g. for
+     * debug information. `None` hides the bar.
+     **/
+    pub fn set_status(&mut self, status: Option<String>) {
+        self.scene.status = status;
+    }
+
+    /**
+     * Sets the buffer shown inside a window. It is scaled to the window's
+     * content area, so buffers drawn at the output's scale stay sharp.
+     **/
+    pub fn attach(&mut self, window: usize, content: Image) {
+        self.hide_video(window);
+        self.scene.windows[window].content = Some(content);
+    }
+
+    /**
+     * Shows a video frame inside a window. The frame goes to an overlay
+     * plane as it is when the window is on top and on a single output whose
+     * display has one; otherwise it is converted to RGB.
+     **/
+    pub fn attach_yuv(&mut self, window: usize, frame: &YuvImage) -> io::Result<()> {
+        if window + 1 == self.scene.windows.len() {
+            let mut left = 0;
+            for (i, output) in self.outputs.iter_mut().enumerate() {
+                self.scene.scale = output.scale;
+                self.scene.origin = (left, 0);
+                left += output.logical_size().0 as i32;
+
+                let w = &self.scene.windows[window];
+                let area = w.content_area(output.scale, self.scene.output_rect(w.bounds()));
+                let screen = Rect::new(0, 0, output.gfx.width(), output.gfx.height());
+                if area.intersect(&screen) != area {
+                    continue;
+                }
+                match output.gfx.backend_mut().show_video(frame, area) {
+                    Ok(true) => {
+                        if let Some((previous, _)) = self.video.filter(|&(previous, _)| previous != i) {
+                            let _ = self.outputs[previous].gfx.backend_mut().hide_video();
+                        }
+                        self.video = Some((i, window));
+                        self.scene.windows[window].content = None;
+                        return Ok(());
+                    }
+                    Ok(false) => {}
+                    Err(_) => {
+                        let _ = output.gfx.backend_mut().hide_video();
+                    }
+                }
+                break;
+            }
+        }
+        self.attach(window, frame.to_image(frame.bounds())?);
+        Ok(())
+    }
+
+    fn hide_video(&mut self, window: usize) {
+        if let Some((output, _)) = self.video.filter(|&(_, w)| w == window) {
+            let _ = self.outputs[output].gfx.backend_mut().hide_video();
+            self.video = None;
+        }
+    }
+
+    /**
+     * Copies the last rendered frame. Outputs are numbered and put side by
+     * side from left to right; a single output keeps its scanout format.
+     **/
+    pub fn capture(&self, capture: &Capture) -> io::Result<Image> {
+        let backends: Vec<&dyn Backend> = self.outputs.iter().map(|output| output.gfx.backend()).collect();
+        screenshot::capture_outputs(&backends, capture)
+    }
+
+    /**
+     * Saves the last rendered frame to `path`, as PPM if it ends in `.ppm`
+     * and PNG otherwise.
+     **/
+    pub fn screenshot(&self, capture: &Capture, path: &Path) -> io::Result<()> {
+        screenshot::save(&self.capture(capture)?, path)
+    }
+
+    /**
+     * Renders on `threads` worker threads, each drawing a part of the
+     * screen. With a single thread, everything is drawn on the calling
+     * thread.
+     **/
+    pub fn set_render_threads(&mut self, threads: usize) {
+        self.pool = if threads > 1 { Some(ThreadPool::new(threads)) } else { None };
+    }
+
+    pub fn frame_clock(&self) -> FrameClock {
+        self.outputs[0].gfx.frame_clock().unwrap()
+    }
+
+    /**
+     * Draws and flushes every output. An output that fails to flush doesn't
+     * keep the others from being drawn; the first error is returned.
+     **/
+    pub fn render(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+        let mut left = 0;
+        for output in self.outputs.iter_mut() {
+            self.scene.scale = output.scale;
+            self.scene.origin = (left, 0);
+            left += output.logical_size().0 as i32;
+            let flushed = render_output(&mut output.gfx, self.pool.as_ref(), &self.scene);
+            result = result.and(flushed);
+        }
+        result
+    }
+}
+
+fn render_output(gfx: &mut GFX, pool: Option<&ThreadPool>, scene: &Scene) -> io::Result<()> {
+    let layers = scene.layers(gfx);
+    match pool {
+        Some(pool) => {
+            // blurring reads pixels from neighbouring tiles, so everything
+            // below a backdrop has to be finished first
+            let mut start = 0;
+            for (i, &(layer, _)) in layers.iter().enumerate() {
+                if let Layer::Backdrop(_) = layer {
+                    render_tiled(gfx, pool, scene, &layers[start..i]);
+                    scene.render_layer(gfx, layer);
+                    start = i + 1;
+                }
+            }
+            render_tiled(gfx, pool, scene, &layers[start..]);
+        }
+        None => for &(layer, _) in layers.iter() {
+            scene.render_layer(gfx, layer);
+        }
+    }
+    gfx.flush()
+}
+
+impl Scene {
+    /**
+     * Everything there is to draw, with the pixels of the screen it covers.
+     **/
+    fn layers(&self, gfx: &mut GFX) -> Vec<(Layer, Rect)> {
+        let mut layers = Vec::new();
+        let screen = Rect::new(0, 0, gfx.width(), gfx.height());
+        if self.wallpaper.is_some() {
+            layers.push((Layer::Wallpaper, screen));
+        } else {
+            layers.push((Layer::Background, screen));
+        }
+        for (i, window) in self.windows.iter().enumerate() {
+            let bounds = self.output_rect(window.bounds());
+            if window.backdrop_blur > 0.0 {
+                layers.push((Layer::Backdrop(i), bounds));
+            }
+            layers.push((Layer::Window(i), bounds.union(&window_shadow(self.scale).bounds(bounds))));
+        }
+        if let Some(ref status) = self.status {
+            layers.push((Layer::StatusBar, status_bar_bounds(gfx, self.scale, status)));
+        }
+        if let Some(ref cursor) = self.cursor {
+            layers.push((Layer::Cursor, self.cursor_bounds(cursor)));
+        }
+        layers
+    }
+
+    fn render_layer(&self, gfx: &mut GFX, layer: Layer) {
+        match layer {
+            Layer::Background => {
+                let (width, height) = (gfx.width(), gfx.height());
+                gfx.rectangle(0, 0, width, height, &Color::new(0, 0, 0, 255));
+            }
+            Layer::Wallpaper => render_wallpaper(gfx, self.wallpaper.as_ref().unwrap()),
+            Layer::Backdrop(i) => {
+                let window = &self.windows[i];
+                gfx.gaussian_blur(self.output_rect(window.bounds()), window.backdrop_blur * self.scale.factor());
+            }
+            Layer::Window(i) => {
+                let window = &self.windows[i];
+                window.render(gfx, self.scale, self.output_rect(window.bounds()));
+            }
+            Layer::StatusBar => render_status_bar(gfx, self.scale, self.status.as_ref().unwrap()),
+            Layer::Cursor => {
+                let cursor = self.cursor.as_ref().unwrap();
+                gfx.blit(&cursor.image, cursor.image.bounds(), self.cursor_bounds(cursor));
+            }
+        }
+    }
+
+    /**
+     * The physical pixels of a logical rectangle on the output being drawn.
+     **/
+    fn output_rect(&self, rect: Rect) -> Rect {
+        self.scale.rect(Rect::new(rect.x - self.origin.0, rect.y - self.origin.1, rect.width, rect.height))
+    }
+
+    fn cursor_bounds(&self, cursor: &Cursor) -> Rect {
+        let (x, y) = self.scale.point(self.pointer.0 - self.origin.0, self.pointer.1 - self.origin.1);
+        let (hotspot_x, hotspot_y) = self.scale.point(cursor.hotspot_x, cursor.hotspot_y);
+        let (width, height) = (self.scale.length(cursor.image.width()), self.scale.length(cursor.image.height()));
+        Rect::new(x - hotspot_x, y - hotspot_y, width, height)
+    }
+}
+
+/**
+ * Splits the screen into tiles and draws each of them on the thread pool,
+ * with just the layers that cover it. The result is identical to drawing
+ * everything on one thread.
+ **/
+fn render_tiled(gfx: &mut GFX, pool: &ThreadPool, scene: &Scene, layers: &[(Layer, Rect)]) {
+    let (font, dither) = (gfx.shared_font(), gfx.dither());
+    let tiles = tiles::split(gfx.backend_mut(), TILE_HEIGHT);
+    let work: Vec<_> = tiles.into_iter().map(|tile| {
+        let area = tile.area();
+        let list: Vec<Layer> = layers.iter().filter(|l| l.1.overlaps(&area)).map(|l| l.0).collect();
+        (tile, list)
+    }).filter(|t| !t.1.is_empty()).collect();
+
+    pool.for_each(work, |(tile, list)| {
+        let mut gfx = GFX::with_font(Box::new(tile), font.clone());
+        gfx.set_dither(dither);
+        for &layer in list.iter() {
+            scene.render_layer(&mut gfx, layer);
         }
+    });
+
+    let screen = Rect::new(0, 0, gfx.width(), gfx.height());
+    for layer in layers.iter() {
+        gfx.backend_mut().damage(layer.1.intersect(&screen));
     }
 }
 
+fn render_wallpaper(gfx: &mut GFX, wallpaper: &Image) {
+    let (width, height) = (gfx.width(), gfx.height());
+    let (w, h) = (wallpaper.width() as u64, wallpaper.height() as u64);
+
+    // crop the wallpaper to the aspect ratio of the screen
+    let src = if w * height as u64 > h * width as u64 {
+        let cropped = (h * width as u64 / height as u64) as u32;
+        Rect::new(((w as u32 - cropped) / 2) as i32, 0, cropped, h as u32)
+    } else {
+        let cropped = (w * height as u64 / width as u64) as u32;
+        Rect::new(0, ((h as u32 - cropped) / 2) as i32, w as u32, cropped)
+    };
+    gfx.blit(wallpaper, src, Rect::new(0, 0, width, height));
+}
+
+const STATUS_PADDING: u32 = 4;
+
+fn status_bar_bounds(gfx: &mut GFX, scale: Scale, status: &str) -> Rect {
+    let height = gfx.measure_text(status).height + 2 * scale.length(STATUS_PADDING);
+    Rect::new(0, gfx.height() as i32 - height as i32, gfx.width(), height)
+}
+
+fn render_status_bar(gfx: &mut GFX, scale: Scale, status: &str) {
+    let bounds = status_bar_bounds(gfx, scale, status);
+    let padding = scale.length(STATUS_PADDING) as i32;
+    gfx.rectangle(bounds.x, bounds.y, bounds.width, bounds.height, &Color::new(40, 40, 40, 255));
+    gfx.text(padding, bounds.y + padding, status, &Color::new(255, 255, 255, 255));
+}
+
 struct Window {
-    x: u32,
-    y: u32,
+    x: i32,
+    y: i32,
     width: u32,
-    height: u32
+    height: u32,
+    title: String,
+    icon: Option<Image>,
+    content: Option<Image>,
+    backdrop_blur: f32
 }
 
 impl Window {
-    pub fn render(&mut self, gfx: &mut GFX) {
-        let top_width = 25;
-        let border_width = 1;
+    fn bounds(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+
+    /**
+     * The part of `bounds` inside the title bar and border.
+     **/
+    fn content_area(&self, scale: Scale, bounds: Rect) -> Rect {
+        let (top_width, border_width) = (scale.length(TITLE_HEIGHT), scale.length(1));
+        Rect::new(
+            bounds.x + border_width as i32,
+            bounds.y + top_width as i32,
+            bounds.width - 2 * border_width,
+            bounds.height - top_width - border_width
+        )
+    }
+
+    /**
+     * Draws the window into `bounds`, its place on the output, at the
+     * output's resolution.
+     **/
+    pub fn render(&self, gfx: &mut GFX, scale: Scale, bounds: Rect) {
+        let top_width = scale.length(TITLE_HEIGHT);
+        let border_width = scale.length(1);
+        let icon_size = scale.length(ICON_SIZE);
         let c = Color::new(255, 255, 255, 255);
-        let x = self.x;
-        let y = self.y;
-        let width = self.width;
-        let height = self.height;
-    
-        gfx.rectangle(x, y, width, 25, &c);
-        gfx.rectangle(x, y + top_width, border_width, height - top_width, &c);
-        gfx.rectangle(x + width - border_width, y + top_width, border_width, height - top_width, &c);
-        gfx.rectangle(x, y + height - border_width, width, border_width, &c);
-        // TODO: render window content
+        let x = bounds.x;
+        let y = bounds.y;
+        let width = bounds.width;
+        let height = bounds.height;
+
+        gfx.box_shadow(bounds, &window_shadow(scale));
+        gfx.push_clip(bounds);
+        gfx.rectangle(x, y, width, top_width, &c);
+        let title_height = gfx.measure_text(&self.title).height as i32;
+        gfx.push_clip(Rect::new(x, y, width, top_width));
+        let mut title_x = x + scale.length(8) as i32;
+        if let Some(ref icon) = self.icon {
+            let icon_y = y + (top_width as i32 - icon_size as i32) / 2;
+            gfx.blit(icon, icon.bounds(), Rect::new(title_x, icon_y, icon_size, icon_size));
+            title_x += (icon_size + scale.length(4)) as i32;
+        }
+        gfx.text(title_x, y + (top_width as i32 - title_height) / 2, &self.title, &Color::new(0, 0, 0, 255));
+        gfx.pop_clip();
+        gfx.rectangle(x, y + top_width as i32, border_width, height - top_width, &c);
+        gfx.rectangle(x + (width - border_width) as i32, y + top_width as i32, border_width, height - top_width, &c);
+        gfx.rectangle(x, y + (height - border_width) as i32, width, border_width, &c);
+
+        if let Some(ref content) = self.content {
+            gfx.blit(content, content.bounds(), self.content_area(scale, bounds));
+        }
+
+        gfx.pop_clip();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use super::super::backend::MemoryBackend;
+    use super::super::format::Format;
+    use super::super::yuv::YuvFormat;
+
+    /**
+     * A display with an overlay plane that remembers where it was put.
+     **/
+    struct OverlayBackend {
+        memory: MemoryBackend,
+        video: Rc<Cell<Option<Rect>>>
+    }
+
+    impl Backend for OverlayBackend {
+        fn width(&self) -> u32 {
+            self.memory.width()
+        }
+
+        fn height(&self) -> u32 {
+            self.memory.height()
+        }
+
+        fn format(&self) -> Format {
+            self.memory.format()
+        }
+
+        fn stride(&self) -> u32 {
+            self.memory.stride()
+        }
+
+        fn pixels(&self) -> &[u8] {
+            self.memory.pixels()
+        }
+
+        fn pixels_mut(&mut self) -> &mut [u8] {
+            self.memory.pixels_mut()
+        }
+
+        fn show_video(&mut self, _frame: &YuvImage, dst: Rect) -> io::Result<bool> {
+            self.video.set(Some(dst));
+            Ok(true)
+        }
+
+        fn hide_video(&mut self) -> io::Result<()> {
+            self.video.set(None);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn repaints_the_background() {
+        let mut c = Compositor::with_gfx(GFX::headless(640, 480, Format::Xrgb8888).unwrap());
+        c.add_window();
+        // whatever was on the screen before
+        c.gfx_mut().clear();
+        c.render().unwrap();
+        let first = c.capture(&Capture::Screen).unwrap();
+        // the translucent shadow must not build up over the last frame
+        c.render().unwrap();
+        assert_eq!(c.capture(&Capture::Screen).unwrap(), first);
+    }
+
+    #[test]
+    fn moves_the_cursor_without_a_trail() {
+        let mut c = Compositor::with_gfx(GFX::headless(640, 480, Format::Xrgb8888).unwrap());
+        c.set_cursor(Some(Cursor::default_cursor()));
+        c.move_pointer(600, 400);
+        c.render().unwrap();
+        let black = Some(Color::new(0, 0, 0, 255));
+        assert_ne!(c.gfx().get(601, 402), black);
+
+        c.move_pointer(100, 100);
+        c.render().unwrap();
+        assert_eq!(c.gfx().get(601, 402), black);
+        assert_ne!(c.gfx().get(101, 102), black);
+    }
+
+    #[test]
+    fn renders_window_decorations() {
+        let mut c = Compositor::with_gfx(GFX::headless(640, 480, Format::Xrgb8888).unwrap());
+        c.add_window();
+        c.render().unwrap();
+
+        let white = Color::new(255, 255, 255, 255);
+        let black = Color::new(0, 0, 0, 255);
+        assert_eq!(c.gfx().get(20, 20), Some(white));
+        assert_eq!(c.gfx().get(519, 319), Some(white));
+        assert_eq!(c.gfx().get(100, 100), Some(black));
+        assert_eq!(c.gfx().get(520, 100), Some(black));
+    }
+
+    #[test]
+    fn renders_at_output_scale() {
+        let mut c = Compositor::with_gfx(GFX::headless(1280, 960, Format::Xrgb8888).unwrap());
+        c.set_scale(0, Scale::new(2.0).unwrap());
+        assert_eq!(c.logical_size(), (640, 480));
+        c.add_window();
+        let mut content = Image::new(4, 4, Format::Xrgb8888).unwrap();
+        content.fill(&Color::new(0, 128, 0, 255));
+        c.attach(0, content);
+        c.set_cursor(Some(Cursor::default_cursor()));
+        c.move_pointer(600, 400);
+        c.render().unwrap();
+
+        let white = Some(Color::new(255, 255, 255, 255));
+        let green = Some(Color::new(0, 128, 0, 255));
+        assert_eq!(c.gfx().get(40, 40), white);
+        assert_eq!(c.gfx().get(1039, 639), white);
+        // two pixel borders below a fifty pixel title bar
+        assert_eq!(c.gfx().get(41, 89), white);
+        assert_eq!(c.gfx().get(41, 90), white);
+        assert_eq!(c.gfx().get(42, 90), green);
+        assert_eq!(c.gfx().get(1037, 637), green);
+        assert_eq!(c.gfx().get(1038, 637), white);
+        // the arrow is twice as large
+        assert_eq!(c.gfx().get(1200, 800), Some(Color::new(0, 0, 0, 255)));
+        assert_eq!(c.gfx().get(1205, 813), white);
+    }
+
+    #[test]
+    fn spans_outputs() {
+        let outputs = vec![GFX::headless(320, 480, Format::Xrgb8888).unwrap(), GFX::headless(320, 480, Format::Xrgb8888).unwrap()];
+        let mut c = Compositor::with_outputs(outputs);
+        assert_eq!(c.logical_size(), (640, 480));
+        c.add_window();
+        c.render().unwrap();
+
+        let white = Some(Color::new(255, 255, 255, 255));
+        assert_eq!(c.output(0).get(20, 20), white);
+        assert_eq!(c.output(1).get(519 - 320, 319), white);
+        assert_eq!(c.output(1).get(100, 100), Some(Color::new(0, 0, 0, 255)));
+
+        let screen = c.capture(&Capture::Screen).unwrap();
+        assert_eq!((screen.width(), screen.height()), (640, 480));
+        assert_eq!(screen.get(519, 319), Color::new(255, 255, 255, 255));
+        let second = c.capture(&Capture::Output(1)).unwrap();
+        assert_eq!(second.get(519 - 320, 319), Color::new(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn scales_each_output() {
+        let outputs = vec![GFX::headless(320, 480, Format::Xrgb8888).unwrap(), GFX::headless(640, 960, Format::Xrgb8888).unwrap()];
+        let mut c = Compositor::with_outputs(outputs);
+        c.set_scale(1, Scale::new(2.0).unwrap());
+        assert_eq!(c.logical_size(), (640, 480));
+        c.add_window();
+        c.render().unwrap();
+
+        // the window's bottom right corner lands on the second output at twice the size
+        let white = Some(Color::new(255, 255, 255, 255));
+        assert_eq!(c.output(0).get(20, 20), white);
+        assert_eq!(c.output(1).get((519 - 320) * 2, 319 * 2), white);
+        assert_eq!(c.output(1).get((519 - 320) * 2 + 1, 319 * 2 + 1), white);
+        assert_eq!(c.output(1).get((519 - 320) * 2 + 2, 319 * 2), Some(Color::new(0, 0, 0, 255)));
+    }
+
+    #[test]
+    fn shows_video_on_overlay() {
+        let video = Rc::new(Cell::new(None));
+        let memory = MemoryBackend::new(1280, 960, Format::Xrgb8888).unwrap();
+        let mut c = Compositor::with_gfx(GFX::with_backend(Box::new(OverlayBackend { memory, video: video.clone() })));
+        c.set_scale(0, Scale::new(2.0).unwrap());
+        c.add_window();
+        let frame = YuvImage::new(16, 16, YuvFormat::Nv12);
+
+        // the content area of the window, in pixels of the output
+        c.attach_yuv(0, &frame).unwrap();
+        assert_eq!(video.get(), Some(Rect::new(42, 90, 996, 548)));
+        assert!(c.scene.windows[0].content.is_none());
+
+        // a window on top would be hidden by the overlay
+        c.add_window();
+        assert_eq!(video.get(), None);
+        c.attach_yuv(0, &frame).unwrap();
+        assert_eq!(video.get(), None);
+        assert!(c.scene.windows[0].content.is_some());
+    }
+
+    #[test]
+    fn renders_title_and_status() {
+        let mut c = Compositor::with_gfx(GFX::headless(640, 480, Format::Xrgb8888).unwrap());
+        c.add_window();
+        c.set_title(0, "I");
+        c.set_status(Some(String::from("I")));
+        c.render().unwrap();
+
+        // the 'I' glyph has a horizontal bar at its top: .####...
+        let black = Some(Color::new(0, 0, 0, 255));
+        assert_eq!(c.gfx().get(20 + 8 + 1, 20 + 8), black);
+        assert_eq!(c.gfx().get(20 + 8, 20 + 8), Some(Color::new(255, 255, 255, 255)));
+        assert_eq!(c.gfx().get(4 + 1, 480 - 12), Some(Color::new(255, 255, 255, 255)));
+        assert_eq!(c.gfx().get(4, 480 - 12), Some(Color::new(40, 40, 40, 255)));
+    }
+
+    #[test]
+    fn renders_wallpaper_icon_and_cursor() {
+        let mut c = Compositor::with_gfx(GFX::headless(640, 480, Format::Xrgb8888).unwrap());
+        c.add_window();
+
+        // a wide wallpaper: the outer columns get cropped
+        let mut wallpaper = Image::new(8, 3, Format::Xrgb8888).unwrap();
+        wallpaper.fill(&Color::new(0, 0, 200, 255));
+        for y in 0..3 {
+            wallpaper.set(0, y, &Color::new(255, 0, 0, 255));
+        }
+        c.set_wallpaper(Some(wallpaper));
+
+        let mut icon = Image::new(2, 2, Format::Xrgb8888).unwrap();
+        icon.fill(&Color::new(0, 200, 0, 255));
+        c.set_icon(0, Some(icon));
+        c.set_cursor(Some(Cursor::default_cursor()));
+        c.move_pointer(600, 400);
+        c.render().unwrap();
+
+        let blue = Some(Color::new(0, 0, 200, 255));
+        assert_eq!(c.gfx().get(0, 0), blue);
+        assert_eq!(c.gfx().get(639, 479), blue);
+        assert_eq!(c.gfx().get(100, 100), blue);
+        assert_eq!(c.gfx().get(20 + 8, 20 + 4), Some(Color::new(0, 200, 0, 255)));
+        assert_eq!(c.gfx().get(20 + 8 + 15, 20 + 4 + 15), Some(Color::new(0, 200, 0, 255)));
+        // the arrow's outline and fill, and a transparent corner
+        assert_eq!(c.gfx().get(600, 400), Some(Color::new(0, 0, 0, 255)));
+        assert_eq!(c.gfx().get(601, 402), Some(Color::new(255, 255, 255, 255)));
+        assert_eq!(c.gfx().get(610, 400), blue);
+    }
+
+    #[test]
+    fn tiled_rendering_matches_serial() {
+        let render = |threads: usize| {
+            let mut c = Compositor::with_gfx(GFX::headless(640, 480, Format::Xrgb8888).unwrap());
+            c.set_render_threads(threads);
+            let mut wallpaper = Image::new(7, 5, Format::Xrgb8888).unwrap();
+            for y in 0..5 {
+                for x in 0..7 {
+                    wallpaper.set(x, y, &Color::new(x as u8 * 30, y as u8 * 50, 90, 255));
+                }
+            }
+            c.set_wallpaper(Some(wallpaper));
+            c.add_window();
+            c.add_window();
+            c.scene.windows[1].x = 200;
+            c.scene.windows[1].y = 150;
+            c.set_title(1, "Tiles");
+            let mut content = Image::new(3, 3, Format::Argb8888).unwrap();
+            content.fill(&Color::new(0, 100, 0, 128).premultiply());
+            c.attach(1, content);
+            c.set_backdrop_blur(1, 4.0);
+            c.set_status(Some(String::from("status")));
+            c.set_cursor(Some(Cursor::default_cursor()));
+            // on the border between two tiles
+            c.move_pointer(100, 2 * TILE_HEIGHT as i32 - 5);
+            c.render().unwrap();
+            c.gfx().backend().pixels().to_vec()
+        };
+        assert!(render(1) == render(4));
+    }
+
+    #[test]
+    fn matches_golden_frame() {
+        let mut c = Compositor::with_gfx(GFX::headless(640, 480, Format::Xrgb8888).unwrap());
+        c.add_window();
+        c.set_title(0, "Terminal");
+        c.set_status(Some(String::from("12:00")));
+        c.set_cursor(Some(Cursor::default_cursor()));
+        c.move_pointer(300, 200);
+        c.render().unwrap();
+        screenshot::assert_golden(&c.capture(&Capture::Screen).unwrap(), "compositor");
+    }
+
+    #[test]
+    fn matches_golden_effects_frame() {
+        let mut c = Compositor::with_gfx(GFX::headless(640, 480, Format::Xrgb8888).unwrap());
+        let mut wallpaper = Image::new(64, 48, Format::Xrgb8888).unwrap();
+        for y in 0..48 {
+            for x in 0..64 {
+                let stripe = if (x / 8 + y / 8) % 2 == 0 { 255 } else { 120 };
+                wallpaper.set(x, y, &Color::new(stripe, x as u8 * 4, 160, 255));
+            }
+        }
+        c.set_wallpaper(Some(wallpaper));
+        c.add_window();
+        c.set_title(0, "Frosted");
+        let mut content = Image::new(1, 1, Format::Argb8888).unwrap();
+        content.set(0, 0, &Color::new(255, 255, 255, 90).premultiply());
+        c.attach(0, content);
+        c.set_backdrop_blur(0, 6.0);
+        c.render().unwrap();
+        screenshot::assert_golden(&c.capture(&Capture::Screen).unwrap(), "compositor_effects");
+    }
+
+    #[test]
+    fn renders_window_content() {
+        let mut c = Compositor::with_gfx(GFX::headless(640, 480, Format::Xrgb8888).unwrap());
+        c.add_window();
+        let mut content = Image::new(4, 4, Format::Xrgb8888).unwrap();
+        content.fill(&Color::new(0, 128, 0, 255));
+        c.attach(0, content);
+        c.render().unwrap();
 
+        assert_eq!(c.gfx().get(21, 45), Some(Color::new(0, 128, 0, 255)));
+        assert_eq!(c.gfx().get(518, 318), Some(Color::new(0, 128, 0, 255)));
+        assert_eq!(c.gfx().get(519, 318), Some(Color::new(255, 255, 255, 255)));
     }
 }
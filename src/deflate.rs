@@ -0,0 +1,179 @@
+use super::inflate::{adler32, DISTANCE_BASE, DISTANCE_EXTRA, LENGTH_BASE, LENGTH_EXTRA};
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+
+/**
+ * How many earlier positions with the same hash are tried per match.
+ **/
+const MAX_CHAIN: usize = 32;
+
+/**
+ * Writes bits least significant first, as DEFLATE expects.
+ **/
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u64,
+    count: u32
+}
+
+impl BitWriter {
+    fn bits(&mut self, value: u32, count: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /**
+     * Writes a Huffman code, which is stored most significant bit first.
+     **/
+    fn code(&mut self, code: u32, len: u32) {
+        let reversed = code.reverse_bits() >> (32 - len);
+        self.bits(reversed, len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+/**
+ * Writes a literal or length symbol with the fixed Huffman code.
+ **/
+fn literal(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.code(0x30 + symbol, 8),
+        144..=255 => writer.code(0x190 + symbol - 144, 9),
+        256..=279 => writer.code(symbol - 256, 7),
+        _ => writer.code(0xc0 + symbol - 280, 8)
+    }
+}
+
+fn length(writer: &mut BitWriter, len: usize) {
+    let code = LENGTH_BASE.iter().rposition(|&base| base as usize <= len).unwrap();
+    literal(writer, 257 + code as u32);
+    writer.bits((len - LENGTH_BASE[code] as usize) as u32, LENGTH_EXTRA[code] as u32);
+}
+
+fn distance(writer: &mut BitWriter, distance: usize) {
+    let code = DISTANCE_BASE.iter().rposition(|&base| base as usize <= distance).unwrap();
+    writer.code(code as u32, 5);
+    writer.bits((distance - DISTANCE_BASE[code] as usize) as u32, DISTANCE_EXTRA[code] as u32);
+}
+
+fn hash(data: &[u8], i: usize) -> usize {
+    let v = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+    (v.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+/**
+ * Adds position `i` to the hash chains.
+ **/
+fn insert(data: &[u8], head: &mut [usize], previous: &mut [usize], i: usize) {
+    if i + MIN_MATCH <= data.len() {
+        let h = hash(data, i);
+        previous[i % WINDOW_SIZE] = head[h];
+        head[h] = i;
+    }
+}
+
+/**
+ * Compresses `data` into a single DEFLATE block with the fixed Huffman
+ * code, finding repeats with hash chains.
+ **/
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter { out: Vec::with_capacity(data.len() / 2), buffer: 0, count: 0 };
+    // final block, fixed codes
+    writer.bits(1, 1);
+    writer.bits(1, 2);
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut previous = vec![usize::MAX; WINDOW_SIZE];
+
+    let mut i = 0;
+    while i < data.len() {
+        let (mut best_len, mut best_distance) = (0, 0);
+        if i + MIN_MATCH <= data.len() {
+            let mut candidate = head[hash(data, i)];
+            let max_len = MAX_MATCH.min(data.len() - i);
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || i - candidate > WINDOW_SIZE {
+                    break;
+                }
+                let len = data[candidate..].iter().zip(&data[i..i + max_len]).take_while(|(a, b)| a == b).count();
+                if len > best_len {
+                    best_len = len;
+                    best_distance = i - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                let next = previous[candidate % WINDOW_SIZE];
+                if next >= candidate {
+                    break;
+                }
+                candidate = next;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            length(&mut writer, best_len);
+            distance(&mut writer, best_distance);
+            for k in i..i + best_len {
+                insert(data, &mut head, &mut previous, k);
+            }
+            i += best_len;
+        } else {
+            literal(&mut writer, data[i] as u32);
+            insert(data, &mut head, &mut previous, i);
+            i += 1;
+        }
+    }
+    literal(&mut writer, 256);
+    writer.finish()
+}
+
+/**
+ * Wraps compressed `data` in a zlib stream.
+ **/
+pub fn zlib_compress(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x9c];
+    out.extend_from_slice(&deflate(data));
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::inflate::zlib_decompress;
+
+    #[test]
+    fn roundtrips() {
+        let inputs: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            b"a".to_vec(),
+            b"hello hello hello hello".to_vec(),
+            (0..100000u64).map(|i| (i * i / 7 % 251) as u8).collect(),
+            vec![0; 70000]
+        ];
+        for input in inputs.iter() {
+            assert_eq!(&zlib_decompress(&zlib_compress(input), usize::MAX).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn compresses_repeats() {
+        let data = vec![42; 10000];
+        assert!(zlib_compress(&data).len() < 100);
+    }
+}
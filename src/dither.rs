@@ -0,0 +1,272 @@
+use std::io;
+
+use super::backend::{Backend, MemoryBackend};
+use super::drm::{Color, Rect};
+use super::format::Format;
+use super::frame_clock::FrameClock;
+use super::input::InputEvent;
+use super::yuv::YuvImage;
+
+/**
+ * How colors are rounded to outputs with fewer bits per channel, to hide
+ * the bands that appear in smooth gradients.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dither {
+    /// Plain rounding down.
+    None,
+    /// A fixed 8 x 8 Bayer pattern. Every pixel is rounded on its own, so
+    /// the result doesn't depend on what is drawn around it.
+    Ordered,
+    /// Floyd-Steinberg: the rounding error of each pixel is spread over its
+    /// neighbours to the right and below. Smoother, but not stable when
+    /// only parts of the screen change.
+    ErrorDiffusion
+}
+
+impl Dither {
+    pub fn parse(s: &str) -> Option<Dither> {
+        match s {
+            "none" => Some(Dither::None),
+            "ordered" | "bayer" => Some(Dither::Ordered),
+            "diffusion" | "floyd-steinberg" => Some(Dither::ErrorDiffusion),
+            _ => None
+        }
+    }
+}
+
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21]
+];
+
+/**
+ * The ordered dithering threshold of pixel `(x, y)`, in 256ths.
+ **/
+pub fn threshold(x: i32, y: i32) -> u32 {
+    BAYER[(y & 7) as usize][(x & 7) as usize] as u32 * 4 + 2
+}
+
+/**
+ * Reduces `value`, from 0 to 255 with 8 fraction bits, to `bits` bits. It
+ * is rounded up when the remainder exceeds `threshold` 256ths.
+ **/
+pub fn quantize(value: u32, bits: u32, threshold: u32) -> u32 {
+    let levels = (1 << bits) - 1;
+    ((value * levels / 255 + 256 - threshold) >> 8).min(levels)
+}
+
+/**
+ * Widens a `bits` bit channel value to 8 bits the way `Format::unpack` does,
+ * so that packing it gives back the same value.
+ **/
+pub fn expand(value: u32, bits: u32) -> u8 {
+    if bits >= 8 {
+        return value as u8;
+    }
+    (value << (8 - bits) | value >> (2 * bits - 8)) as u8
+}
+
+/**
+ * Dithers a color given with 8 fraction bits per channel to what `format`
+ * can hold, with the ordered pattern at `(x, y)`.
+ **/
+pub fn ordered(c: [u32; 4], format: Format, x: i32, y: i32) -> Color {
+    let t = threshold(x, y);
+    let bits = format.channel_bits();
+    let a = quantize(c[3], 8, t) as u8;
+    // premultiplied channels must not end up above alpha
+    let channel = |i: usize| expand(quantize(c[i], bits[i], t), bits[i]).min(a);
+    Color::new(channel(0), channel(1), channel(2), a)
+}
+
+/**
+ * Copies the `rect` part of an Xrgb8888 buffer into a buffer of the same
+ * size in `format`. With error diffusion, errors don't cross the edges of
+ * `rect`.
+ **/
+pub fn convert(dst: &mut [u8], dst_stride: usize, format: Format, src: &[u8], src_stride: usize, rect: Rect, dither: Dither) {
+    let bpp = format.bytes_per_pixel() as usize;
+    let bits = format.channel_bits();
+    let (w, h) = (rect.width as usize, rect.height as usize);
+    // errors of the current and the next row, one pixel of margin each side
+    let mut errors = vec![[0i32; 3]; 2 * (w + 2)];
+
+    for y in 0..h {
+        let sy = rect.y as usize + y;
+        let (current, next) = errors.split_at_mut(w + 2);
+        for e in next.iter_mut() {
+            *e = [0; 3];
+        }
+        for x in 0..w {
+            let sx = rect.x as usize + x;
+            let c = Format::Xrgb8888.read(&src[sy * src_stride + sx * 4..]);
+            let channels = [c.r as i32, c.g as i32, c.b as i32];
+            let mut out = [0u8; 3];
+            for i in 0..3 {
+                let value = channels[i] << 8;
+                out[i] = match dither {
+                    Dither::None => expand(quantize(value as u32, bits[i], 256), bits[i]),
+                    Dither::Ordered => expand(quantize(value as u32, bits[i], threshold(sx as i32, sy as i32)), bits[i]),
+                    Dither::ErrorDiffusion => {
+                        let value = (value + current[x + 1][i]).clamp(0, 255 << 8);
+                        let q = expand(quantize(value as u32, bits[i], 128), bits[i]);
+                        let error = value - ((q as i32) << 8);
+                        current[x + 2][i] += error * 7 / 16;
+                        next[x][i] += error * 3 / 16;
+                        next[x + 1][i] += error * 5 / 16;
+                        next[x + 2][i] += error / 16;
+                        q
+                    }
+                };
+            }
+            let pixel = &mut dst[sy * dst_stride + sx * bpp..];
+            format.write(pixel, &Color::new(out[0], out[1], out[2], 255));
+        }
+        let (current, next) = errors.split_at_mut(w + 2);
+        current.copy_from_slice(next);
+    }
+}
+
+/**
+ * Draws in 8 bits per channel and dithers the damaged parts down to the
+ * output's format on every flush, for outputs with fewer bits such as
+ * Rgb565 panels.
+ **/
+pub struct DitheredBackend {
+    inner: Box<dyn Backend>,
+    dither: Dither,
+    buffer: MemoryBackend,
+    damage: Vec<Rect>
+}
+
+impl DitheredBackend {
+    pub fn new(inner: Box<dyn Backend>, dither: Dither) -> io::Result<DitheredBackend> {
+        Ok(DitheredBackend {
+            buffer: MemoryBackend::new(inner.width(), inner.height(), Format::Xrgb8888)?,
+            inner,
+            dither,
+            damage: Vec::new()
+        })
+    }
+}
+
+impl Backend for DitheredBackend {
+    fn width(&self) -> u32 {
+        self.buffer.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.buffer.height()
+    }
+
+    fn format(&self) -> Format {
+        self.buffer.format()
+    }
+
+    fn stride(&self) -> u32 {
+        self.buffer.stride()
+    }
+
+    fn pixels(&self) -> &[u8] {
+        self.buffer.pixels()
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        self.buffer.pixels_mut()
+    }
+
+    fn damage(&mut self, rect: Rect) {
+        let mut rect = rect.intersect(&Rect::new(0, 0, self.width(), self.height()));
+        if rect.is_empty() {
+            return;
+        }
+        // overlapping rects would be dithered twice
+        while let Some(i) = self.damage.iter().position(|r| r.overlaps(&rect)) {
+            rect = rect.union(&self.damage.swap_remove(i));
+        }
+        self.damage.push(rect);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let (format, stride) = (self.inner.format(), self.inner.stride() as usize);
+        let src_stride = self.buffer.stride() as usize;
+        for rect in self.damage.drain(..) {
+            convert(self.inner.pixels_mut(), stride, format, self.buffer.pixels(), src_stride, rect, self.dither);
+            self.inner.damage(rect);
+        }
+        self.inner.flush()
+    }
+
+    fn size_mm(&self) -> Option<(u32, u32)> {
+        self.inner.size_mm()
+    }
+
+    fn show_video(&mut self, frame: &YuvImage, dst: Rect) -> io::Result<bool> {
+        self.inner.show_video(frame, dst)
+    }
+
+    fn hide_video(&mut self) -> io::Result<()> {
+        self.inner.hide_video()
+    }
+
+    fn frame_clock(&self) -> io::Result<FrameClock> {
+        self.inner.frame_clock()
+    }
+
+    fn input_events(&mut self) -> Vec<InputEvent> {
+        self.inner.input_events()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * The average red of a flushed 64 x 8 area of 8 bit red `v`.
+     **/
+    fn average(v: u8, dither: Dither) -> f32 {
+        let mut backend = DitheredBackend::new(Box::new(MemoryBackend::new(64, 8, Format::Rgb565).unwrap()), dither).unwrap();
+        for pixel in backend.pixels_mut().chunks_mut(4) {
+            Format::Xrgb8888.write(pixel, &Color::new(v, 0, 0, 255));
+        }
+        backend.damage(Rect::new(0, 0, 64, 8));
+        backend.flush().unwrap();
+        let pixels = backend.inner.pixels();
+        let sum: u32 = pixels.chunks(2).map(|p| Format::Rgb565.read(p).r as u32).sum();
+        sum as f32 / (64 * 8) as f32
+    }
+
+    #[test]
+    fn quantizes_channels() {
+        assert_eq!(quantize(0, 5, 2), 0);
+        assert_eq!(quantize(255 << 8, 5, 2), 31);
+        assert_eq!(quantize(255 << 8, 5, 254), 31);
+        // 8 bits pass through unchanged
+        assert_eq!(quantize(77 << 8, 8, 2), 77);
+        assert_eq!(quantize(77 << 8, 8, 254), 77);
+        for q in 0..32 {
+            assert_eq!(Format::Rgb565.pack(&Color::new(expand(q, 5), 0, 0, 255)) >> 11, q);
+        }
+        assert_eq!(Dither::parse("bayer"), Some(Dither::Ordered));
+    }
+
+    #[test]
+    fn dithering_keeps_average() {
+        // 100 lies between the 5 bit levels 99 and 107
+        assert_eq!(average(100, Dither::None), 99.0);
+        for &dither in [Dither::Ordered, Dither::ErrorDiffusion].iter() {
+            let avg = average(100, dither);
+            assert!((avg - 100.0).abs() < 0.5, "{:?} {}", dither, avg);
+            assert_eq!(average(0, dither), 0.0);
+            assert_eq!(average(255, dither), 255.0);
+        }
+    }
+}
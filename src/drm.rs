@@ -1,15 +1,34 @@
 extern crate libc;
 extern crate memmap;
 
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
-use std::os::unix::io::AsRawFd;
+use std::io;
+use std::mem;
+use std::os::raw::c_ulong;
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use self::libc::ioctl;
+use super::atomic::{find_planes, find_property, set_client_cap, AtomicRequest, Blob};
 use super::drm_const::*;
 use super::ffi::*;
+use super::backend::Backend;
+use super::format::Format;
+use super::frame_clock::{poll_readable, read_events, FrameClock};
+use super::overlay::Overlay;
+use super::transform::OutputTransform;
+use super::yuv::YuvImage;
 
 use self::memmap::{MmapMut, MmapOptions};
 
+/**
+ * Upper bound for the damage rectangles tracked per framebuffer. When it is
+ * exceeded, the damage is collapsed into its bounding box.
+ **/
+const MAX_DAMAGE_RECTS: usize = 16;
+
 pub trait Pointer {
     fn as_ptr(&mut self) -> *mut Self {
         self as *mut Self
@@ -23,16 +42,270 @@ impl Pointer for drm_mode_fb_cmd {}
 impl Pointer for drm_mode_map_dumb {}
 impl Pointer for drm_mode_get_encoder {}
 impl Pointer for drm_mode_crtc {}
+impl Pointer for drm_mode_fb_cmd2 {}
+impl Pointer for drm_prime_handle {}
+impl Pointer for drm_gem_close {}
+impl Pointer for drm_version {}
+impl Pointer for drm_mode_fb_dirty_cmd {}
+impl Pointer for u32 {}
+
+pub fn drm_ioctl<T: Pointer>(fd: &File, request: c_ulong, arg: &mut T) -> io::Result<()> {
+    if unsafe { ioctl(fd.as_raw_fd(), request, arg.as_ptr()) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
 
-fn create_buffer<T>(size: u32) -> Vec<T> {
-    let mut buffer: Vec<T> = Vec::with_capacity(size as usize);
-    unsafe { buffer.set_len(size as usize) };
-    buffer
+fn create_buffer<T: Default + Clone>(size: u32) -> Vec<T> {
+    vec![T::default(); size as usize]
 }
 
 pub struct DeviceInterface {
     pub fd: ::std::fs::File,
-    pub fbs: Vec<FrameBuffer>
+    pub fbs: Vec<FrameBuffer>,
+    handles: Arc<Mutex<HandleTable>>
+}
+
+/**
+ * Counts the imports of each GEM handle. Importing a dma-buf that is
+ * already imported returns the same handle, so it may only be closed once
+ * the last import is gone.
+ **/
+#[derive(Debug, Default)]
+struct HandleTable {
+    counts: HashMap<u32, usize>
+}
+
+impl HandleTable {
+    fn retain(&mut self, handle: u32) {
+        *self.counts.entry(handle).or_insert(0) += 1;
+    }
+
+    /**
+     * Drops one import of `handle` and returns whether it was the last.
+     **/
+    fn release(&mut self, handle: u32) -> bool {
+        match self.counts.get_mut(&handle) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                false
+            }
+            _ => {
+                self.counts.remove(&handle);
+                true
+            }
+        }
+    }
+}
+
+fn release_handle(fd: &File, handles: &Mutex<HandleTable>, handle: u32) {
+    if handles.lock().unwrap().release(handle) {
+        close_handle(fd, handle);
+    }
+}
+
+impl DeviceInterface {
+    /**
+     * Imports a dma-buf exported by a client or another device and wraps it
+     * into a framebuffer that can be scanned out.
+     **/
+    pub fn import(&self, dmabuf: RawFd, desc: &DmaBufDesc) -> io::Result<ImportedBuffer> {
+        let handle = import_handle(&self.fd, dmabuf)?;
+        self.handles.lock().unwrap().retain(handle);
+
+        let mut fb_cmd = drm_mode_fb_cmd2 {
+            width: desc.width,
+            height: desc.height,
+            pixel_format: desc.fourcc,
+            handles: [handle, 0, 0, 0],
+            pitches: [desc.stride, 0, 0, 0],
+            offsets: [desc.offset, 0, 0, 0],
+            ..Default::default()
+        };
+        if desc.modifier != DRM_FORMAT_MOD_INVALID {
+            fb_cmd.flags = DRM_MODE_FB_MODIFIERS;
+            fb_cmd.modifier[0] = desc.modifier;
+        }
+
+        let added = self.fd.try_clone().and_then(|fd| drm_ioctl(&fd, DRM_IOCTL_MODE_ADDFB2, &mut fb_cmd).map(|_| fd));
+        let fd = match added {
+            Ok(fd) => fd,
+            Err(e) => {
+                release_handle(&self.fd, &self.handles, handle);
+                return Err(e);
+            }
+        };
+
+        Ok(ImportedBuffer {
+            fd,
+            fb_id: fb_cmd.fb_id,
+            handle,
+            handles: self.handles.clone(),
+            width: desc.width,
+            height: desc.height
+        })
+    }
+
+    pub fn driver_name(&self) -> io::Result<String> {
+        driver_name(&self.fd)
+    }
+}
+
+pub fn driver_name(fd: &File) -> io::Result<String> {
+    let mut version: drm_version = unsafe { mem::zeroed() };
+    drm_ioctl(fd, DRM_IOCTL_VERSION, &mut version)?;
+
+    let mut name: Vec<u8> = vec![0; version.name_len as usize];
+    version.name = name.as_mut_ptr() as *mut _;
+    version.date_len = 0;
+    version.desc_len = 0;
+    drm_ioctl(fd, DRM_IOCTL_VERSION, &mut version)?;
+
+    Ok(String::from_utf8_lossy(&name).into_owned())
+}
+
+/**
+ * Returns the path of the first device node driven by `driver`, e.g. "vgem".
+ **/
+pub fn find_device(driver: &str) -> Option<String> {
+    (0..16)
+        .map(|i| format!("/dev/dri/card{}", i))
+        .find(|path| {
+            OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(path)
+                .and_then(|fd| driver_name(&fd))
+                .map(|name| name == driver)
+                .unwrap_or(false)
+        })
+}
+
+pub fn close_handle(fd: &File, handle: u32) {
+    let mut close = drm_gem_close { handle, ..Default::default() };
+    let _ = drm_ioctl(fd, DRM_IOCTL_GEM_CLOSE, &mut close);
+}
+
+pub fn create_dumb(fd: &File, width: u32, height: u32, bpp: u32) -> io::Result<drm_mode_create_dumb> {
+    let mut buffer_config = drm_mode_create_dumb {
+        width,
+        height,
+        bpp,
+        flags: 0,
+        pitch: 0,
+        size: 0,
+        handle: 0
+    };
+    drm_ioctl(fd, DRM_IOCTL_MODE_CREATE_DUMB, &mut buffer_config)?;
+    Ok(buffer_config)
+}
+
+/**
+ * Exports the GEM object `handle` as a dma-buf file descriptor.
+ **/
+pub fn export_handle(fd: &File, handle: u32) -> io::Result<File> {
+    let mut prime = drm_prime_handle { handle, flags: DRM_CLOEXEC | DRM_RDWR, ..Default::default() };
+    drm_ioctl(fd, DRM_IOCTL_PRIME_HANDLE_TO_FD, &mut prime)?;
+    Ok(unsafe { File::from_raw_fd(prime.fd) })
+}
+
+/**
+ * Imports a dma-buf and returns the GEM handle referring to it on `fd`.
+ **/
+pub fn import_handle(fd: &File, dmabuf: RawFd) -> io::Result<u32> {
+    let mut prime = drm_prime_handle { fd: dmabuf, ..Default::default() };
+    drm_ioctl(fd, DRM_IOCTL_PRIME_FD_TO_HANDLE, &mut prime)?;
+    Ok(prime.handle)
+}
+
+/**
+ * Layout of a single-plane dma-buf as announced by its exporter.
+ **/
+#[derive(Debug, Clone, Copy)]
+pub struct DmaBufDesc {
+    pub width: u32,
+    pub height: u32,
+    pub stride: u32,
+    pub offset: u32,
+    pub fourcc: u32,
+    pub modifier: u64
+}
+
+/**
+ * A framebuffer backed by an imported dma-buf. The framebuffer is removed
+ * when it is dropped, and the GEM handle closed unless another import still
+ * uses it.
+ **/
+#[derive(Debug)]
+pub struct ImportedBuffer {
+    fd: File,
+    fb_id: u32,
+    handle: u32,
+    handles: Arc<Mutex<HandleTable>>,
+    width: u32,
+    height: u32
+}
+
+impl ImportedBuffer {
+    pub fn fb_id(&self) -> u32 {
+        self.fb_id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl Drop for ImportedBuffer {
+    fn drop(&mut self) {
+        let _ = drm_ioctl(&self.fd, DRM_IOCTL_MODE_RMFB, &mut self.fb_id);
+        release_handle(&self.fd, &self.handles, self.handle);
+    }
+}
+
+/**
+ * Creates a dumb buffer, adds it as a framebuffer and maps it.
+ **/
+fn create_framebuffer(fd: &File, width: u32, height: u32) -> io::Result<(drm_mode_fb_cmd, MmapMut)> {
+    let buffer_config = create_dumb(fd, width, height, 32)?;
+
+    let mut fb_cmd = drm_mode_fb_cmd {
+        fb_id: 0,
+        width: buffer_config.width,
+        height: buffer_config.height,
+        bpp: buffer_config.bpp,
+        pitch: buffer_config.pitch,
+        depth: 24,
+        handle: buffer_config.handle
+    };
+    if let Err(e) = drm_ioctl(fd, DRM_IOCTL_MODE_ADDFB, &mut fb_cmd) {
+        close_handle(fd, buffer_config.handle);
+        return Err(e);
+    }
+
+    let mut map_config = drm_mode_map_dumb { handle: buffer_config.handle, ..Default::default() };
+    let mmap = drm_ioctl(fd, DRM_IOCTL_MODE_MAP_DUMB, &mut map_config).and_then(|_| unsafe {
+        MmapOptions::new()
+            .offset(map_config.offset as usize)
+            .len(buffer_config.size as usize)
+            .map_mut(fd)
+    });
+    match mmap {
+        Ok(mmap) => Ok((fb_cmd, mmap)),
+        Err(e) => {
+            destroy_framebuffer(fd, &mut fb_cmd);
+            Err(e)
+        }
+    }
+}
+
+fn destroy_framebuffer(fd: &File, fb_cmd: &mut drm_mode_fb_cmd) {
+    let _ = drm_ioctl(fd, DRM_IOCTL_MODE_RMFB, &mut fb_cmd.fb_id);
+    close_handle(fd, fb_cmd.handle);
 }
 
 pub fn open(path: &str) -> DeviceInterface {
@@ -50,7 +323,7 @@ pub fn open(path: &str) -> DeviceInterface {
      * read device infos
      **/
     let mut fb_ids: Vec<u64> = create_buffer(res.count_fbs);
-    let mut crtc_ids: Vec<u64> = create_buffer(res.count_crtcs);
+    let mut crtc_ids: Vec<u32> = create_buffer(res.count_crtcs);
     let mut connector_ids: Vec<u64> = create_buffer(res.count_connectors);
     let mut encoder_ids: Vec<u64> = create_buffer(res.count_encoders);
     res.fb_id_ptr = fb_ids.as_mut_ptr() as u64;
@@ -59,13 +332,15 @@ pub fn open(path: &str) -> DeviceInterface {
     res.encoder_id_ptr = encoder_ids.as_mut_ptr() as u64;
     unsafe { ioctl(fd.as_raw_fd(), DRM_IOCTL_MODE_GETRESOURCES, res.as_ptr()) };
 
+    // damage clips on planes are only reachable through the atomic api
+    let atomic = set_client_cap(&fd, DRM_CLIENT_CAP_ATOMIC, 1).is_ok();
+
     let mut fbs: Vec<FrameBuffer> = Vec::new();
     for &connector_id in connector_ids.iter() {
         /**
          * read connector infos
          **/
-        let mut connector = drm_mode_get_connector::default();
-        connector.connector_id = connector_id as u32;
+        let mut connector = drm_mode_get_connector { connector_id: connector_id as u32, ..Default::default() };
         unsafe { ioctl(fd.as_raw_fd(), DRM_IOCTL_MODE_GETCONNECTOR, connector.as_ptr()) };
 
         let mut modes: Vec<drm_mode_modeinfo> = create_buffer(connector.count_modes);
@@ -90,66 +365,48 @@ pub fn open(path: &str) -> DeviceInterface {
         /**
          * create the framebuffer
          **/
-        let mut buffer_config = drm_mode_create_dumb {
-            width: modes[0].hdisplay as u32,
-            height: modes[0].vdisplay as u32,
-            bpp: 32,
-            flags: 0,
-            pitch: 0,
-            size: 0,
-            handle: 0
-        };
-        unsafe { ioctl(fd.as_raw_fd(), DRM_IOCTL_MODE_CREATE_DUMB, buffer_config.as_ptr()) };
-
-        let mut fb_cmd = drm_mode_fb_cmd {
-            fb_id: 0,
-            width: buffer_config.width,
-            height: buffer_config.height,
-            bpp: buffer_config.bpp,
-            pitch: buffer_config.pitch,
-            depth: 24,
-            handle: buffer_config.handle
-        };
-        unsafe { ioctl(fd.as_raw_fd(), DRM_IOCTL_MODE_ADDFB, fb_cmd.as_ptr()) };
+        let (fb_cmd, mmap) = create_framebuffer(&fd, modes[0].hdisplay as u32, modes[0].vdisplay as u32).unwrap();
 
-        /**
-         * mmap the framebuffer
-         **/
-        let mut map_config = drm_mode_map_dumb::default();
-        map_config.handle = buffer_config.handle;
-        unsafe { ioctl(fd.as_raw_fd(), DRM_IOCTL_MODE_MAP_DUMB, map_config.as_ptr()) };
-
-        let mut mmap = unsafe {
-            MmapOptions::new()
-                .offset(map_config.offset as usize)
-                .len(buffer_config.size as usize)
-                .map_mut(&fd)
-                .unwrap()
-        };
-            
         /**
          * initialize the crtc
          **/
-        let mut encoder = drm_mode_get_encoder::default();
-        encoder.encoder_id = connector.encoder_id;
+        let mut encoder = drm_mode_get_encoder { encoder_id: connector.encoder_id, ..Default::default() };
         unsafe { ioctl(fd.as_raw_fd(), DRM_IOCTL_MODE_GETENCODER, encoder.as_ptr()) };
 
-        let mut crtc = drm_mode_crtc::default();
-        crtc.crtc_id = encoder.crtc_id;
+        let mut crtc = drm_mode_crtc { crtc_id: encoder.crtc_id, ..Default::default() };
         unsafe { ioctl(fd.as_raw_fd(), DRM_IOCTL_MODE_GETCRTC, crtc.as_ptr()) };
 
-        let mut connectors = vec![connector_id];
+        let connectors = [connector_id];
         crtc.fb_id = fb_cmd.fb_id;
         crtc.set_connectors_ptr = connectors.as_ptr() as u64;
         crtc.count_connectors = connectors.len() as u32;
         crtc.mode_valid = 1;
         unsafe { ioctl(fd.as_raw_fd(), DRM_IOCTL_MODE_SETCRTC, crtc.as_ptr()) };
 
+        let crtc_index = crtc_ids.iter().position(|&id| id == crtc.crtc_id);
+        let damage_mode = match crtc_index {
+            Some(index) if atomic => DamagePlane::find(&fd, index)
+                .map(DamageMode::Atomic)
+                .unwrap_or(DamageMode::Dirty),
+            _ => DamageMode::Dirty
+        };
+
         fbs.push(FrameBuffer {
+            fd: fd.try_clone().unwrap(),
             frame: mmap,
             height: fb_cmd.height,
             width: fb_cmd.width,
-            fb_cmd: fb_cmd
+            format: Format::Xrgb8888,
+            fb_cmd,
+            crtc,
+            connector_id: connector_id as u32,
+            size_mm: (connector.mm_width, connector.mm_height),
+            crtc_index: if atomic { crtc_index } else { None },
+            transform: OutputTransform::Normal,
+            overlay: None,
+            plane_fb: fb_cmd.fb_id,
+            damage: Vec::new(),
+            damage_mode
         });
 
     }
@@ -158,25 +415,82 @@ pub fn open(path: &str) -> DeviceInterface {
 
     DeviceInterface {
         fd,
-        fbs
+        fbs,
+        handles: Arc::new(Mutex::new(HandleTable::default()))
     }
 }
 
 #[derive(Debug)]
 pub struct FrameBuffer {
+    fd: File,
     fb_cmd: drm_mode_fb_cmd,
+    crtc: drm_mode_crtc,
+    connector_id: u32,
+    /// Size of the panel, 0 when unknown.
+    size_mm: (u32, u32),
+    /// Index of the crtc, when planes can be configured atomically.
+    crtc_index: Option<usize>,
+    transform: OutputTransform,
+    overlay: Option<Overlay>,
+    /// Framebuffer on the primary plane, set by the last `scanout`,
+    /// `restore` or `set_transform`.
+    plane_fb: u32,
     frame: MmapMut,
     height: u32,
-    width: u32
+    width: u32,
+    format: Format,
+    damage: Vec<Rect>,
+    damage_mode: DamageMode
+}
+
+/**
+ * How damaged regions are reported to the driver.
+ **/
+#[derive(Debug)]
+enum DamageMode {
+    /// `DRM_IOCTL_MODE_DIRTYFB` on the framebuffer.
+    Dirty,
+    /// `FB_DAMAGE_CLIPS` on the primary plane in an atomic commit.
+    Atomic(DamagePlane),
+    /// The driver scans out directly from memory and needs no flushing.
+    None
+}
+
+#[derive(Debug, Clone, Copy)]
+struct DamagePlane {
+    plane_id: u32,
+    fb_id_prop: u32,
+    damage_clips_prop: u32
+}
+
+impl DamagePlane {
+    fn find(fd: &File, crtc_index: usize) -> Option<DamagePlane> {
+        let plane_id = *find_planes(fd, crtc_index, DRM_PLANE_TYPE_PRIMARY).ok()?.first()?;
+        let (fb_id_prop, _) = find_property(fd, plane_id, DRM_MODE_OBJECT_PLANE, "FB_ID").ok()??;
+        let (damage_clips_prop, _) = find_property(fd, plane_id, DRM_MODE_OBJECT_PLANE, "FB_DAMAGE_CLIPS").ok()??;
+        Some(DamagePlane { plane_id, fb_id_prop, damage_clips_prop })
+    }
+}
+
+/**
+ * Rectangle layout expected by `FB_DAMAGE_CLIPS`.
+ **/
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+struct drm_mode_rect {
+    x1: i32,
+    y1: i32,
+    x2: i32,
+    y2: i32
 }
 
 impl FrameBuffer {
     pub fn set(&mut self, x: u32, y: u32, c: &Color) {
-        let i = (x + y * self.width) * 4;
-        self.frame[i as usize] = c.r;
-        self.frame[(i + 1) as usize] = c.g;
-        self.frame[(i + 2) as usize] = c.b;
-        self.frame[(i + 3) as usize] = c.a;
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let i = y * self.fb_cmd.pitch + x * self.format.bytes_per_pixel();
+        self.format.write(&mut self.frame[i as usize..], c);
     }
 
     pub fn height(&mut self) -> u32 {
@@ -186,15 +500,392 @@ impl FrameBuffer {
     pub fn width(&mut self) -> u32 {
         self.width
     }
+
+    /**
+     * Exports the dumb buffer as a dma-buf so that other devices or clients
+     * can access it without copying.
+     **/
+    pub fn export(&self) -> io::Result<File> {
+        export_handle(&self.fd, self.fb_cmd.handle)
+    }
+
+    /**
+     * Creates a frame clock for the crtc this framebuffer is shown on.
+     **/
+    pub fn frame_clock(&self) -> io::Result<FrameClock> {
+        Ok(FrameClock::new(self.fd.try_clone()?, self.crtc.crtc_id, &self.crtc.mode))
+    }
+
+    /**
+     * Scans out an imported buffer on this framebuffer's crtc instead of the
+     * dumb buffer.
+     **/
+    pub fn scanout(&mut self, buffer: &ImportedBuffer) -> io::Result<()> {
+        self.show(buffer.fb_id, buffer.width, buffer.height)
+    }
+
+    /**
+     * Switches the crtc back to the dumb buffer.
+     **/
+    pub fn restore(&mut self) -> io::Result<()> {
+        let (fb_id, width, height) = (self.fb_cmd.fb_id, self.width, self.height);
+        self.show(fb_id, width, height)
+    }
+
+    /**
+     * Puts a framebuffer on the primary plane, keeping the transform when
+     * planes are configured atomically.
+     **/
+    fn show(&mut self, fb_id: u32, width: u32, height: u32) -> io::Result<()> {
+        if self.crtc_index.is_some() {
+            let transform = self.transform;
+            self.set_plane(fb_id, width, height, transform)?;
+        } else {
+            self.set_crtc(fb_id)?;
+        }
+        self.plane_fb = fb_id;
+        Ok(())
+    }
+
+    pub fn transform(&self) -> OutputTransform {
+        self.transform
+    }
+
+    /**
+     * Lets the primary plane rotate and mirror the picture at scanout. For
+     * 90 and 270 degrees the dumb buffer is replaced by one with width and
+     * height swapped, so drawing stays upright. Fails, leaving everything
+     * as it was, when the driver can't do it; callers then turn the picture
+     * in software.
+     **/
+    pub fn set_transform(&mut self, transform: OutputTransform) -> io::Result<()> {
+        if self.crtc_index.is_none() {
+            return Err(io::Error::other("plane rotation is not supported"));
+        }
+
+        let (mode_width, mode_height) = (self.crtc.mode.hdisplay as u32, self.crtc.mode.vdisplay as u32);
+        let (width, height) = transform.logical_size(mode_width, mode_height);
+        let mut buffer = if (width, height) != (self.width, self.height) {
+            Some(create_framebuffer(&self.fd, width, height)?)
+        } else {
+            None
+        };
+        let fb_id = buffer.as_ref().map_or(self.fb_cmd.fb_id, |b| b.0.fb_id);
+
+        if let Err(e) = self.set_plane(fb_id, width, height, transform) {
+            if let Some((ref mut fb_cmd, _)) = buffer {
+                destroy_framebuffer(&self.fd, fb_cmd);
+            }
+            return Err(e);
+        }
+
+        if let Some((fb_cmd, frame)) = buffer {
+            destroy_framebuffer(&self.fd, &mut self.fb_cmd);
+            self.fb_cmd = fb_cmd;
+            self.frame = frame;
+            self.width = width;
+            self.height = height;
+            self.crtc.fb_id = fb_cmd.fb_id;
+            self.damage.clear();
+        }
+        self.plane_fb = self.fb_cmd.fb_id;
+        self.transform = transform;
+        // the overlay keeps the rotation it was found with
+        self.overlay = None;
+        Ok(())
+    }
+
+    /**
+     * Marks a region as modified so that it is sent to the display on the
+     * next `flush`.
+     **/
+    pub fn damage(&mut self, rect: Rect) {
+        let mut rect = rect.intersect(&Rect::new(0, 0, self.width, self.height));
+        if rect.is_empty() {
+            return;
+        }
+
+        while let Some(i) = self.damage.iter().position(|r| r.overlaps(&rect)) {
+            rect = rect.union(&self.damage.swap_remove(i));
+        }
+        self.damage.push(rect);
+
+        if self.damage.len() > MAX_DAMAGE_RECTS {
+            let bounds = self.damage.iter().fold(rect, |acc, r| acc.union(r));
+            self.damage.clear();
+            self.damage.push(bounds);
+        }
+    }
+
+    /**
+     * Sends the accumulated damage to the driver. Drivers that scan out from
+     * a shadow copy (virtio-gpu, udl, ...) only update the display after this.
+     **/
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.damage.is_empty() {
+            return Ok(());
+        }
+
+        let result = match self.damage_mode {
+            DamageMode::Dirty => self.flush_dirty(),
+            DamageMode::Atomic(plane) => match self.flush_atomic(&plane) {
+                // the previous flip is still queued, keep the damage for
+                // the next flush
+                Ok(false) => return Ok(()),
+                result => result.map(|_| ())
+            },
+            DamageMode::None => Ok(())
+        };
+        self.damage.clear();
+
+        match result {
+            Err(ref e) if e.raw_os_error() == Some(libc::ENOSYS) => {
+                self.damage_mode = DamageMode::None;
+                Ok(())
+            }
+            result => result
+        }
+    }
+
+    fn flush_dirty(&self) -> io::Result<()> {
+        let mut clips: Vec<drm_clip_rect> = self.damage
+            .iter()
+            .map(|r| drm_clip_rect {
+                x1: r.x as u16,
+                y1: r.y as u16,
+                x2: r.right() as u16,
+                y2: r.bottom() as u16
+            })
+            .collect();
+
+        let mut dirty = drm_mode_fb_dirty_cmd {
+            fb_id: self.fb_cmd.fb_id,
+            flags: 0,
+            color: 0,
+            num_clips: clips.len() as u32,
+            clips_ptr: clips.as_mut_ptr() as u64
+        };
+        drm_ioctl(&self.fd, DRM_IOCTL_MODE_DIRTYFB, &mut dirty)
+    }
+
+    /**
+     * Commits the damage without waiting for the flip. Returns false when
+     * an earlier flip hasn't completed yet.
+     **/
+    fn flush_atomic(&mut self, plane: &DamagePlane) -> io::Result<bool> {
+        if self.plane_fb != self.fb_cmd.fb_id {
+            // an imported buffer is scanned out, the dumb buffer isn't visible
+            return Ok(true);
+        }
+
+        // flip events of earlier flushes, only read so they don't pile up
+        while poll_readable(&self.fd, Some(Duration::from_secs(0)))? {
+            read_events(&mut self.fd)?;
+        }
+
+        let clips: Vec<drm_mode_rect> = self.damage
+            .iter()
+            .map(|r| drm_mode_rect {
+                x1: r.x,
+                y1: r.y,
+                x2: r.right(),
+                y2: r.bottom()
+            })
+            .collect();
+        let blob = Blob::new(&self.fd, &clips)?;
+
+        let mut request = AtomicRequest::new();
+        request.add(plane.plane_id, plane.fb_id_prop, self.plane_fb as u64);
+        request.add(plane.plane_id, plane.damage_clips_prop, blob.id() as u64);
+        let flags = DRM_MODE_ATOMIC_NONBLOCK | DRM_MODE_PAGE_FLIP_EVENT;
+        match request.commit_with_event(&self.fd, flags, self.crtc.crtc_id as u64) {
+            Err(ref e) if e.raw_os_error() == Some(libc::EBUSY) => Ok(false),
+            result => result.map(|_| true)
+        }
+    }
+
+    /**
+     * Shows a `width` x `height` framebuffer on the whole mode with an
+     * atomic commit, rotated and mirrored by `transform`.
+     **/
+    fn set_plane(&self, fb_id: u32, width: u32, height: u32, transform: OutputTransform) -> io::Result<()> {
+        let unsupported = || io::Error::other("plane rotation is not supported");
+        let crtc_index = self.crtc_index.ok_or_else(unsupported)?;
+        let plane_id = *find_planes(&self.fd, crtc_index, DRM_PLANE_TYPE_PRIMARY)?.first().ok_or_else(unsupported)?;
+        let prop = |name: &str| -> io::Result<Option<u32>> {
+            Ok(find_property(&self.fd, plane_id, DRM_MODE_OBJECT_PLANE, name)?.map(|(id, _)| id))
+        };
+
+        let (mode_width, mode_height) = (self.crtc.mode.hdisplay as u32, self.crtc.mode.vdisplay as u32);
+        let mut request = AtomicRequest::new();
+        let props = [
+            ("FB_ID", fb_id as u64),
+            ("CRTC_ID", self.crtc.crtc_id as u64),
+            ("SRC_X", 0),
+            ("SRC_Y", 0),
+            ("SRC_W", (width as u64) << 16),
+            ("SRC_H", (height as u64) << 16),
+            ("CRTC_X", 0),
+            ("CRTC_Y", 0),
+            ("CRTC_W", mode_width as u64),
+            ("CRTC_H", mode_height as u64)
+        ];
+        for &(name, value) in props.iter() {
+            request.add(plane_id, prop(name)?.ok_or_else(unsupported)?, value);
+        }
+        // planes without the property can only show the picture as it is
+        match prop("rotation")? {
+            Some(rotation) => request.add(plane_id, rotation, transform.drm_rotation()),
+            None if transform == OutputTransform::Normal => {}
+            None => return Err(unsupported())
+        }
+        request.commit(&self.fd, DRM_MODE_ATOMIC_TEST_ONLY)?;
+        request.commit(&self.fd, 0)
+    }
+
+    fn set_crtc(&mut self, fb_id: u32) -> io::Result<()> {
+        let connectors = [self.connector_id];
+        let mut crtc = self.crtc;
+        crtc.fb_id = fb_id;
+        crtc.set_connectors_ptr = connectors.as_ptr() as u64;
+        crtc.count_connectors = connectors.len() as u32;
+        crtc.mode_valid = 1;
+        drm_ioctl(&self.fd, DRM_IOCTL_MODE_SETCRTC, &mut crtc)
+    }
+}
+
+impl Backend for FrameBuffer {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        self.format
+    }
+
+    fn stride(&self) -> u32 {
+        self.fb_cmd.pitch
+    }
+
+    fn pixels(&self) -> &[u8] {
+        &self.frame
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.frame
+    }
+
+    fn damage(&mut self, rect: Rect) {
+        FrameBuffer::damage(self, rect)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        FrameBuffer::flush(self)
+    }
+
+    fn size_mm(&self) -> Option<(u32, u32)> {
+        match self.size_mm {
+            (0, _) | (_, 0) => None,
+            // the buffer is turned along with the picture
+            (width, height) => Some(self.transform.logical_size(width, height))
+        }
+    }
+
+    fn show_video(&mut self, frame: &YuvImage, dst: Rect) -> io::Result<bool> {
+        if self.overlay.as_ref().map(|o| o.format()) != Some(frame.format()) {
+            let crtc_index = match self.crtc_index {
+                Some(index) => index,
+                None => return Ok(false)
+            };
+            self.overlay = None;
+            self.overlay = Overlay::find(&self.fd, crtc_index, self.crtc.crtc_id, frame.format(), self.transform)?;
+        }
+        // the overlay is placed on the panel, not in the turned picture
+        let dst = self.transform.affine(self.width, self.height).bounds(&dst);
+        match self.overlay {
+            Some(ref mut overlay) => overlay.show(frame, dst).map(|_| true),
+            None => Ok(false)
+        }
+    }
+
+    fn hide_video(&mut self) -> io::Result<()> {
+        match self.overlay {
+            Some(ref mut overlay) => overlay.hide(),
+            None => Ok(())
+        }
+    }
+
+    fn frame_clock(&self) -> io::Result<FrameClock> {
+        FrameBuffer::frame_clock(self)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect {x, y, width, height}
+    }
+
+    pub fn right(&self) -> i32 {
+        self.x + self.width as i32
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height as i32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        !self.intersect(other).is_empty()
+    }
+
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= x || bottom <= y {
+            return Rect::default();
+        }
+        Rect::new(x, y, (right - x) as u32, (bottom - y) as u32)
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Rect::new(x, y, (right - x) as u32, (bottom - y) as u32)
+    }
 }
 
 #[repr(C)]
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Color {
-    r: u8,
-    g: u8,
-    b: u8,
-    a: u8
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8
 }
 
 impl Color {
@@ -202,3 +893,52 @@ impl Color {
         Color {r, g, b, a}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open_vgem() -> Option<File> {
+        let path = find_device("vgem")?;
+        OpenOptions::new().read(true).write(true).open(path).ok()
+    }
+
+    #[test]
+    fn rect_intersect_and_union() {
+        let a = Rect::new(0, 0, 10, 10);
+        let b = Rect::new(5, -5, 10, 10);
+        assert_eq!(a.intersect(&b), Rect::new(5, 0, 5, 5));
+        assert_eq!(a.union(&b), Rect::new(0, -5, 15, 15));
+        assert!(!a.overlaps(&Rect::new(10, 0, 5, 5)));
+        assert_eq!(a.union(&Rect::default()), a);
+    }
+
+    #[test]
+    fn counts_handle_imports() {
+        let mut handles = HandleTable::default();
+        handles.retain(3);
+        handles.retain(3);
+        handles.retain(4);
+        assert!(!handles.release(3));
+        assert!(handles.release(3));
+        assert!(handles.release(4));
+        assert!(handles.counts.is_empty());
+    }
+
+    #[test]
+    fn vgem_prime_roundtrip() {
+        let fd = match open_vgem() {
+            Some(fd) => fd,
+            None => return
+        };
+
+        let buffer = create_dumb(&fd, 64, 32, 32).unwrap();
+        assert!(buffer.pitch >= 64 * 4);
+
+        let dmabuf = export_handle(&fd, buffer.handle).unwrap();
+        let handle = import_handle(&fd, dmabuf.as_raw_fd()).unwrap();
+        assert_eq!(handle, buffer.handle);
+
+        close_handle(&fd, buffer.handle);
+    }
+}
@@ -11,3 +11,41 @@ pub const DRM_IOCTL_MODE_SETCRTC: c_ulong = 0xc06864a2;
 pub const DRM_IOCTL_MODE_CREATE_DUMB: c_ulong = 0xc02064b2;
 pub const DRM_IOCTL_MODE_ADDFB: c_ulong = 0xc01c64ae;
 pub const DRM_IOCTL_MODE_MAP_DUMB: c_ulong = 0xc01064b3;
+pub const DRM_IOCTL_VERSION: c_ulong = 0xc0406400;
+pub const DRM_IOCTL_GEM_CLOSE: c_ulong = 0x40086409;
+pub const DRM_IOCTL_GET_CAP: c_ulong = 0xc010640c;
+pub const DRM_IOCTL_PRIME_HANDLE_TO_FD: c_ulong = 0xc00c642d;
+pub const DRM_IOCTL_PRIME_FD_TO_HANDLE: c_ulong = 0xc00c642e;
+pub const DRM_IOCTL_MODE_RMFB: c_ulong = 0xc00464af;
+pub const DRM_IOCTL_MODE_ADDFB2: c_ulong = 0xc06864b8;
+
+pub const DRM_CLOEXEC: u32 = 0o2000000;
+pub const DRM_RDWR: u32 = 0o2;
+
+pub const DRM_FORMAT_XRGB8888: u32 = 0x34325258;
+pub const DRM_FORMAT_ARGB8888: u32 = 0x34325241;
+pub const DRM_FORMAT_XBGR8888: u32 = 0x34324258;
+pub const DRM_FORMAT_ABGR8888: u32 = 0x34324241;
+pub const DRM_FORMAT_RGB565: u32 = 0x36314752;
+pub const DRM_FORMAT_NV12: u32 = 0x3231564e;
+pub const DRM_FORMAT_YUV420: u32 = 0x32315559;
+pub const DRM_FORMAT_YUYV: u32 = 0x56595559;
+
+pub const DRM_FORMAT_MOD_LINEAR: u64 = 0;
+pub const DRM_FORMAT_MOD_INVALID: u64 = 0x00ff_ffff_ffff_ffff;
+
+pub const DRM_IOCTL_SET_CLIENT_CAP: c_ulong = 0x4010640d;
+pub const DRM_IOCTL_MODE_GETPROPERTY: c_ulong = 0xc04064aa;
+pub const DRM_IOCTL_MODE_DIRTYFB: c_ulong = 0xc01864b1;
+pub const DRM_IOCTL_MODE_GETPLANERESOURCES: c_ulong = 0xc01064b5;
+pub const DRM_IOCTL_MODE_GETPLANE: c_ulong = 0xc02064b6;
+pub const DRM_IOCTL_MODE_OBJ_GETPROPERTIES: c_ulong = 0xc02064b9;
+pub const DRM_IOCTL_MODE_ATOMIC: c_ulong = 0xc03864bc;
+pub const DRM_IOCTL_MODE_CREATEPROPBLOB: c_ulong = 0xc01064bd;
+pub const DRM_IOCTL_MODE_DESTROYPROPBLOB: c_ulong = 0xc00464be;
+
+pub const DRM_PLANE_TYPE_OVERLAY: u64 = 0;
+pub const DRM_PLANE_TYPE_PRIMARY: u64 = 1;
+
+pub const DRM_IOCTL_CRTC_GET_SEQUENCE: c_ulong = 0xc018643b;
+pub const DRM_IOCTL_CRTC_QUEUE_SEQUENCE: c_ulong = 0xc018643c;
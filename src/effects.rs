@@ -0,0 +1,320 @@
+use super::drm::{Color, Rect};
+use super::format::Format;
+
+/**
+ * How a gradient's colors are laid out.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// Colors change along the line from `(x0, y0)` to `(x1, y1)` and stay
+    /// the same across it.
+    Linear { x0: f32, y0: f32, x1: f32, y1: f32 },
+    /// Colors change with the distance from the center.
+    Radial { cx: f32, cy: f32, radius: f32 }
+}
+
+/**
+ * A smooth transition between colors. Points before the first and after
+ * the last stop get the color of that stop.
+ **/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    shape: Shape,
+    stops: Vec<(f32, Color)>
+}
+
+impl Gradient {
+    pub fn linear(x0: f32, y0: f32, x1: f32, y1: f32) -> Gradient {
+        Gradient { shape: Shape::Linear { x0, y0, x1, y1 }, stops: Vec::new() }
+    }
+
+    pub fn radial(cx: f32, cy: f32, radius: f32) -> Gradient {
+        Gradient { shape: Shape::Radial { cx, cy, radius }, stops: Vec::new() }
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /**
+     * Adds the color at `offset`, from 0 at the start to 1 at the end of
+     * the gradient. Stops are kept sorted by offset.
+     **/
+    pub fn add_stop(&mut self, offset: f32, c: &Color) {
+        let i = self.stops.iter().position(|s| s.0 > offset).unwrap_or(self.stops.len());
+        // interpolating premultiplied colors keeps transparent stops from
+        // darkening their neighbours
+        self.stops.insert(i, (offset, c.premultiply()));
+    }
+
+    /**
+     * The premultiplied color at the center of pixel `(x, y)`.
+     **/
+    pub fn color_at(&self, x: i32, y: i32) -> Color {
+        let c = self.channels(self.position(x, y));
+        let round = |v: f32| (v + 0.5) as u8;
+        Color::new(round(c[0]), round(c[1]), round(c[2]), round(c[3]))
+    }
+
+    /**
+     * Like `color_at`, but with 8 fraction bits per channel, for dithering.
+     **/
+    pub fn precise_color_at(&self, x: i32, y: i32) -> [u32; 4] {
+        let c = self.channels(self.position(x, y));
+        let fixed = |v: f32| (v * 256.0) as u32;
+        [fixed(c[0]), fixed(c[1]), fixed(c[2]), fixed(c[3])]
+    }
+
+    /**
+     * How far pixel `(x, y)` is along the gradient, from 0 to 1.
+     **/
+    fn position(&self, x: i32, y: i32) -> f32 {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        match self.shape {
+            Shape::Linear { x0, y0, x1, y1 } => {
+                let (dx, dy) = (x1 - x0, y1 - y0);
+                let len = dx * dx + dy * dy;
+                if len == 0.0 { 0.0 } else { ((px - x0) * dx + (py - y0) * dy) / len }
+            }
+            Shape::Radial { cx, cy, radius } => {
+                let d = ((px - cx) * (px - cx) + (py - cy) * (py - cy)).sqrt();
+                if radius == 0.0 { 1.0 } else { d / radius }
+            }
+        }
+    }
+
+    fn channels(&self, t: f32) -> [f32; 4] {
+        let channels = |c: &Color| [c.r as f32, c.g as f32, c.b as f32, c.a as f32];
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return [0.0; 4]
+        };
+        if t <= first.0 {
+            return channels(&first.1);
+        }
+        if t >= last.0 {
+            return channels(&last.1);
+        }
+        let i = self.stops.iter().position(|s| s.0 > t).unwrap();
+        let (a, b) = (&self.stops[i - 1], &self.stops[i]);
+        let f = if b.0 > a.0 { (t - a.0) / (b.0 - a.0) } else { 1.0 };
+        let (a, b) = (channels(&a.1), channels(&b.1));
+        let mut c = [0.0; 4];
+        for (i, c) in c.iter_mut().enumerate() {
+            *c = a[i] + (b[i] - a[i]) * f;
+        }
+        c
+    }
+}
+
+/**
+ * A soft shadow cast by a rectangle onto what is behind it.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    pub offset_x: i32,
+    pub offset_y: i32,
+    /// How far the shadow fades out beyond the rectangle's edges.
+    pub radius: u32,
+    pub color: Color
+}
+
+impl Shadow {
+    pub fn new(offset_x: i32, offset_y: i32, radius: u32, color: Color) -> Shadow {
+        Shadow { offset_x, offset_y, radius, color }
+    }
+
+    /**
+     * The area a shadow cast by `rect` covers.
+     **/
+    pub fn bounds(&self, rect: Rect) -> Rect {
+        let r = self.radius as i32;
+        Rect::new(
+            rect.x + self.offset_x - r,
+            rect.y + self.offset_y - r,
+            rect.width + 2 * self.radius,
+            rect.height + 2 * self.radius
+        )
+    }
+
+    /**
+     * Coverage of the pixels from `start` on by a shadow edge from `e0` to
+     * `e1`: the edge blurred with a Gaussian whose standard deviation is
+     * half the radius.
+     **/
+    pub fn profile(&self, e0: i32, e1: i32, start: i32, len: u32) -> Vec<f32> {
+        let sigma = self.radius as f32 / 2.0;
+        (start..start + len as i32).map(|p| {
+            let p = p as f32 + 0.5;
+            if sigma == 0.0 {
+                return if p >= e0 as f32 && p < e1 as f32 { 1.0 } else { 0.0 };
+            }
+            let scale = 1.0 / (sigma * std::f32::consts::SQRT_2);
+            0.5 * (erf((e1 as f32 - p) * scale) - erf((e0 as f32 - p) * scale))
+        }).collect()
+    }
+}
+
+/**
+ * The error function, to within 1.5e-7 (Abramowitz and Stegun 7.1.26).
+ **/
+fn erf(x: f32) -> f32 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t * (0.254_829_6 + t * (-0.284_496_7 + t * (1.421_413_7 + t * (-1.453_152 + t * 1.061_405_4))));
+    let y = 1.0 - poly * (-x * x).exp();
+    if x < 0.0 { -y } else { y }
+}
+
+/**
+ * Radii of three box blurs that together approximate a Gaussian blur with
+ * standard deviation `sigma`.
+ **/
+pub fn gaussian_boxes(sigma: f32) -> [u32; 3] {
+    let n = 3.0;
+    let ideal = (12.0 * sigma * sigma / n + 1.0).sqrt();
+    let mut lower = ideal.floor() as i32;
+    if lower % 2 == 0 {
+        lower -= 1;
+    }
+    let lower = lower.max(1);
+    let upper = lower + 2;
+    let l = lower as f32;
+    let m = ((12.0 * sigma * sigma - n * l * l - 4.0 * n * l - 3.0 * n) / (-4.0 * l - 4.0)).round() as i32;
+    let mut radii = [0; 3];
+    for (i, r) in radii.iter_mut().enumerate() {
+        let size = if (i as i32) < m { lower } else { upper };
+        *r = (size as u32 - 1) / 2;
+    }
+    radii
+}
+
+/**
+ * Averages every pixel of a `width` x `height` region with its neighbours
+ * up to `radius` pixels away, first horizontally, then vertically. Pixels
+ * beyond the region's edges repeat the edge pixels.
+ **/
+pub fn box_blur(pixels: &mut [u8], stride: usize, format: Format, width: u32, height: u32, radius: u32) {
+    if radius == 0 || width == 0 || height == 0 {
+        return;
+    }
+    let bpp = format.bytes_per_pixel() as usize;
+    let (w, h) = (width as usize, height as usize);
+
+    let mut colors: Vec<[u32; 4]> = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let c = format.read(&pixels[y * stride + x * bpp..]);
+            colors.push([c.r as u32, c.g as u32, c.b as u32, c.a as u32]);
+        }
+    }
+
+    let mut line = Vec::with_capacity(w.max(h));
+    for y in 0..h {
+        line.clear();
+        line.extend_from_slice(&colors[y * w..(y + 1) * w]);
+        blur_line(&line, radius as usize, |x, c| colors[y * w + x] = c);
+    }
+    for x in 0..w {
+        line.clear();
+        line.extend((0..h).map(|y| colors[y * w + x]));
+        blur_line(&line, radius as usize, |y, c| colors[y * w + x] = c);
+    }
+
+    for y in 0..h {
+        for x in 0..w {
+            let c = colors[y * w + x];
+            let c = Color::new(c[0] as u8, c[1] as u8, c[2] as u8, c[3] as u8);
+            format.write(&mut pixels[y * stride + x * bpp..], &c);
+        }
+    }
+}
+
+/**
+ * Box blurs one line with a running sum.
+ **/
+fn blur_line<F: FnMut(usize, [u32; 4])>(line: &[[u32; 4]], radius: usize, mut put: F) {
+    let len = line.len();
+    let size = 2 * radius as u32 + 1;
+    let at = |i: isize| line[i.max(0).min(len as isize - 1) as usize];
+
+    let mut sum = [0u32; 4];
+    for i in -(radius as isize)..=radius as isize {
+        let c = at(i);
+        for k in 0..4 {
+            sum[k] += c[k];
+        }
+    }
+    for i in 0..len {
+        let mut avg = [0; 4];
+        for k in 0..4 {
+            avg[k] = (sum[k] + size / 2) / size;
+        }
+        put(i, avg);
+        let (add, remove) = (at(i as isize + radius as isize + 1), at(i as isize - radius as isize));
+        for k in 0..4 {
+            sum[k] = sum[k] + add[k] - remove[k];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_gradient_stops() {
+        let mut g = Gradient::linear(0.0, 0.0, 10.0, 0.0);
+        g.add_stop(1.0, &Color::new(0, 0, 255, 255));
+        g.add_stop(0.0, &Color::new(255, 0, 0, 255));
+        assert_eq!(g.color_at(-5, 0), Color::new(255, 0, 0, 255));
+        assert_eq!(g.color_at(4, 7), Color::new(140, 0, 115, 255));
+        assert_eq!(g.color_at(20, 0), Color::new(0, 0, 255, 255));
+
+        let mut r = Gradient::radial(0.0, 0.0, 10.0);
+        r.add_stop(0.0, &Color::new(255, 255, 255, 255));
+        r.add_stop(1.0, &Color::new(255, 255, 255, 0));
+        assert_eq!(r.color_at(0, 0), Color::new(237, 237, 237, 237));
+        assert_eq!(r.color_at(6, 7).a, 2);
+        assert_eq!(r.color_at(8, 8), Color::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn shadow_profile_fades_out() {
+        let shadow = Shadow::new(0, 0, 8, Color::new(0, 0, 0, 255));
+        let profile = shadow.profile(0, 100, -10, 20);
+        assert!(profile[0] < 0.01);
+        assert!((profile[10] - 0.55).abs() < 0.05);
+        assert!(profile[19] > 0.99);
+        assert!(profile.windows(2).all(|w| w[0] <= w[1]));
+
+        let hard = Shadow::new(0, 0, 0, Color::new(0, 0, 0, 255));
+        assert_eq!(hard.profile(2, 4, 0, 6), vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn box_blur_preserves_flat_areas() {
+        let format = Format::Xrgb8888;
+        let mut pixels = vec![0u8; 5 * 4 * 3];
+        for (i, pixel) in pixels.chunks_mut(4).enumerate() {
+            let v = if i % 5 == 2 { 255 } else { 0 };
+            format.write(pixel, &Color::new(v, 100, 0, 255));
+        }
+        box_blur(&mut pixels, 20, format, 5, 3, 1);
+        for y in 0..3 {
+            let row: Vec<u8> = (0..5).map(|x| format.read(&pixels[y * 20 + x * 4..]).r).collect();
+            assert_eq!(row, vec![0, 85, 85, 85, 0]);
+            assert_eq!(format.read(&pixels[y * 20..]).g, 100);
+        }
+    }
+
+    #[test]
+    fn gaussian_boxes_match_variance() {
+        // a box of size w has a variance of (w^2 - 1) / 12
+        for &sigma in [2.0f32, 5.0, 10.0, 30.0].iter() {
+            let variance: f32 = gaussian_boxes(sigma).iter()
+                .map(|&r| ((2 * r + 1) * (2 * r + 1) - 1) as f32 / 12.0)
+                .sum();
+            assert!((variance - sigma * sigma).abs() < 0.25 * sigma * sigma, "{} {}", sigma, variance);
+        }
+    }
+}
@@ -0,0 +1,799 @@
+extern crate libc;
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::os::raw::c_ulong;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::slice;
+use std::time::Duration;
+
+use super::frame_clock::poll_readable;
+use super::input::{Button, InputEvent};
+
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_REL: u16 = 0x02;
+pub const EV_ABS: u16 = 0x03;
+pub const EV_MSC: u16 = 0x04;
+pub const EV_SW: u16 = 0x05;
+pub const EV_LED: u16 = 0x11;
+pub const EV_MAX: u16 = 0x1f;
+
+pub const SYN_REPORT: u16 = 0;
+pub const SYN_DROPPED: u16 = 3;
+
+pub const REL_X: u16 = 0x00;
+pub const REL_Y: u16 = 0x01;
+pub const REL_HWHEEL: u16 = 0x06;
+pub const REL_WHEEL: u16 = 0x08;
+pub const REL_MAX: u16 = 0x0f;
+
+pub const ABS_X: u16 = 0x00;
+pub const ABS_Y: u16 = 0x01;
+pub const ABS_MT_POSITION_X: u16 = 0x35;
+pub const ABS_MAX: u16 = 0x3f;
+
+/// Codes from here up to `BTN_DIGI_LAST` are buttons rather than keys.
+pub const BTN_MISC: u16 = 0x100;
+pub const BTN_MOUSE: u16 = 0x110;
+pub const BTN_TOOL_PEN: u16 = 0x140;
+pub const BTN_TOOL_FINGER: u16 = 0x145;
+pub const BTN_TOUCH: u16 = 0x14a;
+pub const BTN_STYLUS: u16 = 0x14b;
+pub const BTN_DIGI_LAST: u16 = 0x15f;
+pub const KEY_MAX: u16 = 0x2ff;
+
+pub const LED_NUML: u16 = 0x00;
+pub const LED_CAPSL: u16 = 0x01;
+pub const LED_MAX: u16 = 0x0f;
+pub const SW_MAX: u16 = 0x10;
+
+/**
+ * Pointer speed on touchpads, in pixels per millimeter moved.
+ **/
+const TOUCHPAD_PIXELS_PER_MM: f32 = 5.0;
+
+/**
+ * Assumed touchpad width when the device doesn't tell its resolution.
+ **/
+const TOUCHPAD_WIDTH_MM: f32 = 100.0;
+
+/**
+ * `BTN_TOOL_*` codes tell which tool or how many fingers touch, rather
+ * than being buttons.
+ **/
+fn is_tool(code: u16) -> bool {
+    (BTN_TOOL_PEN..=0x148).contains(&code) || (0x14d..=0x14f).contains(&code)
+}
+
+/**
+ * Size of a `struct input_event`, which depends on the size of `long`.
+ **/
+pub const EVENT_SIZE: usize = mem::size_of::<libc::input_event>();
+
+/**
+ * `_IOR('E', nr, size)`.
+ **/
+fn ior(nr: u32, size: usize) -> c_ulong {
+    ((2 << 30) | ((size as u32) << 16) | (0x45 << 8) | nr) as c_ulong
+}
+
+fn eviocgname(len: usize) -> c_ulong {
+    ior(0x06, len)
+}
+
+fn eviocgbit(ev: u16, len: usize) -> c_ulong {
+    ior(0x20 + ev as u32, len)
+}
+
+fn eviocgkey(len: usize) -> c_ulong {
+    ior(0x18, len)
+}
+
+fn eviocgabs(axis: u16) -> c_ulong {
+    ior(0x40 + axis as u32, mem::size_of::<libc::input_absinfo>())
+}
+
+/**
+ * Runs an `EVIOCG*` ioctl that fills `buffer`, returning how many bytes it
+ * wrote.
+ **/
+fn ioctl_read(fd: &File, request: c_ulong, buffer: &mut [u8]) -> io::Result<usize> {
+    let ret = unsafe { libc::ioctl(fd.as_raw_fd(), request, buffer.as_mut_ptr()) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ret as usize)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyState {
+    Released,
+    Pressed,
+    /// Sent by the kernel's own key repeat while a key is held.
+    Repeated
+}
+
+/**
+ * The payload of an evdev event, by event type.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// `SYN_REPORT` ends a group of events that happened together;
+    /// `SYN_DROPPED` means events were lost because they weren't read in
+    /// time.
+    Sync(u16),
+    Key { code: u16, state: KeyState },
+    Relative { axis: u16, value: i32 },
+    Absolute { axis: u16, value: i32 },
+    Led { led: u16, on: bool },
+    Switch { switch: u16, on: bool },
+    Other { kind: u16, code: u16, value: i32 }
+}
+
+/**
+ * A `struct input_event`, with its timestamp.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub time: Duration,
+    pub kind: EventKind
+}
+
+impl Event {
+    pub fn new(time: Duration, kind: u16, code: u16, value: i32) -> Event {
+        let kind = match kind {
+            EV_SYN => EventKind::Sync(code),
+            EV_KEY => {
+                let state = match value {
+                    0 => KeyState::Released,
+                    2 => KeyState::Repeated,
+                    _ => KeyState::Pressed
+                };
+                EventKind::Key { code, state }
+            }
+            EV_REL => EventKind::Relative { axis: code, value },
+            EV_ABS => EventKind::Absolute { axis: code, value },
+            EV_LED => EventKind::Led { led: code, on: value != 0 },
+            EV_SW => EventKind::Switch { switch: code, on: value != 0 },
+            kind => EventKind::Other { kind, code, value }
+        };
+        Event { time, kind }
+    }
+
+    /**
+     * Parses a `struct input_event`. Returns `None` if `bytes` is too short.
+     **/
+    pub fn parse(bytes: &[u8]) -> Option<Event> {
+        if bytes.len() < EVENT_SIZE {
+            return None;
+        }
+        let raw: libc::input_event = unsafe { ptr::read_unaligned(bytes.as_ptr() as *const _) };
+        // the kernel never sends these out of range, but a bad timestamp
+        // shouldn't overflow: clamp it to the valid one closest to it
+        let secs = Duration::from_secs(raw.time.tv_sec.max(0) as u64);
+        let micros = Duration::from_micros(raw.time.tv_usec.clamp(0, 999_999) as u64);
+        let time = secs + micros;
+        Some(Event::new(time, raw.type_, raw.code, raw.value))
+    }
+
+    /**
+     * The event as `(type, code, value)`.
+     **/
+    pub fn raw(&self) -> (u16, u16, i32) {
+        match self.kind {
+            EventKind::Sync(code) => (EV_SYN, code, 0),
+            EventKind::Key { code, state } => (EV_KEY, code, match state {
+                KeyState::Released => 0,
+                KeyState::Pressed => 1,
+                KeyState::Repeated => 2
+            }),
+            EventKind::Relative { axis, value } => (EV_REL, axis, value),
+            EventKind::Absolute { axis, value } => (EV_ABS, axis, value),
+            EventKind::Led { led, on } => (EV_LED, led, on as i32),
+            EventKind::Switch { switch, on } => (EV_SW, switch, on as i32),
+            EventKind::Other { kind, code, value } => (kind, code, value)
+        }
+    }
+
+    /**
+     * The event as a `struct input_event`.
+     **/
+    pub fn to_bytes(self) -> Vec<u8> {
+        let (kind, code, value) = self.raw();
+        let mut raw: libc::input_event = unsafe { mem::zeroed() };
+        raw.time.tv_sec = self.time.as_secs() as libc::time_t;
+        raw.time.tv_usec = self.time.subsec_micros() as libc::suseconds_t;
+        raw.type_ = kind;
+        raw.code = code;
+        raw.value = value;
+        let bytes = unsafe { slice::from_raw_parts(&raw as *const _ as *const u8, EVENT_SIZE) };
+        bytes.to_vec()
+    }
+}
+
+/**
+ * A bit set as returned by `EVIOCGBIT`, one bit per code.
+ **/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bits(Vec<u8>);
+
+impl Bits {
+    pub fn from_bytes(bytes: &[u8]) -> Bits {
+        Bits(bytes.to_vec())
+    }
+
+    pub fn has(&self, code: u16) -> bool {
+        self.0.get(code as usize / 8).is_some_and(|b| b & (1 << (code % 8)) != 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..self.0.len() as u32 * 8).map(|code| code as u16).filter(move |&code| self.has(code))
+    }
+}
+
+/**
+ * The event types and codes a device can send.
+ **/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    pub events: Bits,
+    pub keys: Bits,
+    pub relative: Bits,
+    pub absolute: Bits,
+    pub leds: Bits,
+    pub switches: Bits
+}
+
+impl Capabilities {
+    fn query(fd: &File) -> io::Result<Capabilities> {
+        let bits = |ev: u16, max: u16| -> io::Result<Bits> {
+            let mut buffer = vec![0u8; max as usize / 8 + 1];
+            let len = ioctl_read(fd, eviocgbit(ev, buffer.len()), &mut buffer)?;
+            buffer.truncate(len);
+            Ok(Bits(buffer))
+        };
+        let events = bits(0, EV_MAX)?;
+        let query = |ev: u16, max: u16| if events.has(ev) { bits(ev, max) } else { Ok(Bits::default()) };
+        Ok(Capabilities {
+            keys: query(EV_KEY, KEY_MAX)?,
+            relative: query(EV_REL, REL_MAX)?,
+            absolute: query(EV_ABS, ABS_MAX)?,
+            leds: query(EV_LED, LED_MAX)?,
+            switches: query(EV_SW, SW_MAX)?,
+            events
+        })
+    }
+
+    pub fn has(&self, kind: u16, code: u16) -> bool {
+        let bits = match kind {
+            EV_KEY => &self.keys,
+            EV_REL => &self.relative,
+            EV_ABS => &self.absolute,
+            EV_LED => &self.leds,
+            EV_SW => &self.switches,
+            _ => return self.events.has(kind)
+        };
+        self.events.has(kind) && bits.has(code)
+    }
+}
+
+/**
+ * Range of an absolute axis, from `EVIOCGABS`.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AbsInfo {
+    pub value: i32,
+    pub minimum: i32,
+    pub maximum: i32,
+    /// Units per millimeter, 0 if unknown.
+    pub resolution: i32
+}
+
+impl AbsInfo {
+    /**
+     * Maps `value` from the axis range to `0..length`.
+     **/
+    pub fn scale(&self, value: i32, length: u32) -> i32 {
+        let range = self.maximum as i64 - self.minimum as i64 + 1;
+        if range <= 0 || length == 0 {
+            return 0;
+        }
+        let v = (value as i64 - self.minimum as i64).clamp(0, range - 1);
+        (v * length as i64 / range) as i32
+    }
+}
+
+/**
+ * Turns groups of evdev events, each ended by `SYN_REPORT`, into compositor
+ * input events. Motion within a group is summed up, so a diagonal mouse
+ * movement gives one `PointerMotion` rather than one per axis.
+ **/
+#[derive(Debug, Default)]
+pub struct Translator {
+    dx: i32,
+    dy: i32,
+    wheel_x: i32,
+    wheel_y: i32,
+    x: Option<i32>,
+    y: Option<i32>,
+    moved: bool,
+    buttons: Vec<InputEvent>,
+    /// After `SYN_DROPPED`, events are ignored up to the next report.
+    dropping: bool,
+    /// Set by that report, until the keys are synced with the device.
+    dropped: bool,
+    /// Keys and buttons that were reported pressed.
+    held: Vec<u16>,
+    abs_x: Option<AbsInfo>,
+    abs_y: Option<AbsInfo>,
+    output: (u32, u32),
+    /// Touchpads move the pointer by the distance the finger moved.
+    touchpad: bool,
+    touching: bool,
+    last: Option<(i32, i32)>,
+    /// Fractions of a pixel left over from touchpad motion.
+    remainder: (f32, f32)
+}
+
+impl Translator {
+    pub fn new() -> Translator {
+        Translator::default()
+    }
+
+    /**
+     * Sets the ranges of `ABS_X` and `ABS_Y` and the output size they are
+     * mapped to.
+     **/
+    pub fn set_absolute(&mut self, x: Option<AbsInfo>, y: Option<AbsInfo>, width: u32, height: u32) {
+        self.abs_x = x;
+        self.abs_y = y;
+        self.output = (width, height);
+    }
+
+    /**
+     * Makes absolute motion move the pointer relatively while a finger is
+     * down, as touchpads do.
+     **/
+    pub fn set_touchpad(&mut self, touchpad: bool) {
+        self.touchpad = touchpad;
+    }
+
+    pub fn feed(&mut self, event: &Event, out: &mut Vec<InputEvent>) {
+        match event.kind {
+            EventKind::Sync(SYN_DROPPED) => {
+                self.reset();
+                self.dropping = true;
+            }
+            EventKind::Sync(SYN_REPORT) => {
+                if !self.dropping {
+                    self.report(out);
+                }
+                self.reset();
+                self.dropped |= self.dropping;
+                self.dropping = false;
+            }
+            _ if self.dropping => {}
+            EventKind::Relative { axis, value } => match axis {
+                REL_X => self.dx += value,
+                REL_Y => self.dy += value,
+                REL_WHEEL => self.wheel_y += value,
+                REL_HWHEEL => self.wheel_x += value,
+                _ => {}
+            },
+            EventKind::Absolute { axis, value } => match axis {
+                ABS_X => {
+                    self.x = Some(value);
+                    self.moved = true;
+                }
+                ABS_Y => {
+                    self.y = Some(value);
+                    self.moved = true;
+                }
+                _ => {}
+            },
+            // autorepeat (value 2) is dropped on purpose: a held key is one
+            // press and one release, nothing repeats it
+            EventKind::Key { state: KeyState::Repeated, .. } => {}
+            EventKind::Key { code, .. } if is_tool(code) => {}
+            EventKind::Key { code: BTN_TOUCH, state } if self.touchpad => {
+                self.touching = state == KeyState::Pressed;
+                if !self.touching {
+                    self.last = None;
+                }
+            }
+            EventKind::Key { code, state } => self.key(code, state == KeyState::Pressed),
+            _ => {}
+        }
+    }
+
+    fn key(&mut self, code: u16, pressed: bool) {
+        self.held.retain(|&held| held != code);
+        if pressed {
+            self.held.push(code);
+        }
+        self.buttons.push(match code {
+            // touchscreens and pen tips click where they touch
+            BTN_TOUCH => InputEvent::PointerButton { button: Button::Left, pressed },
+            BTN_MISC..=BTN_DIGI_LAST => InputEvent::PointerButton { button: Button::from_code(code), pressed },
+            _ => InputEvent::Key { key: code, pressed }
+        });
+    }
+
+    /**
+     * Whether events were dropped, so that key releases may have been lost.
+     * The keys should then be synced with the device's state.
+     **/
+    pub fn dropped(&self) -> bool {
+        self.dropped
+    }
+
+    /**
+     * Releases the keys that were reported pressed but aren't in `keys`, the
+     * keys the device has down (`EVIOCGKEY`).
+     **/
+    pub fn sync_keys(&mut self, keys: &Bits, out: &mut Vec<InputEvent>) {
+        self.dropped = false;
+        for code in self.held.clone() {
+            if !keys.has(code) {
+                self.key(code, false);
+            }
+        }
+        out.append(&mut self.buttons);
+        if self.touching && !keys.has(BTN_TOUCH) {
+            self.touching = false;
+            self.last = None;
+        }
+    }
+
+    fn report(&mut self, out: &mut Vec<InputEvent>) {
+        if self.touchpad {
+            self.touchpad_motion();
+        }
+        if self.dx != 0 || self.dy != 0 {
+            out.push(InputEvent::PointerMotion { dx: self.dx, dy: self.dy });
+        }
+        if self.moved && !self.touchpad {
+            if let (Some(x), Some(y), Some(abs_x), Some(abs_y)) = (self.x, self.y, self.abs_x, self.abs_y) {
+                out.push(InputEvent::PointerPosition {
+                    x: abs_x.scale(x, self.output.0),
+                    y: abs_y.scale(y, self.output.1)
+                });
+            }
+        }
+        if self.wheel_x != 0 || self.wheel_y != 0 {
+            out.push(InputEvent::PointerAxis { dx: self.wheel_x, dy: self.wheel_y });
+        }
+        out.append(&mut self.buttons);
+    }
+
+    fn touchpad_motion(&mut self) {
+        let (x, y) = match (self.x, self.y) {
+            (Some(x), Some(y)) if self.touching => (x, y),
+            _ => return
+        };
+        if let (Some((last_x, last_y)), Some(abs_x), Some(abs_y)) = (self.last, self.abs_x, self.abs_y) {
+            let factor = |info: AbsInfo| {
+                let units_per_mm = if info.resolution > 0 {
+                    info.resolution as f32
+                } else {
+                    (info.maximum - info.minimum).max(1) as f32 / TOUCHPAD_WIDTH_MM
+                };
+                TOUCHPAD_PIXELS_PER_MM / units_per_mm
+            };
+            let dx = (x - last_x) as f32 * factor(abs_x) + self.remainder.0;
+            let dy = (y - last_y) as f32 * factor(abs_y) + self.remainder.1;
+            self.remainder = (dx.fract(), dy.fract());
+            self.dx += dx.trunc() as i32;
+            self.dy += dy.trunc() as i32;
+        }
+        self.last = Some((x, y));
+    }
+
+    /**
+     * Forgets the current group. Absolute positions are kept, since devices
+     * only send the axes that changed.
+     **/
+    fn reset(&mut self) {
+        self.dx = 0;
+        self.dy = 0;
+        self.wheel_x = 0;
+        self.wheel_y = 0;
+        self.moved = false;
+        self.buttons.clear();
+    }
+}
+
+/**
+ * An open `/dev/input/event*` device.
+ **/
+#[derive(Debug)]
+pub struct Device {
+    file: File,
+    path: PathBuf,
+    name: String,
+    capabilities: Capabilities,
+    translator: Translator,
+    /// Bytes of an event that was only partly read.
+    partial: Vec<u8>
+}
+
+impl Device {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Device> {
+        let path = path.as_ref();
+        let open = |write: bool| {
+            OpenOptions::new()
+                .read(true)
+                .write(write)
+                .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+                .open(path)
+        };
+        // writing is only needed for LEDs, so read-only access will do
+        let file = open(true).or_else(|_| open(false))?;
+
+        let mut name = [0u8; 256];
+        let len = ioctl_read(&file, eviocgname(name.len()), &mut name)?;
+        let name = String::from_utf8_lossy(&name[..len]).trim_end_matches('\0').to_string();
+        let capabilities = Capabilities::query(&file)?;
+
+        let mut device = Device {
+            file,
+            path: path.to_path_buf(),
+            name,
+            capabilities,
+            translator: Translator::new(),
+            partial: Vec::new()
+        };
+        device.set_output_size(0, 0)?;
+        Ok(device)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    /**
+     * Queries the range of an absolute axis.
+     **/
+    pub fn abs_info(&self, axis: u16) -> io::Result<Option<AbsInfo>> {
+        if !self.capabilities.has(EV_ABS, axis) {
+            return Ok(None);
+        }
+        let mut info: libc::input_absinfo = unsafe { mem::zeroed() };
+        if unsafe { libc::ioctl(self.file.as_raw_fd(), eviocgabs(axis), &mut info) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Some(AbsInfo {
+            value: info.value,
+            minimum: info.minimum,
+            maximum: info.maximum,
+            resolution: info.resolution
+        }))
+    }
+
+    /**
+     * Queries which keys and buttons are down.
+     **/
+    pub fn key_state(&self) -> io::Result<Bits> {
+        if !self.capabilities.events.has(EV_KEY) {
+            return Ok(Bits::default());
+        }
+        let mut buffer = vec![0u8; KEY_MAX as usize / 8 + 1];
+        let len = ioctl_read(&self.file, eviocgkey(buffer.len()), &mut buffer)?;
+        buffer.truncate(len);
+        Ok(Bits(buffer))
+    }
+
+    /**
+     * Sets the size of the output that absolute positions, e.g. from a
+     * touchscreen, are mapped to.
+     **/
+    pub fn set_output_size(&mut self, width: u32, height: u32) -> io::Result<()> {
+        let (x, y) = (self.abs_info(ABS_X)?, self.abs_info(ABS_Y)?);
+        self.translator.set_absolute(x, y, width, height);
+        Ok(())
+    }
+
+    pub fn set_touchpad(&mut self, touchpad: bool) {
+        self.translator.set_touchpad(touchpad);
+    }
+
+    /**
+     * Turns a keyboard LED on or off.
+     **/
+    pub fn set_led(&mut self, led: u16, on: bool) -> io::Result<()> {
+        let mut bytes = Event::new(Duration::new(0, 0), EV_LED, led, on as i32).to_bytes();
+        bytes.extend(Event::new(Duration::new(0, 0), EV_SYN, SYN_REPORT, 0).to_bytes());
+        self.file.write_all(&bytes)
+    }
+
+    /**
+     * Waits until the device has events to read. Returns false on timeout.
+     **/
+    pub fn wait(&self, timeout: Option<Duration>) -> io::Result<bool> {
+        poll_readable(&self.file, timeout)
+    }
+
+    /**
+     * Reads all pending events without blocking.
+     **/
+    pub fn read(&mut self) -> io::Result<Vec<Event>> {
+        let mut events = Vec::new();
+        let mut buffer = [0u8; 64 * EVENT_SIZE];
+        loop {
+            let len = match self.file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e)
+            };
+            self.partial.extend_from_slice(&buffer[..len]);
+            let whole = self.partial.len() / EVENT_SIZE * EVENT_SIZE;
+            events.extend(self.partial[..whole].chunks(EVENT_SIZE).filter_map(Event::parse));
+            self.partial.drain(..whole);
+        }
+        Ok(events)
+    }
+
+    /**
+     * Reads all pending events and translates the complete groups.
+     **/
+    pub fn input_events(&mut self) -> io::Result<Vec<InputEvent>> {
+        let mut out = Vec::new();
+        for event in self.read()? {
+            self.translator.feed(&event, &mut out);
+            if self.translator.dropped() {
+                let keys = self.key_state()?;
+                self.translator.sync_keys(&keys, &mut out);
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: u16, code: u16, value: i32) -> Event {
+        Event::new(Duration::new(0, 0), kind, code, value)
+    }
+
+    fn translate(translator: &mut Translator, events: &[(u16, u16, i32)]) -> Vec<InputEvent> {
+        let mut out = Vec::new();
+        for &(kind, code, value) in events {
+            translator.feed(&event(kind, code, value), &mut out);
+        }
+        out
+    }
+
+    #[test]
+    fn parses_input_events() {
+        let e = Event::new(Duration::new(12, 345_000), EV_REL, REL_WHEEL, -1);
+        let bytes = e.to_bytes();
+        assert_eq!(bytes.len(), EVENT_SIZE);
+        assert_eq!(Event::parse(&bytes), Some(e));
+        assert_eq!(e.kind, EventKind::Relative { axis: REL_WHEEL, value: -1 });
+        assert_eq!(Event::parse(&bytes[1..]), None);
+
+        let mut raw: libc::input_event = unsafe { mem::zeroed() };
+        raw.time.tv_sec = -1;
+        raw.time.tv_usec = libc::suseconds_t::MAX;
+        let bytes = unsafe { slice::from_raw_parts(&raw as *const _ as *const u8, EVENT_SIZE) };
+        assert_eq!(Event::parse(bytes).unwrap().time, Duration::from_micros(999_999));
+        assert_eq!(event(EV_KEY, 30, 2).kind, EventKind::Key { code: 30, state: KeyState::Repeated });
+
+        let bits = Bits::from_bytes(&[0b0000_0110, 0, 0b1000_0000]);
+        assert!(bits.has(EV_KEY) && bits.has(EV_REL) && bits.has(23));
+        assert!(!bits.has(EV_SYN) && !bits.has(100));
+        assert_eq!(bits.iter().collect::<Vec<_>>(), vec![1, 2, 23]);
+    }
+
+    #[test]
+    fn groups_events_by_report() {
+        let mut t = Translator::new();
+        let out = translate(&mut t, &[
+            (EV_REL, REL_X, 3), (EV_REL, REL_Y, -2), (EV_REL, REL_X, 1),
+            (EV_KEY, BTN_MOUSE, 1), (EV_REL, REL_WHEEL, -1)
+        ]);
+        assert!(out.is_empty());
+        let out = translate(&mut t, &[(EV_SYN, SYN_REPORT, 0)]);
+        assert_eq!(out, vec![
+            InputEvent::PointerMotion { dx: 4, dy: -2 },
+            InputEvent::PointerAxis { dx: 0, dy: -1 },
+            InputEvent::PointerButton { button: Button::Left, pressed: true }
+        ]);
+
+        // a key, its repeat and release
+        let out = translate(&mut t, &[
+            (EV_KEY, 30, 1), (EV_SYN, SYN_REPORT, 0), (EV_KEY, 30, 2), (EV_SYN, SYN_REPORT, 0),
+            (EV_KEY, 30, 0), (EV_SYN, SYN_REPORT, 0)
+        ]);
+        assert_eq!(out, vec![InputEvent::Key { key: 30, pressed: true }, InputEvent::Key { key: 30, pressed: false }]);
+
+        // nothing is reported between a drop and the next report
+        let out = translate(&mut t, &[
+            (EV_REL, REL_X, 5), (EV_SYN, SYN_DROPPED, 0), (EV_REL, REL_X, 7),
+            (EV_SYN, SYN_REPORT, 0), (EV_REL, REL_Y, 1), (EV_SYN, SYN_REPORT, 0)
+        ]);
+        assert_eq!(out, vec![InputEvent::PointerMotion { dx: 0, dy: 1 }]);
+    }
+
+    #[test]
+    fn releases_keys_lost_in_a_drop() {
+        let mut t = Translator::new();
+        let out = translate(&mut t, &[
+            (EV_KEY, 30, 1), (EV_KEY, BTN_MOUSE, 1), (EV_SYN, SYN_REPORT, 0),
+            (EV_SYN, SYN_DROPPED, 0), (EV_KEY, 30, 0), (EV_SYN, SYN_REPORT, 0)
+        ]);
+        assert_eq!(out.len(), 2);
+        assert!(t.dropped());
+
+        // the device says only the mouse button is still down
+        let mut keys = vec![0u8; KEY_MAX as usize / 8 + 1];
+        keys[BTN_MOUSE as usize / 8] |= 1 << (BTN_MOUSE % 8);
+        let mut out = Vec::new();
+        t.sync_keys(&Bits::from_bytes(&keys), &mut out);
+        assert_eq!(out, vec![InputEvent::Key { key: 30, pressed: false }]);
+        assert!(!t.dropped());
+
+        t.sync_keys(&Bits::default(), &mut out);
+        assert_eq!(out[1], InputEvent::PointerButton { button: Button::Left, pressed: false });
+        assert_eq!(out.len(), 2);
+    }
+
+    #[test]
+    fn maps_absolute_positions() {
+        let info = AbsInfo { value: 0, minimum: 0, maximum: 4095, resolution: 0 };
+        let mut t = Translator::new();
+        t.set_absolute(Some(info), Some(info), 1024, 768);
+        let out = translate(&mut t, &[(EV_ABS, ABS_X, 2048), (EV_ABS, ABS_Y, 4095), (EV_SYN, SYN_REPORT, 0)]);
+        assert_eq!(out, vec![InputEvent::PointerPosition { x: 512, y: 767 }]);
+        // the other axis keeps its last value
+        let out = translate(&mut t, &[(EV_ABS, ABS_X, 0), (EV_SYN, SYN_REPORT, 0)]);
+        assert_eq!(out, vec![InputEvent::PointerPosition { x: 0, y: 767 }]);
+        assert_eq!(info.scale(-10, 1024), 0);
+
+        // a touchscreen tap
+        let out = translate(&mut t, &[(EV_KEY, BTN_TOUCH, 1), (EV_KEY, BTN_TOOL_FINGER, 1), (EV_SYN, SYN_REPORT, 0)]);
+        assert_eq!(out, vec![InputEvent::PointerButton { button: Button::Left, pressed: true }]);
+    }
+
+    #[test]
+    fn moves_pointer_with_touchpad() {
+        // 20 units per mm, so 4 units per pixel
+        let info = AbsInfo { value: 0, minimum: 0, maximum: 2000, resolution: 20 };
+        let mut t = Translator::new();
+        t.set_absolute(Some(info), Some(info), 1024, 768);
+        t.set_touchpad(true);
+        let out = translate(&mut t, &[
+            (EV_ABS, ABS_X, 100), (EV_ABS, ABS_Y, 100), (EV_KEY, BTN_TOUCH, 1), (EV_SYN, SYN_REPORT, 0),
+            (EV_ABS, ABS_X, 142), (EV_SYN, SYN_REPORT, 0),
+            (EV_ABS, ABS_X, 144), (EV_ABS, ABS_Y, 92), (EV_SYN, SYN_REPORT, 0)
+        ]);
+        assert_eq!(out, vec![InputEvent::PointerMotion { dx: 10, dy: 0 }, InputEvent::PointerMotion { dx: 1, dy: -2 }]);
+        // lifting the finger and putting it down elsewhere doesn't move
+        let out = translate(&mut t, &[
+            (EV_KEY, BTN_TOUCH, 0), (EV_SYN, SYN_REPORT, 0),
+            (EV_ABS, ABS_X, 1000), (EV_KEY, BTN_TOUCH, 1), (EV_SYN, SYN_REPORT, 0)
+        ]);
+        assert!(out.is_empty());
+    }
+}
@@ -221,7 +221,7 @@ fn bindgen_test_layout___kernel_fd_set() {
         concat!("Alignment of ", stringify!(__kernel_fd_set))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<__kernel_fd_set>())).fds_bits as *const _ as usize },
+        ::std::mem::offset_of!(__kernel_fd_set, fds_bits),
         0usize,
         concat!(
             "Offset of field: ",
@@ -271,7 +271,7 @@ fn bindgen_test_layout___kernel_fsid_t() {
         concat!("Alignment of ", stringify!(__kernel_fsid_t))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<__kernel_fsid_t>())).val as *const _ as usize },
+        ::std::mem::offset_of!(__kernel_fsid_t, val),
         0usize,
         concat!(
             "Offset of field: ",
@@ -323,7 +323,7 @@ fn bindgen_test_layout_drm_clip_rect() {
         concat!("Alignment of ", stringify!(drm_clip_rect))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_clip_rect>())).x1 as *const _ as usize },
+        ::std::mem::offset_of!(drm_clip_rect, x1),
         0usize,
         concat!(
             "Offset of field: ",
@@ -333,7 +333,7 @@ fn bindgen_test_layout_drm_clip_rect() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_clip_rect>())).y1 as *const _ as usize },
+        ::std::mem::offset_of!(drm_clip_rect, y1),
         2usize,
         concat!(
             "Offset of field: ",
@@ -343,7 +343,7 @@ fn bindgen_test_layout_drm_clip_rect() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_clip_rect>())).x2 as *const _ as usize },
+        ::std::mem::offset_of!(drm_clip_rect, x2),
         4usize,
         concat!(
             "Offset of field: ",
@@ -353,7 +353,7 @@ fn bindgen_test_layout_drm_clip_rect() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_clip_rect>())).y2 as *const _ as usize },
+        ::std::mem::offset_of!(drm_clip_rect, y2),
         6usize,
         concat!(
             "Offset of field: ",
@@ -382,7 +382,7 @@ fn bindgen_test_layout_drm_drawable_info() {
         concat!("Alignment of ", stringify!(drm_drawable_info))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_drawable_info>())).num_rects as *const _ as usize },
+        ::std::mem::offset_of!(drm_drawable_info, num_rects),
         0usize,
         concat!(
             "Offset of field: ",
@@ -392,7 +392,7 @@ fn bindgen_test_layout_drm_drawable_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_drawable_info>())).rects as *const _ as usize },
+        ::std::mem::offset_of!(drm_drawable_info, rects),
         8usize,
         concat!(
             "Offset of field: ",
@@ -429,7 +429,7 @@ fn bindgen_test_layout_drm_tex_region() {
         concat!("Alignment of ", stringify!(drm_tex_region))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_tex_region>())).next as *const _ as usize },
+        ::std::mem::offset_of!(drm_tex_region, next),
         0usize,
         concat!(
             "Offset of field: ",
@@ -439,7 +439,7 @@ fn bindgen_test_layout_drm_tex_region() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_tex_region>())).prev as *const _ as usize },
+        ::std::mem::offset_of!(drm_tex_region, prev),
         1usize,
         concat!(
             "Offset of field: ",
@@ -449,7 +449,7 @@ fn bindgen_test_layout_drm_tex_region() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_tex_region>())).in_use as *const _ as usize },
+        ::std::mem::offset_of!(drm_tex_region, in_use),
         2usize,
         concat!(
             "Offset of field: ",
@@ -459,7 +459,7 @@ fn bindgen_test_layout_drm_tex_region() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_tex_region>())).padding as *const _ as usize },
+        ::std::mem::offset_of!(drm_tex_region, padding),
         3usize,
         concat!(
             "Offset of field: ",
@@ -469,7 +469,7 @@ fn bindgen_test_layout_drm_tex_region() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_tex_region>())).age as *const _ as usize },
+        ::std::mem::offset_of!(drm_tex_region, age),
         4usize,
         concat!(
             "Offset of field: ",
@@ -498,7 +498,7 @@ fn bindgen_test_layout_drm_hw_lock() {
         concat!("Alignment of ", stringify!(drm_hw_lock))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_hw_lock>())).lock as *const _ as usize },
+        ::std::mem::offset_of!(drm_hw_lock, lock),
         0usize,
         concat!(
             "Offset of field: ",
@@ -508,7 +508,7 @@ fn bindgen_test_layout_drm_hw_lock() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_hw_lock>())).padding as *const _ as usize },
+        ::std::mem::offset_of!(drm_hw_lock, padding),
         4usize,
         concat!(
             "Offset of field: ",
@@ -549,7 +549,7 @@ fn bindgen_test_layout_drm_version() {
         concat!("Alignment of ", stringify!(drm_version))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_version>())).version_major as *const _ as usize },
+        ::std::mem::offset_of!(drm_version, version_major),
         0usize,
         concat!(
             "Offset of field: ",
@@ -559,7 +559,7 @@ fn bindgen_test_layout_drm_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_version>())).version_minor as *const _ as usize },
+        ::std::mem::offset_of!(drm_version, version_minor),
         4usize,
         concat!(
             "Offset of field: ",
@@ -569,7 +569,7 @@ fn bindgen_test_layout_drm_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_version>())).version_patchlevel as *const _ as usize },
+        ::std::mem::offset_of!(drm_version, version_patchlevel),
         8usize,
         concat!(
             "Offset of field: ",
@@ -579,7 +579,7 @@ fn bindgen_test_layout_drm_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_version>())).name_len as *const _ as usize },
+        ::std::mem::offset_of!(drm_version, name_len),
         16usize,
         concat!(
             "Offset of field: ",
@@ -589,7 +589,7 @@ fn bindgen_test_layout_drm_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_version>())).name as *const _ as usize },
+        ::std::mem::offset_of!(drm_version, name),
         24usize,
         concat!(
             "Offset of field: ",
@@ -599,7 +599,7 @@ fn bindgen_test_layout_drm_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_version>())).date_len as *const _ as usize },
+        ::std::mem::offset_of!(drm_version, date_len),
         32usize,
         concat!(
             "Offset of field: ",
@@ -609,7 +609,7 @@ fn bindgen_test_layout_drm_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_version>())).date as *const _ as usize },
+        ::std::mem::offset_of!(drm_version, date),
         40usize,
         concat!(
             "Offset of field: ",
@@ -619,7 +619,7 @@ fn bindgen_test_layout_drm_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_version>())).desc_len as *const _ as usize },
+        ::std::mem::offset_of!(drm_version, desc_len),
         48usize,
         concat!(
             "Offset of field: ",
@@ -629,7 +629,7 @@ fn bindgen_test_layout_drm_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_version>())).desc as *const _ as usize },
+        ::std::mem::offset_of!(drm_version, desc),
         56usize,
         concat!(
             "Offset of field: ",
@@ -663,7 +663,7 @@ fn bindgen_test_layout_drm_unique() {
         concat!("Alignment of ", stringify!(drm_unique))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_unique>())).unique_len as *const _ as usize },
+        ::std::mem::offset_of!(drm_unique, unique_len),
         0usize,
         concat!(
             "Offset of field: ",
@@ -673,7 +673,7 @@ fn bindgen_test_layout_drm_unique() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_unique>())).unique as *const _ as usize },
+        ::std::mem::offset_of!(drm_unique, unique),
         8usize,
         concat!(
             "Offset of field: ",
@@ -707,7 +707,7 @@ fn bindgen_test_layout_drm_list() {
         concat!("Alignment of ", stringify!(drm_list))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_list>())).count as *const _ as usize },
+        ::std::mem::offset_of!(drm_list, count),
         0usize,
         concat!(
             "Offset of field: ",
@@ -717,7 +717,7 @@ fn bindgen_test_layout_drm_list() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_list>())).version as *const _ as usize },
+        ::std::mem::offset_of!(drm_list, version),
         8usize,
         concat!(
             "Offset of field: ",
@@ -750,7 +750,7 @@ fn bindgen_test_layout_drm_block() {
         concat!("Alignment of ", stringify!(drm_block))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_block>())).unused as *const _ as usize },
+        ::std::mem::offset_of!(drm_block, unused),
         0usize,
         concat!(
             "Offset of field: ",
@@ -784,7 +784,7 @@ fn bindgen_test_layout_drm_control() {
         concat!("Alignment of ", stringify!(drm_control))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_control>())).func as *const _ as usize },
+        ::std::mem::offset_of!(drm_control, func),
         0usize,
         concat!(
             "Offset of field: ",
@@ -794,7 +794,7 @@ fn bindgen_test_layout_drm_control() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_control>())).irq as *const _ as usize },
+        ::std::mem::offset_of!(drm_control, irq),
         4usize,
         concat!(
             "Offset of field: ",
@@ -844,7 +844,7 @@ fn bindgen_test_layout_drm_ctx_priv_map() {
         concat!("Alignment of ", stringify!(drm_ctx_priv_map))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_ctx_priv_map>())).ctx_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_ctx_priv_map, ctx_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -854,7 +854,7 @@ fn bindgen_test_layout_drm_ctx_priv_map() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_ctx_priv_map>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_ctx_priv_map, handle),
         8usize,
         concat!(
             "Offset of field: ",
@@ -892,7 +892,7 @@ fn bindgen_test_layout_drm_map() {
         concat!("Alignment of ", stringify!(drm_map))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_map>())).offset as *const _ as usize },
+        ::std::mem::offset_of!(drm_map, offset),
         0usize,
         concat!(
             "Offset of field: ",
@@ -902,7 +902,7 @@ fn bindgen_test_layout_drm_map() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_map>())).size as *const _ as usize },
+        ::std::mem::offset_of!(drm_map, size),
         8usize,
         concat!(
             "Offset of field: ",
@@ -912,7 +912,7 @@ fn bindgen_test_layout_drm_map() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_map>())).type_ as *const _ as usize },
+        ::std::mem::offset_of!(drm_map, type_),
         16usize,
         concat!(
             "Offset of field: ",
@@ -922,7 +922,7 @@ fn bindgen_test_layout_drm_map() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_map>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_map, flags),
         20usize,
         concat!(
             "Offset of field: ",
@@ -932,7 +932,7 @@ fn bindgen_test_layout_drm_map() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_map>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_map, handle),
         24usize,
         concat!(
             "Offset of field: ",
@@ -942,7 +942,7 @@ fn bindgen_test_layout_drm_map() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_map>())).mtrr as *const _ as usize },
+        ::std::mem::offset_of!(drm_map, mtrr),
         32usize,
         concat!(
             "Offset of field: ",
@@ -980,7 +980,7 @@ fn bindgen_test_layout_drm_client() {
         concat!("Alignment of ", stringify!(drm_client))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_client>())).idx as *const _ as usize },
+        ::std::mem::offset_of!(drm_client, idx),
         0usize,
         concat!(
             "Offset of field: ",
@@ -990,7 +990,7 @@ fn bindgen_test_layout_drm_client() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_client>())).auth as *const _ as usize },
+        ::std::mem::offset_of!(drm_client, auth),
         4usize,
         concat!(
             "Offset of field: ",
@@ -1000,7 +1000,7 @@ fn bindgen_test_layout_drm_client() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_client>())).pid as *const _ as usize },
+        ::std::mem::offset_of!(drm_client, pid),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1010,7 +1010,7 @@ fn bindgen_test_layout_drm_client() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_client>())).uid as *const _ as usize },
+        ::std::mem::offset_of!(drm_client, uid),
         16usize,
         concat!(
             "Offset of field: ",
@@ -1020,7 +1020,7 @@ fn bindgen_test_layout_drm_client() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_client>())).magic as *const _ as usize },
+        ::std::mem::offset_of!(drm_client, magic),
         24usize,
         concat!(
             "Offset of field: ",
@@ -1030,7 +1030,7 @@ fn bindgen_test_layout_drm_client() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_client>())).iocs as *const _ as usize },
+        ::std::mem::offset_of!(drm_client, iocs),
         32usize,
         concat!(
             "Offset of field: ",
@@ -1081,7 +1081,7 @@ fn bindgen_test_layout_drm_stats__bindgen_ty_1() {
         concat!("Alignment of ", stringify!(drm_stats__bindgen_ty_1))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_stats__bindgen_ty_1>())).value as *const _ as usize },
+        ::std::mem::offset_of!(drm_stats__bindgen_ty_1, value),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1091,7 +1091,7 @@ fn bindgen_test_layout_drm_stats__bindgen_ty_1() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_stats__bindgen_ty_1>())).type_ as *const _ as usize },
+        ::std::mem::offset_of!(drm_stats__bindgen_ty_1, type_),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1119,7 +1119,7 @@ fn bindgen_test_layout_drm_stats() {
         concat!("Alignment of ", stringify!(drm_stats))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_stats>())).count as *const _ as usize },
+        ::std::mem::offset_of!(drm_stats, count),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1129,7 +1129,7 @@ fn bindgen_test_layout_drm_stats() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_stats>())).data as *const _ as usize },
+        ::std::mem::offset_of!(drm_stats, data),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1170,7 +1170,7 @@ fn bindgen_test_layout_drm_lock() {
         concat!("Alignment of ", stringify!(drm_lock))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_lock>())).context as *const _ as usize },
+        ::std::mem::offset_of!(drm_lock, context),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1180,7 +1180,7 @@ fn bindgen_test_layout_drm_lock() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_lock>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_lock, flags),
         4usize,
         concat!(
             "Offset of field: ",
@@ -1231,7 +1231,7 @@ fn bindgen_test_layout_drm_buf_desc() {
         concat!("Alignment of ", stringify!(drm_buf_desc))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_desc>())).count as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_desc, count),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1241,7 +1241,7 @@ fn bindgen_test_layout_drm_buf_desc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_desc>())).size as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_desc, size),
         4usize,
         concat!(
             "Offset of field: ",
@@ -1251,7 +1251,7 @@ fn bindgen_test_layout_drm_buf_desc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_desc>())).low_mark as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_desc, low_mark),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1261,7 +1261,7 @@ fn bindgen_test_layout_drm_buf_desc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_desc>())).high_mark as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_desc, high_mark),
         12usize,
         concat!(
             "Offset of field: ",
@@ -1271,7 +1271,7 @@ fn bindgen_test_layout_drm_buf_desc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_desc>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_desc, flags),
         16usize,
         concat!(
             "Offset of field: ",
@@ -1281,7 +1281,7 @@ fn bindgen_test_layout_drm_buf_desc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_desc>())).agp_start as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_desc, agp_start),
         24usize,
         concat!(
             "Offset of field: ",
@@ -1315,7 +1315,7 @@ fn bindgen_test_layout_drm_buf_info() {
         concat!("Alignment of ", stringify!(drm_buf_info))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_info>())).count as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_info, count),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1325,7 +1325,7 @@ fn bindgen_test_layout_drm_buf_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_info>())).list as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_info, list),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1359,7 +1359,7 @@ fn bindgen_test_layout_drm_buf_free() {
         concat!("Alignment of ", stringify!(drm_buf_free))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_free>())).count as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_free, count),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1369,7 +1369,7 @@ fn bindgen_test_layout_drm_buf_free() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_free>())).list as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_free, list),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1405,7 +1405,7 @@ fn bindgen_test_layout_drm_buf_pub() {
         concat!("Alignment of ", stringify!(drm_buf_pub))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_pub>())).idx as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_pub, idx),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1415,7 +1415,7 @@ fn bindgen_test_layout_drm_buf_pub() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_pub>())).total as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_pub, total),
         4usize,
         concat!(
             "Offset of field: ",
@@ -1425,7 +1425,7 @@ fn bindgen_test_layout_drm_buf_pub() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_pub>())).used as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_pub, used),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1435,7 +1435,7 @@ fn bindgen_test_layout_drm_buf_pub() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_pub>())).address as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_pub, address),
         16usize,
         concat!(
             "Offset of field: ",
@@ -1470,7 +1470,7 @@ fn bindgen_test_layout_drm_buf_map() {
         concat!("Alignment of ", stringify!(drm_buf_map))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_map>())).count as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_map, count),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1480,7 +1480,7 @@ fn bindgen_test_layout_drm_buf_map() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_map>())).virtual_ as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_map, virtual_),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1490,7 +1490,7 @@ fn bindgen_test_layout_drm_buf_map() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_buf_map>())).list as *const _ as usize },
+        ::std::mem::offset_of!(drm_buf_map, list),
         16usize,
         concat!(
             "Offset of field: ",
@@ -1532,7 +1532,7 @@ fn bindgen_test_layout_drm_dma() {
         concat!("Alignment of ", stringify!(drm_dma))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).context as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, context),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1542,7 +1542,7 @@ fn bindgen_test_layout_drm_dma() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).send_count as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, send_count),
         4usize,
         concat!(
             "Offset of field: ",
@@ -1552,7 +1552,7 @@ fn bindgen_test_layout_drm_dma() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).send_indices as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, send_indices),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1562,7 +1562,7 @@ fn bindgen_test_layout_drm_dma() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).send_sizes as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, send_sizes),
         16usize,
         concat!(
             "Offset of field: ",
@@ -1572,7 +1572,7 @@ fn bindgen_test_layout_drm_dma() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, flags),
         24usize,
         concat!(
             "Offset of field: ",
@@ -1582,7 +1582,7 @@ fn bindgen_test_layout_drm_dma() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).request_count as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, request_count),
         28usize,
         concat!(
             "Offset of field: ",
@@ -1592,7 +1592,7 @@ fn bindgen_test_layout_drm_dma() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).request_size as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, request_size),
         32usize,
         concat!(
             "Offset of field: ",
@@ -1602,7 +1602,7 @@ fn bindgen_test_layout_drm_dma() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).request_indices as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, request_indices),
         40usize,
         concat!(
             "Offset of field: ",
@@ -1612,7 +1612,7 @@ fn bindgen_test_layout_drm_dma() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).request_sizes as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, request_sizes),
         48usize,
         concat!(
             "Offset of field: ",
@@ -1622,7 +1622,7 @@ fn bindgen_test_layout_drm_dma() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_dma>())).granted_count as *const _ as usize },
+        ::std::mem::offset_of!(drm_dma, granted_count),
         56usize,
         concat!(
             "Offset of field: ",
@@ -1659,7 +1659,7 @@ fn bindgen_test_layout_drm_ctx() {
         concat!("Alignment of ", stringify!(drm_ctx))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_ctx>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_ctx, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1669,7 +1669,7 @@ fn bindgen_test_layout_drm_ctx() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_ctx>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_ctx, flags),
         4usize,
         concat!(
             "Offset of field: ",
@@ -1703,7 +1703,7 @@ fn bindgen_test_layout_drm_ctx_res() {
         concat!("Alignment of ", stringify!(drm_ctx_res))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_ctx_res>())).count as *const _ as usize },
+        ::std::mem::offset_of!(drm_ctx_res, count),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1713,7 +1713,7 @@ fn bindgen_test_layout_drm_ctx_res() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_ctx_res>())).contexts as *const _ as usize },
+        ::std::mem::offset_of!(drm_ctx_res, contexts),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1746,7 +1746,7 @@ fn bindgen_test_layout_drm_draw() {
         concat!("Alignment of ", stringify!(drm_draw))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_draw>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_draw, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1779,7 +1779,7 @@ fn bindgen_test_layout_drm_update_draw() {
         concat!("Alignment of ", stringify!(drm_update_draw))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_update_draw>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_update_draw, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1789,7 +1789,7 @@ fn bindgen_test_layout_drm_update_draw() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_update_draw>())).type_ as *const _ as usize },
+        ::std::mem::offset_of!(drm_update_draw, type_),
         4usize,
         concat!(
             "Offset of field: ",
@@ -1799,7 +1799,7 @@ fn bindgen_test_layout_drm_update_draw() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_update_draw>())).num as *const _ as usize },
+        ::std::mem::offset_of!(drm_update_draw, num),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1809,7 +1809,7 @@ fn bindgen_test_layout_drm_update_draw() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_update_draw>())).data as *const _ as usize },
+        ::std::mem::offset_of!(drm_update_draw, data),
         16usize,
         concat!(
             "Offset of field: ",
@@ -1837,7 +1837,7 @@ fn bindgen_test_layout_drm_auth() {
         concat!("Alignment of ", stringify!(drm_auth))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_auth>())).magic as *const _ as usize },
+        ::std::mem::offset_of!(drm_auth, magic),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1868,7 +1868,7 @@ fn bindgen_test_layout_drm_irq_busid() {
         concat!("Alignment of ", stringify!(drm_irq_busid))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_irq_busid>())).irq as *const _ as usize },
+        ::std::mem::offset_of!(drm_irq_busid, irq),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1878,7 +1878,7 @@ fn bindgen_test_layout_drm_irq_busid() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_irq_busid>())).busnum as *const _ as usize },
+        ::std::mem::offset_of!(drm_irq_busid, busnum),
         4usize,
         concat!(
             "Offset of field: ",
@@ -1888,7 +1888,7 @@ fn bindgen_test_layout_drm_irq_busid() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_irq_busid>())).devnum as *const _ as usize },
+        ::std::mem::offset_of!(drm_irq_busid, devnum),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1898,7 +1898,7 @@ fn bindgen_test_layout_drm_irq_busid() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_irq_busid>())).funcnum as *const _ as usize },
+        ::std::mem::offset_of!(drm_irq_busid, funcnum),
         12usize,
         concat!(
             "Offset of field: ",
@@ -1937,7 +1937,7 @@ fn bindgen_test_layout_drm_wait_vblank_request() {
         concat!("Alignment of ", stringify!(drm_wait_vblank_request))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_wait_vblank_request>())).type_ as *const _ as usize },
+        ::std::mem::offset_of!(drm_wait_vblank_request, type_),
         0usize,
         concat!(
             "Offset of field: ",
@@ -1947,9 +1947,7 @@ fn bindgen_test_layout_drm_wait_vblank_request() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_wait_vblank_request>())).sequence as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_wait_vblank_request, sequence),
         4usize,
         concat!(
             "Offset of field: ",
@@ -1959,7 +1957,7 @@ fn bindgen_test_layout_drm_wait_vblank_request() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_wait_vblank_request>())).signal as *const _ as usize },
+        ::std::mem::offset_of!(drm_wait_vblank_request, signal),
         8usize,
         concat!(
             "Offset of field: ",
@@ -1995,7 +1993,7 @@ fn bindgen_test_layout_drm_wait_vblank_reply() {
         concat!("Alignment of ", stringify!(drm_wait_vblank_reply))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_wait_vblank_reply>())).type_ as *const _ as usize },
+        ::std::mem::offset_of!(drm_wait_vblank_reply, type_),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2005,7 +2003,7 @@ fn bindgen_test_layout_drm_wait_vblank_reply() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_wait_vblank_reply>())).sequence as *const _ as usize },
+        ::std::mem::offset_of!(drm_wait_vblank_reply, sequence),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2015,7 +2013,7 @@ fn bindgen_test_layout_drm_wait_vblank_reply() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_wait_vblank_reply>())).tval_sec as *const _ as usize },
+        ::std::mem::offset_of!(drm_wait_vblank_reply, tval_sec),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2025,7 +2023,7 @@ fn bindgen_test_layout_drm_wait_vblank_reply() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_wait_vblank_reply>())).tval_usec as *const _ as usize },
+        ::std::mem::offset_of!(drm_wait_vblank_reply, tval_usec),
         16usize,
         concat!(
             "Offset of field: ",
@@ -2060,7 +2058,7 @@ fn bindgen_test_layout_drm_wait_vblank() {
         concat!("Alignment of ", stringify!(drm_wait_vblank))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_wait_vblank>())).request as *const _ as usize },
+        ::std::mem::offset_of!(drm_wait_vblank, request),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2070,7 +2068,7 @@ fn bindgen_test_layout_drm_wait_vblank() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_wait_vblank>())).reply as *const _ as usize },
+        ::std::mem::offset_of!(drm_wait_vblank, reply),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2104,7 +2102,7 @@ fn bindgen_test_layout_drm_modeset_ctl() {
         concat!("Alignment of ", stringify!(drm_modeset_ctl))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_modeset_ctl>())).crtc as *const _ as usize },
+        ::std::mem::offset_of!(drm_modeset_ctl, crtc),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2114,7 +2112,7 @@ fn bindgen_test_layout_drm_modeset_ctl() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_modeset_ctl>())).cmd as *const _ as usize },
+        ::std::mem::offset_of!(drm_modeset_ctl, cmd),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2142,7 +2140,7 @@ fn bindgen_test_layout_drm_agp_mode() {
         concat!("Alignment of ", stringify!(drm_agp_mode))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_mode>())).mode as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_mode, mode),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2173,7 +2171,7 @@ fn bindgen_test_layout_drm_agp_buffer() {
         concat!("Alignment of ", stringify!(drm_agp_buffer))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_buffer>())).size as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_buffer, size),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2183,7 +2181,7 @@ fn bindgen_test_layout_drm_agp_buffer() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_buffer>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_buffer, handle),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2193,7 +2191,7 @@ fn bindgen_test_layout_drm_agp_buffer() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_buffer>())).type_ as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_buffer, type_),
         16usize,
         concat!(
             "Offset of field: ",
@@ -2203,7 +2201,7 @@ fn bindgen_test_layout_drm_agp_buffer() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_buffer>())).physical as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_buffer, physical),
         24usize,
         concat!(
             "Offset of field: ",
@@ -2232,7 +2230,7 @@ fn bindgen_test_layout_drm_agp_binding() {
         concat!("Alignment of ", stringify!(drm_agp_binding))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_binding>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_binding, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2242,7 +2240,7 @@ fn bindgen_test_layout_drm_agp_binding() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_binding>())).offset as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_binding, offset),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2278,7 +2276,7 @@ fn bindgen_test_layout_drm_agp_info() {
         concat!("Alignment of ", stringify!(drm_agp_info))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_info>())).agp_version_major as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_info, agp_version_major),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2288,7 +2286,7 @@ fn bindgen_test_layout_drm_agp_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_info>())).agp_version_minor as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_info, agp_version_minor),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2298,7 +2296,7 @@ fn bindgen_test_layout_drm_agp_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_info>())).mode as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_info, mode),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2308,7 +2306,7 @@ fn bindgen_test_layout_drm_agp_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_info>())).aperture_base as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_info, aperture_base),
         16usize,
         concat!(
             "Offset of field: ",
@@ -2318,7 +2316,7 @@ fn bindgen_test_layout_drm_agp_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_info>())).aperture_size as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_info, aperture_size),
         24usize,
         concat!(
             "Offset of field: ",
@@ -2328,7 +2326,7 @@ fn bindgen_test_layout_drm_agp_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_info>())).memory_allowed as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_info, memory_allowed),
         32usize,
         concat!(
             "Offset of field: ",
@@ -2338,7 +2336,7 @@ fn bindgen_test_layout_drm_agp_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_info>())).memory_used as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_info, memory_used),
         40usize,
         concat!(
             "Offset of field: ",
@@ -2348,7 +2346,7 @@ fn bindgen_test_layout_drm_agp_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_info>())).id_vendor as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_info, id_vendor),
         48usize,
         concat!(
             "Offset of field: ",
@@ -2358,7 +2356,7 @@ fn bindgen_test_layout_drm_agp_info() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_agp_info>())).id_device as *const _ as usize },
+        ::std::mem::offset_of!(drm_agp_info, id_device),
         50usize,
         concat!(
             "Offset of field: ",
@@ -2387,7 +2385,7 @@ fn bindgen_test_layout_drm_scatter_gather() {
         concat!("Alignment of ", stringify!(drm_scatter_gather))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_scatter_gather>())).size as *const _ as usize },
+        ::std::mem::offset_of!(drm_scatter_gather, size),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2397,7 +2395,7 @@ fn bindgen_test_layout_drm_scatter_gather() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_scatter_gather>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_scatter_gather, handle),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2428,7 +2426,7 @@ fn bindgen_test_layout_drm_set_version() {
         concat!("Alignment of ", stringify!(drm_set_version))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_set_version>())).drm_di_major as *const _ as usize },
+        ::std::mem::offset_of!(drm_set_version, drm_di_major),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2438,7 +2436,7 @@ fn bindgen_test_layout_drm_set_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_set_version>())).drm_di_minor as *const _ as usize },
+        ::std::mem::offset_of!(drm_set_version, drm_di_minor),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2448,7 +2446,7 @@ fn bindgen_test_layout_drm_set_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_set_version>())).drm_dd_major as *const _ as usize },
+        ::std::mem::offset_of!(drm_set_version, drm_dd_major),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2458,7 +2456,7 @@ fn bindgen_test_layout_drm_set_version() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_set_version>())).drm_dd_minor as *const _ as usize },
+        ::std::mem::offset_of!(drm_set_version, drm_dd_minor),
         12usize,
         concat!(
             "Offset of field: ",
@@ -2487,7 +2485,7 @@ fn bindgen_test_layout_drm_gem_close() {
         concat!("Alignment of ", stringify!(drm_gem_close))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_gem_close>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_gem_close, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2497,7 +2495,7 @@ fn bindgen_test_layout_drm_gem_close() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_gem_close>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_gem_close, pad),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2526,7 +2524,7 @@ fn bindgen_test_layout_drm_gem_flink() {
         concat!("Alignment of ", stringify!(drm_gem_flink))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_gem_flink>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_gem_flink, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2536,7 +2534,7 @@ fn bindgen_test_layout_drm_gem_flink() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_gem_flink>())).name as *const _ as usize },
+        ::std::mem::offset_of!(drm_gem_flink, name),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2566,7 +2564,7 @@ fn bindgen_test_layout_drm_gem_open() {
         concat!("Alignment of ", stringify!(drm_gem_open))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_gem_open>())).name as *const _ as usize },
+        ::std::mem::offset_of!(drm_gem_open, name),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2576,7 +2574,7 @@ fn bindgen_test_layout_drm_gem_open() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_gem_open>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_gem_open, handle),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2586,7 +2584,7 @@ fn bindgen_test_layout_drm_gem_open() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_gem_open>())).size as *const _ as usize },
+        ::std::mem::offset_of!(drm_gem_open, size),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2615,7 +2613,7 @@ fn bindgen_test_layout_drm_get_cap() {
         concat!("Alignment of ", stringify!(drm_get_cap))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_get_cap>())).capability as *const _ as usize },
+        ::std::mem::offset_of!(drm_get_cap, capability),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2625,7 +2623,7 @@ fn bindgen_test_layout_drm_get_cap() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_get_cap>())).value as *const _ as usize },
+        ::std::mem::offset_of!(drm_get_cap, value),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2654,7 +2652,7 @@ fn bindgen_test_layout_drm_set_client_cap() {
         concat!("Alignment of ", stringify!(drm_set_client_cap))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_set_client_cap>())).capability as *const _ as usize },
+        ::std::mem::offset_of!(drm_set_client_cap, capability),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2664,7 +2662,7 @@ fn bindgen_test_layout_drm_set_client_cap() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_set_client_cap>())).value as *const _ as usize },
+        ::std::mem::offset_of!(drm_set_client_cap, value),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2694,7 +2692,7 @@ fn bindgen_test_layout_drm_prime_handle() {
         concat!("Alignment of ", stringify!(drm_prime_handle))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_prime_handle>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_prime_handle, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2704,7 +2702,7 @@ fn bindgen_test_layout_drm_prime_handle() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_prime_handle>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_prime_handle, flags),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2714,7 +2712,7 @@ fn bindgen_test_layout_drm_prime_handle() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_prime_handle>())).fd as *const _ as usize },
+        ::std::mem::offset_of!(drm_prime_handle, fd),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2743,7 +2741,7 @@ fn bindgen_test_layout_drm_syncobj_create() {
         concat!("Alignment of ", stringify!(drm_syncobj_create))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_create>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_create, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2753,7 +2751,7 @@ fn bindgen_test_layout_drm_syncobj_create() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_create>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_create, flags),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2782,7 +2780,7 @@ fn bindgen_test_layout_drm_syncobj_destroy() {
         concat!("Alignment of ", stringify!(drm_syncobj_destroy))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_destroy>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_destroy, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2792,7 +2790,7 @@ fn bindgen_test_layout_drm_syncobj_destroy() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_destroy>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_destroy, pad),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2823,7 +2821,7 @@ fn bindgen_test_layout_drm_syncobj_handle() {
         concat!("Alignment of ", stringify!(drm_syncobj_handle))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_handle>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_handle, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2833,7 +2831,7 @@ fn bindgen_test_layout_drm_syncobj_handle() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_handle>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_handle, flags),
         4usize,
         concat!(
             "Offset of field: ",
@@ -2843,7 +2841,7 @@ fn bindgen_test_layout_drm_syncobj_handle() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_handle>())).fd as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_handle, fd),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2853,7 +2851,7 @@ fn bindgen_test_layout_drm_syncobj_handle() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_handle>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_handle, pad),
         12usize,
         concat!(
             "Offset of field: ",
@@ -2886,7 +2884,7 @@ fn bindgen_test_layout_drm_syncobj_wait() {
         concat!("Alignment of ", stringify!(drm_syncobj_wait))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_wait>())).handles as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_wait, handles),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2896,7 +2894,7 @@ fn bindgen_test_layout_drm_syncobj_wait() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_wait>())).timeout_nsec as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_wait, timeout_nsec),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2906,7 +2904,7 @@ fn bindgen_test_layout_drm_syncobj_wait() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_wait>())).count_handles as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_wait, count_handles),
         16usize,
         concat!(
             "Offset of field: ",
@@ -2916,7 +2914,7 @@ fn bindgen_test_layout_drm_syncobj_wait() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_wait>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_wait, flags),
         20usize,
         concat!(
             "Offset of field: ",
@@ -2926,7 +2924,7 @@ fn bindgen_test_layout_drm_syncobj_wait() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_wait>())).first_signaled as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_wait, first_signaled),
         24usize,
         concat!(
             "Offset of field: ",
@@ -2936,7 +2934,7 @@ fn bindgen_test_layout_drm_syncobj_wait() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_wait>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_wait, pad),
         28usize,
         concat!(
             "Offset of field: ",
@@ -2966,7 +2964,7 @@ fn bindgen_test_layout_drm_syncobj_array() {
         concat!("Alignment of ", stringify!(drm_syncobj_array))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_array>())).handles as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_array, handles),
         0usize,
         concat!(
             "Offset of field: ",
@@ -2976,7 +2974,7 @@ fn bindgen_test_layout_drm_syncobj_array() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_array>())).count_handles as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_array, count_handles),
         8usize,
         concat!(
             "Offset of field: ",
@@ -2986,7 +2984,7 @@ fn bindgen_test_layout_drm_syncobj_array() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_syncobj_array>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_syncobj_array, pad),
         12usize,
         concat!(
             "Offset of field: ",
@@ -3017,7 +3015,7 @@ fn bindgen_test_layout_drm_crtc_get_sequence() {
         concat!("Alignment of ", stringify!(drm_crtc_get_sequence))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_crtc_get_sequence>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_crtc_get_sequence, crtc_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -3027,7 +3025,7 @@ fn bindgen_test_layout_drm_crtc_get_sequence() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_crtc_get_sequence>())).active as *const _ as usize },
+        ::std::mem::offset_of!(drm_crtc_get_sequence, active),
         4usize,
         concat!(
             "Offset of field: ",
@@ -3037,7 +3035,7 @@ fn bindgen_test_layout_drm_crtc_get_sequence() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_crtc_get_sequence>())).sequence as *const _ as usize },
+        ::std::mem::offset_of!(drm_crtc_get_sequence, sequence),
         8usize,
         concat!(
             "Offset of field: ",
@@ -3047,9 +3045,7 @@ fn bindgen_test_layout_drm_crtc_get_sequence() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_crtc_get_sequence>())).sequence_ns as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_crtc_get_sequence, sequence_ns),
         16usize,
         concat!(
             "Offset of field: ",
@@ -3080,7 +3076,7 @@ fn bindgen_test_layout_drm_crtc_queue_sequence() {
         concat!("Alignment of ", stringify!(drm_crtc_queue_sequence))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_crtc_queue_sequence>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_crtc_queue_sequence, crtc_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -3090,7 +3086,7 @@ fn bindgen_test_layout_drm_crtc_queue_sequence() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_crtc_queue_sequence>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_crtc_queue_sequence, flags),
         4usize,
         concat!(
             "Offset of field: ",
@@ -3100,9 +3096,7 @@ fn bindgen_test_layout_drm_crtc_queue_sequence() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_crtc_queue_sequence>())).sequence as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_crtc_queue_sequence, sequence),
         8usize,
         concat!(
             "Offset of field: ",
@@ -3112,9 +3106,7 @@ fn bindgen_test_layout_drm_crtc_queue_sequence() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_crtc_queue_sequence>())).user_data as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_crtc_queue_sequence, user_data),
         16usize,
         concat!(
             "Offset of field: ",
@@ -3156,7 +3148,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         concat!("Alignment of ", stringify!(drm_mode_modeinfo))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).clock as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, clock),
         0usize,
         concat!(
             "Offset of field: ",
@@ -3166,7 +3158,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).hdisplay as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, hdisplay),
         4usize,
         concat!(
             "Offset of field: ",
@@ -3176,7 +3168,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).hsync_start as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, hsync_start),
         6usize,
         concat!(
             "Offset of field: ",
@@ -3186,7 +3178,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).hsync_end as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, hsync_end),
         8usize,
         concat!(
             "Offset of field: ",
@@ -3196,7 +3188,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).htotal as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, htotal),
         10usize,
         concat!(
             "Offset of field: ",
@@ -3206,7 +3198,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).hskew as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, hskew),
         12usize,
         concat!(
             "Offset of field: ",
@@ -3216,7 +3208,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).vdisplay as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, vdisplay),
         14usize,
         concat!(
             "Offset of field: ",
@@ -3226,7 +3218,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).vsync_start as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, vsync_start),
         16usize,
         concat!(
             "Offset of field: ",
@@ -3236,7 +3228,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).vsync_end as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, vsync_end),
         18usize,
         concat!(
             "Offset of field: ",
@@ -3246,7 +3238,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).vtotal as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, vtotal),
         20usize,
         concat!(
             "Offset of field: ",
@@ -3256,7 +3248,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).vscan as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, vscan),
         22usize,
         concat!(
             "Offset of field: ",
@@ -3266,7 +3258,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).vrefresh as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, vrefresh),
         24usize,
         concat!(
             "Offset of field: ",
@@ -3276,7 +3268,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, flags),
         28usize,
         concat!(
             "Offset of field: ",
@@ -3286,7 +3278,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).type_ as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, type_),
         32usize,
         concat!(
             "Offset of field: ",
@@ -3296,7 +3288,7 @@ fn bindgen_test_layout_drm_mode_modeinfo() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_modeinfo>())).name as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_modeinfo, name),
         36usize,
         concat!(
             "Offset of field: ",
@@ -3335,7 +3327,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         concat!("Alignment of ", stringify!(drm_mode_card_res))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_card_res>())).fb_id_ptr as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_card_res, fb_id_ptr),
         0usize,
         concat!(
             "Offset of field: ",
@@ -3345,7 +3337,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_card_res>())).crtc_id_ptr as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_card_res, crtc_id_ptr),
         8usize,
         concat!(
             "Offset of field: ",
@@ -3355,9 +3347,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_card_res>())).connector_id_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_card_res, connector_id_ptr),
         16usize,
         concat!(
             "Offset of field: ",
@@ -3367,9 +3357,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_card_res>())).encoder_id_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_card_res, encoder_id_ptr),
         24usize,
         concat!(
             "Offset of field: ",
@@ -3379,7 +3367,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_card_res>())).count_fbs as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_card_res, count_fbs),
         32usize,
         concat!(
             "Offset of field: ",
@@ -3389,7 +3377,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_card_res>())).count_crtcs as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_card_res, count_crtcs),
         36usize,
         concat!(
             "Offset of field: ",
@@ -3399,9 +3387,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_card_res>())).count_connectors as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_card_res, count_connectors),
         40usize,
         concat!(
             "Offset of field: ",
@@ -3411,9 +3397,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_card_res>())).count_encoders as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_card_res, count_encoders),
         44usize,
         concat!(
             "Offset of field: ",
@@ -3423,7 +3407,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_card_res>())).min_width as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_card_res, min_width),
         48usize,
         concat!(
             "Offset of field: ",
@@ -3433,7 +3417,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_card_res>())).max_width as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_card_res, max_width),
         52usize,
         concat!(
             "Offset of field: ",
@@ -3443,7 +3427,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_card_res>())).min_height as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_card_res, min_height),
         56usize,
         concat!(
             "Offset of field: ",
@@ -3453,7 +3437,7 @@ fn bindgen_test_layout_drm_mode_card_res() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_card_res>())).max_height as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_card_res, max_height),
         60usize,
         concat!(
             "Offset of field: ",
@@ -3489,9 +3473,7 @@ fn bindgen_test_layout_drm_mode_crtc() {
         concat!("Alignment of ", stringify!(drm_mode_crtc))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_crtc>())).set_connectors_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_crtc, set_connectors_ptr),
         0usize,
         concat!(
             "Offset of field: ",
@@ -3501,7 +3483,7 @@ fn bindgen_test_layout_drm_mode_crtc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc>())).count_connectors as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc, count_connectors),
         8usize,
         concat!(
             "Offset of field: ",
@@ -3511,7 +3493,7 @@ fn bindgen_test_layout_drm_mode_crtc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc, crtc_id),
         12usize,
         concat!(
             "Offset of field: ",
@@ -3521,7 +3503,7 @@ fn bindgen_test_layout_drm_mode_crtc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc>())).fb_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc, fb_id),
         16usize,
         concat!(
             "Offset of field: ",
@@ -3531,7 +3513,7 @@ fn bindgen_test_layout_drm_mode_crtc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc>())).x as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc, x),
         20usize,
         concat!(
             "Offset of field: ",
@@ -3541,7 +3523,7 @@ fn bindgen_test_layout_drm_mode_crtc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc>())).y as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc, y),
         24usize,
         concat!(
             "Offset of field: ",
@@ -3551,7 +3533,7 @@ fn bindgen_test_layout_drm_mode_crtc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc>())).gamma_size as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc, gamma_size),
         28usize,
         concat!(
             "Offset of field: ",
@@ -3561,7 +3543,7 @@ fn bindgen_test_layout_drm_mode_crtc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc>())).mode_valid as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc, mode_valid),
         32usize,
         concat!(
             "Offset of field: ",
@@ -3571,7 +3553,7 @@ fn bindgen_test_layout_drm_mode_crtc() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc>())).mode as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc, mode),
         36usize,
         concat!(
             "Offset of field: ",
@@ -3610,7 +3592,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         concat!("Alignment of ", stringify!(drm_mode_set_plane))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).plane_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, plane_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -3620,7 +3602,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, crtc_id),
         4usize,
         concat!(
             "Offset of field: ",
@@ -3630,7 +3612,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).fb_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, fb_id),
         8usize,
         concat!(
             "Offset of field: ",
@@ -3640,7 +3622,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, flags),
         12usize,
         concat!(
             "Offset of field: ",
@@ -3650,7 +3632,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).crtc_x as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, crtc_x),
         16usize,
         concat!(
             "Offset of field: ",
@@ -3660,7 +3642,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).crtc_y as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, crtc_y),
         20usize,
         concat!(
             "Offset of field: ",
@@ -3670,7 +3652,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).crtc_w as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, crtc_w),
         24usize,
         concat!(
             "Offset of field: ",
@@ -3680,7 +3662,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).crtc_h as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, crtc_h),
         28usize,
         concat!(
             "Offset of field: ",
@@ -3690,7 +3672,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).src_x as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, src_x),
         32usize,
         concat!(
             "Offset of field: ",
@@ -3700,7 +3682,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).src_y as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, src_y),
         36usize,
         concat!(
             "Offset of field: ",
@@ -3710,7 +3692,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).src_h as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, src_h),
         40usize,
         concat!(
             "Offset of field: ",
@@ -3720,7 +3702,7 @@ fn bindgen_test_layout_drm_mode_set_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_set_plane>())).src_w as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_set_plane, src_w),
         44usize,
         concat!(
             "Offset of field: ",
@@ -3754,7 +3736,7 @@ fn bindgen_test_layout_drm_mode_get_plane() {
         concat!("Alignment of ", stringify!(drm_mode_get_plane))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_plane>())).plane_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_plane, plane_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -3764,7 +3746,7 @@ fn bindgen_test_layout_drm_mode_get_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_plane>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_plane, crtc_id),
         4usize,
         concat!(
             "Offset of field: ",
@@ -3774,7 +3756,7 @@ fn bindgen_test_layout_drm_mode_get_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_plane>())).fb_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_plane, fb_id),
         8usize,
         concat!(
             "Offset of field: ",
@@ -3784,9 +3766,7 @@ fn bindgen_test_layout_drm_mode_get_plane() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_plane>())).possible_crtcs as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_plane, possible_crtcs),
         12usize,
         concat!(
             "Offset of field: ",
@@ -3796,7 +3776,7 @@ fn bindgen_test_layout_drm_mode_get_plane() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_plane>())).gamma_size as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_plane, gamma_size),
         16usize,
         concat!(
             "Offset of field: ",
@@ -3806,9 +3786,7 @@ fn bindgen_test_layout_drm_mode_get_plane() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_plane>())).count_format_types as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_plane, count_format_types),
         20usize,
         concat!(
             "Offset of field: ",
@@ -3818,9 +3796,7 @@ fn bindgen_test_layout_drm_mode_get_plane() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_plane>())).format_type_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_plane, format_type_ptr),
         24usize,
         concat!(
             "Offset of field: ",
@@ -3849,9 +3825,7 @@ fn bindgen_test_layout_drm_mode_get_plane_res() {
         concat!("Alignment of ", stringify!(drm_mode_get_plane_res))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_plane_res>())).plane_id_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_plane_res, plane_id_ptr),
         0usize,
         concat!(
             "Offset of field: ",
@@ -3861,9 +3835,7 @@ fn bindgen_test_layout_drm_mode_get_plane_res() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_plane_res>())).count_planes as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_plane_res, count_planes),
         8usize,
         concat!(
             "Offset of field: ",
@@ -3895,7 +3867,7 @@ fn bindgen_test_layout_drm_mode_get_encoder() {
         concat!("Alignment of ", stringify!(drm_mode_get_encoder))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_encoder>())).encoder_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_encoder, encoder_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -3905,9 +3877,7 @@ fn bindgen_test_layout_drm_mode_get_encoder() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_encoder>())).encoder_type as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_encoder, encoder_type),
         4usize,
         concat!(
             "Offset of field: ",
@@ -3917,7 +3887,7 @@ fn bindgen_test_layout_drm_mode_get_encoder() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_encoder>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_encoder, crtc_id),
         8usize,
         concat!(
             "Offset of field: ",
@@ -3927,9 +3897,7 @@ fn bindgen_test_layout_drm_mode_get_encoder() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_encoder>())).possible_crtcs as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_encoder, possible_crtcs),
         12usize,
         concat!(
             "Offset of field: ",
@@ -3939,9 +3907,7 @@ fn bindgen_test_layout_drm_mode_get_encoder() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_encoder>())).possible_clones as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_encoder, possible_clones),
         16usize,
         concat!(
             "Offset of field: ",
@@ -3993,9 +3959,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         concat!("Alignment of ", stringify!(drm_mode_get_connector))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).encoders_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, encoders_ptr),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4005,9 +3969,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).modes_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, modes_ptr),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4017,9 +3979,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).props_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, props_ptr),
         16usize,
         concat!(
             "Offset of field: ",
@@ -4029,9 +3989,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).prop_values_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, prop_values_ptr),
         24usize,
         concat!(
             "Offset of field: ",
@@ -4041,9 +3999,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).count_modes as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, count_modes),
         32usize,
         concat!(
             "Offset of field: ",
@@ -4053,9 +4009,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).count_props as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, count_props),
         36usize,
         concat!(
             "Offset of field: ",
@@ -4065,9 +4019,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).count_encoders as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, count_encoders),
         40usize,
         concat!(
             "Offset of field: ",
@@ -4077,9 +4029,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).encoder_id as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, encoder_id),
         44usize,
         concat!(
             "Offset of field: ",
@@ -4089,9 +4039,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).connector_id as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, connector_id),
         48usize,
         concat!(
             "Offset of field: ",
@@ -4101,9 +4049,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).connector_type as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, connector_type),
         52usize,
         concat!(
             "Offset of field: ",
@@ -4113,10 +4059,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).connector_type_id as *const _
-                as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, connector_type_id),
         56usize,
         concat!(
             "Offset of field: ",
@@ -4126,9 +4069,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).connection as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, connection),
         60usize,
         concat!(
             "Offset of field: ",
@@ -4138,7 +4079,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_connector>())).mm_width as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_connector, mm_width),
         64usize,
         concat!(
             "Offset of field: ",
@@ -4148,9 +4089,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_connector>())).mm_height as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_connector, mm_height),
         68usize,
         concat!(
             "Offset of field: ",
@@ -4160,7 +4099,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_connector>())).subpixel as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_connector, subpixel),
         72usize,
         concat!(
             "Offset of field: ",
@@ -4170,7 +4109,7 @@ fn bindgen_test_layout_drm_mode_get_connector() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_connector>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_connector, pad),
         76usize,
         concat!(
             "Offset of field: ",
@@ -4199,7 +4138,7 @@ fn bindgen_test_layout_drm_mode_property_enum() {
         concat!("Alignment of ", stringify!(drm_mode_property_enum))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_property_enum>())).value as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_property_enum, value),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4209,7 +4148,7 @@ fn bindgen_test_layout_drm_mode_property_enum() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_property_enum>())).name as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_property_enum, name),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4243,9 +4182,7 @@ fn bindgen_test_layout_drm_mode_get_property() {
         concat!("Alignment of ", stringify!(drm_mode_get_property))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_property>())).values_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_property, values_ptr),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4255,9 +4192,7 @@ fn bindgen_test_layout_drm_mode_get_property() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_property>())).enum_blob_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_property, enum_blob_ptr),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4267,7 +4202,7 @@ fn bindgen_test_layout_drm_mode_get_property() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_property>())).prop_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_property, prop_id),
         16usize,
         concat!(
             "Offset of field: ",
@@ -4277,7 +4212,7 @@ fn bindgen_test_layout_drm_mode_get_property() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_property>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_property, flags),
         20usize,
         concat!(
             "Offset of field: ",
@@ -4287,7 +4222,7 @@ fn bindgen_test_layout_drm_mode_get_property() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_property>())).name as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_property, name),
         24usize,
         concat!(
             "Offset of field: ",
@@ -4297,9 +4232,7 @@ fn bindgen_test_layout_drm_mode_get_property() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_property>())).count_values as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_property, count_values),
         56usize,
         concat!(
             "Offset of field: ",
@@ -4309,9 +4242,7 @@ fn bindgen_test_layout_drm_mode_get_property() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_property>())).count_enum_blobs as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_property, count_enum_blobs),
         60usize,
         concat!(
             "Offset of field: ",
@@ -4341,9 +4272,7 @@ fn bindgen_test_layout_drm_mode_connector_set_property() {
         concat!("Alignment of ", stringify!(drm_mode_connector_set_property))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_connector_set_property>())).value as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_connector_set_property, value),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4353,9 +4282,7 @@ fn bindgen_test_layout_drm_mode_connector_set_property() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_connector_set_property>())).prop_id as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_connector_set_property, prop_id),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4365,10 +4292,7 @@ fn bindgen_test_layout_drm_mode_connector_set_property() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_connector_set_property>())).connector_id as *const _
-                as usize
-        },
+        ::std::mem::offset_of!(drm_mode_connector_set_property, connector_id),
         12usize,
         concat!(
             "Offset of field: ",
@@ -4400,9 +4324,7 @@ fn bindgen_test_layout_drm_mode_obj_get_properties() {
         concat!("Alignment of ", stringify!(drm_mode_obj_get_properties))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_obj_get_properties>())).props_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_obj_get_properties, props_ptr),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4412,10 +4334,7 @@ fn bindgen_test_layout_drm_mode_obj_get_properties() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_obj_get_properties>())).prop_values_ptr as *const _
-                as usize
-        },
+        ::std::mem::offset_of!(drm_mode_obj_get_properties, prop_values_ptr),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4425,9 +4344,7 @@ fn bindgen_test_layout_drm_mode_obj_get_properties() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_obj_get_properties>())).count_props as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_obj_get_properties, count_props),
         16usize,
         concat!(
             "Offset of field: ",
@@ -4437,9 +4354,7 @@ fn bindgen_test_layout_drm_mode_obj_get_properties() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_obj_get_properties>())).obj_id as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_obj_get_properties, obj_id),
         20usize,
         concat!(
             "Offset of field: ",
@@ -4449,9 +4364,7 @@ fn bindgen_test_layout_drm_mode_obj_get_properties() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_obj_get_properties>())).obj_type as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_obj_get_properties, obj_type),
         24usize,
         concat!(
             "Offset of field: ",
@@ -4482,7 +4395,7 @@ fn bindgen_test_layout_drm_mode_obj_set_property() {
         concat!("Alignment of ", stringify!(drm_mode_obj_set_property))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_obj_set_property>())).value as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_obj_set_property, value),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4492,9 +4405,7 @@ fn bindgen_test_layout_drm_mode_obj_set_property() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_obj_set_property>())).prop_id as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_obj_set_property, prop_id),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4504,9 +4415,7 @@ fn bindgen_test_layout_drm_mode_obj_set_property() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_obj_set_property>())).obj_id as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_obj_set_property, obj_id),
         12usize,
         concat!(
             "Offset of field: ",
@@ -4516,9 +4425,7 @@ fn bindgen_test_layout_drm_mode_obj_set_property() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_obj_set_property>())).obj_type as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_obj_set_property, obj_type),
         16usize,
         concat!(
             "Offset of field: ",
@@ -4548,7 +4455,7 @@ fn bindgen_test_layout_drm_mode_get_blob() {
         concat!("Alignment of ", stringify!(drm_mode_get_blob))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_blob>())).blob_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_blob, blob_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4558,7 +4465,7 @@ fn bindgen_test_layout_drm_mode_get_blob() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_blob>())).length as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_blob, length),
         4usize,
         concat!(
             "Offset of field: ",
@@ -4568,7 +4475,7 @@ fn bindgen_test_layout_drm_mode_get_blob() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_blob>())).data as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_blob, data),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4602,7 +4509,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd() {
         concat!("Alignment of ", stringify!(drm_mode_fb_cmd))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd>())).fb_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd, fb_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4612,7 +4519,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd>())).width as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd, width),
         4usize,
         concat!(
             "Offset of field: ",
@@ -4622,7 +4529,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd>())).height as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd, height),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4632,7 +4539,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd>())).pitch as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd, pitch),
         12usize,
         concat!(
             "Offset of field: ",
@@ -4642,7 +4549,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd>())).bpp as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd, bpp),
         16usize,
         concat!(
             "Offset of field: ",
@@ -4652,7 +4559,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd>())).depth as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd, depth),
         20usize,
         concat!(
             "Offset of field: ",
@@ -4662,7 +4569,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd, handle),
         24usize,
         concat!(
             "Offset of field: ",
@@ -4698,7 +4605,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd2() {
         concat!("Alignment of ", stringify!(drm_mode_fb_cmd2))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd2>())).fb_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd2, fb_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4708,7 +4615,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd2>())).width as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd2, width),
         4usize,
         concat!(
             "Offset of field: ",
@@ -4718,7 +4625,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd2>())).height as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd2, height),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4728,7 +4635,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd2>())).pixel_format as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd2, pixel_format),
         12usize,
         concat!(
             "Offset of field: ",
@@ -4738,7 +4645,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd2>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd2, flags),
         16usize,
         concat!(
             "Offset of field: ",
@@ -4748,7 +4655,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd2>())).handles as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd2, handles),
         20usize,
         concat!(
             "Offset of field: ",
@@ -4758,7 +4665,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd2>())).pitches as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd2, pitches),
         36usize,
         concat!(
             "Offset of field: ",
@@ -4768,7 +4675,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd2>())).offsets as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd2, offsets),
         52usize,
         concat!(
             "Offset of field: ",
@@ -4778,7 +4685,7 @@ fn bindgen_test_layout_drm_mode_fb_cmd2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_cmd2>())).modifier as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_cmd2, modifier),
         72usize,
         concat!(
             "Offset of field: ",
@@ -4810,7 +4717,7 @@ fn bindgen_test_layout_drm_mode_fb_dirty_cmd() {
         concat!("Alignment of ", stringify!(drm_mode_fb_dirty_cmd))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_dirty_cmd>())).fb_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_dirty_cmd, fb_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4820,7 +4727,7 @@ fn bindgen_test_layout_drm_mode_fb_dirty_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_dirty_cmd>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_dirty_cmd, flags),
         4usize,
         concat!(
             "Offset of field: ",
@@ -4830,7 +4737,7 @@ fn bindgen_test_layout_drm_mode_fb_dirty_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_dirty_cmd>())).color as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_dirty_cmd, color),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4840,7 +4747,7 @@ fn bindgen_test_layout_drm_mode_fb_dirty_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_dirty_cmd>())).num_clips as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_dirty_cmd, num_clips),
         12usize,
         concat!(
             "Offset of field: ",
@@ -4850,7 +4757,7 @@ fn bindgen_test_layout_drm_mode_fb_dirty_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_fb_dirty_cmd>())).clips_ptr as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_fb_dirty_cmd, clips_ptr),
         16usize,
         concat!(
             "Offset of field: ",
@@ -4879,7 +4786,7 @@ fn bindgen_test_layout_drm_mode_mode_cmd() {
         concat!("Alignment of ", stringify!(drm_mode_mode_cmd))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_mode_cmd>())).connector_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_mode_cmd, connector_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4889,7 +4796,7 @@ fn bindgen_test_layout_drm_mode_mode_cmd() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_mode_cmd>())).mode as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_mode_cmd, mode),
         4usize,
         concat!(
             "Offset of field: ",
@@ -4923,7 +4830,7 @@ fn bindgen_test_layout_drm_mode_cursor() {
         concat!("Alignment of ", stringify!(drm_mode_cursor))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor, flags),
         0usize,
         concat!(
             "Offset of field: ",
@@ -4933,7 +4840,7 @@ fn bindgen_test_layout_drm_mode_cursor() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor, crtc_id),
         4usize,
         concat!(
             "Offset of field: ",
@@ -4943,7 +4850,7 @@ fn bindgen_test_layout_drm_mode_cursor() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor>())).x as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor, x),
         8usize,
         concat!(
             "Offset of field: ",
@@ -4953,7 +4860,7 @@ fn bindgen_test_layout_drm_mode_cursor() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor>())).y as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor, y),
         12usize,
         concat!(
             "Offset of field: ",
@@ -4963,7 +4870,7 @@ fn bindgen_test_layout_drm_mode_cursor() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor>())).width as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor, width),
         16usize,
         concat!(
             "Offset of field: ",
@@ -4973,7 +4880,7 @@ fn bindgen_test_layout_drm_mode_cursor() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor>())).height as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor, height),
         20usize,
         concat!(
             "Offset of field: ",
@@ -4983,7 +4890,7 @@ fn bindgen_test_layout_drm_mode_cursor() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor, handle),
         24usize,
         concat!(
             "Offset of field: ",
@@ -5019,7 +4926,7 @@ fn bindgen_test_layout_drm_mode_cursor2() {
         concat!("Alignment of ", stringify!(drm_mode_cursor2))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor2>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor2, flags),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5029,7 +4936,7 @@ fn bindgen_test_layout_drm_mode_cursor2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor2>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor2, crtc_id),
         4usize,
         concat!(
             "Offset of field: ",
@@ -5039,7 +4946,7 @@ fn bindgen_test_layout_drm_mode_cursor2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor2>())).x as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor2, x),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5049,7 +4956,7 @@ fn bindgen_test_layout_drm_mode_cursor2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor2>())).y as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor2, y),
         12usize,
         concat!(
             "Offset of field: ",
@@ -5059,7 +4966,7 @@ fn bindgen_test_layout_drm_mode_cursor2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor2>())).width as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor2, width),
         16usize,
         concat!(
             "Offset of field: ",
@@ -5069,7 +4976,7 @@ fn bindgen_test_layout_drm_mode_cursor2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor2>())).height as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor2, height),
         20usize,
         concat!(
             "Offset of field: ",
@@ -5079,7 +4986,7 @@ fn bindgen_test_layout_drm_mode_cursor2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor2>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor2, handle),
         24usize,
         concat!(
             "Offset of field: ",
@@ -5089,7 +4996,7 @@ fn bindgen_test_layout_drm_mode_cursor2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor2>())).hot_x as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor2, hot_x),
         28usize,
         concat!(
             "Offset of field: ",
@@ -5099,7 +5006,7 @@ fn bindgen_test_layout_drm_mode_cursor2() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_cursor2>())).hot_y as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_cursor2, hot_y),
         32usize,
         concat!(
             "Offset of field: ",
@@ -5131,7 +5038,7 @@ fn bindgen_test_layout_drm_mode_crtc_lut() {
         concat!("Alignment of ", stringify!(drm_mode_crtc_lut))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc_lut>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc_lut, crtc_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5141,7 +5048,7 @@ fn bindgen_test_layout_drm_mode_crtc_lut() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc_lut>())).gamma_size as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc_lut, gamma_size),
         4usize,
         concat!(
             "Offset of field: ",
@@ -5151,7 +5058,7 @@ fn bindgen_test_layout_drm_mode_crtc_lut() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc_lut>())).red as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc_lut, red),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5161,7 +5068,7 @@ fn bindgen_test_layout_drm_mode_crtc_lut() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc_lut>())).green as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc_lut, green),
         16usize,
         concat!(
             "Offset of field: ",
@@ -5171,7 +5078,7 @@ fn bindgen_test_layout_drm_mode_crtc_lut() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc_lut>())).blue as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc_lut, blue),
         24usize,
         concat!(
             "Offset of field: ",
@@ -5199,7 +5106,7 @@ fn bindgen_test_layout_drm_color_ctm() {
         concat!("Alignment of ", stringify!(drm_color_ctm))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_color_ctm>())).matrix as *const _ as usize },
+        ::std::mem::offset_of!(drm_color_ctm, matrix),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5230,7 +5137,7 @@ fn bindgen_test_layout_drm_color_lut() {
         concat!("Alignment of ", stringify!(drm_color_lut))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_color_lut>())).red as *const _ as usize },
+        ::std::mem::offset_of!(drm_color_lut, red),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5240,7 +5147,7 @@ fn bindgen_test_layout_drm_color_lut() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_color_lut>())).green as *const _ as usize },
+        ::std::mem::offset_of!(drm_color_lut, green),
         2usize,
         concat!(
             "Offset of field: ",
@@ -5250,7 +5157,7 @@ fn bindgen_test_layout_drm_color_lut() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_color_lut>())).blue as *const _ as usize },
+        ::std::mem::offset_of!(drm_color_lut, blue),
         4usize,
         concat!(
             "Offset of field: ",
@@ -5260,7 +5167,7 @@ fn bindgen_test_layout_drm_color_lut() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_color_lut>())).reserved as *const _ as usize },
+        ::std::mem::offset_of!(drm_color_lut, reserved),
         6usize,
         concat!(
             "Offset of field: ",
@@ -5292,7 +5199,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip() {
         concat!("Alignment of ", stringify!(drm_mode_crtc_page_flip))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc_page_flip>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip, crtc_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5302,7 +5209,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc_page_flip>())).fb_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip, fb_id),
         4usize,
         concat!(
             "Offset of field: ",
@@ -5312,7 +5219,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_crtc_page_flip>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip, flags),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5322,9 +5229,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_crtc_page_flip>())).reserved as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip, reserved),
         12usize,
         concat!(
             "Offset of field: ",
@@ -5334,9 +5239,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_crtc_page_flip>())).user_data as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip, user_data),
         16usize,
         concat!(
             "Offset of field: ",
@@ -5368,9 +5271,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip_target() {
         concat!("Alignment of ", stringify!(drm_mode_crtc_page_flip_target))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_crtc_page_flip_target>())).crtc_id as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip_target, crtc_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5380,9 +5281,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip_target() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_crtc_page_flip_target>())).fb_id as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip_target, fb_id),
         4usize,
         concat!(
             "Offset of field: ",
@@ -5392,9 +5291,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip_target() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_crtc_page_flip_target>())).flags as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip_target, flags),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5404,9 +5301,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip_target() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_crtc_page_flip_target>())).sequence as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip_target, sequence),
         12usize,
         concat!(
             "Offset of field: ",
@@ -5416,10 +5311,7 @@ fn bindgen_test_layout_drm_mode_crtc_page_flip_target() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_crtc_page_flip_target>())).user_data as *const _
-                as usize
-        },
+        ::std::mem::offset_of!(drm_mode_crtc_page_flip_target, user_data),
         16usize,
         concat!(
             "Offset of field: ",
@@ -5453,7 +5345,7 @@ fn bindgen_test_layout_drm_mode_create_dumb() {
         concat!("Alignment of ", stringify!(drm_mode_create_dumb))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_dumb>())).height as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_dumb, height),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5463,7 +5355,7 @@ fn bindgen_test_layout_drm_mode_create_dumb() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_dumb>())).width as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_dumb, width),
         4usize,
         concat!(
             "Offset of field: ",
@@ -5473,7 +5365,7 @@ fn bindgen_test_layout_drm_mode_create_dumb() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_dumb>())).bpp as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_dumb, bpp),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5483,7 +5375,7 @@ fn bindgen_test_layout_drm_mode_create_dumb() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_dumb>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_dumb, flags),
         12usize,
         concat!(
             "Offset of field: ",
@@ -5493,7 +5385,7 @@ fn bindgen_test_layout_drm_mode_create_dumb() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_dumb>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_dumb, handle),
         16usize,
         concat!(
             "Offset of field: ",
@@ -5503,7 +5395,7 @@ fn bindgen_test_layout_drm_mode_create_dumb() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_dumb>())).pitch as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_dumb, pitch),
         20usize,
         concat!(
             "Offset of field: ",
@@ -5513,7 +5405,7 @@ fn bindgen_test_layout_drm_mode_create_dumb() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_dumb>())).size as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_dumb, size),
         24usize,
         concat!(
             "Offset of field: ",
@@ -5543,7 +5435,7 @@ fn bindgen_test_layout_drm_mode_map_dumb() {
         concat!("Alignment of ", stringify!(drm_mode_map_dumb))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_map_dumb>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_map_dumb, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5553,7 +5445,7 @@ fn bindgen_test_layout_drm_mode_map_dumb() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_map_dumb>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_map_dumb, pad),
         4usize,
         concat!(
             "Offset of field: ",
@@ -5563,7 +5455,7 @@ fn bindgen_test_layout_drm_mode_map_dumb() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_map_dumb>())).offset as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_map_dumb, offset),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5591,7 +5483,7 @@ fn bindgen_test_layout_drm_mode_destroy_dumb() {
         concat!("Alignment of ", stringify!(drm_mode_destroy_dumb))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_destroy_dumb>())).handle as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_destroy_dumb, handle),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5626,7 +5518,7 @@ fn bindgen_test_layout_drm_mode_atomic() {
         concat!("Alignment of ", stringify!(drm_mode_atomic))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_atomic>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_atomic, flags),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5636,7 +5528,7 @@ fn bindgen_test_layout_drm_mode_atomic() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_atomic>())).count_objs as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_atomic, count_objs),
         4usize,
         concat!(
             "Offset of field: ",
@@ -5646,7 +5538,7 @@ fn bindgen_test_layout_drm_mode_atomic() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_atomic>())).objs_ptr as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_atomic, objs_ptr),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5656,7 +5548,7 @@ fn bindgen_test_layout_drm_mode_atomic() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_atomic>())).count_props_ptr as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_atomic, count_props_ptr),
         16usize,
         concat!(
             "Offset of field: ",
@@ -5666,7 +5558,7 @@ fn bindgen_test_layout_drm_mode_atomic() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_atomic>())).props_ptr as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_atomic, props_ptr),
         24usize,
         concat!(
             "Offset of field: ",
@@ -5676,7 +5568,7 @@ fn bindgen_test_layout_drm_mode_atomic() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_atomic>())).prop_values_ptr as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_atomic, prop_values_ptr),
         32usize,
         concat!(
             "Offset of field: ",
@@ -5686,7 +5578,7 @@ fn bindgen_test_layout_drm_mode_atomic() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_atomic>())).reserved as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_atomic, reserved),
         40usize,
         concat!(
             "Offset of field: ",
@@ -5696,7 +5588,7 @@ fn bindgen_test_layout_drm_mode_atomic() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_atomic>())).user_data as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_atomic, user_data),
         48usize,
         concat!(
             "Offset of field: ",
@@ -5729,9 +5621,7 @@ fn bindgen_test_layout_drm_format_modifier_blob() {
         concat!("Alignment of ", stringify!(drm_format_modifier_blob))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_format_modifier_blob>())).version as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_format_modifier_blob, version),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5741,7 +5631,7 @@ fn bindgen_test_layout_drm_format_modifier_blob() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_format_modifier_blob>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_format_modifier_blob, flags),
         4usize,
         concat!(
             "Offset of field: ",
@@ -5751,9 +5641,7 @@ fn bindgen_test_layout_drm_format_modifier_blob() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_format_modifier_blob>())).count_formats as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_format_modifier_blob, count_formats),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5763,9 +5651,7 @@ fn bindgen_test_layout_drm_format_modifier_blob() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_format_modifier_blob>())).formats_offset as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_format_modifier_blob, formats_offset),
         12usize,
         concat!(
             "Offset of field: ",
@@ -5775,10 +5661,7 @@ fn bindgen_test_layout_drm_format_modifier_blob() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_format_modifier_blob>())).count_modifiers as *const _
-                as usize
-        },
+        ::std::mem::offset_of!(drm_format_modifier_blob, count_modifiers),
         16usize,
         concat!(
             "Offset of field: ",
@@ -5788,10 +5671,7 @@ fn bindgen_test_layout_drm_format_modifier_blob() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_format_modifier_blob>())).modifiers_offset as *const _
-                as usize
-        },
+        ::std::mem::offset_of!(drm_format_modifier_blob, modifiers_offset),
         20usize,
         concat!(
             "Offset of field: ",
@@ -5822,7 +5702,7 @@ fn bindgen_test_layout_drm_format_modifier() {
         concat!("Alignment of ", stringify!(drm_format_modifier))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_format_modifier>())).formats as *const _ as usize },
+        ::std::mem::offset_of!(drm_format_modifier, formats),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5832,7 +5712,7 @@ fn bindgen_test_layout_drm_format_modifier() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_format_modifier>())).offset as *const _ as usize },
+        ::std::mem::offset_of!(drm_format_modifier, offset),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5842,7 +5722,7 @@ fn bindgen_test_layout_drm_format_modifier() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_format_modifier>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_format_modifier, pad),
         12usize,
         concat!(
             "Offset of field: ",
@@ -5852,7 +5732,7 @@ fn bindgen_test_layout_drm_format_modifier() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_format_modifier>())).modifier as *const _ as usize },
+        ::std::mem::offset_of!(drm_format_modifier, modifier),
         16usize,
         concat!(
             "Offset of field: ",
@@ -5882,7 +5762,7 @@ fn bindgen_test_layout_drm_mode_create_blob() {
         concat!("Alignment of ", stringify!(drm_mode_create_blob))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_blob>())).data as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_blob, data),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5892,7 +5772,7 @@ fn bindgen_test_layout_drm_mode_create_blob() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_blob>())).length as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_blob, length),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5902,7 +5782,7 @@ fn bindgen_test_layout_drm_mode_create_blob() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_blob>())).blob_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_blob, blob_id),
         12usize,
         concat!(
             "Offset of field: ",
@@ -5930,7 +5810,7 @@ fn bindgen_test_layout_drm_mode_destroy_blob() {
         concat!("Alignment of ", stringify!(drm_mode_destroy_blob))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_destroy_blob>())).blob_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_destroy_blob, blob_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5962,9 +5842,7 @@ fn bindgen_test_layout_drm_mode_create_lease() {
         concat!("Alignment of ", stringify!(drm_mode_create_lease))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_create_lease>())).object_ids as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_create_lease, object_ids),
         0usize,
         concat!(
             "Offset of field: ",
@@ -5974,9 +5852,7 @@ fn bindgen_test_layout_drm_mode_create_lease() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_create_lease>())).object_count as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_create_lease, object_count),
         8usize,
         concat!(
             "Offset of field: ",
@@ -5986,7 +5862,7 @@ fn bindgen_test_layout_drm_mode_create_lease() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_lease>())).flags as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_lease, flags),
         12usize,
         concat!(
             "Offset of field: ",
@@ -5996,7 +5872,7 @@ fn bindgen_test_layout_drm_mode_create_lease() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_lease>())).lessee_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_lease, lessee_id),
         16usize,
         concat!(
             "Offset of field: ",
@@ -6006,7 +5882,7 @@ fn bindgen_test_layout_drm_mode_create_lease() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_create_lease>())).fd as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_create_lease, fd),
         20usize,
         concat!(
             "Offset of field: ",
@@ -6036,9 +5912,7 @@ fn bindgen_test_layout_drm_mode_list_lessees() {
         concat!("Alignment of ", stringify!(drm_mode_list_lessees))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_list_lessees>())).count_lessees as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_list_lessees, count_lessees),
         0usize,
         concat!(
             "Offset of field: ",
@@ -6048,7 +5922,7 @@ fn bindgen_test_layout_drm_mode_list_lessees() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_list_lessees>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_list_lessees, pad),
         4usize,
         concat!(
             "Offset of field: ",
@@ -6058,9 +5932,7 @@ fn bindgen_test_layout_drm_mode_list_lessees() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_list_lessees>())).lessees_ptr as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_list_lessees, lessees_ptr),
         8usize,
         concat!(
             "Offset of field: ",
@@ -6090,9 +5962,7 @@ fn bindgen_test_layout_drm_mode_get_lease() {
         concat!("Alignment of ", stringify!(drm_mode_get_lease))
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_mode_get_lease>())).count_objects as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_mode_get_lease, count_objects),
         0usize,
         concat!(
             "Offset of field: ",
@@ -6102,7 +5972,7 @@ fn bindgen_test_layout_drm_mode_get_lease() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_lease>())).pad as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_lease, pad),
         4usize,
         concat!(
             "Offset of field: ",
@@ -6112,7 +5982,7 @@ fn bindgen_test_layout_drm_mode_get_lease() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_get_lease>())).objects_ptr as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_get_lease, objects_ptr),
         8usize,
         concat!(
             "Offset of field: ",
@@ -6140,7 +6010,7 @@ fn bindgen_test_layout_drm_mode_revoke_lease() {
         concat!("Alignment of ", stringify!(drm_mode_revoke_lease))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_mode_revoke_lease>())).lessee_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_mode_revoke_lease, lessee_id),
         0usize,
         concat!(
             "Offset of field: ",
@@ -6169,7 +6039,7 @@ fn bindgen_test_layout_drm_event() {
         concat!("Alignment of ", stringify!(drm_event))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event>())).type_ as *const _ as usize },
+        ::std::mem::offset_of!(drm_event, type_),
         0usize,
         concat!(
             "Offset of field: ",
@@ -6179,7 +6049,7 @@ fn bindgen_test_layout_drm_event() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event>())).length as *const _ as usize },
+        ::std::mem::offset_of!(drm_event, length),
         4usize,
         concat!(
             "Offset of field: ",
@@ -6212,7 +6082,7 @@ fn bindgen_test_layout_drm_event_vblank() {
         concat!("Alignment of ", stringify!(drm_event_vblank))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event_vblank>())).base as *const _ as usize },
+        ::std::mem::offset_of!(drm_event_vblank, base),
         0usize,
         concat!(
             "Offset of field: ",
@@ -6222,7 +6092,7 @@ fn bindgen_test_layout_drm_event_vblank() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event_vblank>())).user_data as *const _ as usize },
+        ::std::mem::offset_of!(drm_event_vblank, user_data),
         8usize,
         concat!(
             "Offset of field: ",
@@ -6232,7 +6102,7 @@ fn bindgen_test_layout_drm_event_vblank() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event_vblank>())).tv_sec as *const _ as usize },
+        ::std::mem::offset_of!(drm_event_vblank, tv_sec),
         16usize,
         concat!(
             "Offset of field: ",
@@ -6242,7 +6112,7 @@ fn bindgen_test_layout_drm_event_vblank() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event_vblank>())).tv_usec as *const _ as usize },
+        ::std::mem::offset_of!(drm_event_vblank, tv_usec),
         20usize,
         concat!(
             "Offset of field: ",
@@ -6252,7 +6122,7 @@ fn bindgen_test_layout_drm_event_vblank() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event_vblank>())).sequence as *const _ as usize },
+        ::std::mem::offset_of!(drm_event_vblank, sequence),
         24usize,
         concat!(
             "Offset of field: ",
@@ -6262,7 +6132,7 @@ fn bindgen_test_layout_drm_event_vblank() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event_vblank>())).crtc_id as *const _ as usize },
+        ::std::mem::offset_of!(drm_event_vblank, crtc_id),
         28usize,
         concat!(
             "Offset of field: ",
@@ -6293,7 +6163,7 @@ fn bindgen_test_layout_drm_event_crtc_sequence() {
         concat!("Alignment of ", stringify!(drm_event_crtc_sequence))
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event_crtc_sequence>())).base as *const _ as usize },
+        ::std::mem::offset_of!(drm_event_crtc_sequence, base),
         0usize,
         concat!(
             "Offset of field: ",
@@ -6303,9 +6173,7 @@ fn bindgen_test_layout_drm_event_crtc_sequence() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_event_crtc_sequence>())).user_data as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_event_crtc_sequence, user_data),
         8usize,
         concat!(
             "Offset of field: ",
@@ -6315,7 +6183,7 @@ fn bindgen_test_layout_drm_event_crtc_sequence() {
         )
     );
     assert_eq!(
-        unsafe { &(*(::std::ptr::null::<drm_event_crtc_sequence>())).time_ns as *const _ as usize },
+        ::std::mem::offset_of!(drm_event_crtc_sequence, time_ns),
         16usize,
         concat!(
             "Offset of field: ",
@@ -6325,9 +6193,7 @@ fn bindgen_test_layout_drm_event_crtc_sequence() {
         )
     );
     assert_eq!(
-        unsafe {
-            &(*(::std::ptr::null::<drm_event_crtc_sequence>())).sequence as *const _ as usize
-        },
+        ::std::mem::offset_of!(drm_event_crtc_sequence, sequence),
         24usize,
         concat!(
             "Offset of field: ",
@@ -0,0 +1,146 @@
+use super::drm::Color;
+use super::drm_const::*;
+
+/**
+ * Memory layout of a pixel, named after the DRM fourcc codes. Multi-byte
+ * pixels are stored little endian, so `Xrgb8888` is B, G, R, X in memory.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Xrgb8888,
+    Argb8888,
+    Xbgr8888,
+    Abgr8888,
+    Rgb565
+}
+
+impl Format {
+    pub fn from_fourcc(fourcc: u32) -> Option<Format> {
+        match fourcc {
+            DRM_FORMAT_XRGB8888 => Some(Format::Xrgb8888),
+            DRM_FORMAT_ARGB8888 => Some(Format::Argb8888),
+            DRM_FORMAT_XBGR8888 => Some(Format::Xbgr8888),
+            DRM_FORMAT_ABGR8888 => Some(Format::Abgr8888),
+            DRM_FORMAT_RGB565 => Some(Format::Rgb565),
+            _ => None
+        }
+    }
+
+    pub fn fourcc(&self) -> u32 {
+        match *self {
+            Format::Xrgb8888 => DRM_FORMAT_XRGB8888,
+            Format::Argb8888 => DRM_FORMAT_ARGB8888,
+            Format::Xbgr8888 => DRM_FORMAT_XBGR8888,
+            Format::Abgr8888 => DRM_FORMAT_ABGR8888,
+            Format::Rgb565 => DRM_FORMAT_RGB565
+        }
+    }
+
+    pub fn bytes_per_pixel(&self) -> u32 {
+        match *self {
+            Format::Rgb565 => 2,
+            _ => 4
+        }
+    }
+
+    pub fn has_alpha(&self) -> bool {
+        matches!(*self, Format::Argb8888 | Format::Abgr8888)
+    }
+
+    /**
+     * Bits of red, green and blue.
+     **/
+    pub fn channel_bits(&self) -> [u32; 3] {
+        match *self {
+            Format::Rgb565 => [5, 6, 5],
+            _ => [8, 8, 8]
+        }
+    }
+
+    /**
+     * Converts a color to its pixel value. Formats without alpha channel
+     * store an opaque alpha in the padding bits.
+     **/
+    pub fn pack(&self, c: &Color) -> u32 {
+        let a = if self.has_alpha() { c.a as u32 } else { 0xff };
+        match *self {
+            Format::Xrgb8888 | Format::Argb8888 =>
+                a << 24 | (c.r as u32) << 16 | (c.g as u32) << 8 | c.b as u32,
+            Format::Xbgr8888 | Format::Abgr8888 =>
+                a << 24 | (c.b as u32) << 16 | (c.g as u32) << 8 | c.r as u32,
+            Format::Rgb565 =>
+                (c.r as u32 >> 3) << 11 | (c.g as u32 >> 2) << 5 | c.b as u32 >> 3
+        }
+    }
+
+    pub fn unpack(&self, pixel: u32) -> Color {
+        match *self {
+            Format::Xrgb8888 | Format::Argb8888 => Color::new(
+                (pixel >> 16) as u8,
+                (pixel >> 8) as u8,
+                pixel as u8,
+                if self.has_alpha() { (pixel >> 24) as u8 } else { 0xff }
+            ),
+            Format::Xbgr8888 | Format::Abgr8888 => Color::new(
+                pixel as u8,
+                (pixel >> 8) as u8,
+                (pixel >> 16) as u8,
+                if self.has_alpha() { (pixel >> 24) as u8 } else { 0xff }
+            ),
+            Format::Rgb565 => {
+                // replicate the high bits so that white stays white
+                let r = (pixel >> 11) as u8 & 0x1f;
+                let g = (pixel >> 5) as u8 & 0x3f;
+                let b = pixel as u8 & 0x1f;
+                Color::new(r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 0xff)
+            }
+        }
+    }
+
+    /**
+     * Writes a color to the start of `dst`.
+     **/
+    pub fn write(&self, dst: &mut [u8], c: &Color) {
+        let pixel = self.pack(c);
+        let bpp = self.bytes_per_pixel() as usize;
+        for (i, byte) in dst[..bpp].iter_mut().enumerate() {
+            *byte = (pixel >> (8 * i)) as u8;
+        }
+    }
+
+    /**
+     * Reads the color at the start of `src`.
+     **/
+    pub fn read(&self, src: &[u8]) -> Color {
+        let bpp = self.bytes_per_pixel() as usize;
+        let pixel = src[..bpp]
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, &byte)| acc | (byte as u32) << (8 * i));
+        self.unpack(pixel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xrgb_memory_order() {
+        let mut buffer = [0u8; 4];
+        Format::Xrgb8888.write(&mut buffer, &Color::new(1, 2, 3, 4));
+        assert_eq!(buffer, [3, 2, 1, 0xff]);
+        Format::Abgr8888.write(&mut buffer, &Color::new(1, 2, 3, 4));
+        assert_eq!(buffer, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let c = Color::new(255, 128, 0, 255);
+        for &format in [Format::Xrgb8888, Format::Argb8888, Format::Xbgr8888, Format::Abgr8888].iter() {
+            assert_eq!(format.unpack(format.pack(&c)), c);
+        }
+        let white = Color::new(255, 255, 255, 255);
+        assert_eq!(Format::Rgb565.unpack(Format::Rgb565.pack(&white)), white);
+    }
+}
@@ -0,0 +1,293 @@
+extern crate libc;
+
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use std::thread;
+use std::time::Duration;
+
+use super::drm::{drm_ioctl, Pointer};
+use super::drm_const::*;
+use super::ffi::*;
+
+impl Pointer for drm_crtc_get_sequence {}
+impl Pointer for drm_crtc_queue_sequence {}
+
+/**
+ * Fallback refresh period for modes that don't report a pixel clock.
+ **/
+const DEFAULT_FRAME_NS: u64 = 16_666_667;
+
+/**
+ * A vblank of a crtc, identified by its sequence number and the
+ * CLOCK_MONOTONIC time at which it happened (or is expected to happen).
+ **/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTime {
+    pub sequence: u64,
+    pub time_ns: i64
+}
+
+/**
+ * An event read from the DRM device file.
+ **/
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    CrtcSequence {
+        user_data: u64,
+        frame: FrameTime
+    },
+    Other(u32)
+}
+
+/**
+ * Keeps track of the vblanks of a single output and tells the compositor
+ * when to start rendering the next frame.
+ **/
+#[derive(Debug)]
+pub struct FrameClock {
+    fd: File,
+    crtc_id: u32,
+    frame_ns: u64,
+    last: Option<FrameTime>
+}
+
+impl FrameClock {
+    pub fn new(fd: File, crtc_id: u32, mode: &drm_mode_modeinfo) -> FrameClock {
+        FrameClock {
+            fd,
+            crtc_id,
+            frame_ns: frame_duration(mode),
+            last: None
+        }
+    }
+
+    pub fn crtc_id(&self) -> u32 {
+        self.crtc_id
+    }
+
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_nanos(self.frame_ns)
+    }
+
+    /**
+     * Returns the sequence number and timestamp of the most recent vblank.
+     **/
+    pub fn current(&mut self) -> io::Result<FrameTime> {
+        let mut seq = drm_crtc_get_sequence { crtc_id: self.crtc_id, ..Default::default() };
+        drm_ioctl(&self.fd, DRM_IOCTL_CRTC_GET_SEQUENCE, &mut seq)?;
+
+        let frame = FrameTime {
+            sequence: seq.sequence,
+            time_ns: seq.sequence_ns
+        };
+        self.last = Some(frame);
+        Ok(frame)
+    }
+
+    /**
+     * Predicts the first vblank after `now_ns` from the last known vblank
+     * and the refresh period of the mode.
+     **/
+    pub fn predict(&self, now_ns: i64) -> Option<FrameTime> {
+        let last = self.last?;
+        let frames = if now_ns < last.time_ns {
+            1
+        } else {
+            (now_ns - last.time_ns) as u64 / self.frame_ns + 1
+        };
+        Some(FrameTime {
+            sequence: last.sequence + frames,
+            time_ns: last.time_ns + (frames * self.frame_ns) as i64
+        })
+    }
+
+    pub fn next_vblank(&mut self) -> io::Result<FrameTime> {
+        self.current()?;
+        Ok(self.predict(monotonic_ns()).unwrap())
+    }
+
+    /**
+     * Asks the kernel to send a `CrtcSequence` event once the crtc reaches
+     * `target`. Returns the sequence the event was actually queued for,
+     * which is later than `target` if that vblank has already passed.
+     **/
+    pub fn queue(&mut self, target: u64) -> io::Result<u64> {
+        let mut seq = drm_crtc_queue_sequence {
+            crtc_id: self.crtc_id,
+            flags: DRM_CRTC_SEQUENCE_NEXT_ON_MISS,
+            sequence: target,
+            user_data: self.crtc_id as u64
+        };
+        drm_ioctl(&self.fd, DRM_IOCTL_CRTC_QUEUE_SEQUENCE, &mut seq)?;
+        Ok(seq.sequence)
+    }
+
+    /**
+     * Updates the clock from an event read from the device. Returns the
+     * frame if the event belongs to this clock's crtc.
+     **/
+    pub fn dispatch(&mut self, event: &Event) -> Option<FrameTime> {
+        match *event {
+            Event::CrtcSequence { user_data, frame } if user_data == self.crtc_id as u64 => {
+                self.last = Some(frame);
+                Some(frame)
+            }
+            _ => None
+        }
+    }
+
+    /**
+     * Blocks until the queued event for `target` arrives. Events for other
+     * crtcs on the same device are dropped, so this is meant for single
+     * output setups; otherwise use `read_events` and `dispatch`.
+     **/
+    pub fn wait(&mut self, target: u64) -> io::Result<FrameTime> {
+        let target = self.queue(target)?;
+        loop {
+            poll_readable(&self.fd, None)?;
+            for event in read_events(&mut self.fd)? {
+                if let Some(frame) = self.dispatch(&event) {
+                    if frame.sequence >= target {
+                        return Ok(frame);
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * Sleeps until `budget` before the next vblank and returns the frame
+     * the caller should render for. Rendering then finishes just in time
+     * instead of a full frame early.
+     **/
+    pub fn wait_for_render(&mut self, budget: Duration) -> io::Result<FrameTime> {
+        let now = monotonic_ns();
+        self.current()?;
+        let mut next = self.predict(now).unwrap();
+
+        let budget = budget.as_secs() as i64 * 1_000_000_000 + budget.subsec_nanos() as i64;
+        if next.time_ns - budget < now {
+            // too late for this one, aim for the vblank after
+            next.sequence += 1;
+            next.time_ns += self.frame_ns as i64;
+        }
+
+        let wake = next.time_ns - budget;
+        if wake > now {
+            thread::sleep(Duration::from_nanos((wake - now) as u64));
+        }
+        Ok(next)
+    }
+}
+
+fn frame_duration(mode: &drm_mode_modeinfo) -> u64 {
+    let pixels = mode.htotal as u64 * mode.vtotal as u64;
+    if mode.clock == 0 || pixels == 0 {
+        return DEFAULT_FRAME_NS;
+    }
+    // the clock is given in kHz
+    pixels * 1_000_000 / mode.clock as u64
+}
+
+pub fn monotonic_ns() -> i64 {
+    let mut ts: libc::timespec = unsafe { mem::zeroed() };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as i64 * 1_000_000_000 + ts.tv_nsec as i64
+}
+
+/**
+ * Waits until the device has events to read. Returns false on timeout.
+ **/
+pub fn poll_readable(fd: &File, timeout: Option<Duration>) -> io::Result<bool> {
+    poll_any(&[fd], timeout)
+}
+
+/**
+ * Waits until any of `files` has data to read. Returns false on timeout.
+ **/
+pub fn poll_any(files: &[&File], timeout: Option<Duration>) -> io::Result<bool> {
+    let timeout = timeout
+        .map(|t| (t.as_secs() * 1000 + t.subsec_nanos() as u64 / 1_000_000) as libc::c_int)
+        .unwrap_or(-1);
+    let mut pfds: Vec<libc::pollfd> = files.iter().map(|f| libc::pollfd {
+        fd: f.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0
+    }).collect();
+    loop {
+        let ret = unsafe { libc::poll(pfds.as_mut_ptr(), pfds.len() as libc::nfds_t, timeout) };
+        if ret >= 0 {
+            return Ok(ret > 0);
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/**
+ * Reads all pending events from the device.
+ **/
+pub fn read_events(fd: &mut File) -> io::Result<Vec<Event>> {
+    let mut buffer = [0u8; 1024];
+    let len = fd.read(&mut buffer)?;
+
+    let mut events = Vec::new();
+    let mut offset = 0;
+    while offset + mem::size_of::<drm_event>() <= len {
+        let header: drm_event = unsafe { ptr::read_unaligned(buffer[offset..].as_ptr() as *const _) };
+        if header.length == 0 || offset + header.length as usize > len {
+            break;
+        }
+
+        if header.type_ == DRM_EVENT_CRTC_SEQUENCE {
+            let seq: drm_event_crtc_sequence = unsafe { ptr::read_unaligned(buffer[offset..].as_ptr() as *const _) };
+            events.push(Event::CrtcSequence {
+                user_data: seq.user_data,
+                frame: FrameTime {
+                    sequence: seq.sequence,
+                    time_ns: seq.time_ns
+                }
+            });
+        } else {
+            events.push(Event::Other(header.type_));
+        }
+        offset += header.length as usize;
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(frame_ns: u64, last: FrameTime) -> FrameClock {
+        FrameClock {
+            fd: File::open("/dev/null").unwrap(),
+            crtc_id: 1,
+            frame_ns,
+            last: Some(last)
+        }
+    }
+
+    #[test]
+    fn predicts_next_vblank() {
+        let c = clock(1000, FrameTime { sequence: 10, time_ns: 5000 });
+        assert_eq!(c.predict(5000), Some(FrameTime { sequence: 11, time_ns: 6000 }));
+        assert_eq!(c.predict(7500), Some(FrameTime { sequence: 13, time_ns: 8000 }));
+        assert_eq!(c.predict(4000), Some(FrameTime { sequence: 11, time_ns: 6000 }));
+    }
+
+    #[test]
+    fn frame_duration_from_mode() {
+        let mut mode = drm_mode_modeinfo { clock: 148_500, htotal: 2200, vtotal: 1125, ..Default::default() };
+        assert_eq!(frame_duration(&mode), 16_666_666);
+        mode.clock = 0;
+        assert_eq!(frame_duration(&mode), DEFAULT_FRAME_NS);
+    }
+}
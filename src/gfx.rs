@@ -1,44 +1,793 @@
+use std::io;
 use std::os::unix::io::AsRawFd;
-use super::drm::{open, Color, DeviceInterface};
+use std::sync::{Arc, Mutex, MutexGuard};
+use super::atlas::{Mask, MaskFormat};
+use super::backend::{Backend, MemoryBackend};
+use super::blend::{self, Operator};
+use super::dither::{self, Dither};
+use super::drm::{open, Color, FrameBuffer, Rect};
+use super::effects::{self, Gradient, Shadow};
+use super::format::Format;
+use super::frame_clock::FrameClock;
+use super::image::{Filter, Image};
+use super::raster;
+use super::simd;
+use super::span;
+use super::text::{self, BitmapFont, Font};
+use super::transform::{Affine, OutputTransform, TransformedBackend};
+use super::yuv::YuvImage;
+use super::input::InputEvent;
 
-pub struct GFX {
-    di: DeviceInterface,
+/**
+ * A font that several GFX instances draw with.
+ **/
+pub type SharedFont = Arc<Mutex<Box<dyn Font>>>;
+
+#[allow(clippy::upper_case_acronyms)]
+pub struct GFX<'a> {
+    backend: Box<dyn Backend + 'a>,
+    clips: Vec<Rect>,
+    operator: Operator,
+    stroke_width: u32,
+    filter: Filter,
+    dither: Dither,
+    font: SharedFont,
 }
 
-impl GFX {
-    pub fn new() -> GFX {
+impl<'a> GFX<'a> {
+    pub fn new() -> GFX<'a> {
+        GFX::with_transform(OutputTransform::Normal)
+    }
+
+    /**
+     * Opens the first output, showing it turned by `transform`.
+     **/
+    pub fn with_transform(transform: OutputTransform) -> GFX<'a> {
+        GFX::outputs(transform).remove(0)
+    }
+
+    /**
+     * Opens every connected output of the first card, each showing the
+     * picture turned by `transform`. The display hardware turns the picture
+     * when it can, otherwise it is done in software.
+     **/
+    pub fn outputs(transform: OutputTransform) -> Vec<GFX<'a>> {
+        open("/dev/dri/card0").fbs.into_iter().map(|fb| GFX::with_framebuffer(fb, transform)).collect()
+    }
+
+    fn with_framebuffer(mut fb: FrameBuffer, transform: OutputTransform) -> GFX<'a> {
+        if transform == OutputTransform::Normal || fb.set_transform(transform).is_ok() {
+            return GFX::with_backend(Box::new(fb));
+        }
+        GFX::with_backend(Box::new(TransformedBackend::new(Box::new(fb), transform).unwrap()))
+    }
+
+    pub fn with_backend(backend: Box<dyn Backend + 'a>) -> GFX<'a> {
+        GFX::with_font(backend, Arc::new(Mutex::new(Box::new(BitmapFont::default_font()))))
+    }
+
+    /**
+     * Creates a GFX that draws text with a font shared with other GFX
+     * instances, e.g. those rendering other parts of the screen.
+     **/
+    pub fn with_font(backend: Box<dyn Backend + 'a>, font: SharedFont) -> GFX<'a> {
         GFX {
-            di: open("/dev/dri/card0")
+            backend,
+            clips: Vec::new(),
+            operator: Operator::Over,
+            stroke_width: 1,
+            filter: Filter::Bilinear,
+            dither: Dither::None,
+            font
+        }
+    }
+
+    /**
+     * Creates a GFX that renders into memory instead of a display.
+     **/
+    pub fn headless(width: u32, height: u32, format: Format) -> io::Result<GFX<'a>> {
+        Ok(GFX::with_backend(Box::new(MemoryBackend::new(width, height, format)?)))
+    }
+
+    pub fn backend(&self) -> &dyn Backend {
+        &*self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut dyn Backend {
+        &mut *self.backend
+    }
+
+    pub fn width(&self) -> u32 {
+        self.backend.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.backend.height()
+    }
+
+    /**
+     * Sets how colors drawn from now on are combined with the framebuffer.
+     * Defaults to `Operator::Over`.
+     **/
+    pub fn set_operator(&mut self, op: Operator) {
+        self.operator = op;
+    }
+
+    pub fn operator(&self) -> Operator {
+        self.operator
+    }
+
+    /**
+     * Sets the width of lines and outlines drawn from now on.
+     **/
+    pub fn set_stroke_width(&mut self, width: u32) {
+        self.stroke_width = width.max(1);
+    }
+
+    pub fn stroke_width(&self) -> u32 {
+        self.stroke_width
+    }
+
+    /**
+     * Sets how images are sampled when `blit` scales them. Defaults to
+     * `Filter::Bilinear`.
+     **/
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    /**
+     * Sets whether gradients are dithered to the bits the output has per
+     * channel. Only ordered dithering is used here, as it gives the same
+     * result however the screen is split up for drawing.
+     **/
+    pub fn set_dither(&mut self, dither: Dither) {
+        self.dither = dither;
+    }
+
+    pub fn dither(&self) -> Dither {
+        self.dither
+    }
+
+    /**
+     * Sets the font used by `text`. Defaults to the built-in bitmap font.
+     **/
+    pub fn set_font(&mut self, font: Box<dyn Font>) {
+        self.font = Arc::new(Mutex::new(font));
+    }
+
+    pub fn font(&self) -> MutexGuard<'_, Box<dyn Font>> {
+        self.font.lock().unwrap()
+    }
+
+    pub fn shared_font(&self) -> SharedFont {
+        self.font.clone()
+    }
+
+    /**
+     * Reads back the (premultiplied) color of a pixel. Returns `None`
+     * outside of the framebuffer.
+     **/
+    pub fn get(&self, x: i32, y: i32) -> Option<Color> {
+        if self.backend.area().intersect(&Rect::new(x, y, 1, 1)).is_empty() {
+            return None;
+        }
+        Some(self.backend.format().read(&self.backend.pixels()[self.offset(x, y)..]))
+    }
+
+    /**
+     * Restricts all drawing to `rect` until the matching `pop_clip`. Clip
+     * rectangles nest, so the effective clip never grows.
+     **/
+    pub fn push_clip(&mut self, rect: Rect) {
+        let clip = self.clip().intersect(&rect);
+        self.clips.push(clip);
+    }
+
+    pub fn pop_clip(&mut self) -> Option<Rect> {
+        self.clips.pop()
+    }
+
+    /**
+     * The region drawing is currently restricted to.
+     **/
+    pub fn clip(&self) -> Rect {
+        let bounds = self.backend.area();
+        match self.clips.last() {
+            Some(clip) => clip.intersect(&bounds),
+            None => bounds
+        }
+    }
+
+    pub fn point(&mut self, x: i32, y: i32, c: &Color) {
+        self.fill(Rect::new(x, y, 1, 1), c);
+    }
+
+    pub fn vertical_line(&mut self, x: i32, y: i32, height: u32, c: &Color) {
+        self.fill(Rect::new(x, y, 1, height), c);
+    }
+
+    pub fn horizontal_line(&mut self, x: i32, y: i32, width: u32, c: &Color) {
+        self.fill(Rect::new(x, y, width, 1), c);
+    }
+
+    pub fn rectangle(&mut self, x: i32, y: i32, width: u32, height: u32, c: &Color) {
+        self.fill(Rect::new(x, y, width, height), c);
+    }
+
+    /**
+     * Draws a line between two pixels with the current stroke width.
+     **/
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, c: &Color) {
+        if self.stroke_width > 1 {
+            let corners = raster::thick_line(
+                x0 as f32 + 0.5, y0 as f32 + 0.5,
+                x1 as f32 + 0.5, y1 as f32 + 0.5,
+                self.stroke_width as f32
+            );
+            self.polygon(&corners, c);
+            return;
+        }
+
+        let c = c.premultiply();
+        let clip = self.clip();
+        raster::line(x0, y0, x1, y1, clip, |x, y| { self.paint(Rect::new(x, y, 1, 1), &c); });
+        self.damage_bounds(x0.min(x1), y0.min(y1), x0.max(x1) + 1, y0.max(y1) + 1);
+    }
+
+    /**
+     * Draws an antialiased one pixel wide line. Coordinates refer to pixel
+     * corners, so the center of the top left pixel is `(0.5, 0.5)`.
+     **/
+    pub fn line_aa(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, c: &Color) {
+        let c = *c;
+        let clip = self.clip();
+        raster::line_aa(x0 - 0.5, y0 - 0.5, x1 - 0.5, y1 - 0.5, clip, |x, y, coverage| {
+            let a = (c.a as f32 * coverage).round() as u8;
+            let pixel = Color::new(c.r, c.g, c.b, a).premultiply();
+            self.paint(Rect::new(x, y, 1, 1), &pixel);
+        });
+        self.damage_bounds(
+            x0.min(x1).floor() as i32 - 1,
+            y0.min(y1).floor() as i32 - 1,
+            x0.max(x1).ceil() as i32 + 1,
+            y0.max(y1).ceil() as i32 + 1
+        );
+    }
+
+    /**
+     * Draws the outline of a circle around the pixel `(cx, cy)`.
+     **/
+    pub fn circle(&mut self, cx: i32, cy: i32, radius: u32, c: &Color) {
+        self.ellipse(cx, cy, radius, radius, c);
+    }
+
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: u32, c: &Color) {
+        self.fill_ellipse(cx, cy, radius, radius, c);
+    }
+
+    pub fn ellipse(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, c: &Color) {
+        let stroke = self.stroke_width;
+        self.ellipse_spans(cx, cy, rx, ry, Some(stroke), c);
+    }
+
+    pub fn fill_ellipse(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, c: &Color) {
+        self.ellipse_spans(cx, cy, rx, ry, None, c);
+    }
+
+    pub fn rounded_rectangle(&mut self, x: i32, y: i32, width: u32, height: u32, radius: u32, c: &Color) {
+        let c = c.premultiply();
+        let clip = self.clip();
+        raster::rounded_rectangle(Rect::new(x, y, width, height), radius, clip, |y, x0, x1| self.span(y, x0, x1, &c));
+        self.damage_bounds(x, y, x + width as i32, y + height as i32);
+    }
+
+    /**
+     * Draws the outline of a rounded rectangle. The stroke lies inside the
+     * rectangle.
+     **/
+    pub fn stroke_rounded_rectangle(&mut self, x: i32, y: i32, width: u32, height: u32, radius: u32, c: &Color) {
+        let c = c.premultiply();
+        let stroke = self.stroke_width;
+        let clip = self.clip();
+        raster::stroke_rounded_rectangle(Rect::new(x, y, width, height), radius, stroke, clip, |y, x0, x1| self.span(y, x0, x1, &c));
+        self.damage_bounds(x, y, x + width as i32, y + height as i32);
+    }
+
+    /**
+     * Fills a polygon using the even-odd rule. Coordinates refer to pixel
+     * corners.
+     **/
+    pub fn polygon(&mut self, points: &[(f32, f32)], c: &Color) {
+        if points.is_empty() {
+            return;
+        }
+        let c = c.premultiply();
+        let clip = self.clip();
+        raster::polygon(points, clip, |y, x0, x1| self.span(y, x0, x1, &c));
+
+        let (mut x0, mut y0) = points[0];
+        let (mut x1, mut y1) = points[0];
+        for &(x, y) in points.iter() {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+        self.damage_bounds(x0.floor() as i32, y0.floor() as i32, x1.ceil() as i32, y1.ceil() as i32);
+    }
+
+    /**
+     * Draws the outline of a polygon through the given pixels with the
+     * current stroke width.
+     **/
+    pub fn stroke_polygon(&mut self, points: &[(i32, i32)], c: &Color) {
+        let clip = self.clip();
+        if points.is_empty() || clip.is_empty() {
+            return;
+        }
+
+        // collect the pixels of all edges first, so that the ones where
+        // edges meet are blended only once
+        let width = clip.width as usize;
+        let mut covered = vec![false; width * clip.height as usize];
+        {
+            let mut plot = |y: i32, x0: i32, x1: i32| {
+                if y < clip.y || y >= clip.bottom() {
+                    return;
+                }
+                let row = (y - clip.y) as usize * width;
+                for x in x0.max(clip.x)..x1.min(clip.right()) {
+                    covered[row + (x - clip.x) as usize] = true;
+                }
+            };
+            for i in 0..points.len() {
+                let (x0, y0) = points[i];
+                let (x1, y1) = points[(i + 1) % points.len()];
+                if self.stroke_width > 1 {
+                    let corners = raster::thick_line(
+                        x0 as f32 + 0.5, y0 as f32 + 0.5,
+                        x1 as f32 + 0.5, y1 as f32 + 0.5,
+                        self.stroke_width as f32
+                    );
+                    raster::polygon(&corners, clip, &mut plot);
+                } else {
+                    raster::line(x0, y0, x1, y1, clip, |x, y| plot(y, x, x + 1));
+                }
+            }
+        }
+
+        let c = c.premultiply();
+        for (row, pixels) in covered.chunks(width).enumerate() {
+            let y = clip.y + row as i32;
+            let mut x = 0;
+            while x < width {
+                if !pixels[x] {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < width && pixels[x] {
+                    x += 1;
+                }
+                self.span(y, clip.x + start as i32, clip.x + x as i32, &c);
+            }
+        }
+
+        let half = self.stroke_width as i32 / 2 + 1;
+        let (mut x0, mut y0) = points[0];
+        let (mut x1, mut y1) = points[0];
+        for &(x, y) in points.iter() {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+        self.damage_bounds(x0 - half, y0 - half, x1 + half + 1, y1 + half + 1);
+    }
+
+    /**
+     * Draws the `src_rect` part of `src` into `dst_rect`, scaling it with
+     * the current filter if the sizes differ.
+     **/
+    pub fn blit(&mut self, src: &Image, src_rect: Rect, dst_rect: Rect) {
+        let src_rect = src_rect.intersect(&src.bounds());
+        let clipped = dst_rect.intersect(&self.clip());
+        if src_rect.is_empty() || clipped.is_empty() {
+            return;
+        }
+
+        let format = self.backend.format();
+        let bpp = format.bytes_per_pixel() as usize;
+        let stride = self.backend.stride() as usize;
+        let unscaled = src_rect.width == dst_rect.width && src_rect.height == dst_rect.height;
+        let opaque = !src.format().has_alpha() || self.operator == Operator::Source;
+        let copy = unscaled && (src.format() == format || simd::supports(src.format(), format))
+            && (self.operator == Operator::Source || (self.operator == Operator::Over && opaque));
+        let over = unscaled && self.operator == Operator::Over && !opaque && simd::same_order(src.format(), format);
+
+        let scale_x = src_rect.width as f32 / dst_rect.width as f32;
+        let scale_y = src_rect.height as f32 / dst_rect.height as f32;
+        let filter = if unscaled { Filter::Nearest } else { self.filter };
+        let op = self.operator;
+
+        let offset = self.offset(clipped.x, clipped.y);
+        let mut colors: Vec<Color> = Vec::with_capacity(clipped.width as usize);
+        let pixels = self.backend.pixels_mut();
+        for y in clipped.y..clipped.bottom() {
+            let start = offset + (y - clipped.y) as usize * stride;
+            let row = &mut pixels[start..start + clipped.width as usize * bpp];
+
+            if copy || over {
+                let sx = (src_rect.x + clipped.x - dst_rect.x) as usize * bpp;
+                let sy = (src_rect.y + y - dst_rect.y) as u32;
+                let src_row = &src.row(sy)[sx..sx + row.len()];
+                if over {
+                    simd::over(row, format, src_row);
+                } else if src.format() == format {
+                    simd::copy(row, src_row);
+                } else {
+                    simd::convert(row, format, src_row, src.format());
+                }
+                continue;
+            }
+
+            let sy = src_rect.y as f32 + (y - dst_rect.y) as f32 * scale_y + scale_y / 2.0;
+            colors.clear();
+            for x in clipped.x..clipped.right() {
+                let sx = src_rect.x as f32 + (x - dst_rect.x) as f32 * scale_x + scale_x / 2.0;
+                colors.push(filter.sample(src, &src_rect, sx, sy));
+            }
+            if op == Operator::Source {
+                for (pixel, c) in row.chunks_mut(bpp).zip(colors.iter()) {
+                    format.write(pixel, c);
+                }
+            } else {
+                blend::blend_span(row, format, &colors, op);
+            }
+        }
+        self.backend.damage(clipped);
+    }
+
+    /**
+     * Draws the `src_rect` part of `src` moved, scaled, rotated or mirrored
+     * by `transform`, which maps image coordinates to screen coordinates.
+     * Pixels are sampled with the current filter.
+     **/
+    pub fn blit_transformed(&mut self, src: &Image, src_rect: Rect, transform: &Affine) {
+        let src_rect = src_rect.intersect(&src.bounds());
+        let inverse = match transform.invert() {
+            Some(inverse) => inverse,
+            None => return
+        };
+        let clipped = transform.bounds(&src_rect).intersect(&self.clip());
+        if src_rect.is_empty() || clipped.is_empty() {
+            return;
+        }
+
+        let filter = self.filter;
+        let (left, top) = (src_rect.x as f32, src_rect.y as f32);
+        let (right, bottom) = (src_rect.right() as f32, src_rect.bottom() as f32);
+        let mut colors = Vec::with_capacity(clipped.width as usize);
+        for y in clipped.y..clipped.bottom() {
+            // the image is convex, so it covers one run of pixels per row
+            colors.clear();
+            let mut start = None;
+            for x in clipped.x..clipped.right() {
+                let (sx, sy) = inverse.apply(x as f32 + 0.5, y as f32 + 0.5);
+                if sx >= left && sx < right && sy >= top && sy < bottom {
+                    start.get_or_insert(x);
+                    colors.push(filter.sample(src, &src_rect, sx, sy));
+                } else if start.is_some() {
+                    break;
+                }
+            }
+            if let Some(x) = start {
+                self.paint_span(x, y, &colors);
+            }
         }
+        self.backend.damage(clipped);
     }
 
-    pub fn point(&mut self, x: u32, y: u32, c: &Color) {
-        self.di.fbs[0].set(x, y, c);
+    /**
+     * Draws the `src_rect` part of a YUV frame into `dst_rect`, converting
+     * it to RGB and scaling it with the current filter.
+     **/
+    pub fn blit_yuv(&mut self, src: &YuvImage, src_rect: Rect, dst_rect: Rect) {
+        if let Ok(image) = src.to_image(src_rect) {
+            self.blit(&image, image.bounds(), dst_rect);
+        }
     }
 
-    pub fn vertical_line(&mut self, x: u32, y: u32, height: u32, c: &Color) {
-        for l in 0..height {
-            self.point(x, y + l, c);
+    /**
+     * Shows a video frame in `dst_rect`, on an overlay plane if the display
+     * has one for the frame's format and drawn like `blit_yuv` otherwise.
+     * The overlay hides whatever is drawn below it, so it suits video that
+     * nothing overlaps, like fullscreen playback. Returns whether the
+     * overlay was used.
+     **/
+    pub fn show_video(&mut self, frame: &YuvImage, dst_rect: Rect) -> bool {
+        match self.backend.show_video(frame, dst_rect) {
+            Ok(true) => return true,
+            Ok(false) => {}
+            Err(_) => {
+                let _ = self.backend.hide_video();
+            }
         }
+        self.blit_yuv(frame, frame.bounds(), dst_rect);
+        false
     }
 
-    pub fn horizontal_line(&mut self, x:u32, y:u32, width: u32, c: &Color) {
-        for l in 0..width {
-            self.point(x + l, y, c);
+    /**
+     * Fills `rect` with a gradient, using the current operator.
+     **/
+    pub fn fill_gradient(&mut self, rect: Rect, gradient: &Gradient) {
+        let clipped = rect.intersect(&self.clip());
+        if clipped.is_empty() {
+            return;
+        }
+        let (format, dither) = (self.backend.format(), self.dither);
+        let mut colors = Vec::with_capacity(clipped.width as usize);
+        for y in clipped.y..clipped.bottom() {
+            colors.clear();
+            colors.extend((clipped.x..clipped.right()).map(|x| match dither {
+                Dither::None => gradient.color_at(x, y),
+                _ => dither::ordered(gradient.precise_color_at(x, y), format, x, y)
+            }));
+            self.paint_span(clipped.x, y, &colors);
+        }
+        self.backend.damage(clipped);
+    }
+
+    /**
+     * Draws the shadow `rect` casts onto what is behind it. Like CSS box
+     * shadows, nothing is drawn inside `rect` itself.
+     **/
+    pub fn box_shadow(&mut self, rect: Rect, shadow: &Shadow) {
+        let bounds = shadow.bounds(rect);
+        let clipped = bounds.intersect(&self.clip());
+        if clipped.is_empty() {
+            return;
+        }
+        let (x0, y0) = (rect.x + shadow.offset_x, rect.y + shadow.offset_y);
+        let columns = shadow.profile(x0, x0 + rect.width as i32, clipped.x, clipped.width);
+        let rows = shadow.profile(y0, y0 + rect.height as i32, clipped.y, clipped.height);
+        let c = shadow.color.premultiply();
+
+        let mut colors = Vec::with_capacity(clipped.width as usize);
+        for (y, row_coverage) in (clipped.y..clipped.bottom()).zip(rows) {
+            colors.clear();
+            for (x, column_coverage) in (clipped.x..clipped.right()).zip(columns.iter()) {
+                let inside = x >= rect.x && x < rect.right() && y >= rect.y && y < rect.bottom();
+                let coverage = if inside { 0.0 } else { row_coverage * column_coverage };
+                let scale = |v: u8| (v as f32 * coverage + 0.5) as u8;
+                colors.push(Color::new(scale(c.r), scale(c.g), scale(c.b), scale(c.a)));
+            }
+            self.paint_span(clipped.x, y, &colors);
         }
+        self.backend.damage(clipped);
     }
 
-    pub fn rectangle(&mut self, x: u32, y: u32, width: u32, height: u32, c: &Color) {
-        for l in 0..width {
-            self.vertical_line(x + l, y, height, c);
+    /**
+     * Blurs the part of `rect` inside the clip rectangle by averaging each
+     * pixel with its neighbours up to `radius` pixels away.
+     **/
+    pub fn blur(&mut self, rect: Rect, radius: u32) {
+        self.blur_passes(rect, &[radius]);
+    }
+
+    /**
+     * Blurs the part of `rect` inside the clip rectangle with a Gaussian of
+     * standard deviation `sigma`, approximated by three box blurs.
+     **/
+    pub fn gaussian_blur(&mut self, rect: Rect, sigma: f32) {
+        self.blur_passes(rect, &effects::gaussian_boxes(sigma));
+    }
+
+    fn blur_passes(&mut self, rect: Rect, radii: &[u32]) {
+        let clipped = rect.intersect(&self.clip());
+        if clipped.is_empty() {
+            return;
         }
+        let offset = self.offset(clipped.x, clipped.y);
+        let (stride, format) = (self.backend.stride() as usize, self.backend.format());
+        for &radius in radii.iter() {
+            let pixels = &mut self.backend.pixels_mut()[offset..];
+            effects::box_blur(pixels, stride, format, clipped.width, clipped.height, radius);
+        }
+        self.backend.damage(clipped);
+    }
+
+    /**
+     * Draws UTF-8 text with the top left corner of the first line at
+     * `(x, y)`. Newlines start a new line.
+     **/
+    pub fn text(&mut self, x: i32, y: i32, s: &str, c: &Color) {
+        let mut glyphs = Vec::new();
+        text::layout(&mut **self.font(), x, y, s, |x, y, glyph| glyphs.push((x, y, glyph.clone())));
+
+        let c = c.premultiply();
+        for &(x, y, ref glyph) in glyphs.iter() {
+            let rect = Rect::new(x + glyph.left, y + glyph.top, glyph.width, glyph.height);
+            self.mask(rect, &glyph.mask, &c);
+        }
+
+        let bounds = text::measure(&mut **self.font(), s);
+        self.damage_bounds(x, y, x + bounds.width as i32, y + bounds.height as i32);
+    }
+
+    /**
+     * Returns the size of `s` when drawn with the current font.
+     **/
+    pub fn measure_text(&mut self, s: &str) -> Rect {
+        text::measure(&mut **self.font(), s)
     }
 
     pub fn clear(&mut self) {
         let c = Color::new(255, 255, 255, 255);
-        let height = self.di.fbs[0].height();        
-        let width = self.di.fbs[0].width();
-        self.rectangle(0, 0, width, height, &c);
+        let clip = self.clip();
+        self.fill(clip, &c);
+    }
+
+    /**
+     * Pushes everything drawn since the last flush to the display.
+     **/
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.backend.flush()
+    }
+
+    pub fn frame_clock(&self) -> io::Result<FrameClock> {
+        self.backend.frame_clock()
+    }
+
+    pub fn input_events(&mut self) -> Vec<InputEvent> {
+        self.backend.input_events()
+    }
+
+    fn ellipse_spans(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, stroke: Option<u32>, c: &Color) {
+        let c = c.premultiply();
+        let clip = self.clip();
+        raster::ellipse(cx, cy, rx, ry, stroke, clip, |y, x0, x1| self.span(y, x0, x1, &c));
+        let extra = stroke.unwrap_or(1) as i32;
+        self.damage_bounds(cx - rx as i32 - extra, cy - ry as i32 - extra, cx + rx as i32 + extra + 1, cy + ry as i32 + extra + 1);
+    }
+
+    /**
+     * Blends the premultiplied color `c` through a coverage mask covering
+     * `rect`, without reporting damage.
+     **/
+    fn mask(&mut self, rect: Rect, mask: &Mask, c: &Color) {
+        let clipped = rect.intersect(&self.clip());
+        if clipped.is_empty() {
+            return;
+        }
+
+        let op = self.operator;
+        let format = self.backend.format();
+        let bpp = format.bytes_per_pixel() as usize;
+        let stride = self.backend.stride() as usize;
+        let scale = |v: u8, coverage: u8| blend::mul_div255(v, coverage);
+
+        let offset = self.offset(clipped.x, clipped.y);
+        let mut colors = Vec::with_capacity(clipped.width as usize);
+        let mut coverages = Vec::with_capacity(clipped.width as usize);
+        let pixels = self.backend.pixels_mut();
+        mask.read(|data, mask_stride| {
+            for y in clipped.y..clipped.bottom() {
+                let mask_row = &data[(y - rect.y) as usize * mask_stride..];
+                let start = offset + (y - clipped.y) as usize * stride;
+                let row = &mut pixels[start..start + clipped.width as usize * bpp];
+                let columns = (clipped.x - rect.x) as usize..(clipped.right() - rect.x) as usize;
+
+                match mask.format() {
+                    MaskFormat::Alpha => {
+                        colors.clear();
+                        for &coverage in mask_row[columns].iter() {
+                            colors.push(Color::new(scale(c.r, coverage), scale(c.g, coverage), scale(c.b, coverage), scale(c.a, coverage)));
+                        }
+                        blend::blend_span(row, format, &colors, op);
+                    }
+                    MaskFormat::Subpixel => {
+                        coverages.clear();
+                        for x in columns {
+                            coverages.push([mask_row[x * 3], mask_row[x * 3 + 1], mask_row[x * 3 + 2]]);
+                        }
+                        blend::blend_component_span(row, format, c, &coverages, op);
+                    }
+                }
+            }
+        });
+    }
+
+    /**
+     * Index of the pixel at screen position `(x, y)` in the backend's
+     * pixels.
+     **/
+    fn offset(&self, x: i32, y: i32) -> usize {
+        let area = self.backend.area();
+        (y - area.y) as usize * self.backend.stride() as usize
+            + (x - area.x) as usize * self.backend.format().bytes_per_pixel() as usize
+    }
+
+    /**
+     * Blends premultiplied colors into the row starting at `(x, y)`, which
+     * must be inside the clip rectangle.
+     **/
+    fn paint_span(&mut self, x: i32, y: i32, colors: &[Color]) {
+        let op = self.operator;
+        let format = self.backend.format();
+        let start = self.offset(x, y);
+        let row = &mut self.backend.pixels_mut()[start..start + colors.len() * format.bytes_per_pixel() as usize];
+        blend::blend_span(row, format, colors, op);
+    }
+
+    fn span(&mut self, y: i32, x0: i32, x1: i32, c: &Color) {
+        self.paint(Rect::new(x0, y, (x1 - x0) as u32, 1), c);
+    }
+
+    /**
+     * Marks the region between two corners as damaged, restricted to the
+     * clip rectangle.
+     **/
+    fn damage_bounds(&mut self, x0: i32, y0: i32, x1: i32, y1: i32) {
+        let rect = Rect::new(x0, y0, (x1 - x0).max(0) as u32, (y1 - y0).max(0) as u32);
+        let rect = rect.intersect(&self.clip());
+        if !rect.is_empty() {
+            self.backend.damage(rect);
+        }
+    }
+
+    /**
+     * Fills the part of `rect` inside the clip rectangle using the current
+     * operator.
+     **/
+    fn fill(&mut self, rect: Rect, c: &Color) {
+        let c = c.premultiply();
+        if let Some(rect) = self.paint(rect, &c) {
+            self.backend.damage(rect);
+        }
+    }
+
+    /**
+     * Writes the premultiplied color `c` to the part of `rect` inside the
+     * clip rectangle without reporting damage. Returns the area written.
+     **/
+    fn paint(&mut self, rect: Rect, c: &Color) -> Option<Rect> {
+        let rect = rect.intersect(&self.clip());
+        if rect.is_empty() {
+            return None;
+        }
+
+        let c = *c;
+        let op = self.operator;
+        let format = self.backend.format();
+        let bpp = format.bytes_per_pixel();
+        let stride = self.backend.stride() as usize;
+        let offset = self.offset(rect.x, rect.y);
+        let row_len = (rect.width * bpp) as usize;
+
+        if op.is_copy(&c) {
+            span::fill_rows(
+                self.backend.pixels_mut(),
+                offset,
+                stride,
+                row_len,
+                rect.height as usize,
+                format.pack(&c),
+                bpp
+            );
+        } else {
+            let pixels = self.backend.pixels_mut();
+            for row in 0..rect.height as usize {
+                let start = offset + row * stride;
+                blend::blend_fill(&mut pixels[start..start + row_len], format, &c, op);
+            }
+        }
+        Some(rect)
     }
 
     pub fn test(&mut self) {
@@ -48,3 +797,278 @@ impl GFX {
         self.rectangle(10, 10, 100, 100, &c);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::truetype::TrueTypeFont;
+    use super::super::yuv::YuvFormat;
+
+    #[test]
+    fn gradients_and_shadows() {
+        let mut g = GFX::headless(40, 40, Format::Xrgb8888).unwrap();
+        g.rectangle(0, 0, 40, 40, &Color::new(255, 255, 255, 255));
+
+        let mut gradient = Gradient::linear(0.0, 0.0, 0.0, 10.0);
+        gradient.add_stop(0.0, &Color::new(255, 0, 0, 255));
+        gradient.add_stop(1.0, &Color::new(0, 0, 255, 255));
+        g.fill_gradient(Rect::new(0, 0, 4, 10), &gradient);
+        assert_eq!(g.get(0, 0), Some(Color::new(242, 0, 13, 255)));
+        assert_eq!(g.get(3, 9), Some(Color::new(13, 0, 242, 255)));
+        assert_eq!(g.get(4, 0), Some(Color::new(255, 255, 255, 255)));
+
+        let shadow = Shadow::new(2, 2, 4, Color::new(0, 0, 0, 255));
+        g.box_shadow(Rect::new(20, 20, 10, 10), &shadow);
+        // nothing under the box, dark right next to it, fading out
+        assert_eq!(g.get(25, 25), Some(Color::new(255, 255, 255, 255)));
+        assert!(g.get(30, 25).unwrap().r < 80);
+        assert!(g.get(34, 25).unwrap().r > 200);
+        assert_eq!(g.get(10, 25), Some(Color::new(255, 255, 255, 255)));
+    }
+
+    #[test]
+    fn dithers_gradients() {
+        // a flat gradient between two levels of the 5 bit red channel
+        let mut gradient = Gradient::linear(0.0, 0.0, 8.0, 0.0);
+        gradient.add_stop(0.0, &Color::new(100, 0, 0, 255));
+        let average = |dither: Dither| {
+            let mut g = GFX::headless(8, 8, Format::Rgb565).unwrap();
+            g.set_dither(dither);
+            g.fill_gradient(Rect::new(0, 0, 8, 8), &gradient);
+            let sum: u32 = (0..64).map(|i| g.get(i % 8, i / 8).unwrap().r as u32).sum();
+            sum as f32 / 64.0
+        };
+        assert_eq!(average(Dither::None), 99.0);
+        assert!((average(Dither::Ordered) - 100.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn blurs_regions() {
+        let mut g = GFX::headless(20, 10, Format::Xrgb8888).unwrap();
+        g.rectangle(10, 0, 10, 10, &Color::new(255, 255, 255, 255));
+        g.gaussian_blur(Rect::new(0, 0, 20, 5), 2.0);
+        let row: Vec<u8> = (0..20).map(|x| g.get(x, 2).unwrap().r).collect();
+        assert_eq!(row[0], 0);
+        assert_eq!(row[19], 255);
+        assert!(row.windows(2).all(|w| w[0] <= w[1]));
+        assert!(row[9] > 40 && row[10] < 215);
+        // outside the region the edge stays sharp
+        assert_eq!(g.get(9, 7).unwrap().r, 0);
+    }
+
+    #[test]
+    fn blits_transformed_images() {
+        let mut image = Image::new(4, 2, Format::Xrgb8888).unwrap();
+        image.fill(&Color::new(0, 0, 255, 255));
+        image.set(0, 0, &Color::new(255, 0, 0, 255));
+
+        // a quarter turn around the origin, then down into view
+        let mut g = GFX::headless(8, 8, Format::Xrgb8888).unwrap();
+        g.set_filter(Filter::Nearest);
+        let t = Affine::rotate(std::f32::consts::FRAC_PI_2).then(&Affine::translate(4.0, 1.0));
+        g.blit_transformed(&image, image.bounds(), &t);
+        assert_eq!(g.get(3, 1), Some(Color::new(255, 0, 0, 255)));
+        assert_eq!(g.get(2, 4), Some(Color::new(0, 0, 255, 255)));
+        assert_eq!(g.get(4, 1), Some(Color::new(0, 0, 0, 255)));
+        assert_eq!(g.get(2, 5), Some(Color::new(0, 0, 0, 255)));
+        assert_eq!(g.get(1, 1), Some(Color::new(0, 0, 0, 255)));
+    }
+
+    #[test]
+    fn draws_video_without_overlay() {
+        let mut frame = YuvImage::new(4, 4, YuvFormat::Nv12);
+        frame.plane_mut(0)[..4].copy_from_slice(&[235, 235, 235, 235]);
+        let mut g = GFX::headless(8, 8, Format::Xrgb8888).unwrap();
+        g.set_filter(Filter::Nearest);
+        assert!(!g.show_video(&frame, Rect::new(0, 0, 8, 8)));
+        assert_eq!(g.get(7, 1), Some(Color::new(255, 255, 255, 255)));
+        assert_eq!(g.get(7, 2), Some(Color::new(0, 0, 0, 255)));
+    }
+
+    #[test]
+    fn headless_rectangle() {
+        let mut g = GFX::headless(16, 8, Format::Xrgb8888).unwrap();
+        let c = Color::new(0, 0, 255, 255);
+        g.rectangle(2, 3, 4, 2, &c);
+
+        assert_eq!(g.get(2, 3), Some(c));
+        assert_eq!(g.get(5, 4), Some(c));
+        assert_eq!(g.get(6, 4), Some(Color::new(0, 0, 0, 255)));
+        assert_eq!(g.get(2, 5), Some(Color::new(0, 0, 0, 255)));
+    }
+
+    #[test]
+    fn blends_translucent_fills() {
+        let mut g = GFX::headless(2, 1, Format::Argb8888).unwrap();
+        g.set_operator(Operator::Source);
+        g.rectangle(0, 0, 2, 1, &Color::new(0, 0, 255, 255));
+        g.set_operator(Operator::Over);
+        g.point(0, 0, &Color::new(255, 0, 0, 128));
+        assert_eq!(g.get(0, 0), Some(Color::new(128, 0, 127, 255)));
+
+        g.set_operator(Operator::Source);
+        g.point(1, 0, &Color::new(255, 0, 0, 128));
+        assert_eq!(g.get(1, 0), Some(Color::new(128, 0, 0, 128)));
+    }
+
+    #[test]
+    fn shapes() {
+        let mut g = GFX::headless(32, 32, Format::Xrgb8888).unwrap();
+        let c = Color::new(255, 0, 0, 255);
+        let black = Some(Color::new(0, 0, 0, 255));
+
+        g.line(0, 0, 31, 31, &c);
+        assert_eq!(g.get(15, 15), Some(c));
+        assert_eq!(g.get(15, 16), black);
+
+        g.set_stroke_width(3);
+        g.line(0, 20, 31, 20, &c);
+        assert_eq!(g.get(10, 19), Some(c));
+        assert_eq!(g.get(10, 21), Some(c));
+        assert_eq!(g.get(10, 22), black);
+
+        // shapes outside the framebuffer are clipped away entirely
+        g.fill_circle(-100, -100, 10, &c);
+        g.polygon(&[(40.0, 0.0), (50.0, 10.0), (40.0, 10.0)], &c);
+        assert_eq!(g.get(0, 31), black);
+        assert_eq!(g.get(31, 0), black);
+        assert_eq!(g.get(0, 1), black);
+    }
+
+    #[test]
+    fn stroke_polygon_blends_corners_once() {
+        let mut g = GFX::headless(8, 8, Format::Xrgb8888).unwrap();
+        let c = Color::new(255, 255, 255, 128);
+        g.stroke_polygon(&[(1, 1), (5, 1), (5, 5), (1, 5)], &c);
+
+        let edge = g.get(3, 1);
+        assert_eq!(edge, Some(Color::new(128, 128, 128, 255)));
+        for &(x, y) in [(1, 1), (5, 1), (5, 5), (1, 5)].iter() {
+            assert_eq!(g.get(x, y), edge);
+        }
+        assert_eq!(g.get(3, 3), Some(Color::new(0, 0, 0, 255)));
+
+        g.set_stroke_width(3);
+        g.stroke_polygon(&[(1, 1), (6, 1), (6, 6)], &c);
+        assert_eq!(g.get(6, 1), edge);
+    }
+
+    #[test]
+    fn blit_scaled() {
+        let mut image = Image::new(2, 2, Format::Xrgb8888).unwrap();
+        image.set(0, 0, &Color::new(255, 0, 0, 255));
+        image.set(1, 1, &Color::new(0, 255, 0, 255));
+
+        let mut g = GFX::headless(8, 8, Format::Xrgb8888).unwrap();
+        g.blit(&image, image.bounds(), Rect::new(0, 0, 2, 2));
+        assert_eq!(g.get(0, 0), Some(Color::new(255, 0, 0, 255)));
+        assert_eq!(g.get(1, 1), Some(Color::new(0, 255, 0, 255)));
+
+        g.set_filter(Filter::Nearest);
+        g.blit(&image, image.bounds(), Rect::new(4, 4, 4, 4));
+        assert_eq!(g.get(5, 5), Some(Color::new(255, 0, 0, 255)));
+        assert_eq!(g.get(6, 6), Some(Color::new(0, 255, 0, 255)));
+        assert_eq!(g.get(6, 5), Some(Color::new(0, 0, 0, 255)));
+
+        g.set_filter(Filter::Bilinear);
+        g.blit(&image, Rect::new(0, 0, 1, 1), Rect::new(-2, -2, 3, 3));
+        assert_eq!(g.get(0, 0), Some(Color::new(255, 0, 0, 255)));
+    }
+
+    #[test]
+    fn blit_blends_translucent_images() {
+        let mut image = Image::new(1, 1, Format::Argb8888).unwrap();
+        image.set(0, 0, &Color::new(255, 0, 0, 128).premultiply());
+
+        let mut g = GFX::headless(1, 1, Format::Xrgb8888).unwrap();
+        g.rectangle(0, 0, 1, 1, &Color::new(0, 0, 255, 255));
+        g.blit(&image, image.bounds(), Rect::new(0, 0, 1, 1));
+        assert_eq!(g.get(0, 0), Some(Color::new(128, 0, 127, 255)));
+    }
+
+    #[test]
+    fn draws_text() {
+        let mut g = GFX::headless(64, 16, Format::Xrgb8888).unwrap();
+        let c = Color::new(255, 255, 255, 255);
+        let black = Some(Color::new(0, 0, 0, 255));
+        g.text(0, 0, "A\nA", &c);
+
+        // top row of the 'A' glyph is ..##....
+        assert_eq!(g.get(1, 0), black);
+        assert_eq!(g.get(2, 0), Some(c));
+        assert_eq!(g.get(3, 0), Some(c));
+        assert_eq!(g.get(4, 0), black);
+        assert_eq!(g.get(2, 8), Some(c));
+        assert_eq!(g.measure_text("A\nA"), Rect::new(0, 0, 8, 16));
+    }
+
+    #[test]
+    fn draws_scalable_text() {
+        let data = match std::fs::read("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf") {
+            Ok(data) => data,
+            Err(_) => return
+        };
+        let mut font = TrueTypeFont::load(&data, 16.0).unwrap();
+        font.set_antialias(MaskFormat::Subpixel);
+
+        let mut g = GFX::headless(64, 32, Format::Xrgb8888).unwrap();
+        g.clear();
+        g.set_font(Box::new(font));
+        g.text(2, 2, "Hi", &Color::new(0, 0, 0, 255));
+
+        let pixels: Vec<Color> = (0..32).flat_map(|y| (0..64).map(move |x| (x, y)))
+            .filter_map(|(x, y)| g.get(x, y))
+            .collect();
+        // dark stems, and coloured fringes along their edges
+        assert!(pixels.iter().any(|c| c.r < 64 && c.g < 64 && c.b < 64));
+        assert!(pixels.iter().any(|c| c.r != c.b));
+        assert_eq!(g.get(40, 2), Some(Color::new(255, 255, 255, 255)));
+    }
+
+    #[test]
+    fn clips_to_framebuffer() {
+        let mut g = GFX::headless(4, 4, Format::Xrgb8888).unwrap();
+        let c = Color::new(255, 0, 0, 255);
+        g.point(4, 0, &c);
+        g.point(0, 4, &c);
+        g.point(-1, -1, &c);
+        g.rectangle(-2, 3, 10, 10, &c);
+
+        assert_eq!(g.get(0, 1), Some(Color::new(0, 0, 0, 255)));
+        assert_eq!(g.get(0, 3), Some(c));
+        assert_eq!(g.get(3, 3), Some(c));
+        assert_eq!(g.get(4, 3), None);
+    }
+
+    #[test]
+    fn clip_stack() {
+        let mut g = GFX::headless(8, 8, Format::Xrgb8888).unwrap();
+        let c = Color::new(255, 0, 0, 255);
+        let black = Some(Color::new(0, 0, 0, 255));
+        g.push_clip(Rect::new(2, 2, 4, 4));
+        g.push_clip(Rect::new(0, 0, 3, 3));
+        assert_eq!(g.clip(), Rect::new(2, 2, 1, 1));
+        g.rectangle(0, 0, 8, 8, &c);
+        assert_eq!(g.get(2, 2), Some(c));
+        assert_eq!(g.get(3, 3), black);
+
+        g.pop_clip();
+        g.rectangle(0, 0, 8, 8, &c);
+        assert_eq!(g.get(5, 5), Some(c));
+        assert_eq!(g.get(6, 6), black);
+        assert_eq!(g.get(1, 1), black);
+
+        g.pop_clip();
+        assert_eq!(g.clip(), Rect::new(0, 0, 8, 8));
+    }
+
+    #[test]
+    fn headless_any_format() {
+        for &format in [Format::Argb8888, Format::Abgr8888, Format::Rgb565].iter() {
+            let mut g = GFX::headless(3, 3, format).unwrap();
+            g.clear();
+            assert_eq!(g.get(2, 2), Some(Color::new(255, 255, 255, 255)));
+        }
+        assert!(GFX::headless(u32::MAX, 1, Format::Xrgb8888).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+use std::io;
+
+use super::bmp;
+use super::drm::{Color, Rect};
+use super::format::Format;
+use super::png;
+use super::pnm;
+
+/**
+ * A block of pixels in memory, such as a window buffer, an icon or a
+ * decoded picture. Like framebuffers, images with alpha channel hold
+ * premultiplied colors.
+ **/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    stride: u32,
+    format: Format,
+    pixels: Vec<u8>
+}
+
+impl Image {
+    /**
+     * Allocates an image cleared to zero. Fails if the size doesn't fit in
+     * memory.
+     **/
+    pub fn new(width: u32, height: u32, format: Format) -> io::Result<Image> {
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "image is too large");
+        let stride = width.checked_mul(format.bytes_per_pixel()).ok_or_else(too_large)?;
+        let len = (stride as usize).checked_mul(height as usize).ok_or_else(too_large)?;
+        Ok(Image {
+            width,
+            height,
+            stride,
+            format,
+            pixels: vec![0; len]
+        })
+    }
+
+    /**
+     * Wraps existing pixel data. Returns `None` if `pixels` is too small for
+     * the given dimensions.
+     **/
+    pub fn from_pixels(width: u32, height: u32, stride: u32, format: Format, pixels: Vec<u8>) -> Option<Image> {
+        let row_len = width as usize * format.bytes_per_pixel() as usize;
+        if stride as usize * height.saturating_sub(1) as usize + row_len > pixels.len() || (stride as usize) < row_len {
+            return None;
+        }
+        Some(Image {
+            width,
+            height,
+            stride,
+            format,
+            pixels
+        })
+    }
+
+    /**
+     * Decodes a PNG, BMP, or netpbm (PBM, PGM, PPM, PAM) file. Images with
+     * transparency are `Argb8888`, all others `Xrgb8888`.
+     **/
+    pub fn load(data: &[u8]) -> io::Result<Image> {
+        if data.starts_with(&png::SIGNATURE) {
+            png::decode(data)
+        } else if data.starts_with(b"BM") {
+            bmp::decode(data)
+        } else if data.starts_with(b"P") {
+            pnm::decode(data)
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "unknown image format"))
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    /**
+     * The bytes of row `y`, without the padding up to the stride.
+     **/
+    pub fn row(&self, y: u32) -> &[u8] {
+        let start = (y * self.stride) as usize;
+        &self.pixels[start..start + (self.width * self.format.bytes_per_pixel()) as usize]
+    }
+
+    pub fn row_mut(&mut self, y: u32) -> &mut [u8] {
+        let start = (y * self.stride) as usize;
+        let len = (self.width * self.format.bytes_per_pixel()) as usize;
+        &mut self.pixels[start..start + len]
+    }
+
+    /**
+     * Reads a pixel. Coordinates must be inside the image.
+     **/
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        let i = y * self.stride + x * self.format.bytes_per_pixel();
+        self.format.read(&self.pixels[i as usize..])
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, c: &Color) {
+        let i = y * self.stride + x * self.format.bytes_per_pixel();
+        self.format.write(&mut self.pixels[i as usize..], c);
+    }
+
+    /**
+     * Sets every pixel to the straight alpha color `c`.
+     **/
+    pub fn fill(&mut self, c: &Color) {
+        let c = c.premultiply();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.set(x, y, &c);
+            }
+        }
+    }
+}
+
+/**
+ * How images are sampled when they are drawn at a different size.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Bilinear
+}
+
+fn lerp(a: u8, b: u8, t: u32) -> u32 {
+    // t is a weight in 1/256
+    a as u32 * (256 - t) + b as u32 * t
+}
+
+impl Filter {
+    /**
+     * Samples `image` at the continuous position `(x, y)`, where pixel
+     * centers lie at `.5`. Samples are clamped to `bounds`.
+     **/
+    pub fn sample(&self, image: &Image, bounds: &Rect, x: f32, y: f32) -> Color {
+        let clamp_x = |x: i32| x.max(bounds.x).min(bounds.right() - 1) as u32;
+        let clamp_y = |y: i32| y.max(bounds.y).min(bounds.bottom() - 1) as u32;
+
+        match *self {
+            Filter::Nearest => image.get(clamp_x(x.floor() as i32), clamp_y(y.floor() as i32)),
+            Filter::Bilinear => {
+                let (fx, fy) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (fx.floor() as i32, fy.floor() as i32);
+                let tx = ((fx - x0 as f32) * 256.0) as u32;
+                let ty = ((fy - y0 as f32) * 256.0) as u32;
+
+                let c00 = image.get(clamp_x(x0), clamp_y(y0));
+                let c10 = image.get(clamp_x(x0 + 1), clamp_y(y0));
+                let c01 = image.get(clamp_x(x0), clamp_y(y0 + 1));
+                let c11 = image.get(clamp_x(x0 + 1), clamp_y(y0 + 1));
+
+                let channel = |a: u8, b: u8, c: u8, d: u8| {
+                    let top = lerp(a, b, tx);
+                    let bottom = lerp(c, d, tx);
+                    ((top * (256 - ty) + bottom * ty + (1 << 15)) >> 16) as u8
+                };
+                Color::new(
+                    channel(c00.r, c10.r, c01.r, c11.r),
+                    channel(c00.g, c10.g, c01.g, c11.g),
+                    channel(c00.b, c10.b, c01.b, c11.b),
+                    channel(c00.a, c10.a, c01.a, c11.a)
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_short_buffers() {
+        assert!(Image::from_pixels(2, 2, 8, Format::Xrgb8888, vec![0; 16]).is_some());
+        assert!(Image::from_pixels(2, 2, 12, Format::Xrgb8888, vec![0; 20]).is_some());
+        assert!(Image::from_pixels(2, 2, 12, Format::Xrgb8888, vec![0; 19]).is_none());
+        assert!(Image::from_pixels(2, 2, 4, Format::Xrgb8888, vec![0; 16]).is_none());
+    }
+
+    #[test]
+    fn rejects_overflowing_sizes() {
+        assert!(Image::new(1 << 30, 1, Format::Xrgb8888).is_err());
+        assert!(Image::new(0, u32::MAX, Format::Xrgb8888).unwrap().pixels().is_empty());
+    }
+
+    #[test]
+    fn bilinear_midpoint() {
+        let mut image = Image::new(2, 1, Format::Argb8888).unwrap();
+        image.set(0, 0, &Color::new(0, 0, 0, 255));
+        image.set(1, 0, &Color::new(255, 255, 255, 255));
+        let bounds = image.bounds();
+
+        let c = Filter::Bilinear.sample(&image, &bounds, 1.0, 0.5);
+        assert_eq!(c, Color::new(128, 128, 128, 255));
+        assert_eq!(Filter::Bilinear.sample(&image, &bounds, 0.5, 0.5), Color::new(0, 0, 0, 255));
+        assert_eq!(Filter::Nearest.sample(&image, &bounds, 1.2, 0.5), Color::new(255, 255, 255, 255));
+    }
+}
@@ -0,0 +1,333 @@
+use std::io;
+
+const MAX_BITS: usize = 15;
+
+/**
+ * Order in which the code lengths of the code length alphabet are stored.
+ **/
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+pub const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258
+];
+pub const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+pub const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577
+];
+pub const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13
+];
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/**
+ * Computes the Adler-32 checksum zlib streams end with.
+ **/
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    b << 16 | a
+}
+
+/**
+ * Decompresses a zlib stream, checking its header and checksum. Streams
+ * that expand to more than `limit` bytes are rejected.
+ **/
+pub fn zlib_decompress(data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    if data.len() < 6 {
+        return Err(invalid("truncated zlib stream"));
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0f != 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err(invalid("invalid zlib header"));
+    }
+    if flg & 0x20 != 0 {
+        return Err(invalid("zlib preset dictionaries are not supported"));
+    }
+
+    let mut inflater = Inflater { data, pos: 2, bit_buffer: 0, bit_count: 0, output: Vec::new(), limit };
+    inflater.inflate()?;
+    let end = inflater.pos;
+    let checksum = data.get(end..end + 4).ok_or_else(|| invalid("missing zlib checksum"))?;
+    let expected = (checksum[0] as u32) << 24 | (checksum[1] as u32) << 16 | (checksum[2] as u32) << 8 | checksum[3] as u32;
+    if adler32(&inflater.output) != expected {
+        return Err(invalid("zlib checksum mismatch"));
+    }
+    Ok(inflater.output)
+}
+
+/**
+ * Decompresses a raw DEFLATE stream of at most `limit` bytes.
+ **/
+pub fn inflate(data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    let mut inflater = Inflater { data, pos: 0, bit_buffer: 0, bit_count: 0, output: Vec::new(), limit };
+    inflater.inflate()?;
+    Ok(inflater.output)
+}
+
+/**
+ * A canonical Huffman code: the number of codes of each length and the
+ * symbols ordered by code.
+ **/
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // reject oversubscribed codes; incomplete ones are allowed
+        let mut left = 1i32;
+        for &count in counts[1..].iter() {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err(invalid("invalid Huffman code"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+}
+
+struct Inflater<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+    output: Vec<u8>,
+    limit: usize
+}
+
+impl<'a> Inflater<'a> {
+    fn reserve(&self, len: usize) -> io::Result<()> {
+        if len > self.limit - self.output.len() {
+            return Err(invalid("DEFLATE stream expands too much"));
+        }
+        Ok(())
+    }
+
+    fn bits(&mut self, count: u32) -> io::Result<u32> {
+        while self.bit_count < count {
+            let byte = *self.data.get(self.pos).ok_or_else(|| invalid("truncated DEFLATE stream"))?;
+            self.pos += 1;
+            self.bit_buffer |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buffer & ((1u64 << count) - 1) as u32;
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    fn decode(&mut self, huffman: &Huffman) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..=MAX_BITS {
+            code |= self.bits(1)? as i32;
+            let count = huffman.counts[len] as i32;
+            if code - first < count {
+                return Ok(huffman.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid Huffman code"))
+    }
+
+    fn inflate(&mut self) -> io::Result<()> {
+        loop {
+            let last = self.bits(1)? == 1;
+            match self.bits(2)? {
+                0 => self.stored()?,
+                1 => {
+                    let (literals, distances) = fixed_codes()?;
+                    self.codes(&literals, &distances)?;
+                }
+                2 => {
+                    let (literals, distances) = self.dynamic_codes()?;
+                    self.codes(&literals, &distances)?;
+                }
+                _ => return Err(invalid("invalid DEFLATE block type"))
+            }
+            if last {
+                // give back whole bytes read ahead so the caller can find
+                // what follows the stream
+                self.pos -= (self.bit_count / 8) as usize;
+                self.bit_buffer = 0;
+                self.bit_count = 0;
+                return Ok(());
+            }
+        }
+    }
+
+    fn stored(&mut self) -> io::Result<()> {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+        let header = self.data.get(self.pos..self.pos + 4).ok_or_else(|| invalid("truncated stored block"))?;
+        let len = header[0] as usize | (header[1] as usize) << 8;
+        let nlen = header[2] as usize | (header[3] as usize) << 8;
+        if len != !nlen & 0xffff {
+            return Err(invalid("invalid stored block length"));
+        }
+        self.pos += 4;
+        let block = self.data.get(self.pos..self.pos + len).ok_or_else(|| invalid("truncated stored block"))?;
+        self.reserve(len)?;
+        self.output.extend_from_slice(block);
+        self.pos += len;
+        Ok(())
+    }
+
+    fn dynamic_codes(&mut self) -> io::Result<(Huffman, Huffman)> {
+        let literal_count = self.bits(5)? as usize + 257;
+        let distance_count = self.bits(5)? as usize + 1;
+        let code_length_count = self.bits(4)? as usize + 4;
+        if literal_count > 286 || distance_count > 30 {
+            return Err(invalid("too many DEFLATE codes"));
+        }
+
+        let mut code_lengths = [0u8; 19];
+        for &symbol in CODE_LENGTH_ORDER[..code_length_count].iter() {
+            code_lengths[symbol] = self.bits(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        let mut lengths = Vec::with_capacity(literal_count + distance_count);
+        while lengths.len() < literal_count + distance_count {
+            let symbol = self.decode(&code_length_code)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => (*lengths.last().ok_or_else(|| invalid("repeat without a previous length"))?, 3 + self.bits(2)?),
+                17 => (0, 3 + self.bits(3)?),
+                _ => (0, 11 + self.bits(7)?)
+            };
+            for _ in 0..repeat {
+                lengths.push(value);
+            }
+        }
+        if lengths.len() > literal_count + distance_count {
+            return Err(invalid("code lengths overflow"));
+        }
+        if lengths[256] == 0 {
+            return Err(invalid("missing end of block code"));
+        }
+        Ok((Huffman::new(&lengths[..literal_count])?, Huffman::new(&lengths[literal_count..])?))
+    }
+
+    fn codes(&mut self, literals: &Huffman, distances: &Huffman) -> io::Result<()> {
+        loop {
+            let symbol = self.decode(literals)? as usize;
+            if symbol < 256 {
+                self.reserve(1)?;
+                self.output.push(symbol as u8);
+                continue;
+            }
+            if symbol == 256 {
+                return Ok(());
+            }
+
+            let symbol = symbol - 257;
+            if symbol >= LENGTH_BASE.len() {
+                return Err(invalid("invalid length code"));
+            }
+            let length = LENGTH_BASE[symbol] as usize + self.bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+            let symbol = self.decode(distances)? as usize;
+            if symbol >= DISTANCE_BASE.len() {
+                return Err(invalid("invalid distance code"));
+            }
+            let distance = DISTANCE_BASE[symbol] as usize + self.bits(DISTANCE_EXTRA[symbol] as u32)? as usize;
+            if distance > self.output.len() {
+                return Err(invalid("distance too far back"));
+            }
+
+            self.reserve(length)?;
+
+            // copies may overlap their own output
+            let start = self.output.len() - distance;
+            for i in 0..length {
+                let byte = self.output[start + i];
+                self.output.push(byte);
+            }
+        }
+    }
+}
+
+fn fixed_codes() -> io::Result<(Huffman, Huffman)> {
+    let mut lengths = [0u8; 288];
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        *length = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8
+        };
+    }
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adler() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn decompresses_all_block_types() {
+        // output of Python's zlib.compress at levels 0, 6 and 9
+        assert_eq!(zlib_decompress(&hex("7801010500faff68656c6c6f062c0215"), usize::MAX).unwrap(), b"hello");
+        assert_eq!(zlib_decompress(&hex("789ccb48cdc9c957c84090003a2e067d"), usize::MAX).unwrap(), b"hello hello hello");
+        assert_eq!(zlib_decompress(&hex("789ccb48cdc9c957c84090003a2e067d"), 17).unwrap(), b"hello hello hello");
+        assert!(zlib_decompress(&hex("789ccb48cdc9c957c84090003a2e067d"), 16).is_err());
+        assert!(zlib_decompress(&hex("7801010500faff68656c6c6f062c0215"), 4).is_err());
+
+        let dynamic = hex("78da258cc111003008c2660de0fe2b14ad3e3c2002623286c126a246c75d322bea795d19a9e4811229a5a96c7063e9b3657a47ee532ebace6c09fb0a0fdffc2df4");
+        let expected = "aabaedecaaeaccadbaabaaaacacaadeabaadbacaababcabbecaeaccabbadaacbeaeabcaaaaaacbbdbacaacaebacebcbabdaaaaabaaaadababaabbaaa";
+        assert_eq!(zlib_decompress(&dynamic, usize::MAX).unwrap(), expected.as_bytes());
+    }
+
+    #[test]
+    fn rejects_corrupt_streams() {
+        let mut stream = hex("789ccb48cdc9c957c84090003a2e067d");
+        assert!(zlib_decompress(&stream[..8], usize::MAX).is_err());
+        stream[15] ^= 1;
+        assert!(zlib_decompress(&stream, usize::MAX).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+/**
+ * Input events as seen by the compositor, independent of the device or
+ * backend they came from.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    /// Relative pointer movement, e.g. from a mouse.
+    PointerMotion { dx: i32, dy: i32 },
+    /// Absolute pointer position in framebuffer coordinates.
+    PointerPosition { x: i32, y: i32 },
+    PointerButton { button: Button, pressed: bool },
+    /// Scroll wheel movement in detents.
+    PointerAxis { dx: i32, dy: i32 },
+    /// A key identified by its Linux keycode (`KEY_*`).
+    Key { key: u16, pressed: bool },
+    /// The nested backend's window was closed.
+    Quit
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    Left,
+    Right,
+    Middle,
+    Side,
+    Extra,
+    Other(u16)
+}
+
+impl Button {
+    /**
+     * Maps a Linux `BTN_*` code to a button.
+     **/
+    pub fn from_code(code: u16) -> Button {
+        match code {
+            0x110 => Button::Left,
+            0x111 => Button::Right,
+            0x112 => Button::Middle,
+            0x113 => Button::Side,
+            0x114 => Button::Extra,
+            code => Button::Other(code)
+        }
+    }
+
+    pub fn code(&self) -> u16 {
+        match *self {
+            Button::Left => 0x110,
+            Button::Right => 0x111,
+            Button::Middle => 0x112,
+            Button::Side => 0x113,
+            Button::Extra => 0x114,
+            Button::Other(code) => code
+        }
+    }
+}
+
+/**
+ * Linux keycode of the Print Screen / SysRq key.
+ **/
+pub const KEY_SYSRQ: u16 = 99;
+
+/**
+ * Linux keycodes for the USB HID keyboard usages 0x04 to 0x65.
+ **/
+const HID_KEYCODES: [u16; 98] = [
+    30, 48, 46, 32, 18, 33, 34, 35, 23, 36, 37, 38, 50, 49, 24, 25,
+    16, 19, 31, 20, 22, 47, 17, 45, 21, 44, 2, 3, 4, 5, 6, 7,
+    8, 9, 10, 11, 28, 1, 14, 15, 57, 12, 13, 26, 27, 43, 43, 39,
+    40, 41, 51, 52, 53, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67, 68,
+    87, 88, 99, 70, 119, 110, 102, 104, 111, 107, 109, 106, 105, 108, 103, 69,
+    98, 55, 74, 78, 96, 79, 80, 81, 75, 76, 77, 71, 72, 73, 82, 83,
+    86, 127
+];
+
+/**
+ * Linux keycodes for the HID modifier usages 0xe0 to 0xe7.
+ **/
+const HID_MODIFIER_KEYCODES: [u16; 8] = [29, 42, 56, 125, 97, 54, 100, 126];
+
+/**
+ * Translates a USB HID keyboard usage (which SDL scancodes are based on) to
+ * a Linux keycode.
+ **/
+pub fn keycode_from_hid(usage: u32) -> Option<u16> {
+    match usage {
+        0x04..=0x65 => Some(HID_KEYCODES[(usage - 0x04) as usize]),
+        0xe0..=0xe7 => Some(HID_MODIFIER_KEYCODES[(usage - 0xe0) as usize]),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hid_keycodes() {
+        assert_eq!(keycode_from_hid(0x04), Some(30)); // a
+        assert_eq!(keycode_from_hid(0x27), Some(11)); // 0
+        assert_eq!(keycode_from_hid(0x28), Some(28)); // enter
+        assert_eq!(keycode_from_hid(0x45), Some(88)); // f12
+        assert_eq!(keycode_from_hid(0x52), Some(103)); // up
+        assert_eq!(keycode_from_hid(0xe1), Some(42)); // left shift
+        assert_eq!(keycode_from_hid(0x00), None);
+    }
+}
@@ -0,0 +1,413 @@
+extern crate libc;
+
+use std::ffi::CString;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::FromRawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::evdev::*;
+use super::frame_clock::poll_any;
+use super::input::InputEvent;
+
+/**
+ * Size of a `struct inotify_event` without its name.
+ **/
+const INOTIFY_EVENT_SIZE: usize = std::mem::size_of::<libc::inotify_event>();
+
+/**
+ * Keys every keyboard has: Esc, A, Z and Space. Devices with only a few
+ * keys, such as power buttons, aren't keyboards.
+ **/
+const KEYBOARD_KEYS: [u16; 4] = [1, 30, 44, 57];
+
+/**
+ * What kind of input a device gives. A device can be several kinds, e.g. a
+ * keyboard with a built-in trackpoint.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceClass {
+    Keyboard,
+    /// Mice and trackballs, and absolute pointers such as those of
+    /// virtual machines.
+    Pointer,
+    Touchpad,
+    Touchscreen,
+    /// Pen tablets.
+    Tablet,
+    /// Lid and tablet mode switches.
+    Switch
+}
+
+impl DeviceClass {
+    /**
+     * Tells what a device is from the events it can send.
+     **/
+    pub fn classify(caps: &Capabilities) -> Vec<DeviceClass> {
+        let key = |code| caps.has(EV_KEY, code);
+        let mut classes = Vec::new();
+        if KEYBOARD_KEYS.iter().all(|&code| key(code)) {
+            classes.push(DeviceClass::Keyboard);
+        }
+        if caps.has(EV_ABS, ABS_X) && caps.has(EV_ABS, ABS_Y) {
+            if key(BTN_TOOL_PEN) || key(BTN_STYLUS) {
+                classes.push(DeviceClass::Tablet);
+            } else if key(BTN_TOOL_FINGER) {
+                classes.push(DeviceClass::Touchpad);
+            } else if key(BTN_TOUCH) || caps.has(EV_ABS, ABS_MT_POSITION_X) {
+                classes.push(DeviceClass::Touchscreen);
+            } else if key(BTN_MOUSE) {
+                classes.push(DeviceClass::Pointer);
+            }
+        }
+        if caps.has(EV_REL, REL_X) && caps.has(EV_REL, REL_Y) && key(BTN_MOUSE) && !classes.contains(&DeviceClass::Pointer) {
+            classes.push(DeviceClass::Pointer);
+        }
+        if caps.events.has(EV_SW) && caps.switches.iter().next().is_some() {
+            classes.push(DeviceClass::Switch);
+        }
+        classes
+    }
+}
+
+/**
+ * Whether `name` is an evdev node such as `event3`.
+ **/
+fn is_event_node(name: &str) -> bool {
+    name.starts_with("event") && name.len() > 5 && name[5..].bytes().all(|b| b.is_ascii_digit())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NodeChange {
+    /// Created, or its permissions changed, which udev does right after
+    /// creating it.
+    Added(String),
+    Removed(String)
+}
+
+/**
+ * Watches a directory for device nodes coming and going.
+ **/
+#[derive(Debug)]
+struct Watcher {
+    file: File
+}
+
+impl Watcher {
+    fn new(dir: &Path) -> io::Result<Watcher> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let file = unsafe { File::from_raw_fd(fd) };
+        let path = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains a nul byte"))?;
+        let mask = libc::IN_CREATE | libc::IN_ATTRIB | libc::IN_DELETE | libc::IN_MOVED_TO | libc::IN_MOVED_FROM;
+        if unsafe { libc::inotify_add_watch(fd, path.as_ptr(), mask) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Watcher { file })
+    }
+
+    fn read(&mut self) -> io::Result<Vec<NodeChange>> {
+        let mut changes = Vec::new();
+        // room for at least one event with the longest name
+        let mut buffer = [0u8; 4096];
+        loop {
+            let len = match self.file.read(&mut buffer) {
+                // end of file, there is nothing more to read
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e)
+            };
+            let mut offset = 0;
+            while offset + INOTIFY_EVENT_SIZE <= len {
+                let word = |i: usize| {
+                    let b = &buffer[offset + i * 4..offset + i * 4 + 4];
+                    u32::from_ne_bytes([b[0], b[1], b[2], b[3]])
+                };
+                let (mask, name_len) = (word(1), word(3) as usize);
+                let start = offset + INOTIFY_EVENT_SIZE;
+                let name = &buffer[start..(start + name_len).min(len)];
+                let name = String::from_utf8_lossy(name).trim_end_matches('\0').to_string();
+                offset = start + name_len;
+                if !is_event_node(&name) {
+                    continue;
+                }
+                if mask & (libc::IN_DELETE | libc::IN_MOVED_FROM) != 0 {
+                    changes.push(NodeChange::Removed(name));
+                } else if mask & (libc::IN_CREATE | libc::IN_ATTRIB | libc::IN_MOVED_TO) != 0 {
+                    changes.push(NodeChange::Added(name));
+                }
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/**
+ * An open device and what it was recognized as.
+ **/
+#[derive(Debug)]
+pub struct InputDevice {
+    device: Device,
+    classes: Vec<DeviceClass>
+}
+
+impl InputDevice {
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    pub fn classes(&self) -> &[DeviceClass] {
+        &self.classes
+    }
+
+    pub fn is(&self, class: DeviceClass) -> bool {
+        self.classes.contains(&class)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceChange {
+    Added { path: PathBuf, name: String, classes: Vec<DeviceClass> },
+    Removed { path: PathBuf }
+}
+
+/**
+ * Opens every input device in `/dev/input` that the compositor has a use
+ * for, and follows devices being plugged in and out.
+ **/
+#[derive(Debug)]
+pub struct DeviceManager {
+    dir: PathBuf,
+    watcher: Watcher,
+    devices: Vec<InputDevice>,
+    output: (u32, u32),
+    /// LED state, given to keyboards as they are plugged in.
+    leds: Vec<(u16, bool)>,
+    changes: Vec<DeviceChange>
+}
+
+impl DeviceManager {
+    pub fn open() -> io::Result<DeviceManager> {
+        DeviceManager::with_dir("/dev/input")
+    }
+
+    /**
+     * Uses the device nodes in `dir`. Nodes that can't be opened, e.g. for
+     * lack of permissions, are skipped.
+     **/
+    pub fn with_dir<P: AsRef<Path>>(dir: P) -> io::Result<DeviceManager> {
+        let dir = dir.as_ref().to_path_buf();
+        // watch first so that nothing plugged in during the scan is missed
+        let watcher = Watcher::new(&dir)?;
+        let mut manager = DeviceManager {
+            dir,
+            watcher,
+            devices: Vec::new(),
+            output: (0, 0),
+            leds: Vec::new(),
+            changes: Vec::new()
+        };
+        let mut names: Vec<String> = fs::read_dir(&manager.dir)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| is_event_node(name))
+            .collect();
+        names.sort_by_key(|name| name[5..].parse::<u32>().unwrap_or(0));
+        for name in names {
+            manager.add(&name);
+        }
+        Ok(manager)
+    }
+
+    pub fn devices(&self) -> &[InputDevice] {
+        &self.devices
+    }
+
+    /**
+     * Sets the size of the output that touchscreens and other absolute
+     * devices are mapped to.
+     **/
+    pub fn set_output_size(&mut self, width: u32, height: u32) -> io::Result<()> {
+        self.output = (width, height);
+        for d in self.devices.iter_mut() {
+            d.device.set_output_size(width, height)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Sets the LEDs of all keyboards, given as `(LED_*, on)`.
+     **/
+    pub fn set_leds(&mut self, leds: &[(u16, bool)]) {
+        self.leds = leds.to_vec();
+        for d in self.devices.iter_mut() {
+            update_leds(&mut d.device, leds);
+        }
+    }
+
+    /**
+     * Waits until a device has events or devices were plugged in or out.
+     * Returns false on timeout.
+     **/
+    pub fn wait(&self, timeout: Option<Duration>) -> io::Result<bool> {
+        let mut files = vec![&self.watcher.file];
+        files.extend(self.devices.iter().map(|d| d.device.file()));
+        poll_any(&files, timeout)
+    }
+
+    /**
+     * Handles hotplug and reads the pending events of all devices. Devices
+     * that fail, e.g. because they were unplugged, are closed.
+     **/
+    pub fn dispatch(&mut self) -> io::Result<Vec<InputEvent>> {
+        for change in self.watcher.read()? {
+            match change {
+                NodeChange::Added(name) => self.add(&name),
+                NodeChange::Removed(name) => {
+                    let path = self.dir.join(name);
+                    self.remove(&path);
+                }
+            }
+        }
+
+        let mut events = Vec::new();
+        let mut failed = Vec::new();
+        for d in self.devices.iter_mut() {
+            match d.device.input_events() {
+                Ok(mut e) => events.append(&mut e),
+                Err(_) => failed.push(d.device.path().to_path_buf())
+            }
+        }
+        for path in failed {
+            self.remove(&path);
+        }
+        Ok(events)
+    }
+
+    /**
+     * Returns the devices added and removed since the last call.
+     **/
+    pub fn take_changes(&mut self) -> Vec<DeviceChange> {
+        self.changes.split_off(0)
+    }
+
+    fn add(&mut self, name: &str) {
+        let path = self.dir.join(name);
+        if self.devices.iter().any(|d| d.device.path() == path) {
+            return;
+        }
+        let mut device = match Device::open(&path) {
+            Ok(device) => device,
+            Err(_) => return
+        };
+        let classes = DeviceClass::classify(device.capabilities());
+        if classes.is_empty() {
+            return;
+        }
+        device.set_touchpad(classes.contains(&DeviceClass::Touchpad));
+        update_leds(&mut device, &self.leds);
+        if device.set_output_size(self.output.0, self.output.1).is_err() {
+            return;
+        }
+        self.changes.push(DeviceChange::Added {
+            path,
+            name: device.name().to_string(),
+            classes: classes.clone()
+        });
+        self.devices.push(InputDevice { device, classes });
+    }
+
+    fn remove(&mut self, path: &Path) {
+        if let Some(i) = self.devices.iter().position(|d| d.device.path() == path) {
+            self.devices.remove(i);
+            self.changes.push(DeviceChange::Removed { path: path.to_path_buf() });
+        }
+    }
+}
+
+fn update_leds(device: &mut Device, leds: &[(u16, bool)]) {
+    for &(led, on) in leds {
+        if device.capabilities().has(EV_LED, led) {
+            // devices opened read-only keep their LEDs as they are
+            let _ = device.set_led(led, on);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn bits(codes: &[u16]) -> Bits {
+        let mut bytes = vec![0u8; 0x300 / 8];
+        for &code in codes {
+            bytes[code as usize / 8] |= 1 << (code % 8);
+        }
+        Bits::from_bytes(&bytes)
+    }
+
+    fn classify(keys: &[u16], relative: &[u16], absolute: &[u16], switches: &[u16]) -> Vec<DeviceClass> {
+        let caps = Capabilities {
+            events: bits(&[EV_SYN, EV_KEY, EV_REL, EV_ABS, EV_SW]),
+            keys: bits(keys),
+            relative: bits(relative),
+            absolute: bits(absolute),
+            leds: bits(&[]),
+            switches: bits(switches)
+        };
+        DeviceClass::classify(&caps)
+    }
+
+    #[test]
+    fn classifies_devices() {
+        let letters: Vec<u16> = (1..88).collect();
+        assert_eq!(classify(&letters, &[], &[], &[]), vec![DeviceClass::Keyboard]);
+        // a power button has keys, but isn't a keyboard
+        assert!(classify(&[116], &[], &[], &[]).is_empty());
+        assert_eq!(classify(&[BTN_MOUSE, 0x111], &[REL_X, REL_Y, REL_WHEEL], &[], &[]), vec![DeviceClass::Pointer]);
+        assert_eq!(
+            classify(&[BTN_MOUSE, BTN_TOUCH, BTN_TOOL_FINGER], &[], &[ABS_X, ABS_Y], &[]),
+            vec![DeviceClass::Touchpad]
+        );
+        assert_eq!(classify(&[BTN_TOUCH], &[], &[ABS_X, ABS_Y, ABS_MT_POSITION_X], &[]), vec![DeviceClass::Touchscreen]);
+        assert_eq!(classify(&[BTN_TOUCH, BTN_TOOL_PEN, BTN_STYLUS], &[], &[ABS_X, ABS_Y], &[]), vec![DeviceClass::Tablet]);
+        assert_eq!(classify(&[BTN_MOUSE], &[], &[ABS_X, ABS_Y], &[]), vec![DeviceClass::Pointer]);
+        assert_eq!(classify(&[], &[], &[], &[0]), vec![DeviceClass::Switch]);
+        // a keyboard with a trackpoint
+        let mut keys = letters.clone();
+        keys.push(BTN_MOUSE);
+        assert_eq!(classify(&keys, &[REL_X, REL_Y], &[], &[]), vec![DeviceClass::Keyboard, DeviceClass::Pointer]);
+    }
+
+    #[test]
+    fn watches_device_nodes() {
+        let dir = env::temp_dir().join(format!("drmtest-input-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut manager = DeviceManager::with_dir(&dir).unwrap();
+        assert!(manager.devices().is_empty());
+
+        fs::write(dir.join("event7"), b"").unwrap();
+        fs::write(dir.join("mouse0"), b"").unwrap();
+        fs::remove_file(dir.join("event7")).unwrap();
+        let changes = manager.watcher.read().unwrap();
+        assert_eq!(changes.first(), Some(&NodeChange::Added("event7".to_string())));
+        assert_eq!(changes.last(), Some(&NodeChange::Removed("event7".to_string())));
+
+        // files that aren't evdev devices are skipped
+        fs::write(dir.join("event8"), b"").unwrap();
+        assert!(manager.dispatch().unwrap().is_empty());
+        assert!(manager.devices().is_empty() && manager.take_changes().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(is_event_node("event12"));
+        assert!(!is_event_node("event") && !is_event_node("event1a") && !is_event_node("mice"));
+    }
+}
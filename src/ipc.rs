@@ -0,0 +1,259 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::drm::Rect;
+use super::screenshot::Capture;
+
+/**
+ * A request received over the control socket.
+ **/
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// `screenshot [screen | output N | region X Y W H] [PATH]`
+    Screenshot { capture: Capture, path: Option<PathBuf> },
+    /// `keymap`: the keyboard's XKB keymap, as clients load it.
+    Keymap
+}
+
+fn number<T: std::str::FromStr>(word: Option<&str>) -> Result<T, String> {
+    word.and_then(|w| w.parse().ok()).ok_or_else(|| String::from("expected a number"))
+}
+
+/**
+ * Parses a single command line.
+ **/
+pub fn parse(line: &str) -> Result<Command, String> {
+    let mut words = line.split_whitespace().peekable();
+    match words.next() {
+        Some("screenshot") => {
+            let capture = match words.peek().cloned() {
+                Some("screen") => {
+                    words.next();
+                    Capture::Screen
+                }
+                Some("output") => {
+                    words.next();
+                    Capture::Output(number(words.next())?)
+                }
+                Some("region") => {
+                    words.next();
+                    let (x, y) = (number(words.next())?, number(words.next())?);
+                    Capture::Region(Rect::new(x, y, number(words.next())?, number(words.next())?))
+                }
+                _ => Capture::Screen
+            };
+            let path = words.next().map(PathBuf::from);
+            if words.next().is_some() {
+                return Err(String::from("too many arguments"));
+            }
+            Ok(Command::Screenshot { capture, path })
+        }
+        Some("keymap") => match words.next() {
+            Some(_) => Err(String::from("too many arguments")),
+            None => Ok(Command::Keymap)
+        },
+        Some(command) => Err(format!("unknown command {}", command)),
+        None => Err(String::from("empty command"))
+    }
+}
+
+/**
+ * A Unix socket accepting one command per connection, answered with
+ * `ok ...` or `error ...`. It is polled from the render loop, so it never
+ * blocks.
+ **/
+pub struct Server {
+    listener: UnixListener,
+    path: PathBuf,
+    clients: Vec<Client>
+}
+
+/**
+ * Commands longer than this are refused.
+ **/
+const MAX_LINE: usize = 4096;
+
+/**
+ * A connection whose command hasn't completely arrived yet.
+ **/
+struct Client {
+    stream: UnixStream,
+    line: Vec<u8>
+}
+
+impl Client {
+    /**
+     * Reads what the client has sent so far, without blocking. Returns the
+     * command once its line is complete or the client stops sending.
+     **/
+    fn read(&mut self) -> io::Result<Option<String>> {
+        let mut buffer = [0; 256];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(len) => {
+                    self.line.extend_from_slice(&buffer[..len]);
+                    if let Some(end) = self.line.iter().position(|&b| b == b'\n') {
+                        self.line.truncate(end);
+                        break;
+                    }
+                    if self.line.len() > MAX_LINE {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, "command too long"));
+                    }
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e)
+            }
+        }
+        Ok(Some(String::from_utf8_lossy(&self.line).into_owned()))
+    }
+}
+
+impl Server {
+    pub fn bind(path: &Path) -> io::Result<Server> {
+        // a socket left behind by an earlier run would make bind fail;
+        // anything else at the path is left alone
+        if let Ok(metadata) = fs::symlink_metadata(path) {
+            if metadata.file_type().is_socket() && UnixStream::connect(path).is_err() {
+                fs::remove_file(path)?;
+            }
+        }
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Server { listener, path: path.to_path_buf(), clients: Vec::new() })
+    }
+
+    /**
+     * Where the socket lives unless configured otherwise:
+     * `$XDG_RUNTIME_DIR/drmtest.sock`.
+     **/
+    pub fn default_path() -> PathBuf {
+        env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(env::temp_dir)
+            .join("drmtest.sock")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /**
+     * Accepts new connections and answers the clients whose command has
+     * arrived, passing each parsed command to `handle`. Never waits for
+     * clients that are slow to send.
+     **/
+    pub fn poll<F: FnMut(Command) -> Result<String, String>>(&mut self, mut handle: F) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.clients.push(Client { stream, line: Vec::new() });
+            }
+        }
+
+        let mut i = 0;
+        while i < self.clients.len() {
+            match self.clients[i].read() {
+                Ok(None) => i += 1,
+                Ok(Some(line)) => {
+                    let client = self.clients.swap_remove(i);
+                    let _ = answer(&client.stream, &line, &mut handle);
+                }
+                Err(_) => {
+                    self.clients.swap_remove(i);
+                }
+            }
+        }
+    }
+}
+
+fn answer<F: FnMut(Command) -> Result<String, String>>(mut stream: &UnixStream, line: &str, handle: &mut F) -> io::Result<()> {
+    let reply = match parse(line).and_then(handle) {
+        Ok(message) => format!("ok {}\n", message),
+        Err(message) => format!("error {}\n", message)
+    };
+    stream.set_nonblocking(false)?;
+    stream.set_write_timeout(Some(Duration::from_millis(100)))?;
+    stream.write_all(reply.as_bytes())
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_screenshot_commands() {
+        assert_eq!(parse("screenshot"), Ok(Command::Screenshot { capture: Capture::Screen, path: None }));
+        assert_eq!(parse("screenshot output 1 /tmp/a.ppm\n"), Ok(Command::Screenshot {
+            capture: Capture::Output(1),
+            path: Some(PathBuf::from("/tmp/a.ppm"))
+        }));
+        assert_eq!(parse("screenshot region 1 2 3 4"), Ok(Command::Screenshot {
+            capture: Capture::Region(Rect::new(1, 2, 3, 4)),
+            path: None
+        }));
+        assert!(parse("screenshot region 1 2").is_err());
+        assert!(parse("screenshot a b").is_err());
+        assert!(parse("reboot").is_err());
+        assert_eq!(parse("keymap\n"), Ok(Command::Keymap));
+        assert!(parse("keymap us").is_err());
+    }
+
+    #[test]
+    fn answers_clients() {
+        let path = env::temp_dir().join(format!("drmtest-ipc-{}.sock", std::process::id()));
+        let mut server = Server::bind(&path).unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(b"screenshot output 0 out.png\n").unwrap();
+        server.poll(|command| match command {
+            Command::Screenshot { path, .. } => Ok(path.unwrap().display().to_string()),
+            Command::Keymap => Err(String::from("no keyboard"))
+        });
+        let mut reply = String::new();
+        client.read_to_string(&mut reply).unwrap();
+        assert_eq!(reply, "ok out.png\n");
+
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn waits_for_complete_lines() {
+        let path = env::temp_dir().join(format!("drmtest-ipc-partial-{}.sock", std::process::id()));
+        let mut server = Server::bind(&path).unwrap();
+        let mut handled = 0;
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(b"key").unwrap();
+        server.poll(|_| { handled += 1; Ok(String::new()) });
+        assert_eq!(handled, 0);
+
+        client.write_all(b"map\n").unwrap();
+        server.poll(|command| { handled += 1; assert_eq!(command, Command::Keymap); Ok(String::from("us")) });
+        assert_eq!(handled, 1);
+        let mut reply = String::new();
+        client.read_to_string(&mut reply).unwrap();
+        assert_eq!(reply, "ok us\n");
+    }
+
+    #[test]
+    fn keeps_files_in_the_way() {
+        let path = env::temp_dir().join(format!("drmtest-ipc-file-{}.sock", std::process::id()));
+        fs::write(&path, b"data").unwrap();
+        assert!(Server::bind(&path).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"data");
+        fs::remove_file(&path).unwrap();
+    }
+}
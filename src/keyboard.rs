@@ -0,0 +1,649 @@
+use std::collections::BTreeMap;
+use std::ops::{BitAnd, BitOr, Not};
+
+use super::evdev::{LED_CAPSL, LED_NUML};
+use super::keysym::Keysym;
+
+/**
+ * A set of modifiers, with the bits of the X11 core modifiers so that they
+ * can be sent to clients as they are.
+ **/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers(pub u32);
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers(0);
+    pub const SHIFT: Modifiers = Modifiers(1 << 0);
+    pub const CAPS_LOCK: Modifiers = Modifiers(1 << 1);
+    pub const CTRL: Modifiers = Modifiers(1 << 2);
+    /// `Mod1`.
+    pub const ALT: Modifiers = Modifiers(1 << 3);
+    /// `Mod2`.
+    pub const NUM_LOCK: Modifiers = Modifiers(1 << 4);
+    /// `Mod3`, used by some layouts for a fifth level.
+    pub const MOD3: Modifiers = Modifiers(1 << 5);
+    /// `Mod4`.
+    pub const SUPER: Modifiers = Modifiers(1 << 6);
+    /// `Mod5`, held with AltGr to type the third and fourth levels.
+    pub const LEVEL3: Modifiers = Modifiers(1 << 7);
+    pub const ALL: Modifiers = Modifiers(0xff);
+
+    pub fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, other: Modifiers) -> Modifiers {
+        Modifiers(self.0 | other.0)
+    }
+}
+
+impl BitAnd for Modifiers {
+    type Output = Modifiers;
+
+    fn bitand(self, other: Modifiers) -> Modifiers {
+        Modifiers(self.0 & other.0)
+    }
+}
+
+impl Not for Modifiers {
+    type Output = Modifiers;
+
+    fn not(self) -> Modifiers {
+        Modifiers(!self.0)
+    }
+}
+
+/**
+ * Which level of a key the modifiers select. Only the type's `modifiers`
+ * count; combinations without an entry in `map` select the first level.
+ **/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyType {
+    pub name: String,
+    pub modifiers: Modifiers,
+    pub map: Vec<(Modifiers, usize)>
+}
+
+impl KeyType {
+    pub fn new(name: &str, modifiers: Modifiers, map: Vec<(Modifiers, usize)>) -> KeyType {
+        KeyType { name: name.to_string(), modifiers, map }
+    }
+
+    pub fn one_level() -> KeyType {
+        KeyType::new("ONE_LEVEL", Modifiers::NONE, Vec::new())
+    }
+
+    pub fn two_level() -> KeyType {
+        KeyType::new("TWO_LEVEL", Modifiers::SHIFT, vec![(Modifiers::SHIFT, 1)])
+    }
+
+    /**
+     * Letters: Caps Lock works like Shift, and both together cancel out.
+     **/
+    pub fn alphabetic() -> KeyType {
+        KeyType::new(
+            "ALPHABETIC",
+            Modifiers::SHIFT | Modifiers::CAPS_LOCK,
+            vec![(Modifiers::SHIFT, 1), (Modifiers::CAPS_LOCK, 1)]
+        )
+    }
+
+    /**
+     * Keypad keys: Num Lock selects the digits, and Shift undoes it.
+     **/
+    pub fn keypad() -> KeyType {
+        KeyType::new(
+            "KEYPAD",
+            Modifiers::SHIFT | Modifiers::NUM_LOCK,
+            vec![(Modifiers::SHIFT, 1), (Modifiers::NUM_LOCK, 1)]
+        )
+    }
+
+    pub fn level(&self, modifiers: Modifiers) -> usize {
+        let modifiers = modifiers & self.modifiers;
+        self.map.iter().find(|&&(m, _)| m == modifiers).map_or(0, |&(_, level)| level)
+    }
+}
+
+/**
+ * The keysyms of a key in one layout, by level.
+ **/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyGroup {
+    /// Index into `Keymap::types`.
+    pub key_type: usize,
+    pub syms: Vec<Keysym>,
+    /// The action of each level; missing entries have none.
+    pub actions: Vec<Option<Action>>
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Key {
+    pub groups: Vec<KeyGroup>
+}
+
+/**
+ * The US layout, by Linux keycode: the keysyms without and with Shift.
+ * Keys that type letters or sit on the keypad get their types from the
+ * keysyms.
+ **/
+const US_LAYOUT: [(u16, &str, &str); 99] = [
+    (1, "Escape", "Escape"),
+    (2, "1", "exclam"),
+    (3, "2", "at"),
+    (4, "3", "numbersign"),
+    (5, "4", "dollar"),
+    (6, "5", "percent"),
+    (7, "6", "asciicircum"),
+    (8, "7", "ampersand"),
+    (9, "8", "asterisk"),
+    (10, "9", "parenleft"),
+    (11, "0", "parenright"),
+    (12, "minus", "underscore"),
+    (13, "equal", "plus"),
+    (14, "BackSpace", "BackSpace"),
+    (15, "Tab", "ISO_Left_Tab"),
+    (16, "q", "Q"),
+    (17, "w", "W"),
+    (18, "e", "E"),
+    (19, "r", "R"),
+    (20, "t", "T"),
+    (21, "y", "Y"),
+    (22, "u", "U"),
+    (23, "i", "I"),
+    (24, "o", "O"),
+    (25, "p", "P"),
+    (26, "bracketleft", "braceleft"),
+    (27, "bracketright", "braceright"),
+    (28, "Return", "Return"),
+    (29, "Control_L", "Control_L"),
+    (30, "a", "A"),
+    (31, "s", "S"),
+    (32, "d", "D"),
+    (33, "f", "F"),
+    (34, "g", "G"),
+    (35, "h", "H"),
+    (36, "j", "J"),
+    (37, "k", "K"),
+    (38, "l", "L"),
+    (39, "semicolon", "colon"),
+    (40, "apostrophe", "quotedbl"),
+    (41, "grave", "asciitilde"),
+    (42, "Shift_L", "Shift_L"),
+    (43, "backslash", "bar"),
+    (44, "z", "Z"),
+    (45, "x", "X"),
+    (46, "c", "C"),
+    (47, "v", "V"),
+    (48, "b", "B"),
+    (49, "n", "N"),
+    (50, "m", "M"),
+    (51, "comma", "less"),
+    (52, "period", "greater"),
+    (53, "slash", "question"),
+    (54, "Shift_R", "Shift_R"),
+    (55, "KP_Multiply", "KP_Multiply"),
+    (56, "Alt_L", "Meta_L"),
+    (57, "space", "space"),
+    (58, "Caps_Lock", "Caps_Lock"),
+    (59, "F1", "F1"),
+    (60, "F2", "F2"),
+    (61, "F3", "F3"),
+    (62, "F4", "F4"),
+    (63, "F5", "F5"),
+    (64, "F6", "F6"),
+    (65, "F7", "F7"),
+    (66, "F8", "F8"),
+    (67, "F9", "F9"),
+    (68, "F10", "F10"),
+    (69, "Num_Lock", "Num_Lock"),
+    (70, "Scroll_Lock", "Scroll_Lock"),
+    (71, "KP_Home", "KP_7"),
+    (72, "KP_Up", "KP_8"),
+    (73, "KP_Prior", "KP_9"),
+    (74, "KP_Subtract", "KP_Subtract"),
+    (75, "KP_Left", "KP_4"),
+    (76, "KP_Begin", "KP_5"),
+    (77, "KP_Right", "KP_6"),
+    (78, "KP_Add", "KP_Add"),
+    (79, "KP_End", "KP_1"),
+    (80, "KP_Down", "KP_2"),
+    (81, "KP_Next", "KP_3"),
+    (82, "KP_Insert", "KP_0"),
+    (83, "KP_Delete", "KP_Decimal"),
+    (86, "less", "greater"),
+    (87, "F11", "F11"),
+    (88, "F12", "F12"),
+    (96, "KP_Enter", "KP_Enter"),
+    (97, "Control_R", "Control_R"),
+    (98, "KP_Divide", "KP_Divide"),
+    (99, "Print", "Sys_Req"),
+    (100, "Alt_R", "Meta_R"),
+    (102, "Home", "Home"),
+    (103, "Up", "Up"),
+    (104, "Prior", "Prior"),
+    (105, "Left", "Left"),
+    (106, "Right", "Right"),
+    (107, "End", "End"),
+    (108, "Down", "Down"),
+    (109, "Next", "Next")
+];
+
+/**
+ * Keys of the US layout past the end of `US_LAYOUT` that have a single
+ * keysym.
+ **/
+const US_EXTRA_KEYS: [(u16, &str); 6] = [
+    (110, "Insert"),
+    (111, "Delete"),
+    (119, "Pause"),
+    (125, "Super_L"),
+    (126, "Super_R"),
+    (127, "Menu")
+];
+
+/**
+ * What a key does to the keyboard state, besides typing its keysym.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Active while the key is held. Latches work the same way.
+    SetMods(Modifiers),
+    /// Toggled when the key is pressed.
+    LockMods(Modifiers),
+    /// Switches the layout while the key is held.
+    SetGroup(GroupChange),
+    /// Switches the layout until another key switches it back.
+    LockGroup(GroupChange)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupChange {
+    /// Moves by that many groups, wrapping around.
+    Relative(i32),
+    /// Selects a group by index.
+    Absolute(usize)
+}
+
+impl Action {
+    /**
+     * The usual action of a modifier keysym, for keymaps without XKB
+     * compatibility rules.
+     **/
+    pub fn for_keysym(sym: Keysym) -> Option<Action> {
+        let action = match sym {
+            Keysym::SHIFT_L | Keysym::SHIFT_R => Action::SetMods(Modifiers::SHIFT),
+            Keysym::CONTROL_L | Keysym::CONTROL_R => Action::SetMods(Modifiers::CTRL),
+            Keysym::ALT_L | Keysym::ALT_R | Keysym::META_L | Keysym::META_R => Action::SetMods(Modifiers::ALT),
+            Keysym::SUPER_L | Keysym::SUPER_R => Action::SetMods(Modifiers::SUPER),
+            Keysym::CAPS_LOCK => Action::LockMods(Modifiers::CAPS_LOCK),
+            Keysym::NUM_LOCK => Action::LockMods(Modifiers::NUM_LOCK),
+            _ => return None
+        };
+        Some(action)
+    }
+
+    pub fn modifiers(self) -> Modifiers {
+        match self {
+            Action::SetMods(m) | Action::LockMods(m) => m,
+            _ => Modifiers::NONE
+        }
+    }
+}
+
+/**
+ * Maps keycodes to keysyms and actions. A keymap can hold several layouts,
+ * called groups; keys with fewer groups than the keymap wrap around, as in
+ * X11.
+ **/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keymap {
+    pub types: Vec<KeyType>,
+    keys: BTreeMap<u16, Key>,
+    /// XKB key names such as `AE01`, used when serializing.
+    names: BTreeMap<u16, String>,
+    /// The modifiers each key is bound to, sent to clients along with the
+    /// keymap.
+    modmap: BTreeMap<u16, Modifiers>,
+    group_names: Vec<String>
+}
+
+impl Keymap {
+    pub fn new() -> Keymap {
+        Keymap::default()
+    }
+
+    /**
+     * The built-in US layout.
+     **/
+    pub fn us() -> Keymap {
+        let mut keymap = Keymap::new();
+        let one = keymap.add_type(KeyType::one_level());
+        let two = keymap.add_type(KeyType::two_level());
+        let alphabetic = keymap.add_type(KeyType::alphabetic());
+        let keypad = keymap.add_type(KeyType::keypad());
+        let sym = |name: &str| Keysym::from_name(name).unwrap();
+        let add = |keymap: &mut Keymap, code: u16, key_type: usize, syms: Vec<Keysym>| {
+            let actions: Vec<Option<Action>> = syms.iter().map(|&s| Action::for_keysym(s)).collect();
+            if let Some(action) = actions[0] {
+                keymap.set_modmap(code, action.modifiers());
+            }
+            keymap.set_key(code, Key { groups: vec![KeyGroup { key_type, syms, actions }] });
+        };
+        for &(code, lower, upper) in US_LAYOUT.iter() {
+            let (lower, upper) = (sym(lower), sym(upper));
+            let key_type = if lower == upper {
+                one
+            } else if lower.is_keypad() && upper.is_keypad() {
+                keypad
+            } else if lower.to_char().is_some_and(|c| c.is_lowercase()) {
+                alphabetic
+            } else {
+                two
+            };
+            let syms = if key_type == one { vec![lower] } else { vec![lower, upper] };
+            add(&mut keymap, code, key_type, syms);
+        }
+        for &(code, name) in US_EXTRA_KEYS.iter() {
+            add(&mut keymap, code, one, vec![sym(name)]);
+        }
+        keymap.set_group_names(vec!["English (US)".to_string()]);
+        keymap
+    }
+
+    /**
+     * Adds a key type, or finds one with the same name, and returns its
+     * index.
+     **/
+    pub fn add_type(&mut self, key_type: KeyType) -> usize {
+        if let Some(i) = self.types.iter().position(|t| t.name == key_type.name) {
+            return i;
+        }
+        self.types.push(key_type);
+        self.types.len() - 1
+    }
+
+    pub fn set_key(&mut self, code: u16, key: Key) {
+        self.keys.insert(code, key);
+    }
+
+    pub fn key(&self, code: u16) -> Option<&Key> {
+        self.keys.get(&code)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = (u16, &Key)> {
+        self.keys.iter().map(|(&code, key)| (code, key))
+    }
+
+    pub fn set_name(&mut self, code: u16, name: &str) {
+        self.names.insert(code, name.to_string());
+    }
+
+    pub fn name(&self, code: u16) -> Option<&str> {
+        self.names.get(&code).map(|name| name.as_str())
+    }
+
+    pub fn set_modmap(&mut self, code: u16, modifiers: Modifiers) {
+        self.modmap.insert(code, modifiers);
+    }
+
+    pub fn modmap(&self, code: u16) -> Modifiers {
+        self.modmap.get(&code).cloned().unwrap_or_default()
+    }
+
+    pub fn set_group_names(&mut self, names: Vec<String>) {
+        self.group_names = names;
+    }
+
+    pub fn group_names(&self) -> &[String] {
+        &self.group_names
+    }
+
+    /**
+     * Number of groups, i.e. layouts, in the keymap.
+     **/
+    pub fn groups(&self) -> usize {
+        let keys = self.keys.values().map(|key| key.groups.len()).max().unwrap_or(0);
+        keys.max(self.group_names.len()).max(1)
+    }
+
+    /**
+     * The keysym of key `code` in layout `group` with `modifiers` active.
+     **/
+    pub fn keysym(&self, code: u16, group: usize, modifiers: Modifiers) -> Keysym {
+        self.lookup(code, group, modifiers)
+            .and_then(|(group, level)| group.syms.get(level).or_else(|| group.syms.first()).cloned())
+            .unwrap_or(Keysym::NO_SYMBOL)
+    }
+
+    pub fn action(&self, code: u16, group: usize, modifiers: Modifiers) -> Option<Action> {
+        self.lookup(code, group, modifiers).and_then(|(group, level)| group.actions.get(level).cloned().unwrap_or(None))
+    }
+
+    fn lookup(&self, code: u16, group: usize, modifiers: Modifiers) -> Option<(&KeyGroup, usize)> {
+        let key = self.keys.get(&code).filter(|key| !key.groups.is_empty())?;
+        let group = &key.groups[group % key.groups.len()];
+        let level = self.types.get(group.key_type).map_or(0, |t| t.level(modifiers));
+        Some((group, level))
+    }
+}
+
+/**
+ * A key press or release, as the keymap sees it.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: u16,
+    pub pressed: bool,
+    pub keysym: Keysym,
+    /// The modifiers that were active when the key went down or up.
+    pub modifiers: Modifiers,
+    /// The layout that was active.
+    pub group: usize
+}
+
+impl KeyEvent {
+    /**
+     * The text the key press types. With Ctrl held, letters and some
+     * punctuation type ASCII control characters, e.g. Ctrl+C types 0x03.
+     **/
+    pub fn utf8(&self) -> Option<String> {
+        if !self.pressed {
+            return None;
+        }
+        let c = self.keysym.to_char()?;
+        if self.modifiers.contains(Modifiers::CTRL) && ('@'..='~').contains(&c) && c != '`' {
+            return Some(((c as u8) & 0x1f) as char).map(|c| c.to_string());
+        }
+        Some(c.to_string())
+    }
+}
+
+/**
+ * The state of all keyboards together: which keys are held, which
+ * modifiers are active and locked, and which layout is in use.
+ **/
+#[derive(Debug, Clone)]
+pub struct Keyboard {
+    keymap: Keymap,
+    /// Held keys, with the action each did when it went down.
+    pressed: Vec<(u16, Option<Action>)>,
+    locked: Modifiers,
+    locked_group: usize
+}
+
+impl Keyboard {
+    pub fn new(keymap: Keymap) -> Keyboard {
+        Keyboard {
+            keymap,
+            pressed: Vec::new(),
+            locked: Modifiers::NONE,
+            locked_group: 0
+        }
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    /**
+     * Updates the state for a key going down or up. Presses of keys that
+     * are already held, e.g. on a second keyboard, don't change anything.
+     **/
+    pub fn key(&mut self, key: u16, pressed: bool) -> KeyEvent {
+        let (modifiers, group) = (self.modifiers(), self.group());
+        let keysym = self.keymap.keysym(key, group, modifiers);
+        let event = KeyEvent { key, pressed, keysym, modifiers, group };
+        if pressed && !self.is_pressed(key) {
+            let action = self.keymap.action(key, group, modifiers);
+            match action {
+                Some(Action::LockMods(m)) => self.locked = Modifiers(self.locked.0 ^ m.0),
+                Some(Action::LockGroup(change)) => self.locked_group = self.change_group(self.locked_group, change),
+                _ => {}
+            }
+            self.pressed.push((key, action));
+        } else if !pressed {
+            self.pressed.retain(|&(k, _)| k != key);
+        }
+        event
+    }
+
+    pub fn is_pressed(&self, key: u16) -> bool {
+        self.pressed.iter().any(|&(k, _)| k == key)
+    }
+
+    pub fn pressed_keys(&self) -> Vec<u16> {
+        self.pressed.iter().map(|&(k, _)| k).collect()
+    }
+
+    /**
+     * Modifiers active because their keys are held.
+     **/
+    pub fn depressed(&self) -> Modifiers {
+        self.pressed.iter().fold(Modifiers::NONE, |m, &(_, action)| match action {
+            Some(Action::SetMods(set)) => m | set,
+            _ => m
+        })
+    }
+
+    pub fn locked(&self) -> Modifiers {
+        self.locked
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.depressed() | self.locked
+    }
+
+    /**
+     * The layout in use, counting keys that switch it while held.
+     **/
+    pub fn group(&self) -> usize {
+        self.pressed.iter().fold(self.locked_group, |group, &(_, action)| match action {
+            Some(Action::SetGroup(change)) => self.change_group(group, change),
+            _ => group
+        })
+    }
+
+    /**
+     * Switches to another layout, e.g. from a layout menu.
+     **/
+    pub fn lock_group(&mut self, group: usize) {
+        self.locked_group = group % self.keymap.groups();
+    }
+
+    /**
+     * The keyboard LEDs as `(LED_*, on)`.
+     **/
+    pub fn leds(&self) -> [(u16, bool); 2] {
+        [
+            (LED_NUML, self.locked.contains(Modifiers::NUM_LOCK)),
+            (LED_CAPSL, self.locked.contains(Modifiers::CAPS_LOCK))
+        ]
+    }
+
+    fn change_group(&self, group: usize, change: GroupChange) -> usize {
+        let groups = self.keymap.groups();
+        match change {
+            GroupChange::Relative(n) => (group as i64 + n as i64).rem_euclid(groups as i64) as usize,
+            GroupChange::Absolute(n) => n.min(groups - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: u16 = 30;
+    const KEY_1: u16 = 2;
+    const KEY_C: u16 = 46;
+    const KEY_LEFTSHIFT: u16 = 42;
+    const KEY_RIGHTSHIFT: u16 = 54;
+    const KEY_LEFTCTRL: u16 = 29;
+    const KEY_CAPSLOCK: u16 = 58;
+    const KEY_NUMLOCK: u16 = 69;
+    const KEY_KP7: u16 = 71;
+
+    fn type_key(keyboard: &mut Keyboard, key: u16) -> Option<String> {
+        let text = keyboard.key(key, true).utf8();
+        keyboard.key(key, false);
+        text
+    }
+
+    #[test]
+    fn picks_levels() {
+        let keymap = Keymap::us();
+        let name = |key, modifiers| keymap.keysym(key, 0, modifiers).name();
+        assert_eq!(name(KEY_A, Modifiers::NONE), "a");
+        assert_eq!(name(KEY_A, Modifiers::SHIFT), "A");
+        assert_eq!(name(KEY_A, Modifiers::CAPS_LOCK), "A");
+        assert_eq!(name(KEY_A, Modifiers::SHIFT | Modifiers::CAPS_LOCK), "a");
+        assert_eq!(name(KEY_1, Modifiers::CAPS_LOCK), "1");
+        assert_eq!(name(KEY_1, Modifiers::SHIFT | Modifiers::CTRL), "exclam");
+        assert_eq!(name(KEY_KP7, Modifiers::NONE), "KP_Home");
+        assert_eq!(name(KEY_KP7, Modifiers::NUM_LOCK), "KP_7");
+        assert_eq!(name(KEY_KP7, Modifiers::NUM_LOCK | Modifiers::SHIFT), "KP_Home");
+        assert_eq!(name(KEY_LEFTSHIFT, Modifiers::SHIFT), "Shift_L");
+        assert_eq!(name(240, Modifiers::NONE), "NoSymbol");
+        // a single layout is used for every group
+        assert_eq!(keymap.keysym(KEY_A, 1, Modifiers::NONE).name(), "a");
+    }
+
+    #[test]
+    fn tracks_modifiers_and_leds() {
+        let mut keyboard = Keyboard::new(Keymap::us());
+        assert_eq!(type_key(&mut keyboard, KEY_A), Some("a".to_string()));
+
+        keyboard.key(KEY_LEFTSHIFT, true);
+        keyboard.key(KEY_RIGHTSHIFT, true);
+        keyboard.key(KEY_LEFTSHIFT, false);
+        assert_eq!(keyboard.modifiers(), Modifiers::SHIFT);
+        assert_eq!(type_key(&mut keyboard, KEY_1), Some("!".to_string()));
+        keyboard.key(KEY_RIGHTSHIFT, false);
+        assert!(keyboard.modifiers().is_empty());
+        assert!(keyboard.pressed_keys().is_empty());
+
+        // locks toggle on press, not release
+        keyboard.key(KEY_CAPSLOCK, true);
+        assert_eq!(keyboard.locked(), Modifiers::CAPS_LOCK);
+        keyboard.key(KEY_CAPSLOCK, false);
+        assert_eq!(type_key(&mut keyboard, KEY_A), Some("A".to_string()));
+        type_key(&mut keyboard, KEY_NUMLOCK);
+        assert_eq!(keyboard.leds(), [(LED_NUML, true), (LED_CAPSL, true)]);
+        assert_eq!(type_key(&mut keyboard, KEY_KP7), Some("7".to_string()));
+        type_key(&mut keyboard, KEY_CAPSLOCK);
+        assert_eq!(keyboard.leds(), [(LED_NUML, true), (LED_CAPSL, false)]);
+
+        keyboard.key(KEY_LEFTCTRL, true);
+        assert_eq!(type_key(&mut keyboard, KEY_C), Some("\u{3}".to_string()));
+        assert!(keyboard.key(KEY_LEFTCTRL, false).utf8().is_none());
+    }
+}
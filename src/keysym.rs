@@ -0,0 +1,393 @@
+/**
+ * An X11 keysym: what a key means under the current keymap, as opposed to
+ * the keycode of the key that was pressed. Latin-1 characters have their
+ * code point as keysym, other characters their code point plus
+ * `0x01000000`, and function keys values from `0xff00` up.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Keysym(pub u32);
+
+/**
+ * Names of the printable ASCII keysyms, from 0x20 to 0x7e.
+ **/
+const ASCII_NAMES: [&str; 95] = [
+    "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "apostrophe",
+    "parenleft", "parenright", "asterisk", "plus", "comma", "minus", "period", "slash",
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9",
+    "colon", "semicolon", "less", "equal", "greater", "question", "at",
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M",
+    "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z",
+    "bracketleft", "backslash", "bracketright", "asciicircum", "underscore", "grave",
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m",
+    "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z",
+    "braceleft", "bar", "braceright", "asciitilde"
+];
+
+/**
+ * Names of the Latin-1 keysyms, from 0xa0 to 0xff.
+ **/
+const LATIN1_NAMES: [&str; 96] = [
+    "nobreakspace", "exclamdown", "cent", "sterling", "currency", "yen", "brokenbar", "section",
+    "diaeresis", "copyright", "ordfeminine", "guillemotleft", "notsign", "hyphen", "registered", "macron",
+    "degree", "plusminus", "twosuperior", "threesuperior", "acute", "mu", "paragraph", "periodcentered",
+    "cedilla", "onesuperior", "masculine", "guillemotright", "onequarter", "onehalf", "threequarters", "questiondown",
+    "Agrave", "Aacute", "Acircumflex", "Atilde", "Adiaeresis", "Aring", "AE", "Ccedilla",
+    "Egrave", "Eacute", "Ecircumflex", "Ediaeresis", "Igrave", "Iacute", "Icircumflex", "Idiaeresis",
+    "ETH", "Ntilde", "Ograve", "Oacute", "Ocircumflex", "Otilde", "Odiaeresis", "multiply",
+    "Oslash", "Ugrave", "Uacute", "Ucircumflex", "Udiaeresis", "Yacute", "THORN", "ssharp",
+    "agrave", "aacute", "acircumflex", "atilde", "adiaeresis", "aring", "ae", "ccedilla",
+    "egrave", "eacute", "ecircumflex", "ediaeresis", "igrave", "iacute", "icircumflex", "idiaeresis",
+    "eth", "ntilde", "ograve", "oacute", "ocircumflex", "otilde", "odiaeresis", "division",
+    "oslash", "ugrave", "uacute", "ucircumflex", "udiaeresis", "yacute", "thorn", "ydiaeresis"
+];
+
+/**
+ * Names of function, keypad, modifier and dead keysyms, and aliases of
+ * Latin-1 names. Where a keysym has several names, the first one is used
+ * when naming it.
+ **/
+const NAMES: [(&str, u32); 165] = [
+    ("NoSymbol", 0),
+    ("ISO_Left_Tab", 0xfe20),
+    ("BackSpace", 0xff08),
+    ("Tab", 0xff09),
+    ("Linefeed", 0xff0a),
+    ("Clear", 0xff0b),
+    ("Return", 0xff0d),
+    ("Pause", 0xff13),
+    ("Scroll_Lock", 0xff14),
+    ("Sys_Req", 0xff15),
+    ("Escape", 0xff1b),
+    ("Home", 0xff50),
+    ("Left", 0xff51),
+    ("Up", 0xff52),
+    ("Right", 0xff53),
+    ("Down", 0xff54),
+    ("Prior", 0xff55),
+    ("Page_Up", 0xff55),
+    ("Next", 0xff56),
+    ("Page_Down", 0xff56),
+    ("End", 0xff57),
+    ("Begin", 0xff58),
+    ("Print", 0xff61),
+    ("Insert", 0xff63),
+    ("Menu", 0xff67),
+    ("Num_Lock", 0xff7f),
+    ("KP_Space", 0xff80),
+    ("KP_Tab", 0xff89),
+    ("KP_Enter", 0xff8d),
+    ("KP_F1", 0xff91),
+    ("KP_F2", 0xff92),
+    ("KP_F3", 0xff93),
+    ("KP_F4", 0xff94),
+    ("KP_Home", 0xff95),
+    ("KP_Left", 0xff96),
+    ("KP_Up", 0xff97),
+    ("KP_Right", 0xff98),
+    ("KP_Down", 0xff99),
+    ("KP_Prior", 0xff9a),
+    ("KP_Next", 0xff9b),
+    ("KP_End", 0xff9c),
+    ("KP_Begin", 0xff9d),
+    ("KP_Insert", 0xff9e),
+    ("KP_Delete", 0xff9f),
+    ("KP_Multiply", 0xffaa),
+    ("KP_Add", 0xffab),
+    ("KP_Separator", 0xffac),
+    ("KP_Subtract", 0xffad),
+    ("KP_Decimal", 0xffae),
+    ("KP_Divide", 0xffaf),
+    ("KP_0", 0xffb0),
+    ("KP_1", 0xffb1),
+    ("KP_2", 0xffb2),
+    ("KP_3", 0xffb3),
+    ("KP_4", 0xffb4),
+    ("KP_5", 0xffb5),
+    ("KP_6", 0xffb6),
+    ("KP_7", 0xffb7),
+    ("KP_8", 0xffb8),
+    ("KP_9", 0xffb9),
+    ("KP_Equal", 0xffbd),
+    ("F1", 0xffbe),
+    ("F2", 0xffbf),
+    ("F3", 0xffc0),
+    ("F4", 0xffc1),
+    ("F5", 0xffc2),
+    ("F6", 0xffc3),
+    ("F7", 0xffc4),
+    ("F8", 0xffc5),
+    ("F9", 0xffc6),
+    ("F10", 0xffc7),
+    ("F11", 0xffc8),
+    ("F12", 0xffc9),
+    ("Shift_L", 0xffe1),
+    ("Shift_R", 0xffe2),
+    ("Control_L", 0xffe3),
+    ("Control_R", 0xffe4),
+    ("Caps_Lock", 0xffe5),
+    ("Shift_Lock", 0xffe6),
+    ("Meta_L", 0xffe7),
+    ("Meta_R", 0xffe8),
+    ("Alt_L", 0xffe9),
+    ("Alt_R", 0xffea),
+    ("Super_L", 0xffeb),
+    ("Super_R", 0xffec),
+    ("Delete", 0xffff),
+    ("guillemetleft", 0xab),
+    ("guillemetright", 0xbb),
+    ("ordmasculine", 0xba),
+    ("Ooblique", 0xd8),
+    ("ooblique", 0xf8),
+    ("ISO_Lock", 0xfe01),
+    ("ISO_Level2_Latch", 0xfe02),
+    ("ISO_Level3_Shift", 0xfe03),
+    ("ISO_Level3_Latch", 0xfe04),
+    ("ISO_Level3_Lock", 0xfe05),
+    ("ISO_Group_Latch", 0xfe06),
+    ("ISO_Group_Lock", 0xfe07),
+    ("ISO_Next_Group", 0xfe08),
+    ("ISO_Next_Group_Lock", 0xfe09),
+    ("ISO_Prev_Group", 0xfe0a),
+    ("ISO_Prev_Group_Lock", 0xfe0b),
+    ("ISO_First_Group", 0xfe0c),
+    ("ISO_First_Group_Lock", 0xfe0d),
+    ("ISO_Last_Group", 0xfe0e),
+    ("ISO_Last_Group_Lock", 0xfe0f),
+    ("ISO_Level5_Shift", 0xfe11),
+    ("ISO_Level5_Latch", 0xfe12),
+    ("ISO_Level5_Lock", 0xfe13),
+    ("dead_grave", 0xfe50),
+    ("dead_acute", 0xfe51),
+    ("dead_circumflex", 0xfe52),
+    ("dead_tilde", 0xfe53),
+    ("dead_macron", 0xfe54),
+    ("dead_breve", 0xfe55),
+    ("dead_abovedot", 0xfe56),
+    ("dead_diaeresis", 0xfe57),
+    ("dead_abovering", 0xfe58),
+    ("dead_doubleacute", 0xfe59),
+    ("dead_caron", 0xfe5a),
+    ("dead_cedilla", 0xfe5b),
+    ("dead_ogonek", 0xfe5c),
+    ("dead_iota", 0xfe5d),
+    ("dead_belowmacron", 0xfe68),
+    ("dead_belowdot", 0xfe60),
+    ("dead_hook", 0xfe61),
+    ("dead_horn", 0xfe62),
+    ("dead_stroke", 0xfe63),
+    ("dead_currency", 0xfe6f),
+    ("dead_greek", 0xfe8c),
+    ("Multi_key", 0xff20),
+    ("Select", 0xff60),
+    ("Execute", 0xff62),
+    ("Undo", 0xff65),
+    ("Redo", 0xff66),
+    ("Find", 0xff68),
+    ("Cancel", 0xff69),
+    ("Help", 0xff6a),
+    ("Break", 0xff6b),
+    ("Mode_switch", 0xff7e),
+    ("F13", 0xffca),
+    ("F14", 0xffcb),
+    ("F15", 0xffcc),
+    ("F16", 0xffcd),
+    ("F17", 0xffce),
+    ("F18", 0xffcf),
+    ("F19", 0xffd0),
+    ("F20", 0xffd1),
+    ("F21", 0xffd2),
+    ("F22", 0xffd3),
+    ("F23", 0xffd4),
+    ("F24", 0xffd5),
+    ("F25", 0xffd6),
+    ("F26", 0xffd7),
+    ("F27", 0xffd8),
+    ("F28", 0xffd9),
+    ("F29", 0xffda),
+    ("F30", 0xffdb),
+    ("F31", 0xffdc),
+    ("F32", 0xffdd),
+    ("F33", 0xffde),
+    ("F34", 0xffdf),
+    ("F35", 0xffe0),
+    ("Hyper_L", 0xffed),
+    ("Hyper_R", 0xffee)
+];
+
+/**
+ * Names of keysyms from before Unicode keysyms existed that type
+ * characters beyond Latin-1, with those characters.
+ **/
+const CHARACTER_NAMES: [(&str, u32, char); 37] = [
+    ("Lstroke", 0x1a3, '\u{141}'),
+    ("lstroke", 0x1b3, '\u{142}'),
+    ("Dstroke", 0x1d0, '\u{110}'),
+    ("dstroke", 0x1f0, '\u{111}'),
+    ("Hstroke", 0x2a1, '\u{126}'),
+    ("hstroke", 0x2b1, '\u{127}'),
+    ("idotless", 0x2b9, '\u{131}'),
+    ("kra", 0x3a2, '\u{138}'),
+    ("Tslash", 0x3ac, '\u{166}'),
+    ("tslash", 0x3bc, '\u{167}'),
+    ("ENG", 0x3bd, '\u{14a}'),
+    ("eng", 0x3bf, '\u{14b}'),
+    ("OE", 0x13bc, '\u{152}'),
+    ("oe", 0x13bd, '\u{153}'),
+    ("Greek_OMEGA", 0x7d9, '\u{3a9}'),
+    ("leftarrow", 0x8fb, '\u{2190}'),
+    ("uparrow", 0x8fc, '\u{2191}'),
+    ("rightarrow", 0x8fd, '\u{2192}'),
+    ("downarrow", 0x8fe, '\u{2193}'),
+    ("emdash", 0xaa9, '\u{2014}'),
+    ("endash", 0xaaa, '\u{2013}'),
+    ("ellipsis", 0xaae, '\u{2026}'),
+    ("oneeighth", 0xac3, '\u{215b}'),
+    ("threeeighths", 0xac4, '\u{215c}'),
+    ("fiveeighths", 0xac5, '\u{215d}'),
+    ("seveneighths", 0xac6, '\u{215e}'),
+    ("trademark", 0xac9, '\u{2122}'),
+    ("leftsinglequotemark", 0xad0, '\u{2018}'),
+    ("rightsinglequotemark", 0xad1, '\u{2019}'),
+    ("leftdoublequotemark", 0xad2, '\u{201c}'),
+    ("rightdoublequotemark", 0xad3, '\u{201d}'),
+    ("dagger", 0xaf1, '\u{2020}'),
+    ("doubledagger", 0xaf2, '\u{2021}'),
+    ("singlelowquotemark", 0xafd, '\u{201a}'),
+    ("doublelowquotemark", 0xafe, '\u{201e}'),
+    ("enfilledcircbullet", 0xae6, '\u{2022}'),
+    ("EuroSign", 0x20ac, '\u{20ac}')
+];
+
+/**
+ * Offset of keysyms given by Unicode code point.
+ **/
+const UNICODE_OFFSET: u32 = 0x0100_0000;
+
+impl Keysym {
+    pub const NO_SYMBOL: Keysym = Keysym(0);
+    pub const SHIFT_L: Keysym = Keysym(0xffe1);
+    pub const SHIFT_R: Keysym = Keysym(0xffe2);
+    pub const CONTROL_L: Keysym = Keysym(0xffe3);
+    pub const CONTROL_R: Keysym = Keysym(0xffe4);
+    pub const CAPS_LOCK: Keysym = Keysym(0xffe5);
+    pub const META_L: Keysym = Keysym(0xffe7);
+    pub const META_R: Keysym = Keysym(0xffe8);
+    pub const ALT_L: Keysym = Keysym(0xffe9);
+    pub const ALT_R: Keysym = Keysym(0xffea);
+    pub const SUPER_L: Keysym = Keysym(0xffeb);
+    pub const SUPER_R: Keysym = Keysym(0xffec);
+    pub const NUM_LOCK: Keysym = Keysym(0xff7f);
+
+    /**
+     * Looks up a keysym by its X11 name, such as `a`, `Return` or
+     * `U20AC`, or by a hexadecimal value such as `0x1000e4`.
+     **/
+    pub fn from_name(name: &str) -> Option<Keysym> {
+        if let Some(i) = ASCII_NAMES.iter().position(|&n| n == name) {
+            return Some(Keysym(0x20 + i as u32));
+        }
+        if let Some(i) = LATIN1_NAMES.iter().position(|&n| n == name) {
+            return Some(Keysym(0xa0 + i as u32));
+        }
+        if let Some(&(_, value)) = NAMES.iter().find(|&&(n, _)| n == name) {
+            return Some(Keysym(value));
+        }
+        if let Some(&(_, value, _)) = CHARACTER_NAMES.iter().find(|&&(n, _, _)| n == name) {
+            return Some(Keysym(value));
+        }
+        if let Some(hex) = name.strip_prefix("0x") {
+            return u32::from_str_radix(hex, 16).ok().map(Keysym);
+        }
+        if name.len() > 1 && name.starts_with('U') {
+            let c = u32::from_str_radix(&name[1..], 16).ok().and_then(std::char::from_u32)?;
+            return Some(Keysym::from_char(c));
+        }
+        None
+    }
+
+    /**
+     * The keysym's X11 name, or a `U` or `0x` form for keysyms without one.
+     **/
+    pub fn name(self) -> String {
+        if (0x20..=0x7e).contains(&self.0) {
+            return ASCII_NAMES[self.0 as usize - 0x20].to_string();
+        }
+        if (0xa0..=0xff).contains(&self.0) {
+            return LATIN1_NAMES[self.0 as usize - 0xa0].to_string();
+        }
+        if let Some(&(name, _)) = NAMES.iter().find(|&&(_, v)| v == self.0) {
+            return name.to_string();
+        }
+        if let Some(&(name, _, _)) = CHARACTER_NAMES.iter().find(|&&(_, v, _)| v == self.0) {
+            return name.to_string();
+        }
+        match self.to_char() {
+            Some(c) if self.0 >= UNICODE_OFFSET => format!("U{:04X}", c as u32),
+            _ => format!("0x{:08x}", self.0)
+        }
+    }
+
+    pub fn from_char(c: char) -> Keysym {
+        let c = c as u32;
+        if (0x20..=0x7e).contains(&c) || (0xa0..=0xff).contains(&c) {
+            Keysym(c)
+        } else {
+            Keysym(c + UNICODE_OFFSET)
+        }
+    }
+
+    /**
+     * The character the keysym types, if any. Keypad keys type the same as
+     * their main keyboard counterparts.
+     **/
+    pub fn to_char(self) -> Option<char> {
+        let c = match self.0 {
+            0x20..=0x7e | 0xa0..=0xff => self.0,
+            0xff08 => 0x08,
+            0xff09 | 0xfe20 | 0xff89 => 0x09,
+            0xff0d | 0xff8d => 0x0d,
+            0xff1b => 0x1b,
+            0xffff | 0xff9f => 0x7f,
+            0xff80 => 0x20,
+            0xffaa..=0xffb9 => self.0 - 0xffaa + 0x2a,
+            0xffbd => 0x3d,
+            0x0100_0100..=0x0110_ffff => self.0 - UNICODE_OFFSET,
+            _ => return CHARACTER_NAMES.iter().find(|&&(_, v, _)| v == self.0).map(|&(_, _, c)| c)
+        };
+        std::char::from_u32(c)
+    }
+
+    pub fn is_keypad(self) -> bool {
+        (0xff80..=0xffbd).contains(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_keysyms() {
+        for &name in ["a", "Z", "space", "asciitilde", "adiaeresis", "Return", "KP_7", "Prior", "dead_acute", "U20AC"].iter() {
+            assert_eq!(Keysym::from_name(name).unwrap().name(), name);
+        }
+        assert_eq!(Keysym::from_name("Page_Up"), Keysym::from_name("Prior"));
+        assert_eq!(Keysym::from_name("U00E4"), Some(Keysym(0xe4)));
+        assert_eq!(Keysym::from_name("0x1000e4"), Some(Keysym(0x1000e4)));
+        assert_eq!(Keysym::from_name("nonsense"), None);
+        assert_eq!(Keysym(0x1234_5678).name(), "0x12345678");
+    }
+
+    #[test]
+    fn converts_to_characters() {
+        assert_eq!(Keysym::from_name("braceleft").unwrap().to_char(), Some('{'));
+        assert_eq!(Keysym::from_name("KP_Add").unwrap().to_char(), Some('+'));
+        assert_eq!(Keysym::from_name("KP_3").unwrap().to_char(), Some('3'));
+        assert_eq!(Keysym::from_name("Return").unwrap().to_char(), Some('\r'));
+        assert_eq!(Keysym::from_char('€').to_char(), Some('€'));
+        assert_eq!(Keysym::from_char('é'), Keysym(0xe9));
+        assert_eq!(Keysym::from_name("EuroSign").unwrap().to_char(), Some('€'));
+        assert_eq!(Keysym::from_name("lstroke").unwrap().to_char(), Some('ł'));
+        assert_eq!(Keysym::SHIFT_L.to_char(), None);
+    }
+}
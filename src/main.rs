@@ -1,84 +1,348 @@
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(unused)]
+// generated by bindgen
+#[allow(non_snake_case, clippy::all)]
 mod ffi;
 mod drm_const;
 mod drm;
+mod atomic;
+mod format;
+mod backend;
 mod gfx;
 mod compositor;
+mod frame_clock;
+mod input;
+mod evdev;
+mod input_devices;
+mod keysym;
+mod keyboard;
+mod xkb;
+mod sdl_backend;
+mod span;
+mod simd;
+mod thread_pool;
+mod tiles;
+mod transform;
+mod scale;
+mod yuv;
+mod overlay;
+mod dither;
+mod blend;
+mod raster;
+mod effects;
+mod image;
+mod inflate;
+mod deflate;
+mod png;
+mod bmp;
+mod pnm;
+mod atlas;
+mod text;
+mod outline;
+mod truetype;
+mod cff;
+mod screenshot;
+mod ipc;
+mod bench;
 
 use std::{thread, time};
 use gfx::GFX;
 use drm::Color;
-use compositor::Compositor;
-
-use std::io::prelude::*;
-use std::fs::{File, OpenOptions};
-use std::os::unix::io::AsRawFd;
-use std::{cmp, mem, slice};
-
-#[repr(C)]
-#[derive(Debug, Default, Copy, Clone)]
-struct InputEvent {
-    button: u8,
-    dx: i8,
-    dy: i8,
-}
+use compositor::{Compositor, Cursor};
+use input::{Button, InputEvent, KEY_SYSRQ};
+use input_devices::{DeviceChange, DeviceManager};
+use keyboard::{Keyboard, Keymap};
+use ipc::Command;
+use screenshot::Capture;
+use sdl_backend::SdlBackend;
+use backend::Backend;
+use transform::{OutputTransform, TransformedBackend};
+use scale::Scale;
+use dither::Dither;
+use image::Image;
+use text::{BitmapFont, Font};
+use truetype::TrueTypeFont;
+
+use std::env;
 
 #[derive(Debug, Default)]
 struct MouseState {
-    x: u32,
-    y: u32,
+    x: i32,
+    y: i32,
     left_down: bool,
     middle_down: bool,
     right_down: bool
 }
 
+/**
+ * Switches every output to the font file named by `$FONT` (TrueType,
+ * OpenType, PSF or BDF), rendering scalable fonts at `$FONT_SIZE` logical
+ * pixels. Bitmap fonts, the built-in one included, are blown up to the
+ * nearest whole scale of each output.
+ **/
+fn load_font(c: &mut Compositor) {
+    let data = env::var("FONT").ok().map(|path| std::fs::read(&path).unwrap());
+    let size: f32 = env::var("FONT_SIZE").ok().and_then(|s| s.parse().ok()).unwrap_or(14.0);
+    for output in 0..c.output_count() {
+        let factor = c.scale(output).factor();
+        let font: Box<dyn Font> = match data {
+            Some(ref data) => match TrueTypeFont::load(data, size * factor) {
+                Ok(font) => Box::new(font),
+                Err(_) => Box::new(BitmapFont::load(data).unwrap().scaled(factor.round().max(1.0) as u32))
+            },
+            None if factor >= 1.5 => Box::new(BitmapFont::default_font().scaled(factor.round() as u32)),
+            None => continue
+        };
+        c.output_mut(output).set_font(font);
+    }
+}
+
+/**
+ * Shows the image file named by `$WALLPAPER` behind the windows.
+ **/
+fn load_wallpaper(c: &mut Compositor) {
+    if let Ok(path) = env::var("WALLPAPER") {
+        let data = std::fs::read(&path).unwrap();
+        c.set_wallpaper(Some(Image::load(&data).unwrap()));
+    }
+}
+
+/**
+ * Saves a screenshot to `path`, or a timestamped file if none is given, and
+ * returns where it went.
+ **/
+fn take_screenshot(c: &Compositor, capture: &Capture, path: Option<std::path::PathBuf>) -> Result<String, String> {
+    let path = path.unwrap_or_else(screenshot::default_path);
+    c.screenshot(capture, &path).map_err(|e| e.to_string())?;
+    Ok(path.display().to_string())
+}
+
+/**
+ * Opens the control socket, which is optional: the compositor keeps running
+ * without it.
+ **/
+fn start_ipc() -> Option<ipc::Server> {
+    match ipc::Server::bind(&ipc::Server::default_path()) {
+        Ok(server) => Some(server),
+        Err(e) => {
+            eprintln!("control socket unavailable: {}", e);
+            None
+        }
+    }
+}
+
+fn poll_ipc(server: &mut Option<ipc::Server>, c: &Compositor, keymap: &str) {
+    if let Some(ref mut server) = *server {
+        server.poll(|command| match command {
+            Command::Screenshot { capture, path } => take_screenshot(c, &capture, path),
+            Command::Keymap => Ok(keymap.to_string())
+        });
+    }
+}
+
+/**
+ * The keymap named by `$XKB_DEFAULT_LAYOUT` and the other `XKB_DEFAULT_*`
+ * variables, compiled from the system's xkb data. Without that data, the
+ * built-in US layout is used.
+ **/
+fn load_keymap() -> Keymap {
+    let names = xkb::Names::from_env();
+    match xkb::Xkb::system().compile(&names) {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            eprintln!("keymap {} unavailable, using the built-in US layout: {}", names.layout, e);
+            Keymap::us()
+        }
+    }
+}
+
+/**
+ * How the output is turned, from `$OUTPUT_TRANSFORM`.
+ **/
+fn output_transform() -> OutputTransform {
+    match env::var("OUTPUT_TRANSFORM") {
+        Ok(s) => OutputTransform::parse(&s).unwrap_or_else(|| {
+            eprintln!("unknown output transform {}", s);
+            OutputTransform::Normal
+        }),
+        Err(_) => OutputTransform::Normal
+    }
+}
+
+/**
+ * The dithering named by `$DITHER`: `ordered` or `diffusion`.
+ **/
+fn output_dither() -> Dither {
+    env::var("DITHER").ok().and_then(|s| Dither::parse(&s)).unwrap_or(Dither::None)
+}
+
+/**
+ * Uses the scale in `$OUTPUT_SCALE` on every output, or one that suits the
+ * pixel density of each.
+ **/
+fn set_scale(c: &mut Compositor) {
+    let forced = env::var("OUTPUT_SCALE").ok().and_then(|s| Scale::parse(&s));
+    for output in 0..c.output_count() {
+        let scale = forced.unwrap_or_else(|| {
+            let gfx = c.output(output);
+            match gfx.backend().size_mm() {
+                Some((width_mm, _)) => Scale::for_density(gfx.width(), width_mm),
+                None => Scale::default()
+            }
+        });
+        c.set_scale(output, scale);
+    }
+}
+
 fn start_compositor() {
-    let mut c = Compositor::new();
+    let mut c = Compositor::with_outputs(GFX::outputs(output_transform()));
+    for output in 0..c.output_count() {
+        c.output_mut(output).set_dither(output_dither());
+    }
+    c.set_render_threads(thread_pool::default_threads());
+    set_scale(&mut c);
+    load_font(&mut c);
+    load_wallpaper(&mut c);
     c.add_window();
-    c.render();
+    let mut server = start_ipc();
+    let keymap = xkb::serialize(&load_keymap());
 
-    thread::sleep(time::Duration::from_millis(1000));
+    let mut clock = c.frame_clock();
+    let budget = time::Duration::from_millis(4);
+    for _ in 0..60 {
+        clock.wait_for_render(budget).unwrap();
+        if let Err(e) = c.render() {
+            eprintln!("render failed: {}", e);
+        }
+        poll_ipc(&mut server, &c, &keymap);
+    }
 }
 
-fn read_mouse() {
+/**
+ * Runs the compositor inside a window of the current desktop session.
+ **/
+fn start_nested() {
+    let backend: Box<dyn Backend> = Box::new(SdlBackend::new(1024, 768).unwrap());
+    let backend = match output_transform() {
+        OutputTransform::Normal => backend,
+        transform => Box::new(TransformedBackend::new(backend, transform).unwrap())
+    };
+    let mut c = Compositor::with_gfx(GFX::with_backend(backend));
+    c.gfx_mut().set_dither(output_dither());
+    c.set_render_threads(thread_pool::default_threads());
+    set_scale(&mut c);
+    load_font(&mut c);
+    load_wallpaper(&mut c);
+    c.set_cursor(Some(Cursor::default_cursor()));
+    c.add_window();
+    let mut server = start_ipc();
+    let keymap = xkb::serialize(&load_keymap());
+
+    let mut mouse_state = MouseState::default();
+    loop {
+        for event in c.gfx_mut().input_events() {
+            match event {
+                InputEvent::Quit => return,
+                InputEvent::PointerPosition { x, y } => {
+                    let (x, y) = c.scale(0).to_logical(x, y);
+                    mouse_state.x = x;
+                    mouse_state.y = y;
+                }
+                InputEvent::PointerButton { button, pressed } => match button {
+                    Button::Left => mouse_state.left_down = pressed,
+                    Button::Middle => mouse_state.middle_down = pressed,
+                    Button::Right => mouse_state.right_down = pressed,
+                    _ => {}
+                },
+                InputEvent::Key { key: KEY_SYSRQ, pressed: true } => {
+                    match take_screenshot(&c, &Capture::Screen, None) {
+                        Ok(path) => println!("saved screenshot to {}", path),
+                        Err(e) => eprintln!("screenshot failed: {}", e)
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        c.move_pointer(mouse_state.x, mouse_state.y);
+        if let Err(e) = c.render() {
+            eprintln!("render failed: {}", e);
+        }
+        poll_ipc(&mut server, &c, &keymap);
+        thread::sleep(time::Duration::from_millis(16));
+    }
+}
+
+/**
+ * Reads all input devices, draws the pointer straight into the framebuffer
+ * and prints what is typed.
+ **/
+fn read_input() {
     let mut g = GFX::new();
     let c = Color::new(0, 0, 255, 255);
     g.clear();
-    g.point(20, 20, &c);
 
-    let mut fd = OpenOptions::new()
-        .read(true)
-        .open("/dev/input/mouse0").unwrap();
+    let mut devices = DeviceManager::open().unwrap();
+    devices.set_output_size(g.width(), g.height()).unwrap();
 
-    let mut mouse_state = MouseState::default();
-    mouse_state.x = 10;
-    mouse_state.y = 10;
-    g.point(30, 30, &c);
-
-    let event_size = mem::size_of::<InputEvent>();
-    let mut event = InputEvent::default();
-    let mut buffer = &mut event as *mut InputEvent as *mut u8;
-    let mut buffer = unsafe { slice::from_raw_parts_mut(buffer, event_size) };
+    let mut keyboard = Keyboard::new(load_keymap());
+    let mut mouse_state = MouseState { x: 10, y: 10, ..MouseState::default() };
     loop {
-        g.point(40, 40, &c);
-        fd.read(buffer).unwrap(); 
-        g.point(50, 50, &c);
-        mouse_state.x = cmp::max(0, mouse_state.x as i32 + event.dx as i32) as u32;
-        mouse_state.y = cmp::max(0, mouse_state.y as i32 + event.dy as i32) as u32;
-        mouse_state.left_down = event.button & 0b100 != 0;
-        mouse_state.middle_down = event.button & 0b010 != 0;
-        mouse_state.right_down = event.button & 0b001 != 0;
+        devices.wait(None).unwrap();
+        let events = devices.dispatch().unwrap();
+        for change in devices.take_changes() {
+            match change {
+                DeviceChange::Added { path, name, classes } => println!("added {} ({}): {:?}", name, path.display(), classes),
+                DeviceChange::Removed { path } => println!("removed {}", path.display())
+            }
+        }
+        for event in events {
+            match event {
+                InputEvent::PointerMotion { dx, dy } => {
+                    mouse_state.x += dx;
+                    mouse_state.y += dy;
+                }
+                InputEvent::PointerPosition { x, y } => {
+                    mouse_state.x = x;
+                    mouse_state.y = y;
+                }
+                InputEvent::PointerButton { button, pressed } => match button {
+                    Button::Left => mouse_state.left_down = pressed,
+                    Button::Middle => mouse_state.middle_down = pressed,
+                    Button::Right => mouse_state.right_down = pressed,
+                    _ => {}
+                },
+                InputEvent::Key { key, pressed } => {
+                    let (leds, group) = (keyboard.leds(), keyboard.group());
+                    let event = keyboard.key(key, pressed);
+                    if let Some(text) = event.utf8() {
+                        println!("{} {:?}", event.keysym.name(), text);
+                    }
+                    if keyboard.group() != group {
+                        let names = keyboard.keymap().group_names();
+                        println!("layout {}", names.get(keyboard.group()).map_or("", |n| n.as_str()));
+                    }
+                    if keyboard.leds() != leds {
+                        devices.set_leds(&keyboard.leds());
+                    }
+                }
+                _ => {}
+            }
+        }
+        mouse_state.x = mouse_state.x.clamp(0, g.width() as i32 - 1);
+        mouse_state.y = mouse_state.y.clamp(0, g.height() as i32 - 1);
         println!("{:?}", mouse_state);
-        //g.clear();
-        let c = Color::new(0, 0, 255, 255);
-        //g.point(300, 300, &c);
         g.point(mouse_state.x, mouse_state.y, &c);
+        g.flush().unwrap();
     }
 }
 
 fn main() {
-    //start_compositor();
-    read_mouse();
+    match env::args().nth(1).as_deref() {
+        Some("compositor") => start_compositor(),
+        Some("nested") => start_nested(),
+        Some("bench") => bench::run(),
+        _ => read_input()
+    }
 }
@@ -0,0 +1,419 @@
+use super::atlas::MaskFormat;
+
+/**
+ * Maximum distance in pixels between a curve and the lines approximating it.
+ **/
+const TOLERANCE: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathCommand {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    /// Control point, end point.
+    QuadTo(f32, f32, f32, f32),
+    /// Two control points, end point.
+    CubicTo(f32, f32, f32, f32, f32, f32),
+    Close
+}
+
+/**
+ * A glyph outline in font units, with y pointing up.
+ **/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outline {
+    commands: Vec<PathCommand>
+}
+
+/**
+ * Coverage produced by rasterizing an outline. `left` and `top` are the
+ * offset of the mask from the origin, with y pointing down.
+ **/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    pub width: u32,
+    pub height: u32,
+    pub left: i32,
+    pub top: i32,
+    pub format: MaskFormat,
+    pub data: Vec<u8>
+}
+
+impl Outline {
+    pub fn new() -> Outline {
+        Outline::default()
+    }
+
+    pub fn commands(&self) -> &[PathCommand] {
+        &self.commands
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.commands.push(PathCommand::MoveTo(x, y));
+    }
+
+    pub fn line_to(&mut self, x: f32, y: f32) {
+        self.commands.push(PathCommand::LineTo(x, y));
+    }
+
+    pub fn quad_to(&mut self, cx: f32, cy: f32, x: f32, y: f32) {
+        self.commands.push(PathCommand::QuadTo(cx, cy, x, y));
+    }
+
+    pub fn cubic_to(&mut self, c1x: f32, c1y: f32, c2x: f32, c2y: f32, x: f32, y: f32) {
+        self.commands.push(PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y));
+    }
+
+    pub fn close(&mut self) {
+        self.commands.push(PathCommand::Close);
+    }
+
+    /**
+     * Appends `other` transformed by the matrix `[xx, yx, xy, yy, dx, dy]`,
+     * mapping `(x, y)` to `(xx * x + xy * y + dx, yx * x + yy * y + dy)`.
+     **/
+    pub fn append(&mut self, other: &Outline, m: [f32; 6]) {
+        let t = |x: f32, y: f32| (m[0] * x + m[2] * y + m[4], m[1] * x + m[3] * y + m[5]);
+        for command in other.commands.iter() {
+            self.commands.push(match *command {
+                PathCommand::MoveTo(x, y) => {
+                    let (x, y) = t(x, y);
+                    PathCommand::MoveTo(x, y)
+                }
+                PathCommand::LineTo(x, y) => {
+                    let (x, y) = t(x, y);
+                    PathCommand::LineTo(x, y)
+                }
+                PathCommand::QuadTo(cx, cy, x, y) => {
+                    let (cx, cy) = t(cx, cy);
+                    let (x, y) = t(x, y);
+                    PathCommand::QuadTo(cx, cy, x, y)
+                }
+                PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y) => {
+                    let (c1x, c1y) = t(c1x, c1y);
+                    let (c2x, c2y) = t(c2x, c2y);
+                    let (x, y) = t(x, y);
+                    PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y)
+                }
+                PathCommand::Close => PathCommand::Close
+            });
+        }
+    }
+
+    /**
+     * Approximates the outline scaled by `(sx, sy)` with closed polygons of
+     * line segments, flipping y so it points down.
+     **/
+    fn flatten(&self, sx: f32, sy: f32) -> Vec<((f32, f32), (f32, f32))> {
+        let mut lines = Vec::new();
+        let p = |x: f32, y: f32| (x * sx, -y * sy);
+        let mut start = (0.0, 0.0);
+        let mut current = (0.0, 0.0);
+
+        for command in self.commands.iter() {
+            match *command {
+                PathCommand::MoveTo(x, y) => {
+                    if current != start {
+                        lines.push((current, start));
+                    }
+                    start = p(x, y);
+                    current = start;
+                }
+                PathCommand::LineTo(x, y) => {
+                    let to = p(x, y);
+                    lines.push((current, to));
+                    current = to;
+                }
+                PathCommand::QuadTo(cx, cy, x, y) => {
+                    let (c, to) = (p(cx, cy), p(x, y));
+                    let dd = distance(current.0 - 2.0 * c.0 + to.0, current.1 - 2.0 * c.1 + to.1);
+                    let n = ((dd / (4.0 * TOLERANCE)).sqrt().ceil() as usize).max(1);
+                    let mut from = current;
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        let point = (
+                            mt * mt * current.0 + 2.0 * mt * t * c.0 + t * t * to.0,
+                            mt * mt * current.1 + 2.0 * mt * t * c.1 + t * t * to.1
+                        );
+                        lines.push((from, point));
+                        from = point;
+                    }
+                    current = to;
+                }
+                PathCommand::CubicTo(c1x, c1y, c2x, c2y, x, y) => {
+                    let (c1, c2, to) = (p(c1x, c1y), p(c2x, c2y), p(x, y));
+                    let dd = distance(current.0 - 2.0 * c1.0 + c2.0, current.1 - 2.0 * c1.1 + c2.1)
+                        .max(distance(c1.0 - 2.0 * c2.0 + to.0, c1.1 - 2.0 * c2.1 + to.1));
+                    let n = ((0.75 * dd / TOLERANCE).sqrt().ceil() as usize).max(1);
+                    let mut from = current;
+                    for i in 1..=n {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+                        let point = (
+                            a * current.0 + b * c1.0 + c * c2.0 + d * to.0,
+                            a * current.1 + b * c1.1 + c * c2.1 + d * to.1
+                        );
+                        lines.push((from, point));
+                        from = point;
+                    }
+                    current = to;
+                }
+                PathCommand::Close => {
+                    if current != start {
+                        lines.push((current, start));
+                    }
+                    current = start;
+                }
+            }
+        }
+        if current != start {
+            lines.push((current, start));
+        }
+        lines
+    }
+
+    /**
+     * Rasterizes the outline scaled by `scale` pixels per font unit. Returns
+     * `None` for outlines that cover no pixels, like the one of a space.
+     **/
+    pub fn rasterize(&self, scale: f32, format: MaskFormat) -> Option<Coverage> {
+        let subpixels = format.bytes_per_pixel() as f32;
+        let mut lines = self.flatten(scale * subpixels, scale);
+        if lines.is_empty() {
+            return None;
+        }
+
+        let (mut x0, mut y0) = (f32::MAX, f32::MAX);
+        let (mut x1, mut y1) = (f32::MIN, f32::MIN);
+        for &(a, b) in lines.iter() {
+            x0 = x0.min(a.0).min(b.0);
+            x1 = x1.max(a.0).max(b.0);
+            y0 = y0.min(a.1).min(b.1);
+            y1 = y1.max(a.1).max(b.1);
+        }
+
+        // the subpixel filter spreads coverage into the neighbouring pixels
+        let padding = if format == MaskFormat::Subpixel { 1 } else { 0 };
+        let left = (x0 / subpixels).floor() as i32 - padding;
+        let right = (x1 / subpixels).ceil() as i32 + padding;
+        let top = y0.floor() as i32;
+        let bottom = y1.ceil() as i32;
+        if right <= left || bottom <= top {
+            return None;
+        }
+
+        let width = (right - left) as usize;
+        let height = (bottom - top) as usize;
+        let (dx, dy) = (left as f32 * subpixels, top as f32);
+        for line in lines.iter_mut() {
+            line.0 = (line.0 .0 - dx, line.0 .1 - dy);
+            line.1 = (line.1 .0 - dx, line.1 .1 - dy);
+        }
+
+        let mut raster = Accumulator::new(width * subpixels as usize, height);
+        for &(a, b) in lines.iter() {
+            raster.line(a, b);
+        }
+        let coverage = raster.coverage();
+
+        let data = match format {
+            MaskFormat::Alpha => coverage,
+            MaskFormat::Subpixel => subpixel_filter(&coverage, width * 3)
+        };
+        Some(Coverage {
+            width: width as u32,
+            height: height as u32,
+            left,
+            top,
+            format,
+            data
+        })
+    }
+}
+
+fn distance(dx: f32, dy: f32) -> f32 {
+    (dx * dx + dy * dy).sqrt()
+}
+
+/**
+ * Spreads subpixel coverage with a [1, 2, 3, 2, 1] / 9 filter so colour
+ * fringes stay faint, keeping rows `row_len` subpixels wide.
+ **/
+fn subpixel_filter(coverage: &[u8], row_len: usize) -> Vec<u8> {
+    const WEIGHTS: [u32; 5] = [1, 2, 3, 2, 1];
+    let mut filtered = vec![0; coverage.len()];
+    for (src, dst) in coverage.chunks(row_len).zip(filtered.chunks_mut(row_len)) {
+        for (i, dst) in dst.iter_mut().enumerate() {
+            let mut sum = 0;
+            for (k, weight) in WEIGHTS.iter().enumerate() {
+                let j = i as isize + k as isize - 2;
+                if j >= 0 && (j as usize) < row_len {
+                    sum += weight * src[j as usize] as u32;
+                }
+            }
+            *dst = ((sum + 4) / 9) as u8;
+        }
+    }
+    filtered
+}
+
+/**
+ * Accumulates signed area for each pixel crossed by the outline edges; a
+ * running sum over each row then yields the coverage.
+ **/
+struct Accumulator {
+    width: usize,
+    height: usize,
+    area: Vec<f32>
+}
+
+impl Accumulator {
+    fn new(width: usize, height: usize) -> Accumulator {
+        Accumulator {
+            width,
+            height,
+            area: vec![0.0; width * height + 4]
+        }
+    }
+
+    fn line(&mut self, p0: (f32, f32), p1: (f32, f32)) {
+        if p0.1 == p1.1 {
+            return;
+        }
+        let (dir, p0, p1) = if p0.1 < p1.1 { (1.0, p0, p1) } else { (-1.0, p1, p0) };
+        let dxdy = (p1.0 - p0.0) / (p1.1 - p0.1);
+        let mut x = p0.0;
+        if p0.1 < 0.0 {
+            x -= p0.1 * dxdy;
+        }
+
+        let y_start = p0.1.max(0.0) as usize;
+        let y_end = self.height.min(p1.1.ceil() as usize);
+        for y in y_start..y_end {
+            let row = y * self.width;
+            let dy = ((y + 1) as f32).min(p1.1) - (y as f32).max(p0.1);
+            let x_next = x + dxdy * dy;
+            let d = dy * dir;
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let x0_floor = x0.floor();
+            let x0i = x0_floor.max(0.0) as usize;
+            let x1_ceil = x1.ceil();
+            let x1i = x1_ceil.max(0.0) as usize;
+
+            if x1i <= x0i + 1 {
+                // the edge stays within one pixel column on this row
+                let xm = 0.5 * (x + x_next) - x0_floor;
+                self.area[row + x0i] += d - d * xm;
+                self.area[row + x0i + 1] += d * xm;
+            } else {
+                let s = (x1 - x0).recip();
+                let x0f = x0 - x0_floor;
+                let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+                let x1f = x1 - x1_ceil + 1.0;
+                let am = 0.5 * s * x1f * x1f;
+                self.area[row + x0i] += d * a0;
+                if x1i == x0i + 2 {
+                    self.area[row + x0i + 1] += d * (1.0 - a0 - am);
+                } else {
+                    let a1 = s * (1.5 - x0f);
+                    self.area[row + x0i + 1] += d * (a1 - a0);
+                    for xi in x0i + 2..x1i - 1 {
+                        self.area[row + xi] += d * s;
+                    }
+                    let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                    self.area[row + x1i - 1] += d * (1.0 - a2 - am);
+                }
+                self.area[row + x1i] += d * am;
+            }
+            x = x_next;
+        }
+    }
+
+    fn coverage(&self) -> Vec<u8> {
+        let mut sum = 0.0;
+        self.area[..self.width * self.height].iter().map(|a| {
+            sum += a;
+            (sum.abs().min(1.0) * 255.0 + 0.5) as u8
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(outline: &mut Outline, x0: f32, y0: f32, x1: f32, y1: f32) {
+        outline.move_to(x0, y0);
+        outline.line_to(x1, y0);
+        outline.line_to(x1, y1);
+        outline.line_to(x0, y1);
+        outline.close();
+    }
+
+    #[test]
+    fn rasterizes_squares() {
+        let mut outline = Outline::new();
+        square(&mut outline, 0.0, 0.0, 2.0, 2.0);
+        let coverage = outline.rasterize(1.0, MaskFormat::Alpha).unwrap();
+        assert_eq!((coverage.width, coverage.height, coverage.left, coverage.top), (2, 2, 0, -2));
+        assert_eq!(coverage.data, vec![255; 4]);
+
+        // half a pixel off the grid gives half coverage along the edges
+        let mut outline = Outline::new();
+        square(&mut outline, 0.5, 0.0, 2.5, 1.0);
+        let coverage = outline.rasterize(1.0, MaskFormat::Alpha).unwrap();
+        assert_eq!(coverage.data, vec![128, 255, 128]);
+    }
+
+    #[test]
+    fn winding_direction_does_not_matter() {
+        let mut ccw = Outline::new();
+        square(&mut ccw, 0.0, 0.0, 3.0, 3.0);
+        let mut cw = Outline::new();
+        cw.move_to(0.0, 0.0);
+        cw.line_to(0.0, 3.0);
+        cw.line_to(3.0, 3.0);
+        cw.line_to(3.0, 0.0);
+        cw.close();
+        assert_eq!(ccw.rasterize(1.0, MaskFormat::Alpha), cw.rasterize(1.0, MaskFormat::Alpha));
+    }
+
+    #[test]
+    fn curves_stay_inside_their_bounds() {
+        let mut outline = Outline::new();
+        outline.move_to(0.0, 0.0);
+        outline.quad_to(5.0, 10.0, 10.0, 0.0);
+        outline.close();
+        let coverage = outline.rasterize(1.0, MaskFormat::Alpha).unwrap();
+        assert_eq!((coverage.width, coverage.height), (10, 5));
+        // the apex is at the middle of the top row
+        assert!(coverage.data[4] > 100 && coverage.data[0] < 10);
+        assert_eq!(coverage.data[4 * 10 + 5], 255);
+    }
+
+    #[test]
+    fn subpixel_coverage() {
+        let mut outline = Outline::new();
+        square(&mut outline, 0.0, 0.0, 1.0, 1.0);
+        let coverage = outline.rasterize(1.0, MaskFormat::Subpixel).unwrap();
+        // one pixel plus padding on both sides, three channels each
+        assert_eq!((coverage.width, coverage.left), (3, -1));
+        let pixel = &coverage.data[3..6];
+        assert!(pixel.iter().all(|&c| c > 150));
+        assert!(coverage.data[2] > 0 && coverage.data[0] == 0);
+    }
+
+    #[test]
+    fn append_transforms() {
+        let mut a = Outline::new();
+        a.move_to(1.0, 2.0);
+        let mut b = Outline::new();
+        b.append(&a, [2.0, 0.0, 0.0, 1.0, 10.0, 0.0]);
+        assert_eq!(b.commands(), &[PathCommand::MoveTo(12.0, 2.0)]);
+    }
+}
@@ -0,0 +1,285 @@
+extern crate memmap;
+
+use std::fs::File;
+use std::io;
+
+use super::atomic::{enum_value, find_planes, find_property, plane_formats, AtomicRequest};
+use super::drm::{close_handle, create_dumb, drm_ioctl, Rect};
+use super::drm_const::*;
+use super::ffi::*;
+use super::transform::OutputTransform;
+use super::yuv::{Matrix, Range, YuvFormat, YuvImage};
+
+use self::memmap::{MmapMut, MmapOptions};
+
+/**
+ * Plane properties set when showing a frame.
+ **/
+const PROPERTIES: [&str; 10] = [
+    "FB_ID", "CRTC_ID", "SRC_X", "SRC_Y", "SRC_W", "SRC_H", "CRTC_X", "CRTC_Y", "CRTC_W", "CRTC_H"
+];
+
+/**
+ * A dumb buffer holding a YUV frame, with all planes in one allocation.
+ **/
+#[derive(Debug)]
+struct YuvBuffer {
+    fd: File,
+    fb_id: u32,
+    handle: u32,
+    width: u32,
+    height: u32,
+    format: YuvFormat,
+    pitches: [u32; 3],
+    offsets: [u32; 3],
+    map: MmapMut
+}
+
+impl YuvBuffer {
+    fn new(fd: &File, width: u32, height: u32, format: YuvFormat) -> io::Result<YuvBuffer> {
+        let chroma_height = height.div_ceil(2);
+        let dumb = match format {
+            YuvFormat::Yuyv => create_dumb(fd, width.div_ceil(2) * 2, height, 16)?,
+            // the chroma rows go below the luma rows
+            _ => create_dumb(fd, width.div_ceil(2) * 2, height + chroma_height, 8)?
+        };
+        let pitch = dumb.pitch;
+        let (pitches, offsets) = match format {
+            YuvFormat::Nv12 => ([pitch, pitch, 0], [0, pitch * height, 0]),
+            YuvFormat::Yuv420 => {
+                let v = pitch * height + pitch / 2 * chroma_height;
+                ([pitch, pitch / 2, pitch / 2], [0, pitch * height, v])
+            }
+            YuvFormat::Yuyv => ([pitch, 0, 0], [0, 0, 0])
+        };
+
+        let mut fb_cmd = drm_mode_fb_cmd2 { width, height, pixel_format: format.fourcc(), ..Default::default() };
+        for i in 0..format.planes() {
+            fb_cmd.handles[i] = dumb.handle;
+            fb_cmd.pitches[i] = pitches[i];
+            fb_cmd.offsets[i] = offsets[i];
+        }
+        if let Err(e) = drm_ioctl(fd, DRM_IOCTL_MODE_ADDFB2, &mut fb_cmd) {
+            close_handle(fd, dumb.handle);
+            return Err(e);
+        }
+        // from here on, dropping the buffer releases it
+        let fb_id = fb_cmd.fb_id;
+
+        let mut map_config = drm_mode_map_dumb { handle: dumb.handle, ..Default::default() };
+        let map = drm_ioctl(fd, DRM_IOCTL_MODE_MAP_DUMB, &mut map_config).and_then(|_| unsafe {
+            MmapOptions::new()
+                .offset(map_config.offset as usize)
+                .len(dumb.size as usize)
+                .map_mut(fd)
+        });
+        let map = match map {
+            Ok(map) => map,
+            Err(e) => {
+                let mut fb_id = fb_id;
+                let _ = drm_ioctl(fd, DRM_IOCTL_MODE_RMFB, &mut fb_id);
+                close_handle(fd, dumb.handle);
+                return Err(e);
+            }
+        };
+
+        Ok(YuvBuffer {
+            fd: fd.try_clone()?,
+            fb_id,
+            handle: dumb.handle,
+            width,
+            height,
+            format,
+            pitches,
+            offsets,
+            map
+        })
+    }
+
+    fn upload(&mut self, frame: &YuvImage) {
+        for plane in 0..self.format.planes() {
+            let (row_len, rows) = self.format.plane_size(plane, self.width, self.height);
+            let (pitch, offset) = (self.pitches[plane] as usize, self.offsets[plane] as usize);
+            let (stride, data) = (frame.stride(plane) as usize, frame.plane(plane));
+            for y in 0..rows as usize {
+                let dst = offset + y * pitch;
+                self.map[dst..dst + row_len as usize].copy_from_slice(&data[y * stride..y * stride + row_len as usize]);
+            }
+        }
+    }
+}
+
+impl Drop for YuvBuffer {
+    fn drop(&mut self) {
+        let _ = drm_ioctl(&self.fd, DRM_IOCTL_MODE_RMFB, &mut self.fb_id);
+        close_handle(&self.fd, self.handle);
+    }
+}
+
+/**
+ * An overlay plane that scans out YUV frames as they are, so that video
+ * needs neither a conversion to RGB nor a copy into the framebuffer. The
+ * display hardware scales the frame and shows it above everything else on
+ * the crtc.
+ **/
+#[derive(Debug)]
+pub struct Overlay {
+    fd: File,
+    plane_id: u32,
+    crtc_id: u32,
+    format: YuvFormat,
+    props: Vec<u32>,
+    /// COLOR_ENCODING and its values for BT.601 and BT.709.
+    color_encoding: Option<(u32, [Option<u64>; 2])>,
+    /// COLOR_RANGE and its values for limited and full range.
+    color_range: Option<(u32, [Option<u64>; 2])>,
+    rotation: Option<u64>,
+    /// Frames alternate between two buffers so that the one on screen is
+    /// never written to.
+    buffers: Vec<YuvBuffer>,
+    visible: bool
+}
+
+impl Overlay {
+    /**
+     * Finds an overlay plane for the crtc that can scan out `format`,
+     * turned by `transform` like the primary plane.
+     **/
+    pub fn find(fd: &File, crtc_index: usize, crtc_id: u32, format: YuvFormat, transform: OutputTransform) -> io::Result<Option<Overlay>> {
+        for plane_id in find_planes(fd, crtc_index, DRM_PLANE_TYPE_OVERLAY)? {
+            if !plane_formats(fd, plane_id)?.contains(&format.fourcc()) {
+                continue;
+            }
+            let prop = |name: &str| -> io::Result<Option<u32>> {
+                Ok(find_property(fd, plane_id, DRM_MODE_OBJECT_PLANE, name)?.map(|(id, _)| id))
+            };
+            let mut props = Vec::new();
+            for name in PROPERTIES.iter() {
+                match prop(name)? {
+                    Some(id) => props.push(id),
+                    None => break
+                }
+            }
+            if props.len() < PROPERTIES.len() {
+                continue;
+            }
+            // planes without the property can only show frames as they are
+            let rotation = match prop("rotation")? {
+                Some(id) => {
+                    props.push(id);
+                    Some(transform.drm_rotation())
+                }
+                None if transform == OutputTransform::Normal => None,
+                None => continue
+            };
+            let values = |prop: Option<u32>, names: [&str; 2]| -> io::Result<Option<(u32, [Option<u64>; 2])>> {
+                match prop {
+                    Some(prop) => Ok(Some((prop, [enum_value(fd, prop, names[0])?, enum_value(fd, prop, names[1])?]))),
+                    None => Ok(None)
+                }
+            };
+            return Ok(Some(Overlay {
+                fd: fd.try_clone()?,
+                plane_id,
+                crtc_id,
+                format,
+                props,
+                color_encoding: values(prop("COLOR_ENCODING")?, ["ITU-R BT.601 YCbCr", "ITU-R BT.709 YCbCr"])?,
+                color_range: values(prop("COLOR_RANGE")?, ["YCbCr limited range", "YCbCr full range"])?,
+                rotation,
+                buffers: Vec::new(),
+                visible: false
+            }));
+        }
+        Ok(None)
+    }
+
+    pub fn format(&self) -> YuvFormat {
+        self.format
+    }
+
+    /**
+     * Shows `frame` scaled to `dst`, in the crtc's coordinates. Fails if the frame's format isn't the
+     * overlay's or the hardware can't show it like this, e.g. because it
+     * can't scale that much.
+     **/
+    pub fn show(&mut self, frame: &YuvImage, dst: Rect) -> io::Result<()> {
+        if frame.format() != self.format {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame format differs from overlay format"));
+        }
+        let (width, height) = (frame.width(), frame.height());
+        if self.buffers.first().is_none_or(|b| (b.width, b.height) != (width, height)) {
+            self.hide()?;
+            self.buffers.clear();
+            for _ in 0..2 {
+                let buffer = YuvBuffer::new(&self.fd, width, height, self.format)?;
+                self.buffers.push(buffer);
+            }
+        }
+        // the first buffer is always the one to draw into next
+        self.buffers[0].upload(frame);
+
+        let values = [
+            self.buffers[0].fb_id as u64,
+            self.crtc_id as u64,
+            0,
+            0,
+            (width as u64) << 16,
+            (height as u64) << 16,
+            dst.x as i64 as u64,
+            dst.y as i64 as u64,
+            dst.width as u64,
+            dst.height as u64
+        ];
+        let mut request = AtomicRequest::new();
+        for (&prop, &value) in self.props.iter().zip(values.iter().chain(self.rotation.iter())) {
+            request.add(self.plane_id, prop, value);
+        }
+        if let Some((prop, values)) = self.color_encoding {
+            let value = match frame.matrix() {
+                Matrix::Bt601 => values[0],
+                Matrix::Bt709 => values[1]
+            };
+            if let Some(value) = value {
+                request.add(self.plane_id, prop, value);
+            }
+        }
+        if let Some((prop, values)) = self.color_range {
+            let value = match frame.range() {
+                Range::Limited => values[0],
+                Range::Full => values[1]
+            };
+            if let Some(value) = value {
+                request.add(self.plane_id, prop, value);
+            }
+        }
+        request.commit(&self.fd, DRM_MODE_ATOMIC_TEST_ONLY)?;
+        request.commit(&self.fd, 0)?;
+
+        self.buffers.swap(0, 1);
+        self.visible = true;
+        Ok(())
+    }
+
+    /**
+     * Takes the overlay off the screen.
+     **/
+    pub fn hide(&mut self) -> io::Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+        let mut request = AtomicRequest::new();
+        request.add(self.plane_id, self.props[0], 0);
+        request.add(self.plane_id, self.props[1], 0);
+        request.commit(&self.fd, 0)?;
+        self.visible = false;
+        Ok(())
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        // the buffers can't be released while they are scanned out
+        let _ = self.hide();
+    }
+}
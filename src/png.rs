@@ -0,0 +1,494 @@
+use std::io;
+
+use super::deflate;
+use super::drm::Color;
+use super::format::Format;
+use super::image::Image;
+use super::inflate;
+
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+const COLOR_GRAY: u8 = 0;
+const COLOR_RGB: u8 = 2;
+const COLOR_PALETTE: u8 = 3;
+const COLOR_GRAY_ALPHA: u8 = 4;
+const COLOR_RGB_ALPHA: u8 = 6;
+
+/**
+ * Origin and spacing of the pixels in each of the seven Adam7 passes.
+ **/
+const ADAM7: [(u32, u32, u32, u32); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2)
+];
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> io::Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| (b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32)
+        .ok_or_else(|| invalid("truncated PNG"))
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            c = if c & 1 != 0 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+            bit += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+/**
+ * Computes the CRC-32 that protects every PNG chunk.
+ **/
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |c, &byte| CRC_TABLE[((c ^ byte as u32) & 0xff) as usize] ^ (c >> 8))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    width: u32,
+    height: u32,
+    depth: u8,
+    color_type: u8,
+    interlaced: bool
+}
+
+impl Header {
+    fn channels(&self) -> u32 {
+        match self.color_type {
+            COLOR_GRAY | COLOR_PALETTE => 1,
+            COLOR_GRAY_ALPHA => 2,
+            COLOR_RGB => 3,
+            _ => 4
+        }
+    }
+
+    fn bits_per_pixel(&self) -> u32 {
+        self.channels() * self.depth as u32
+    }
+
+    /**
+     * Bytes of a row of `width` pixels, without the filter byte.
+     **/
+    fn row_len(&self, width: u32) -> usize {
+        (width as usize * self.bits_per_pixel() as usize).div_ceil(8)
+    }
+
+    /**
+     * Offset and step of the interlacing passes.
+     **/
+    fn passes(&self) -> &'static [(u32, u32, u32, u32)] {
+        if self.interlaced {
+            &ADAM7
+        } else {
+            &[(0, 0, 1, 1)]
+        }
+    }
+
+    /**
+     * Width and height of an interlacing pass, `None` if it holds no pixels.
+     **/
+    fn pass_size(&self, (x0, y0, dx, dy): (u32, u32, u32, u32)) -> Option<(u32, u32)> {
+        if x0 >= self.width || y0 >= self.height {
+            return None;
+        }
+        Some(((self.width - x0).div_ceil(dx), (self.height - y0).div_ceil(dy)))
+    }
+
+    /**
+     * Size of the decompressed image data, including the filter bytes.
+     **/
+    fn data_len(&self) -> usize {
+        self.passes()
+            .iter()
+            .filter_map(|&pass| self.pass_size(pass))
+            .fold(0usize, |len, (width, height)| {
+                len.saturating_add((self.row_len(width) + 1).saturating_mul(height as usize))
+            })
+    }
+}
+
+/**
+ * Decodes a PNG file of any color type, bit depth and interlacing.
+ **/
+pub fn decode(data: &[u8]) -> io::Result<Image> {
+    if !data.starts_with(&SIGNATURE) {
+        return Err(invalid("not a PNG file"));
+    }
+
+    let mut header = None;
+    let mut palette: Vec<Color> = Vec::new();
+    let mut transparency = None;
+    let mut compressed = Vec::new();
+    let mut pos = SIGNATURE.len();
+    loop {
+        let len = read_u32(data, pos)? as usize;
+        let kind = data.get(pos + 4..pos + 8).ok_or_else(|| invalid("truncated PNG"))?;
+        let body = data.get(pos + 8..pos + 8 + len).ok_or_else(|| invalid("truncated PNG chunk"))?;
+        if crc32(&data[pos + 4..pos + 8 + len]) != read_u32(data, pos + 8 + len)? {
+            return Err(invalid("PNG chunk checksum mismatch"));
+        }
+        pos += 12 + len;
+
+        match kind {
+            b"IHDR" => {
+                if body.len() < 13 {
+                    return Err(invalid("truncated PNG header"));
+                }
+                let h = Header {
+                    width: read_u32(body, 0)?,
+                    height: read_u32(body, 4)?,
+                    depth: body[8],
+                    color_type: body[9],
+                    interlaced: body[12] == 1
+                };
+                let valid_depth = match h.color_type {
+                    COLOR_GRAY => [1, 2, 4, 8, 16].contains(&h.depth),
+                    COLOR_PALETTE => [1, 2, 4, 8].contains(&h.depth),
+                    COLOR_RGB | COLOR_GRAY_ALPHA | COLOR_RGB_ALPHA => h.depth == 8 || h.depth == 16,
+                    _ => false
+                };
+                if !valid_depth || body[10] != 0 || body[11] != 0 || body[12] > 1 {
+                    return Err(invalid("unsupported PNG format"));
+                }
+                if h.width == 0 || h.height == 0 || h.width > 1 << 16 || h.height > 1 << 16 {
+                    return Err(invalid("invalid PNG dimensions"));
+                }
+                header = Some(h);
+            }
+            b"PLTE" => {
+                palette = body.chunks(3).filter(|c| c.len() == 3).map(|c| Color::new(c[0], c[1], c[2], 255)).collect();
+            }
+            b"tRNS" => transparency = Some(body.to_vec()),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {
+                // ancillary chunks have a lowercase first letter
+                if kind[0] & 0x20 == 0 {
+                    return Err(invalid("unknown critical PNG chunk"));
+                }
+            }
+        }
+    }
+
+    let header = header.ok_or_else(|| invalid("missing PNG header"))?;
+    if header.color_type == COLOR_PALETTE && palette.is_empty() {
+        return Err(invalid("missing PNG palette"));
+    }
+    if let (Some(alpha), COLOR_PALETTE) = (transparency.as_ref(), header.color_type) {
+        for (c, &a) in palette.iter_mut().zip(alpha.iter()) {
+            c.a = a;
+        }
+    }
+
+    let raw = inflate::zlib_decompress(&compressed, header.data_len())?;
+    let has_alpha = header.color_type == COLOR_GRAY_ALPHA || header.color_type == COLOR_RGB_ALPHA || transparency.is_some();
+    let format = if has_alpha { Format::Argb8888 } else { Format::Xrgb8888 };
+    let mut image = Image::new(header.width, header.height, format).map_err(|_| invalid("PNG image is too large"))?;
+
+    let mut offset = 0;
+    for &(x0, y0, dx, dy) in header.passes().iter() {
+        let (width, height) = match header.pass_size((x0, y0, dx, dy)) {
+            Some(size) => size,
+            None => continue
+        };
+        let row_len = header.row_len(width);
+        let size = (row_len + 1) * height as usize;
+        let pass = raw.get(offset..offset + size).ok_or_else(|| invalid("truncated PNG image data"))?;
+        let rows = unfilter(pass, row_len, header.bits_per_pixel().div_ceil(8) as usize)?;
+        offset += size;
+
+        for (y, row) in rows.chunks(row_len).enumerate() {
+            for x in 0..width {
+                let c = pixel(&header, row, x, &palette, transparency.as_deref());
+                image.set(x0 + x * dx, y0 + y as u32 * dy, &c.premultiply());
+            }
+        }
+    }
+    Ok(image)
+}
+
+/**
+ * Reverses the per-row filters, returning the rows without filter bytes.
+ **/
+fn unfilter(data: &[u8], row_len: usize, bpp: usize) -> io::Result<Vec<u8>> {
+    let rows = data.len() / (row_len + 1);
+    let mut out = vec![0u8; rows * row_len];
+    for y in 0..rows {
+        let filter = data[y * (row_len + 1)];
+        let src = &data[y * (row_len + 1) + 1..(y + 1) * (row_len + 1)];
+        let (done, rest) = out.split_at_mut(y * row_len);
+        let previous = if y > 0 { &done[(y - 1) * row_len..] } else { &[][..] };
+        let row = &mut rest[..row_len];
+
+        for i in 0..row_len {
+            let a = if i >= bpp { row[i - bpp] as i16 } else { 0 };
+            let b = previous.get(i).cloned().unwrap_or(0) as i16;
+            let c = if i >= bpp { previous.get(i - bpp).cloned().unwrap_or(0) as i16 } else { 0 };
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => (a + b) / 2,
+                4 => paeth(a, b, c),
+                _ => return Err(invalid("invalid PNG filter"))
+            };
+            row[i] = src[i].wrapping_add(predictor as u8);
+        }
+    }
+    Ok(out)
+}
+
+/**
+ * Reads sample `i` of `depth` bits from a row and scales it to 8 bits.
+ * 16 bit samples are returned unscaled for transparency key comparisons.
+ **/
+fn sample(row: &[u8], i: u32, depth: u8) -> u16 {
+    match depth {
+        16 => (row[i as usize * 2] as u16) << 8 | row[i as usize * 2 + 1] as u16,
+        8 => row[i as usize] as u16,
+        _ => {
+            let bit = i as usize * depth as usize;
+            let shift = 8 - depth as usize - bit % 8;
+            (row[bit / 8] >> shift) as u16 & ((1 << depth) - 1)
+        }
+    }
+}
+
+fn pixel(header: &Header, row: &[u8], x: u32, palette: &[Color], transparency: Option<&[u8]>) -> Color {
+    let depth = header.depth;
+    let channels = header.channels();
+    let to_8bit = |v: u16| match depth {
+        16 => (v >> 8) as u8,
+        8 => v as u8,
+        _ => (v as u32 * 255 / ((1 << depth) - 1)) as u8
+    };
+    // transparency keys for gray and RGB images are 16 bit values
+    let key = |i: usize| transparency.and_then(|t| t.get(i * 2..i * 2 + 2)).map(|k| (k[0] as u16) << 8 | k[1] as u16);
+
+    match header.color_type {
+        COLOR_PALETTE => {
+            let index = sample(row, x, depth) as usize;
+            palette.get(index).cloned().unwrap_or(Color::new(0, 0, 0, 255))
+        }
+        COLOR_GRAY => {
+            let v = sample(row, x, depth);
+            let g = to_8bit(v);
+            Color::new(g, g, g, if key(0) == Some(v) { 0 } else { 255 })
+        }
+        COLOR_GRAY_ALPHA => {
+            let g = to_8bit(sample(row, x * 2, depth));
+            Color::new(g, g, g, to_8bit(sample(row, x * 2 + 1, depth)))
+        }
+        _ => {
+            let v: Vec<u16> = (0..channels).map(|c| sample(row, x * channels + c, depth)).collect();
+            let a = if channels == 4 {
+                to_8bit(v[3])
+            } else if key(0) == Some(v[0]) && key(1) == Some(v[1]) && key(2) == Some(v[2]) {
+                0
+            } else {
+                255
+            };
+            Color::new(to_8bit(v[0]), to_8bit(v[1]), to_8bit(v[2]), a)
+        }
+    }
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+/**
+ * Encodes an image as an 8 bit RGB PNG, or RGBA if its format has alpha.
+ **/
+pub fn encode(image: &Image) -> Vec<u8> {
+    let has_alpha = image.format().has_alpha();
+    let channels = if has_alpha { 4 } else { 3 };
+    let row_len = image.width() as usize * channels;
+
+    let mut rows = vec![0u8; row_len * image.height() as usize];
+    for (y, row) in rows.chunks_mut(row_len).enumerate() {
+        for (x, pixel) in row.chunks_mut(channels).enumerate() {
+            let c = image.get(x as u32, y as u32).unpremultiply();
+            pixel[..3].copy_from_slice(&[c.r, c.g, c.b]);
+            if has_alpha {
+                pixel[3] = c.a;
+            }
+        }
+    }
+
+    let mut filtered = Vec::with_capacity((row_len + 1) * image.height() as usize);
+    let mut candidate = vec![0u8; row_len];
+    for y in 0..image.height() as usize {
+        let row = &rows[y * row_len..(y + 1) * row_len];
+        let previous = if y > 0 { &rows[(y - 1) * row_len..y * row_len] } else { &[][..] };
+        // pick the filter whose output has the smallest sum of magnitudes,
+        // which usually compresses best
+        let mut best = (u64::MAX, 0u8, Vec::new());
+        for filter in 0..5u8 {
+            filter_row(filter, row, previous, channels, &mut candidate);
+            let cost = candidate.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum();
+            if cost < best.0 {
+                best = (cost, filter, candidate.clone());
+            }
+        }
+        filtered.push(best.1);
+        filtered.extend_from_slice(&best.2);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&image.width().to_be_bytes());
+    header.extend_from_slice(&image.height().to_be_bytes());
+    header.extend_from_slice(&[8, if has_alpha { COLOR_RGB_ALPHA } else { COLOR_RGB }, 0, 0, 0]);
+
+    let mut out = SIGNATURE.to_vec();
+    chunk(&mut out, b"IHDR", &header);
+    chunk(&mut out, b"IDAT", &deflate::zlib_compress(&filtered));
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn filter_row(filter: u8, row: &[u8], previous: &[u8], bpp: usize, out: &mut [u8]) {
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] as i16 } else { 0 };
+        let b = previous.get(i).cloned().unwrap_or(0) as i16;
+        let c = if i >= bpp { previous.get(i - bpp).cloned().unwrap_or(0) as i16 } else { 0 };
+        let predictor = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => (a + b) / 2,
+            _ => paeth(a, b, c)
+        };
+        out[i] = row[i].wrapping_sub(predictor as u8);
+    }
+}
+
+fn paeth(a: i16, b: i16, c: i16) -> i16 {
+    let p = a + b - c;
+    let (pa, pb, pc) = ((p - a).abs(), (p - b).abs(), (p - c).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+    }
+
+    #[test]
+    fn decodes_rgba() {
+        // 2x2 RGBA: opaque red, half transparent green / opaque blue, clear;
+        // the second row uses the sub filter
+        let png = hex(concat!(
+            "89504e470d0a1a0a0000000d494844520000000200000002080600000072b60d24",
+            "0000001649444154789c63f8cfc0f01f081b18813490cdc808003ae8057fdb8e2c20",
+            "0000000049454e44ae426082"
+        ));
+        let image = decode(&png).unwrap();
+        assert_eq!((image.width(), image.height(), image.format()), (2, 2, Format::Argb8888));
+        assert_eq!(image.get(0, 0), Color::new(255, 0, 0, 255));
+        assert_eq!(image.get(1, 0), Color::new(0, 128, 0, 128));
+        assert_eq!(image.get(0, 1), Color::new(0, 0, 255, 255));
+        assert_eq!(image.get(1, 1), Color::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn decodes_interlaced_palette() {
+        // 3x3 interlaced, 2 bit palette of black, white and red, with
+        // index (x + y) % 3
+        let png = hex(concat!(
+            "89504e470d0a1a0a0000000d49484452000000030000000302030000015c416dba",
+            "00000009504c5445000000ffffffff0000cd5eb79c0000001449444154789c6360",
+            "60686098c0e0c0c0c09000000a1c01b16d2c65910000000049454e44ae426082"
+        ));
+        let image = decode(&png).unwrap();
+        assert_eq!(image.format(), Format::Xrgb8888);
+        assert_eq!(image.get(0, 0), Color::new(0, 0, 0, 255));
+        assert_eq!(image.get(1, 0), Color::new(255, 255, 255, 255));
+        assert_eq!(image.get(1, 1), Color::new(255, 0, 0, 255));
+        assert_eq!(image.get(2, 2), Color::new(255, 255, 255, 255));
+        assert_eq!(image.get(0, 2), Color::new(255, 0, 0, 255));
+        assert_eq!(image.get(2, 1), Color::new(0, 0, 0, 255));
+    }
+
+    #[test]
+    fn decodes_16_bit_gray_with_color_key() {
+        let png = hex(concat!(
+            "89504e470d0a1a0a0000000d49484452000000030000000110000000006e1b972b",
+            "0000000274524e53abcddeb516170000000f49444154789c631032597df6ff7f00",
+            "098703bde50c77050000000049454e44ae426082"
+        ));
+        let image = decode(&png).unwrap();
+        assert_eq!(image.format(), Format::Argb8888);
+        assert_eq!(image.get(0, 0), Color::new(0x12, 0x12, 0x12, 255));
+        assert_eq!(image.get(1, 0), Color::new(0, 0, 0, 0));
+        assert_eq!(image.get(2, 0), Color::new(255, 255, 255, 255));
+    }
+
+    #[test]
+    fn encodes_roundtrip() {
+        let mut image = Image::new(5, 3, Format::Argb8888).unwrap();
+        for y in 0..3 {
+            for x in 0..5 {
+                image.set(x, y, &Color::new((x * 50) as u8, (y * 100) as u8, 7, 255));
+            }
+        }
+        image.set(4, 2, &Color::new(0, 0, 0, 0));
+        assert_eq!(decode(&encode(&image)).unwrap(), image);
+
+        let mut opaque = Image::new(2, 2, Format::Rgb565).unwrap();
+        opaque.fill(&Color::new(255, 0, 0, 255));
+        let decoded = decode(&encode(&opaque)).unwrap();
+        assert_eq!(decoded.format(), Format::Xrgb8888);
+        assert_eq!(decoded.get(1, 1), Color::new(255, 0, 0, 255));
+    }
+
+    #[test]
+    fn rejects_corruption() {
+        let mut png = hex(concat!(
+            "89504e470d0a1a0a0000000d494844520000000200000002080600000072b60d24",
+            "0000001649444154789c63f8cfc0f01f081b18813490cdc808003ae8057fdb8e2c20",
+            "0000000049454e44ae426082"
+        ));
+        png[20] ^= 1;
+        assert!(decode(&png).is_err());
+        assert!(decode(b"GIF89a").is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use std::io;
+
+use super::drm::Color;
+use super::format::Format;
+use super::image::Image;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/**
+ * Reads the whitespace separated tokens of a netpbm header, skipping
+ * comments.
+ **/
+struct Tokens<'a> {
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Tokens<'a> {
+    fn skip_space(&mut self) {
+        while let Some(&b) = self.data.get(self.pos) {
+            if b == b'#' {
+                while self.data.get(self.pos).is_some_and(|&b| b != b'\n') {
+                    self.pos += 1;
+                }
+            } else if b.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn word(&mut self) -> io::Result<&'a str> {
+        self.skip_space();
+        let start = self.pos;
+        while self.data.get(self.pos).is_some_and(|b| !b.is_ascii_whitespace() && *b != b'#') {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(invalid("truncated netpbm header"));
+        }
+        std::str::from_utf8(&self.data[start..self.pos]).map_err(|_| invalid("invalid netpbm header"))
+    }
+
+    fn number(&mut self) -> io::Result<u32> {
+        self.word()?.parse().map_err(|_| invalid("invalid number in netpbm file"))
+    }
+
+    /**
+     * Skips the single whitespace byte that separates the header from
+     * binary pixel data.
+     **/
+    fn binary(&mut self) -> &'a [u8] {
+        &self.data[(self.pos + 1).min(self.data.len())..]
+    }
+}
+
+/**
+ * Decodes PBM, PGM and PPM images in ASCII (P1-P3) and binary (P4-P6)
+ * form, and PAM (P7) images with any of the standard tuple types.
+ **/
+pub fn decode(data: &[u8]) -> io::Result<Image> {
+    let kind = match data {
+        [b'P', k @ b'1'..=b'7', ..] => *k,
+        _ => return Err(invalid("not a netpbm file"))
+    };
+    let mut tokens = Tokens { data, pos: 2 };
+    if kind == b'7' {
+        return decode_pam(&mut tokens);
+    }
+
+    let width = tokens.number()?;
+    let height = tokens.number()?;
+    let (channels, max) = match kind {
+        b'1' | b'4' => (1, 1),
+        b'2' | b'5' => (1, tokens.number()?),
+        _ => (3, tokens.number()?)
+    };
+    check_dimensions(width, height, max)?;
+
+    let count = width as usize * height as usize * channels as usize;
+    let samples: Vec<u32> = match kind {
+        b'1' => {
+            // bitmaps may omit the whitespace between digits
+            let mut samples = Vec::with_capacity(count);
+            while samples.len() < count {
+                tokens.skip_space();
+                match data.get(tokens.pos) {
+                    Some(b'0') => samples.push(1),
+                    Some(b'1') => samples.push(0),
+                    _ => return Err(invalid("truncated PBM"))
+                }
+                tokens.pos += 1;
+            }
+            samples
+        }
+        b'2' | b'3' => (0..count).map(|_| tokens.number()).collect::<io::Result<_>>()?,
+        b'4' => {
+            let row_len = (width as usize).div_ceil(8);
+            let bits = tokens.binary();
+            if bits.len() < row_len * height as usize {
+                return Err(invalid("truncated PBM"));
+            }
+            (0..count).map(|i| {
+                let (x, y) = (i % width as usize, i / width as usize);
+                // 1 is black
+                ((bits[y * row_len + x / 8] >> (7 - x % 8)) as u32 & 1) ^ 1
+            }).collect()
+        }
+        _ => binary_samples(tokens.binary(), count, max)?
+    };
+    image(width, height, channels, false, max, &samples)
+}
+
+fn decode_pam(tokens: &mut Tokens) -> io::Result<Image> {
+    let (mut width, mut height, mut depth, mut max) = (0, 0, 0, 0);
+    let mut tuple_type = String::new();
+    loop {
+        match tokens.word()? {
+            "WIDTH" => width = tokens.number()?,
+            "HEIGHT" => height = tokens.number()?,
+            "DEPTH" => depth = tokens.number()?,
+            "MAXVAL" => max = tokens.number()?,
+            "TUPLTYPE" => tuple_type = String::from(tokens.word()?),
+            "ENDHDR" => break,
+            _ => return Err(invalid("unknown PAM header field"))
+        }
+    }
+    check_dimensions(width, height, max)?;
+
+    let has_alpha = tuple_type.ends_with("_ALPHA") || depth == 2 || depth == 4;
+    let channels = depth - has_alpha as u32;
+    if channels != 1 && channels != 3 {
+        return Err(invalid("unsupported PAM tuple type"));
+    }
+    let samples = binary_samples(tokens.binary(), width as usize * height as usize * depth as usize, max)?;
+    image(width, height, channels, has_alpha, max, &samples)
+}
+
+fn check_dimensions(width: u32, height: u32, max: u32) -> io::Result<()> {
+    if width == 0 || height == 0 || width > 1 << 16 || height > 1 << 16 {
+        return Err(invalid("invalid netpbm dimensions"));
+    }
+    if max == 0 || max > 65535 {
+        return Err(invalid("invalid netpbm maximum value"));
+    }
+    Ok(())
+}
+
+/**
+ * Reads binary samples, which take two big endian bytes if `max` exceeds
+ * 255.
+ **/
+fn binary_samples(data: &[u8], count: usize, max: u32) -> io::Result<Vec<u32>> {
+    if max > 255 {
+        let bytes = data.get(..count * 2).ok_or_else(|| invalid("truncated netpbm pixels"))?;
+        Ok(bytes.chunks(2).map(|b| (b[0] as u32) << 8 | b[1] as u32).collect())
+    } else {
+        let bytes = data.get(..count).ok_or_else(|| invalid("truncated netpbm pixels"))?;
+        Ok(bytes.iter().map(|&b| b as u32).collect())
+    }
+}
+
+fn image(width: u32, height: u32, channels: u32, has_alpha: bool, max: u32, samples: &[u32]) -> io::Result<Image> {
+    let format = if has_alpha { Format::Argb8888 } else { Format::Xrgb8888 };
+    let mut image = Image::new(width, height, format).map_err(|_| invalid("netpbm image is too large"))?;
+    let scale = |v: u32| ((v.min(max) * 255 + max / 2) / max) as u8;
+    let depth = (channels + has_alpha as u32) as usize;
+
+    for (i, tuple) in samples.chunks(depth).enumerate() {
+        let (r, g, b) = if channels == 1 {
+            (tuple[0], tuple[0], tuple[0])
+        } else {
+            (tuple[0], tuple[1], tuple[2])
+        };
+        let a = if has_alpha { scale(tuple[depth - 1]) } else { 255 };
+        let c = Color::new(scale(r), scale(g), scale(b), a);
+        image.set(i as u32 % width, i as u32 / width, &c.premultiply());
+    }
+    Ok(image)
+}
+
+/**
+ * Encodes an image as a binary PPM. Transparent pixels end up composited
+ * onto black.
+ **/
+pub fn encode(image: &Image) -> Vec<u8> {
+    let mut out = format!("P6\n{} {}\n255\n", image.width(), image.height()).into_bytes();
+    out.reserve((image.width() * image.height() * 3) as usize);
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let c = image.get(x, y);
+            out.extend_from_slice(&[c.r, c.g, c.b]);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_ascii_formats() {
+        let ppm = decode(b"P3\n# a comment\n2 1\n15\n15 0 0  0 15 15\n").unwrap();
+        assert_eq!(ppm.get(0, 0), Color::new(255, 0, 0, 255));
+        assert_eq!(ppm.get(1, 0), Color::new(0, 255, 255, 255));
+
+        let pbm = decode(b"P1 3 1 010").unwrap();
+        assert_eq!(pbm.get(0, 0), Color::new(255, 255, 255, 255));
+        assert_eq!(pbm.get(1, 0), Color::new(0, 0, 0, 255));
+    }
+
+    #[test]
+    fn decodes_binary_formats() {
+        let mut pgm = b"P5 2 1 65535\n".to_vec();
+        pgm.extend_from_slice(&[0x80, 0x00, 0xff, 0xff]);
+        let pgm = decode(&pgm).unwrap();
+        assert_eq!(pgm.get(0, 0), Color::new(128, 128, 128, 255));
+        assert_eq!(pgm.get(1, 0), Color::new(255, 255, 255, 255));
+
+        let mut ppm = b"P6 1 1 255\n".to_vec();
+        ppm.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(decode(&ppm).unwrap().get(0, 0), Color::new(1, 2, 3, 255));
+
+        let mut pbm = b"P4 9 1\n".to_vec();
+        pbm.extend_from_slice(&[0b1000_0000, 0b1000_0000]);
+        let pbm = decode(&pbm).unwrap();
+        assert_eq!(pbm.get(0, 0), Color::new(0, 0, 0, 255));
+        assert_eq!(pbm.get(1, 0), Color::new(255, 255, 255, 255));
+        assert_eq!(pbm.get(8, 0), Color::new(0, 0, 0, 255));
+    }
+
+    #[test]
+    fn decodes_pam_with_alpha() {
+        let mut pam = b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n".to_vec();
+        pam.extend_from_slice(&[255, 0, 0, 255, 0, 255, 0, 0]);
+        let pam = decode(&pam).unwrap();
+        assert_eq!(pam.format(), Format::Argb8888);
+        assert_eq!(pam.get(0, 0), Color::new(255, 0, 0, 255));
+        assert_eq!(pam.get(1, 0), Color::new(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn encodes_ppm() {
+        let mut image = Image::new(2, 1, Format::Xbgr8888).unwrap();
+        image.set(0, 0, &Color::new(1, 2, 3, 255));
+        image.set(1, 0, &Color::new(4, 5, 6, 255));
+        assert_eq!(encode(&image), b"P6\n2 1\n255\n\x01\x02\x03\x04\x05\x06".to_vec());
+        assert_eq!(decode(&encode(&image)).unwrap().get(1, 0), Color::new(4, 5, 6, 255));
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        assert!(decode(b"P6 2 2 255\n\x00\x00").is_err());
+        assert!(decode(b"P7\nWIDTH 1\nENDHDR\n").is_err());
+        assert!(decode(b"P9").is_err());
+    }
+}
@@ -0,0 +1,401 @@
+/*!
+ * Scan conversion of geometric shapes. The functions don't draw anything
+ * themselves, they report pixels or horizontal spans `[x0, x1)` to a
+ * callback. A pixel belongs to a shape if its center does. Only the part
+ * inside a clip rectangle is reported, and the work done is bounded by the
+ * clip, not by the size of the shape.
+ **/
+
+use super::drm::Rect;
+
+/**
+ * Visits every pixel inside `clip` of a one pixel wide line (Bresenham).
+ **/
+pub fn line<F: FnMut(i32, i32)>(x0: i32, y0: i32, x1: i32, y1: i32, clip: Rect, mut plot: F) {
+    let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+    let (dx, dy) = ((x1 - x0).abs(), (y1 - y0).abs());
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let (left, top) = (clip.x as i64, clip.y as i64);
+    let (right, bottom) = (left + clip.width as i64, top + clip.height as i64);
+
+    // Bresenham's pixels in closed form, so that only the steps along the
+    // major axis that land inside the clip are taken
+    if dx >= dy {
+        for i in steps(x0, sx, dx, left, right) {
+            let y = y0 + sy * along(i, dy, dx);
+            if top <= y && y < bottom {
+                plot((x0 + sx * i) as i32, y as i32);
+            }
+        }
+    } else {
+        for j in steps(y0, sy, dy, top, bottom) {
+            let x = x0 + sx * along(j, dx, dy);
+            if left <= x && x < right {
+                plot(x as i32, (y0 + sy * j) as i32);
+            }
+        }
+    }
+}
+
+/**
+ * How far Bresenham has moved along the minor axis after `i` steps along the
+ * major one, i.e. `i * minor / major` rounded half up. The product can exceed
+ * 64 bits for lines spanning the whole `i32` range.
+ **/
+fn along(i: i64, minor: i64, major: i64) -> i64 {
+    ((2 * i as i128 * minor as i128 + major as i128) / (2 * major as i128).max(1)) as i64
+}
+
+/**
+ * The steps `i` in `0..=len` for which `start + step * i` lies in
+ * `[min, max)`, with `step` 1 or -1.
+ **/
+fn steps(start: i64, step: i64, len: i64, min: i64, max: i64) -> ::std::ops::Range<i64> {
+    let (first, last) = if step > 0 { (min - start, max - 1 - start) } else { (start - (max - 1), start - min) };
+    first.max(0)..last.min(len) + 1
+}
+
+/**
+ * Clamps the rows `first..last` to those of `clip`.
+ **/
+fn clip_rows(first: i64, last: i64, clip: Rect) -> ::std::ops::Range<i64> {
+    first.max(clip.y as i64)..last.min(clip.bottom() as i64)
+}
+
+/**
+ * Emits the part of the span `[x0, x1)` in row `y` that lies inside `clip`.
+ **/
+fn clip_span<F: FnMut(i32, i32, i32)>(y: i64, x0: i64, x1: i64, clip: Rect, span: &mut F) {
+    let (x0, x1) = (x0.max(clip.x as i64), x1.min(clip.right() as i64));
+    if x0 < x1 {
+        span(y as i32, x0 as i32, x1 as i32);
+    }
+}
+
+fn fpart(x: f32) -> f32 {
+    x - x.floor()
+}
+
+/**
+ * Visits the pixels of an antialiased line (Xiaolin Wu) together with
+ * their coverage in `0.0..=1.0`, skipping the part outside `clip`.
+ **/
+pub fn line_aa<F: FnMut(i32, i32, f32)>(x0: f32, y0: f32, x1: f32, y1: f32, clip: Rect, mut plot: F) {
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    let (mut x0, mut y0, mut x1, mut y1) = if steep { (y0, x0, y1, x1) } else { (x0, y0, x1, y1) };
+    if x0 > x1 {
+        let (tx, ty) = (x0, y0);
+        x0 = x1;
+        y0 = y1;
+        x1 = tx;
+        y1 = ty;
+    }
+
+    let mut put = |x: i32, y: i32, c: f32| {
+        if c > 0.0 {
+            if steep { plot(y, x, c) } else { plot(x, y, c) }
+        }
+    };
+
+    let dx = x1 - x0;
+    let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+    // first endpoint
+    let xend = x0.round();
+    let yend = y0 + gradient * (xend - x0);
+    let xgap = 1.0 - fpart(x0 + 0.5);
+    let xpxl1 = xend as i32;
+    let ypxl1 = yend.floor() as i32;
+    put(xpxl1, ypxl1, (1.0 - fpart(yend)) * xgap);
+    put(xpxl1, ypxl1 + 1, fpart(yend) * xgap);
+    let mut intery = yend + gradient;
+
+    // second endpoint
+    let xend = x1.round();
+    let yend = y1 + gradient * (xend - x1);
+    let xgap = fpart(x1 + 0.5);
+    let xpxl2 = xend as i32;
+    let ypxl2 = yend.floor() as i32;
+    put(xpxl2, ypxl2, (1.0 - fpart(yend)) * xgap);
+    put(xpxl2, ypxl2 + 1, fpart(yend) * xgap);
+
+    let (min, max) = if steep { (clip.y, clip.bottom()) } else { (clip.x, clip.right()) };
+    let (first, last) = ((xpxl1 + 1).max(min), xpxl2.min(max));
+    if first > xpxl1 + 1 {
+        intery += gradient * (first - xpxl1 - 1) as f32;
+    }
+    for x in first..last {
+        let y = intery.floor() as i32;
+        put(x, y, 1.0 - fpart(intery));
+        put(x, y + 1, fpart(intery));
+        intery += gradient;
+    }
+}
+
+/**
+ * Returns the corners of a line with the given width as a polygon.
+ **/
+pub fn thick_line(x0: f32, y0: f32, x1: f32, y1: f32, width: f32) -> [(f32, f32); 4] {
+    let (dx, dy) = (x1 - x0, y1 - y0);
+    let len = (dx * dx + dy * dy).sqrt();
+    let (nx, ny) = if len == 0.0 {
+        (0.0, width / 2.0)
+    } else {
+        (-dy / len * width / 2.0, dx / len * width / 2.0)
+    };
+    [(x0 + nx, y0 + ny), (x1 + nx, y1 + ny), (x1 - nx, y1 - ny), (x0 - nx, y0 - ny)]
+}
+
+/**
+ * Horizontal half-extent of an axis aligned ellipse at vertical distance
+ * `dy` from its center, or `None` if the row misses it.
+ **/
+fn ellipse_extent(rx: f32, ry: f32, dy: f32) -> Option<f32> {
+    if rx <= 0.0 || ry <= 0.0 || dy.abs() > ry {
+        return None;
+    }
+    Some(rx * (1.0 - (dy * dy) / (ry * ry)).sqrt())
+}
+
+/**
+ * Emits the spans of an ellipse centered on the pixel `(cx, cy)`. Without
+ * `stroke` the ellipse is filled, otherwise only a ring of that width
+ * centered on the outline is produced. Only rows inside `clip` are
+ * emitted.
+ **/
+pub fn ellipse<F: FnMut(i32, i32, i32)>(cx: i32, cy: i32, rx: u32, ry: u32, stroke: Option<u32>, clip: Rect, mut span: F) {
+    let (rx, ry) = (rx as f32, ry as f32);
+    let half = stroke.map(|w| w as f32 / 2.0);
+    let (orx, ory) = match half {
+        Some(h) => (rx + h, ry + h),
+        None => (rx + 0.5, ry + 0.5)
+    };
+
+    let (cx, cy, top) = (cx as i64, cy as i64, ory.floor() as i64);
+    for y in clip_rows(cy - top, cy + top + 1, clip) {
+        let dy = (y - cy) as f32;
+        let outer = match ellipse_extent(orx, ory, dy) {
+            Some(x) => x.floor() as i64,
+            None => continue
+        };
+        let inner = half.and_then(|h| ellipse_extent(rx - h, ry - h, dy));
+        match inner {
+            Some(x) => {
+                let inner = x.floor() as i64 + 1;
+                if inner <= outer {
+                    clip_span(y, cx - outer, cx - inner + 1, clip, &mut span);
+                    clip_span(y, cx + inner, cx + outer + 1, clip, &mut span);
+                }
+            }
+            None => clip_span(y, cx - outer, cx + outer + 1, clip, &mut span)
+        }
+    }
+}
+
+/**
+ * Horizontal inset of a rounded rectangle's corner in row `y` (relative to
+ * the top), for a rectangle of the given height.
+ **/
+fn corner_inset(y: u32, height: u32, radius: f32) -> f32 {
+    let center = y as f32 + 0.5;
+    let dy = if center < radius {
+        radius - center
+    } else if center > height as f32 - radius {
+        center - (height as f32 - radius)
+    } else {
+        return 0.0;
+    };
+    radius - (radius * radius - dy * dy).max(0.0).sqrt()
+}
+
+/**
+ * Emits the spans of a filled rectangle with rounded corners, in the rows
+ * of `clip`.
+ **/
+pub fn rounded_rectangle<F: FnMut(i32, i32, i32)>(rect: Rect, radius: u32, clip: Rect, mut span: F) {
+    let Rect { x, y, width, height } = rect;
+    let radius = (radius as f32).min(width as f32 / 2.0).min(height as f32 / 2.0);
+    let (x, width) = (x as i64, width as i64);
+    for row in clip_rows(y as i64, y as i64 + height as i64, clip) {
+        let inset = corner_inset((row - y as i64) as u32, height, radius).round() as i64;
+        clip_span(row, x + inset, x + width - inset, clip, &mut span);
+    }
+}
+
+/**
+ * Emits the spans of the outline of a rounded rectangle, `stroke` pixels
+ * wide and lying inside the rectangle, in the rows of `clip`.
+ **/
+pub fn stroke_rounded_rectangle<F: FnMut(i32, i32, i32)>(rect: Rect, radius: u32, stroke: u32, clip: Rect, mut span: F) {
+    let Rect { x, y, width, height } = rect;
+    let outer_radius = (radius as f32).min(width as f32 / 2.0).min(height as f32 / 2.0);
+    let inner_radius = (outer_radius - stroke as f32).max(0.0);
+    let inner_width = width.saturating_sub(2 * stroke);
+    let inner_height = height.saturating_sub(2 * stroke);
+
+    let (x, width, stroke) = (x as i64, width as i64, stroke as i64);
+    for row in clip_rows(y as i64, y as i64 + height as i64, clip) {
+        let inset = corner_inset((row - y as i64) as u32, height, outer_radius).round() as i64;
+        let (x0, x1) = (x + inset, x + width - inset);
+        if x0 >= x1 {
+            continue;
+        }
+
+        let inner_row = row - y as i64 - stroke;
+        if inner_width == 0 || inner_row < 0 || inner_row >= inner_height as i64 {
+            clip_span(row, x0, x1, clip, &mut span);
+            continue;
+        }
+        let inset = corner_inset(inner_row as u32, inner_height, inner_radius).round() as i64;
+        let (i0, i1) = (x + stroke + inset, x + stroke + inner_width as i64 - inset);
+        if i0 >= i1 {
+            clip_span(row, x0, x1, clip, &mut span);
+        } else {
+            clip_span(row, x0, i0.max(x0), clip, &mut span);
+            clip_span(row, i1.min(x1), x1, clip, &mut span);
+        }
+    }
+}
+
+/**
+ * Emits the spans of a filled polygon using the even-odd rule, in the
+ * rows of `clip`. Polygons with points that aren't finite are skipped.
+ **/
+pub fn polygon<F: FnMut(i32, i32, i32)>(points: &[(f32, f32)], clip: Rect, mut span: F) {
+    if points.len() < 3 || points.iter().any(|p| !p.0.is_finite() || !p.1.is_finite()) {
+        return;
+    }
+
+    let min_y = points.iter().fold(f32::INFINITY, |acc, p| acc.min(p.1));
+    let max_y = points.iter().fold(f32::NEG_INFINITY, |acc, p| acc.max(p.1));
+
+    let mut crossings: Vec<f32> = Vec::new();
+    for y in clip_rows(min_y.floor() as i64, max_y.ceil() as i64, clip) {
+        let center = y as f32 + 0.5;
+        crossings.clear();
+        for i in 0..points.len() {
+            let (x0, y0) = points[i];
+            let (x1, y1) = points[(i + 1) % points.len()];
+            if (y0 <= center && center < y1) || (y1 <= center && center < y0) {
+                crossings.push(x0 + (center - y0) / (y1 - y0) * (x1 - x0));
+            }
+        }
+        crossings.sort_by(|a, b| a.total_cmp(b));
+
+        for pair in crossings.chunks(2) {
+            if pair.len() < 2 {
+                break;
+            }
+            let x0 = (pair[0] - 0.5).ceil() as i64;
+            let x1 = (pair[1] - 0.5).ceil() as i64;
+            clip_span(y, x0, x1, clip, &mut span);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bresenham_endpoints() {
+        let mut pixels = Vec::new();
+        line(0, 0, 4, 2, Rect::new(-100, -100, 200, 200), |x, y| pixels.push((x, y)));
+        assert_eq!(pixels, vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn wu_coverage_sums_to_one() {
+        let mut total = [0.0; 11];
+        line_aa(0.0, 0.0, 10.0, 3.0, Rect::new(-100, -100, 200, 200), |x, _, c| total[x as usize] += c);
+        for c in &total[1..10] {
+            assert!((c - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn filled_circle_is_symmetric() {
+        let mut spans = Vec::new();
+        ellipse(10, 10, 3, 3, None, Rect::new(-100, -100, 200, 200), |y, x0, x1| spans.push((y, x0, x1)));
+        assert_eq!(spans.first(), Some(&(7, 9, 12)));
+        assert_eq!(spans[3], (10, 7, 14));
+        assert_eq!(spans.len(), 7);
+    }
+
+    #[test]
+    fn circle_outline_has_no_center() {
+        let mut spans = Vec::new();
+        ellipse(0, 0, 5, 5, Some(1), Rect::new(-100, -100, 200, 200), |y, x0, x1| spans.push((y, x0, x1)));
+        assert!(spans.contains(&(0, -5, -4)));
+        assert!(spans.contains(&(0, 5, 6)));
+    }
+
+    #[test]
+    fn polygon_even_odd() {
+        let mut area = 0;
+        polygon(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)], Rect::new(-100, -100, 200, 200), |_, x0, x1| area += x1 - x0);
+        assert_eq!(area, 16);
+
+        let mut spans = Vec::new();
+        polygon(&[(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)], Rect::new(-100, -100, 200, 200), |y, x0, x1| spans.push((y, x0, x1)));
+        assert_eq!(spans, vec![(0, 0, 3), (1, 0, 2), (2, 0, 1)]);
+
+        let mut called = false;
+        polygon(&[(0.0, 0.0), (f32::NAN, 0.0), (0.0, f32::INFINITY)], Rect::new(-100, -100, 200, 200), |_, _, _| called = true);
+        assert!(!called);
+    }
+
+    #[test]
+    fn rounded_corners() {
+        let mut spans = Vec::new();
+        rounded_rectangle(Rect::new(0, 0, 10, 10), 4, Rect::new(-100, -100, 200, 200), |y, x0, x1| spans.push((y, x0, x1)));
+        assert_eq!(spans.len(), 10);
+        assert!(spans[0].1 > 0);
+        assert_eq!(spans[5], (5, 0, 10));
+
+        let mut area = 0;
+        stroke_rounded_rectangle(Rect::new(0, 0, 10, 10), 0, 1, Rect::new(-100, -100, 200, 200), |_, x0, x1| area += x1 - x0);
+        assert_eq!(area, 36);
+    }
+
+    #[test]
+    fn clipped_line_matches_unclipped() {
+        let clip = Rect::new(2, -1, 5, 3);
+        for &(x0, y0, x1, y1) in &[(0, 0, 9, 4), (9, 4, 0, 0), (1, -3, 4, 8), (8, 1, -2, 0), (3, 0, 3, 5)] {
+            let mut all = Vec::new();
+            line(x0, y0, x1, y1, Rect::new(-100, -100, 200, 200), |x, y| all.push((x, y)));
+            all.retain(|&(x, y)| (2..7).contains(&x) && (-1..2).contains(&y));
+            let mut clipped = Vec::new();
+            line(x0, y0, x1, y1, clip, |x, y| clipped.push((x, y)));
+            assert_eq!(clipped, all);
+        }
+    }
+
+    #[test]
+    fn huge_shapes_stay_inside_the_clip() {
+        let clip = Rect::new(0, 0, 8, 8);
+        let inside = |x: i32, y: i32| (0..8).contains(&x) && (0..8).contains(&y);
+        let mut count = 0;
+        line(i32::MIN, i32::MIN, i32::MAX, i32::MAX, clip, |x, y| {
+            assert!(inside(x, y));
+            count += 1;
+        });
+        assert_eq!(count, 8);
+
+        let mut rows = 0;
+        ellipse(0, 0, u32::MAX, u32::MAX, None, clip, |y, x0, x1| {
+            assert!(inside(x0, y) && x1 <= 8);
+            rows += 1;
+        });
+        rounded_rectangle(Rect::new(i32::MIN, i32::MIN, u32::MAX, u32::MAX), u32::MAX, clip, |y, x0, x1| {
+            assert!(inside(x0, y) && x1 <= 8);
+            rows += 1;
+        });
+        polygon(&[(-1e9, -1e9), (1e9, -1e9), (1e9, 1e9)], clip, |y, x0, x1| {
+            assert!(inside(x0, y) && x1 <= 8);
+            rows += 1;
+        });
+        assert!(rows <= 24);
+    }
+}
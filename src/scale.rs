@@ -0,0 +1,118 @@
+use super::drm::Rect;
+
+/**
+ * Pixel density the UI is designed for, at a scale of 1.
+ **/
+const BASE_DPI: f32 = 96.0;
+
+/**
+ * How many physical pixels an output has per logical pixel, e.g. 2 on a
+ * HiDPI laptop panel. Fractional factors are rounded to whole pixels at
+ * the edges of everything drawn, so that neighbouring shapes still meet.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scale(f32);
+
+impl Scale {
+    /**
+     * Returns `None` unless `factor` is positive.
+     **/
+    pub fn new(factor: f32) -> Option<Scale> {
+        if factor > 0.0 && factor.is_finite() { Some(Scale(factor)) } else { None }
+    }
+
+    pub fn parse(s: &str) -> Option<Scale> {
+        s.parse().ok().and_then(Scale::new)
+    }
+
+    /**
+     * A factor for a panel `width` pixels and `width_mm` millimeters wide
+     * that brings it close to 96 dpi, in steps of a quarter.
+     **/
+    pub fn for_density(width: u32, width_mm: u32) -> Scale {
+        if width_mm == 0 {
+            return Scale::default();
+        }
+        let dpi = width as f32 * 25.4 / width_mm as f32;
+        Scale(((dpi / BASE_DPI * 4.0).round() / 4.0).clamp(1.0, 3.0))
+    }
+
+    pub fn factor(&self) -> f32 {
+        self.0
+    }
+
+    /**
+     * Physical length of `length` logical pixels. Lines stay at least a
+     * pixel wide.
+     **/
+    pub fn length(&self, length: u32) -> u32 {
+        if length == 0 { 0 } else { ((length as f32 * self.0).round() as u32).max(1) }
+    }
+
+    pub fn point(&self, x: i32, y: i32) -> (i32, i32) {
+        (self.coordinate(x), self.coordinate(y))
+    }
+
+    /**
+     * The physical pixels of a logical rectangle. Edges are rounded on
+     * their own, so rectangles that touch keep touching.
+     **/
+    pub fn rect(&self, rect: Rect) -> Rect {
+        let (x0, y0) = self.point(rect.x, rect.y);
+        let (x1, y1) = self.point(rect.right(), rect.bottom());
+        Rect::new(x0, y0, (x1 - x0) as u32, (y1 - y0) as u32)
+    }
+
+    /**
+     * The logical pixel a physical pixel lies in.
+     **/
+    pub fn to_logical(self, x: i32, y: i32) -> (i32, i32) {
+        ((x as f32 / self.0).floor() as i32, (y as f32 / self.0).floor() as i32)
+    }
+
+    /**
+     * Logical size of an output with the given physical size.
+     **/
+    pub fn logical_size(&self, width: u32, height: u32) -> (u32, u32) {
+        ((width as f32 / self.0).round() as u32, (height as f32 / self.0).round() as u32)
+    }
+
+    fn coordinate(&self, v: i32) -> i32 {
+        (v as f32 * self.0).round() as i32
+    }
+}
+
+impl Default for Scale {
+    fn default() -> Scale {
+        Scale(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_edges() {
+        let scale = Scale::new(1.25).unwrap();
+        assert_eq!(scale.rect(Rect::new(1, 2, 3, 4)), Rect::new(1, 3, 4, 5));
+        // the second rectangle starts where the first one ends
+        assert_eq!(scale.rect(Rect::new(4, 0, 3, 1)).x, scale.rect(Rect::new(1, 0, 3, 1)).right());
+        assert_eq!(scale.length(1), 1);
+        assert_eq!(Scale::new(2.0).unwrap().length(25), 50);
+        assert_eq!(scale.to_logical(5, 4), (4, 3));
+        assert_eq!(scale.logical_size(2560, 1600), (2048, 1280));
+    }
+
+    #[test]
+    fn picks_factor_for_density() {
+        // a 14" 4K laptop panel and a 24" 1080p monitor
+        assert_eq!(Scale::for_density(3840, 310).factor(), 3.0);
+        assert_eq!(Scale::for_density(2560, 300).factor(), 2.25);
+        assert_eq!(Scale::for_density(1920, 530).factor(), 1.0);
+        assert_eq!(Scale::for_density(1920, 0).factor(), 1.0);
+        assert!(Scale::parse("1.5").is_some());
+        assert!(Scale::parse("0").is_none());
+        assert!(Scale::parse("two").is_none());
+    }
+}
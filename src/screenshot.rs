@@ -0,0 +1,186 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::backend::Backend;
+use super::drm::Rect;
+use super::format::Format;
+use super::image::Image;
+use super::png;
+use super::pnm;
+
+/**
+ * Which part of the display a screenshot shows.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capture {
+    /// All outputs, placed next to each other from left to right.
+    Screen,
+    /// A single output.
+    Output(usize),
+    /// A rectangle in screen coordinates.
+    Region(Rect)
+}
+
+/**
+ * Copies `region` of a backend into an image in the backend's format. The
+ * region is clipped to the backend's size.
+ **/
+pub fn capture(backend: &dyn Backend, region: Rect) -> io::Result<Image> {
+    let region = region.intersect(&Rect::new(0, 0, backend.width(), backend.height()));
+    let format = backend.format();
+    let bpp = format.bytes_per_pixel() as usize;
+    let stride = backend.stride() as usize;
+    let row_len = region.width as usize * bpp;
+
+    let mut image = Image::new(region.width, region.height, format)?;
+    for y in 0..region.height {
+        let start = (region.y as usize + y as usize) * stride + region.x as usize * bpp;
+        image.row_mut(y).copy_from_slice(&backend.pixels()[start..start + row_len]);
+    }
+    Ok(image)
+}
+
+/**
+ * Takes a screenshot of a set of outputs.
+ **/
+pub fn capture_outputs(outputs: &[&dyn Backend], target: &Capture) -> io::Result<Image> {
+    if let Capture::Output(index) = *target {
+        let output = outputs.get(index).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such output"))?;
+        return capture(*output, Rect::new(0, 0, output.width(), output.height()));
+    }
+
+    let width = outputs.iter().map(|o| o.width()).sum();
+    let height = outputs.iter().map(|o| o.height()).max().unwrap_or(0);
+    let region = match *target {
+        Capture::Region(region) => region.intersect(&Rect::new(0, 0, width, height)),
+        _ => Rect::new(0, 0, width, height)
+    };
+
+    // a single output is copied as is, keeping its pixel format
+    if outputs.len() == 1 {
+        return capture(outputs[0], region);
+    }
+
+    let mut image = Image::new(region.width, region.height, Format::Xrgb8888)?;
+    let mut left = 0;
+    for output in outputs.iter() {
+        let bounds = Rect::new(left, 0, output.width(), output.height());
+        let part = bounds.intersect(&region);
+        if !part.is_empty() {
+            let pixels = capture(*output, Rect::new(part.x - left, part.y, part.width, part.height))?;
+            for y in 0..part.height {
+                for x in 0..part.width {
+                    let (dx, dy) = ((part.x - region.x) as u32 + x, (part.y - region.y) as u32 + y);
+                    image.set(dx, dy, &pixels.get(x, y));
+                }
+            }
+        }
+        left += output.width() as i32;
+    }
+    Ok(image)
+}
+
+/**
+ * Encodes an image as PNG or, for paths ending in `.ppm`, as PPM.
+ **/
+pub fn save(image: &Image, path: &Path) -> io::Result<()> {
+    let data = match path.extension().and_then(|e| e.to_str()) {
+        Some("ppm") => pnm::encode(image),
+        _ => png::encode(image)
+    };
+    fs::write(path, data)
+}
+
+/**
+ * Where screenshots go when no path is given: a timestamped PNG in
+ * `$XDG_PICTURES_DIR`, falling back to the home and current directory.
+ **/
+pub fn default_path() -> PathBuf {
+    let dir = env::var_os("XDG_PICTURES_DIR")
+        .or_else(|| env::var_os("HOME"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    dir.join(format!("screenshot-{}-{:03}.png", now.as_secs(), now.subsec_millis()))
+}
+
+/**
+ * Compares an image against `src/golden/<name>.png`. Set `UPDATE_GOLDEN`
+ * to write the image as the new reference instead. On mismatch, the actual
+ * image is saved to the temporary directory for inspection.
+ **/
+#[cfg(test)]
+pub fn assert_golden(image: &Image, name: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/golden").join(format!("{}.png", name));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        save(image, &path).unwrap();
+        return;
+    }
+
+    let data = fs::read(&path).unwrap_or_else(|_| panic!("missing {}, run with UPDATE_GOLDEN=1", path.display()));
+    let golden = png::decode(&data).unwrap();
+    let matches = golden.width() == image.width() && golden.height() == image.height()
+        && (0..image.height()).all(|y| (0..image.width()).all(|x| golden.get(x, y) == image.get(x, y)));
+    if !matches {
+        let actual = env::temp_dir().join(format!("{}.actual.png", name));
+        save(image, &actual).unwrap();
+        panic!("{} differs from {}, see {}", name, path.display(), actual.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::backend::MemoryBackend;
+    use super::super::drm::Color;
+
+    fn output(width: u32, height: u32, format: Format, c: &Color) -> MemoryBackend {
+        let mut backend = MemoryBackend::new(width, height, format).unwrap();
+        let bpp = format.bytes_per_pixel() as usize;
+        for pixel in backend.pixels_mut().chunks_mut(bpp) {
+            format.write(pixel, c);
+        }
+        backend
+    }
+
+    #[test]
+    fn captures_outputs_and_regions() {
+        let red = Color::new(255, 0, 0, 255);
+        let blue = Color::new(0, 0, 255, 255);
+        let a = output(4, 2, Format::Xrgb8888, &red);
+        let b = output(2, 3, Format::Xbgr8888, &blue);
+        let outputs: [&dyn Backend; 2] = [&a, &b];
+
+        let screen = capture_outputs(&outputs, &Capture::Screen).unwrap();
+        assert_eq!((screen.width(), screen.height()), (6, 3));
+        assert_eq!(screen.get(0, 0), red);
+        assert_eq!(screen.get(5, 2), blue);
+        assert_eq!(screen.get(0, 2), Color::new(0, 0, 0, 255));
+
+        let second = capture_outputs(&outputs, &Capture::Output(1)).unwrap();
+        assert_eq!((second.width(), second.height(), second.format()), (2, 3, Format::Xbgr8888));
+        assert!(capture_outputs(&outputs, &Capture::Output(2)).is_err());
+
+        let region = capture_outputs(&outputs, &Capture::Region(Rect::new(3, 1, 10, 10))).unwrap();
+        assert_eq!((region.width(), region.height()), (3, 2));
+        assert_eq!(region.get(0, 0), red);
+        assert_eq!(region.get(1, 0), blue);
+    }
+
+    #[test]
+    fn saves_by_extension() {
+        let image = capture(&output(3, 2, Format::Rgb565, &Color::new(0, 255, 0, 255)), Rect::new(0, 0, 3, 2)).unwrap();
+        let dir = env::temp_dir();
+        for name in ["screenshot-test.png", "screenshot-test.ppm"].iter() {
+            let path = dir.join(name);
+            save(&image, &path).unwrap();
+            let loaded = Image::load(&fs::read(&path).unwrap()).unwrap();
+            assert_eq!(loaded.get(2, 1), Color::new(0, 255, 0, 255));
+            fs::remove_file(&path).unwrap();
+        }
+    }
+}
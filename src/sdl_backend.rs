@@ -0,0 +1,153 @@
+extern crate sdl2;
+
+use std::io;
+
+use self::sdl2::event::Event;
+use self::sdl2::mouse::{MouseButton, MouseWheelDirection};
+use self::sdl2::pixels::PixelFormatEnum;
+use self::sdl2::surface::Surface;
+use self::sdl2::video::Window;
+use self::sdl2::{EventPump, Sdl};
+
+use super::backend::Backend;
+use super::drm::Rect;
+use super::format::Format;
+use super::input::{keycode_from_hid, Button, InputEvent};
+
+/**
+ * Presents the framebuffer in a window of an existing desktop session. With
+ * `SDL_VIDEODRIVER=dummy` it runs without any display, e.g. on CI.
+ **/
+pub struct SdlBackend {
+    _context: Sdl,
+    window: Window,
+    event_pump: EventPump,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    damage: Vec<Rect>
+}
+
+impl SdlBackend {
+    pub fn new(width: u32, height: u32) -> Result<SdlBackend, String> {
+        let context = sdl2::init()?;
+        let video = context.video()?;
+        let window = video
+            .window("comp-rs", width, height)
+            .position_centered()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let event_pump = context.event_pump()?;
+
+        Ok(SdlBackend {
+            _context: context,
+            window,
+            event_pump,
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+            damage: Vec::new()
+        })
+    }
+}
+
+impl Backend for SdlBackend {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        // SDL's ARGB8888 is a packed 32 bit value, just like the DRM format
+        Format::Xrgb8888
+    }
+
+    fn stride(&self) -> u32 {
+        self.width * 4
+    }
+
+    fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    fn damage(&mut self, rect: Rect) {
+        self.damage.push(rect);
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.damage.is_empty() {
+            return Ok(());
+        }
+
+        let stride = self.stride();
+        let rects: Vec<sdl2::rect::Rect> = self.damage
+            .drain(..)
+            .map(|r| sdl2::rect::Rect::new(r.x, r.y, r.width, r.height))
+            .collect();
+
+        let source = Surface::from_data(&mut self.pixels, self.width, self.height, stride, PixelFormatEnum::ARGB8888)
+            .map_err(sdl_error)?;
+        let mut target = self.window.surface(&self.event_pump).map_err(sdl_error)?;
+        for &rect in rects.iter() {
+            source.blit(rect, &mut target, rect).map_err(sdl_error)?;
+        }
+        target.update_window_rects(&rects).map_err(sdl_error)
+    }
+
+    fn input_events(&mut self) -> Vec<InputEvent> {
+        self.event_pump
+            .poll_iter()
+            .filter_map(|event| convert_event(&event))
+            .collect()
+    }
+}
+
+fn sdl_error(e: String) -> io::Error {
+    io::Error::other(e)
+}
+
+fn convert_button(button: MouseButton) -> Button {
+    match button {
+        MouseButton::Left => Button::Left,
+        MouseButton::Middle => Button::Middle,
+        MouseButton::Right => Button::Right,
+        MouseButton::X1 => Button::Side,
+        MouseButton::X2 => Button::Extra,
+        MouseButton::Unknown => Button::Other(0)
+    }
+}
+
+fn convert_event(event: &Event) -> Option<InputEvent> {
+    match *event {
+        Event::Quit { .. } => Some(InputEvent::Quit),
+        Event::MouseMotion { x, y, .. } => Some(InputEvent::PointerPosition { x, y }),
+        Event::MouseButtonDown { mouse_btn, .. } => Some(InputEvent::PointerButton {
+            button: convert_button(mouse_btn),
+            pressed: true
+        }),
+        Event::MouseButtonUp { mouse_btn, .. } => Some(InputEvent::PointerButton {
+            button: convert_button(mouse_btn),
+            pressed: false
+        }),
+        Event::MouseWheel { x, y, direction, .. } => {
+            let (dx, dy) = match direction {
+                MouseWheelDirection::Flipped => (-x, -y),
+                _ => (x, y)
+            };
+            // SDL scrolls up for positive values, the compositor scrolls down
+            Some(InputEvent::PointerAxis { dx, dy: -dy })
+        }
+        Event::KeyDown { scancode: Some(scancode), repeat: false, .. } =>
+            keycode_from_hid(scancode as u32).map(|key| InputEvent::Key { key, pressed: true }),
+        Event::KeyUp { scancode: Some(scancode), .. } =>
+            keycode_from_hid(scancode as u32).map(|key| InputEvent::Key { key, pressed: false }),
+        _ => None
+    }
+}
@@ -0,0 +1,454 @@
+/*!
+ * Vectorized kernels for 32 bit pixel rows, with SSE2 and AVX2 versions
+ * chosen at runtime and a scalar fallback. All versions give bit-identical
+ * results; the scalar one is the reference.
+ *
+ * Rows are byte slices whose length is a multiple of 4 and that need not
+ * be aligned.
+ **/
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+use super::blend::mul_div255;
+use super::format::Format;
+
+const ALPHA_MASK: u32 = 0xff00_0000;
+
+/**
+ * The instruction set a kernel runs with.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Scalar,
+    Sse2,
+    Avx2
+}
+
+impl Level {
+    /**
+     * The best level the current CPU supports.
+     **/
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    pub fn detect() -> Level {
+        if is_x86_feature_detected!("avx2") {
+            Level::Avx2
+        } else if is_x86_feature_detected!("sse2") {
+            Level::Sse2
+        } else {
+            Level::Scalar
+        }
+    }
+
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    pub fn detect() -> Level {
+        Level::Scalar
+    }
+
+    /**
+     * All levels the current CPU supports, for comparing them in tests and
+     * benchmarks.
+     **/
+    pub fn available() -> Vec<Level> {
+        let best = Level::detect();
+        [Level::Scalar, Level::Sse2, Level::Avx2].iter().cloned().filter(|&l| l <= best).collect()
+    }
+}
+
+/**
+ * Whether pixels of `src` can be turned into `dst` by the 32 bit kernels.
+ **/
+pub fn supports(src: Format, dst: Format) -> bool {
+    src.bytes_per_pixel() == 4 && dst.bytes_per_pixel() == 4
+}
+
+/**
+ * Whether `src` pixels can be blended onto `dst` with `over`.
+ **/
+pub fn same_order(src: Format, dst: Format) -> bool {
+    supports(src, dst) && !swaps_rb(src, dst)
+}
+
+/**
+ * Whether red and blue trade places between two 32 bit formats.
+ **/
+fn swaps_rb(src: Format, dst: Format) -> bool {
+    let rgb = |f: Format| f == Format::Xrgb8888 || f == Format::Argb8888;
+    rgb(src) != rgb(dst)
+}
+
+/**
+ * Sets every pixel of `row` to `pixel`.
+ **/
+pub fn fill(row: &mut [u8], pixel: u32) {
+    fill_with(Level::detect(), row, pixel)
+}
+
+pub fn fill_with(level: Level, row: &mut [u8], pixel: u32) {
+    let done = match level {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Avx2 => unsafe { fill_avx2(row, pixel) },
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Sse2 => unsafe { fill_sse2(row, pixel) },
+        _ => 0
+    };
+    let bytes = pixel.to_le_bytes();
+    for chunk in row[done..].chunks_exact_mut(4) {
+        chunk.copy_from_slice(&bytes);
+    }
+}
+
+/**
+ * Copies `src` to `dst`, which have the same length.
+ **/
+pub fn copy(dst: &mut [u8], src: &[u8]) {
+    copy_with(Level::detect(), dst, src)
+}
+
+pub fn copy_with(level: Level, dst: &mut [u8], src: &[u8]) {
+    assert_eq!(dst.len(), src.len());
+    let done = match level {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Avx2 => unsafe { copy_avx2(dst, src) },
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Sse2 => unsafe { copy_sse2(dst, src) },
+        _ => 0
+    };
+    dst[done..].copy_from_slice(&src[done..]);
+}
+
+/**
+ * Converts a row of `src_format` pixels to `dst_format`, swapping red and
+ * blue between RGBA and BGRA orders and making the result opaque unless
+ * both formats have alpha. Formats must be `supports`ed.
+ **/
+pub fn convert(dst: &mut [u8], dst_format: Format, src: &[u8], src_format: Format) {
+    convert_with(Level::detect(), dst, dst_format, src, src_format)
+}
+
+pub fn convert_with(level: Level, dst: &mut [u8], dst_format: Format, src: &[u8], src_format: Format) {
+    assert_eq!(dst.len(), src.len());
+    let swap = swaps_rb(src_format, dst_format);
+    let opaque = if src_format.has_alpha() && dst_format.has_alpha() { 0 } else { ALPHA_MASK };
+    let done = match level {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Avx2 => unsafe { convert_avx2(dst, src, swap, opaque) },
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Sse2 => unsafe { convert_sse2(dst, src, swap, opaque) },
+        _ => 0
+    };
+    for (d, s) in dst[done..].chunks_exact_mut(4).zip(src[done..].chunks_exact(4)) {
+        let p = u32::from_le_bytes([s[0], s[1], s[2], s[3]]);
+        let p = if swap { p & 0xff00_ff00 | (p >> 16) & 0xff | (p & 0xff) << 16 } else { p };
+        d.copy_from_slice(&(p | opaque).to_le_bytes());
+    }
+}
+
+/**
+ * Draws premultiplied `src` pixels over `dst` pixels of the same channel
+ * order. If `dst_format` has no alpha, the result is kept opaque.
+ **/
+pub fn over(dst: &mut [u8], dst_format: Format, src: &[u8]) {
+    over_with(Level::detect(), dst, dst_format, src)
+}
+
+pub fn over_with(level: Level, dst: &mut [u8], dst_format: Format, src: &[u8]) {
+    assert_eq!(dst.len(), src.len());
+    let opaque = if dst_format.has_alpha() { 0 } else { ALPHA_MASK };
+    let done = match level {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Avx2 => unsafe { over_avx2(dst, src, opaque) },
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        Level::Sse2 => unsafe { over_sse2(dst, src, opaque) },
+        _ => 0
+    };
+    let opaque = opaque.to_le_bytes();
+    for (d, s) in dst[done..].chunks_exact_mut(4).zip(src[done..].chunks_exact(4)) {
+        let inv = 255 - s[3];
+        for i in 0..4 {
+            d[i] = s[i].saturating_add(mul_div255(d[i], inv)) | opaque[i];
+        }
+    }
+}
+
+// The vector versions handle as many whole vectors as fit and return the
+// number of bytes done; the caller finishes the rest with scalar code.
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn fill_sse2(row: &mut [u8], pixel: u32) -> usize {
+    let v = _mm_set1_epi32(pixel as i32);
+    let end = row.len() / 16 * 16;
+    let ptr = row.as_mut_ptr();
+    for i in (0..end).step_by(16) {
+        _mm_storeu_si128(ptr.add(i) as *mut __m128i, v);
+    }
+    end
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn fill_avx2(row: &mut [u8], pixel: u32) -> usize {
+    let v = _mm256_set1_epi32(pixel as i32);
+    let end = row.len() / 32 * 32;
+    let ptr = row.as_mut_ptr();
+    for i in (0..end).step_by(32) {
+        _mm256_storeu_si256(ptr.add(i) as *mut __m256i, v);
+    }
+    end
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn copy_sse2(dst: &mut [u8], src: &[u8]) -> usize {
+    let end = dst.len() / 16 * 16;
+    for i in (0..end).step_by(16) {
+        let v = _mm_loadu_si128(src.as_ptr().add(i) as *const __m128i);
+        _mm_storeu_si128(dst.as_mut_ptr().add(i) as *mut __m128i, v);
+    }
+    end
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn copy_avx2(dst: &mut [u8], src: &[u8]) -> usize {
+    let end = dst.len() / 32 * 32;
+    for i in (0..end).step_by(32) {
+        let v = _mm256_loadu_si256(src.as_ptr().add(i) as *const __m256i);
+        _mm256_storeu_si256(dst.as_mut_ptr().add(i) as *mut __m256i, v);
+    }
+    end
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn convert_sse2(dst: &mut [u8], src: &[u8], swap: bool, opaque: u32) -> usize {
+    let keep = _mm_set1_epi32(0xff00_ff00u32 as i32);
+    let low = _mm_set1_epi32(0xff);
+    let opaque = _mm_set1_epi32(opaque as i32);
+    let end = dst.len() / 16 * 16;
+    for i in (0..end).step_by(16) {
+        let mut p = _mm_loadu_si128(src.as_ptr().add(i) as *const __m128i);
+        if swap {
+            let r = _mm_and_si128(_mm_srli_epi32(p, 16), low);
+            let b = _mm_slli_epi32(_mm_and_si128(p, low), 16);
+            p = _mm_or_si128(_mm_and_si128(p, keep), _mm_or_si128(r, b));
+        }
+        _mm_storeu_si128(dst.as_mut_ptr().add(i) as *mut __m128i, _mm_or_si128(p, opaque));
+    }
+    end
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn convert_avx2(dst: &mut [u8], src: &[u8], swap: bool, opaque: u32) -> usize {
+    let keep = _mm256_set1_epi32(0xff00_ff00u32 as i32);
+    let low = _mm256_set1_epi32(0xff);
+    let opaque = _mm256_set1_epi32(opaque as i32);
+    let end = dst.len() / 32 * 32;
+    for i in (0..end).step_by(32) {
+        let mut p = _mm256_loadu_si256(src.as_ptr().add(i) as *const __m256i);
+        if swap {
+            let r = _mm256_and_si256(_mm256_srli_epi32(p, 16), low);
+            let b = _mm256_slli_epi32(_mm256_and_si256(p, low), 16);
+            p = _mm256_or_si256(_mm256_and_si256(p, keep), _mm256_or_si256(r, b));
+        }
+        _mm256_storeu_si256(dst.as_mut_ptr().add(i) as *mut __m256i, _mm256_or_si256(p, opaque));
+    }
+    end
+}
+
+/**
+ * Computes `d * (255 - alpha of s) / 255` for the two pixels in each half
+ * of a vector widened to 16 bit lanes, rounded like `mul_div255`.
+ **/
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn scale_sse2(d: __m128i, s: __m128i) -> __m128i {
+    // broadcast each pixel's alpha (lane 3) to its four lanes
+    let alpha = _mm_shufflehi_epi16(_mm_shufflelo_epi16(s, 0xff), 0xff);
+    let inv = _mm_sub_epi16(_mm_set1_epi16(255), alpha);
+    let t = _mm_add_epi16(_mm_mullo_epi16(d, inv), _mm_set1_epi16(128));
+    _mm_srli_epi16(_mm_add_epi16(t, _mm_srli_epi16(t, 8)), 8)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "sse2")]
+unsafe fn over_sse2(dst: &mut [u8], src: &[u8], opaque: u32) -> usize {
+    let zero = _mm_setzero_si128();
+    let opaque = _mm_set1_epi32(opaque as i32);
+    let end = dst.len() / 16 * 16;
+    for i in (0..end).step_by(16) {
+        let s = _mm_loadu_si128(src.as_ptr().add(i) as *const __m128i);
+        let d = _mm_loadu_si128(dst.as_ptr().add(i) as *const __m128i);
+        let lo = scale_sse2(_mm_unpacklo_epi8(d, zero), _mm_unpacklo_epi8(s, zero));
+        let hi = scale_sse2(_mm_unpackhi_epi8(d, zero), _mm_unpackhi_epi8(s, zero));
+        let result = _mm_adds_epu8(s, _mm_packus_epi16(lo, hi));
+        _mm_storeu_si128(dst.as_mut_ptr().add(i) as *mut __m128i, _mm_or_si128(result, opaque));
+    }
+    end
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn scale_avx2(d: __m256i, s: __m256i) -> __m256i {
+    let alpha = _mm256_shufflehi_epi16(_mm256_shufflelo_epi16(s, 0xff), 0xff);
+    let inv = _mm256_sub_epi16(_mm256_set1_epi16(255), alpha);
+    let t = _mm256_add_epi16(_mm256_mullo_epi16(d, inv), _mm256_set1_epi16(128));
+    _mm256_srli_epi16(_mm256_add_epi16(t, _mm256_srli_epi16(t, 8)), 8)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn over_avx2(dst: &mut [u8], src: &[u8], opaque: u32) -> usize {
+    // unpacking and packing both work within 128 bit lanes, so pixels end
+    // up where they started
+    let zero = _mm256_setzero_si256();
+    let opaque = _mm256_set1_epi32(opaque as i32);
+    let end = dst.len() / 32 * 32;
+    for i in (0..end).step_by(32) {
+        let s = _mm256_loadu_si256(src.as_ptr().add(i) as *const __m256i);
+        let d = _mm256_loadu_si256(dst.as_ptr().add(i) as *const __m256i);
+        let lo = scale_avx2(_mm256_unpacklo_epi8(d, zero), _mm256_unpacklo_epi8(s, zero));
+        let hi = scale_avx2(_mm256_unpackhi_epi8(d, zero), _mm256_unpackhi_epi8(s, zero));
+        let result = _mm256_adds_epu8(s, _mm256_packus_epi16(lo, hi));
+        _mm256_storeu_si256(dst.as_mut_ptr().add(i) as *mut __m256i, _mm256_or_si256(result, opaque));
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::blend::Operator;
+    use super::super::drm::Color;
+
+    /**
+     * A xorshift generator, so that the property tests are reproducible.
+     **/
+    struct Rng(u32);
+
+    impl Rng {
+        fn next(&mut self) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            self.0
+        }
+
+        fn bytes(&mut self, len: usize) -> Vec<u8> {
+            (0..len).map(|_| self.next() as u8).collect()
+        }
+
+        /**
+         * Random premultiplied pixels, with plenty of fully transparent and
+         * opaque ones.
+         **/
+        fn premultiplied(&mut self, pixels: usize) -> Vec<u8> {
+            let mut out = Vec::with_capacity(pixels * 4);
+            for _ in 0..pixels {
+                let a = match self.next() % 4 {
+                    0 => 0,
+                    1 => 255,
+                    _ => self.next() as u8
+                };
+                let c = Color::new(self.next() as u8, self.next() as u8, self.next() as u8, a).premultiply();
+                out.extend_from_slice(&[c.b, c.g, c.r, c.a]);
+            }
+            out
+        }
+    }
+
+    const FORMATS: [Format; 4] = [Format::Xrgb8888, Format::Argb8888, Format::Xbgr8888, Format::Abgr8888];
+
+    /**
+     * Runs `check` on rows of many lengths and offsets, so that every
+     * kernel sees both whole vectors and scalar tails.
+     **/
+    fn for_rows<F: FnMut(&mut Rng, usize, usize)>(mut check: F) {
+        let mut rng = Rng(0x1234_5678);
+        for pixels in 0..70 {
+            for offset in 0..4 {
+                check(&mut rng, pixels * 4, offset);
+            }
+        }
+    }
+
+    #[test]
+    fn fill_matches_scalar() {
+        for_rows(|rng, len, offset| {
+            let pixel = rng.next();
+            let mut expected = rng.bytes(len + offset);
+            let original = expected.clone();
+            fill_with(Level::Scalar, &mut expected[offset..], pixel);
+            for &level in Level::available().iter() {
+                let mut row = original.clone();
+                fill_with(level, &mut row[offset..], pixel);
+                assert_eq!(row, expected, "{:?}", level);
+            }
+        });
+    }
+
+    #[test]
+    fn copy_matches_scalar() {
+        for_rows(|rng, len, offset| {
+            let src = rng.bytes(len);
+            for &level in Level::available().iter() {
+                let mut row = rng.bytes(len + offset);
+                copy_with(level, &mut row[offset..], &src);
+                assert_eq!(&row[offset..], &src[..], "{:?}", level);
+            }
+        });
+    }
+
+    #[test]
+    fn convert_matches_format() {
+        for_rows(|rng, len, offset| {
+            let src = rng.premultiplied(len / 4);
+            for &from in FORMATS.iter() {
+                for &to in FORMATS.iter() {
+                    let mut expected = vec![0; len];
+                    for (d, s) in expected.chunks_mut(4).zip(src.chunks(4)) {
+                        to.write(d, &from.read(s));
+                    }
+                    for &level in Level::available().iter() {
+                        let mut row = rng.bytes(len + offset);
+                        convert_with(level, &mut row[offset..], to, &src, from);
+                        assert_eq!(&row[offset..], &expected[..], "{:?} {:?} -> {:?}", level, from, to);
+                    }
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn over_matches_operator() {
+        for_rows(|rng, len, offset| {
+            let dst = rng.premultiplied(len / 4);
+            // random bytes have colors above their alpha, which saturate
+            for src in [rng.premultiplied(len / 4), rng.bytes(len)] {
+                for &format in FORMATS.iter() {
+                    // the source always has alpha, in the same channel order
+                    let src_format = match format {
+                        Format::Xrgb8888 => Format::Argb8888,
+                        Format::Xbgr8888 => Format::Abgr8888,
+                        f => f
+                    };
+                    let mut expected = dst.clone();
+                    for (d, s) in expected.chunks_mut(4).zip(src.chunks(4)) {
+                        let c = Operator::Over.blend(&src_format.read(s), &format.read(d));
+                        format.write(d, &c);
+                    }
+                    for &level in Level::available().iter() {
+                        let mut row = rng.bytes(offset);
+                        row.extend_from_slice(&dst);
+                        over_with(level, &mut row[offset..], format, &src);
+                        assert_eq!(&row[offset..], &expected[..], "{:?} {:?}", level, format);
+                    }
+                }
+            }
+        });
+    }
+}
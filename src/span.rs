@@ -0,0 +1,76 @@
+/*!
+ * Row based pixel kernels. Rows are byte slices in the framebuffer's own
+ * format; 32 bit formats go through the vectorized kernels in `simd`, and
+ * 16 bit formats are processed as `u16` words whenever the row is suitably
+ * aligned.
+ **/
+
+use super::simd;
+
+/**
+ * Sets every pixel of `row` to the packed `pixel` value.
+ **/
+pub fn fill_row(row: &mut [u8], pixel: u32, bpp: u32) {
+    match bpp {
+        4 => simd::fill(row, pixel),
+        2 => {
+            let (head, halves, tail) = unsafe { row.align_to_mut::<u16>() };
+            if head.is_empty() && tail.is_empty() {
+                let pixel = (pixel as u16).to_le();
+                for half in halves.iter_mut() {
+                    *half = pixel;
+                }
+            } else {
+                fill_row_bytes(row, pixel, 2);
+            }
+        }
+        bpp => fill_row_bytes(row, pixel, bpp)
+    }
+}
+
+fn fill_row_bytes(row: &mut [u8], pixel: u32, bpp: u32) {
+    let bytes = [pixel as u8, (pixel >> 8) as u8, (pixel >> 16) as u8, (pixel >> 24) as u8];
+    for chunk in row.chunks_mut(bpp as usize) {
+        let len = chunk.len();
+        chunk.copy_from_slice(&bytes[..len]);
+    }
+}
+
+/**
+ * Fills `height` rows of `row_len` bytes starting at `offset`. The first
+ * row is filled pixel by pixel, all others are copied from it.
+ **/
+pub fn fill_rows(pixels: &mut [u8], offset: usize, stride: usize, row_len: usize, height: usize, pixel: u32, bpp: u32) {
+    if height == 0 {
+        return;
+    }
+
+    let (first, rest) = pixels[offset..].split_at_mut(row_len);
+    fill_row(first, pixel, bpp);
+
+    for row in 1..height {
+        let start = row * stride - row_len;
+        rest[start..start + row_len].copy_from_slice(first);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_unaligned_rows() {
+        let mut buffer = [0u8; 11];
+        fill_row(&mut buffer[1..9], 0x11223344, 4);
+        assert_eq!(buffer, [0, 0x44, 0x33, 0x22, 0x11, 0x44, 0x33, 0x22, 0x11, 0, 0]);
+    }
+
+    #[test]
+    fn fills_rows_with_stride() {
+        let mut buffer = [0u8; 24];
+        fill_rows(&mut buffer, 2, 8, 4, 3, 0xabcd, 2);
+        assert_eq!(&buffer[..8], &[0, 0, 0xcd, 0xab, 0xcd, 0xab, 0, 0]);
+        assert_eq!(&buffer[8..16], &buffer[..8]);
+        assert_eq!(&buffer[16..], &buffer[..8]);
+    }
+}
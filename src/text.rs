@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use super::atlas::{Atlas, Mask, MaskFormat};
+use super::drm::Rect;
+
+/**
+ * The font compiled into the binary, used until another one is loaded.
+ **/
+const DEFAULT_FONT: &str = include_str!("fonts/font8x8.bdf");
+
+/**
+ * Size of the atlas pages glyphs of bitmap fonts are cached in.
+ **/
+const ATLAS_SIZE: u32 = 256;
+/// Bounding boxes in BDF files are checked against this, in pixels.
+const MAX_GLYPH_SIZE: i32 = 1024;
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE_512: u8 = 0x01;
+const PSF1_MODE_HAS_TABLE: u8 = 0x02;
+const PSF1_SEPARATOR: u16 = 0xffff;
+const PSF1_START_SEQ: u16 = 0xfffe;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xff;
+const PSF2_START_SEQ: u8 = 0xfe;
+
+/**
+ * A rendered glyph: a coverage mask and where to place it relative to the
+ * pen position on the top of the line.
+ **/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyph {
+    pub width: u32,
+    pub height: u32,
+    /// Horizontal offset of the mask from the pen position.
+    pub left: i32,
+    /// Vertical offset of the mask from the top of the line.
+    pub top: i32,
+    /// How far to move the pen after this glyph.
+    pub advance: i32,
+    /// Coverage values from 0 (empty) to 255 (covered).
+    pub mask: Mask
+}
+
+/**
+ * Something that produces glyphs for characters. Fonts are shared with the
+ * threads of the tiled renderer, so they must be `Send`.
+ **/
+pub trait Font: Send {
+    /**
+     * Distance between the top of a line and the baseline.
+     **/
+    fn ascent(&self) -> i32;
+
+    fn line_height(&self) -> i32;
+
+    /**
+     * Returns the glyph for `c`, or the font's replacement glyph.
+     **/
+    fn glyph(&mut self, c: char) -> Option<Arc<Glyph>>;
+
+    /**
+     * Adjustment of the advance between two consecutive characters.
+     **/
+    fn kerning(&mut self, _left: char, _right: char) -> i32 {
+        0
+    }
+}
+
+/**
+ * A monochrome bitmap font loaded from PSF or BDF data. Glyph masks are
+ * expanded from the bitmaps on first use and cached in an atlas.
+ **/
+#[derive(Debug)]
+pub struct BitmapFont {
+    ascent: i32,
+    line_height: i32,
+    bitmaps: Vec<Bitmap>,
+    chars: HashMap<char, usize>,
+    default: Option<usize>,
+    cache: HashMap<usize, Arc<Glyph>>,
+    atlas: Atlas
+}
+
+/**
+ * A glyph as stored in the font file, with one bit per pixel and rows
+ * padded to whole bytes.
+ **/
+#[derive(Debug, Clone)]
+struct Bitmap {
+    width: u32,
+    height: u32,
+    left: i32,
+    top: i32,
+    advance: i32,
+    bits: Vec<u8>
+}
+
+impl Bitmap {
+    fn render(&self, atlas: &mut Atlas) -> Glyph {
+        let row_len = self.width.div_ceil(8) as usize;
+        let len = (self.width as usize).checked_mul(self.height as usize).unwrap_or(0);
+        let mut mask = Vec::with_capacity(len);
+        for y in 0..self.height as usize {
+            for x in 0..self.width as usize {
+                let byte = self.bits.get(y * row_len + x / 8).cloned().unwrap_or(0);
+                mask.push(if byte & (0x80 >> (x % 8)) != 0 { 255 } else { 0 });
+            }
+        }
+        Glyph {
+            width: self.width,
+            height: self.height,
+            left: self.left,
+            top: self.top,
+            advance: self.advance,
+            mask: atlas.insert(self.width, self.height, &mask)
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> io::Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+        .ok_or_else(|| invalid("truncated header"))
+}
+
+impl BitmapFont {
+    /**
+     * The built-in 8x8 font.
+     **/
+    pub fn default_font() -> BitmapFont {
+        BitmapFont::from_bdf(DEFAULT_FONT).unwrap()
+    }
+
+    /**
+     * A copy of this font with every pixel blown up to a `factor` by
+     * `factor` square, for outputs drawn at a whole-number scale.
+     **/
+    pub fn scaled(&self, factor: u32) -> BitmapFont {
+        let factor = factor.max(1);
+        let bitmaps = self.bitmaps.iter().map(|bitmap| {
+            let (width, height) = (bitmap.width * factor, bitmap.height * factor);
+            let (row_len, src_row_len) = (width.div_ceil(8) as usize, bitmap.width.div_ceil(8) as usize);
+            let mut bits = vec![0; row_len * height as usize];
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    let (src_x, src_y) = (x / factor as usize, y / factor as usize);
+                    let byte = bitmap.bits.get(src_y * src_row_len + src_x / 8).cloned().unwrap_or(0);
+                    if byte & (0x80 >> (src_x % 8)) != 0 {
+                        bits[y * row_len + x / 8] |= 0x80 >> (x % 8);
+                    }
+                }
+            }
+            Bitmap {
+                width,
+                height,
+                left: bitmap.left * factor as i32,
+                top: bitmap.top * factor as i32,
+                advance: bitmap.advance * factor as i32,
+                bits
+            }
+        }).collect();
+        BitmapFont {
+            ascent: self.ascent * factor as i32,
+            line_height: self.line_height * factor as i32,
+            bitmaps,
+            chars: self.chars.clone(),
+            default: self.default,
+            cache: HashMap::new(),
+            atlas: Atlas::new(ATLAS_SIZE, MaskFormat::Alpha)
+        }
+    }
+
+    /**
+     * Loads a PSF (version 1 or 2) or BDF font, detected by its header.
+     **/
+    pub fn load(data: &[u8]) -> io::Result<BitmapFont> {
+        if data.starts_with(&PSF1_MAGIC) || data.starts_with(&PSF2_MAGIC) {
+            BitmapFont::from_psf(data)
+        } else if data.starts_with(b"STARTFONT") {
+            let text = ::std::str::from_utf8(data).map_err(|_| invalid("bdf is not valid utf-8"))?;
+            BitmapFont::from_bdf(text)
+        } else {
+            Err(invalid("unknown font format"))
+        }
+    }
+
+    pub fn from_psf(data: &[u8]) -> io::Result<BitmapFont> {
+        let (width, height, count, header_len, has_table, version) = if data.starts_with(&PSF1_MAGIC) {
+            let mode = *data.get(2).ok_or_else(|| invalid("truncated header"))?;
+            let height = *data.get(3).ok_or_else(|| invalid("truncated header"))? as u32;
+            let count = if mode & PSF1_MODE_512 != 0 { 512 } else { 256 };
+            (8, height, count, 4, mode & PSF1_MODE_HAS_TABLE != 0, 1)
+        } else {
+            let header_len = read_u32(data, 8)? as usize;
+            let flags = read_u32(data, 12)?;
+            let count = read_u32(data, 16)? as usize;
+            let height = read_u32(data, 24)?;
+            let width = read_u32(data, 28)?;
+            (width, height, count, header_len, flags & PSF2_HAS_UNICODE_TABLE != 0, 2)
+        };
+
+        let glyph_len = (width as usize).div_ceil(8).checked_mul(height as usize)
+            .filter(|&len| len > 0)
+            .ok_or_else(|| invalid("invalid glyph size"))?;
+        let table_start = count.checked_mul(glyph_len)
+            .and_then(|len| len.checked_add(header_len))
+            .ok_or_else(|| invalid("truncated glyph data"))?;
+        let glyphs = data.get(header_len..table_start).ok_or_else(|| invalid("truncated glyph data"))?;
+
+        let bitmaps = glyphs
+            .chunks(glyph_len)
+            .map(|bits| Bitmap {
+                width,
+                height,
+                left: 0,
+                top: 0,
+                advance: width as i32,
+                bits: bits.to_vec()
+            })
+            .collect();
+
+        let mut chars = HashMap::new();
+        if !has_table {
+            // without a table, glyphs are indexed by their code point
+            for i in 0..count.min(256) {
+                chars.insert(i as u8 as char, i);
+            }
+        } else if version == 1 {
+            let mut glyph = 0;
+            let mut in_sequence = false;
+            for entry in data[table_start..].chunks(2).filter(|e| e.len() == 2) {
+                let value = entry[0] as u16 | (entry[1] as u16) << 8;
+                if value == PSF1_SEPARATOR {
+                    glyph += 1;
+                    in_sequence = false;
+                } else if value == PSF1_START_SEQ {
+                    in_sequence = true;
+                } else if !in_sequence {
+                    if let Some(c) = ::std::char::from_u32(value as u32) {
+                        chars.entry(c).or_insert(glyph);
+                    }
+                }
+            }
+        } else {
+            for (glyph, entry) in data[table_start..].split(|&b| b == PSF2_SEPARATOR).enumerate() {
+                // combining sequences follow the single characters
+                let singles = entry.split(|&b| b == PSF2_START_SEQ).next().unwrap_or(&[]);
+                for c in String::from_utf8_lossy(singles).chars() {
+                    if c != '\u{fffd}' {
+                        chars.entry(c).or_insert(glyph);
+                    }
+                }
+            }
+        }
+
+        let default = chars.get(&'\u{fffd}').or_else(|| chars.get(&'?')).cloned();
+        Ok(BitmapFont {
+            ascent: height as i32 * 3 / 4,
+            line_height: height as i32,
+            bitmaps,
+            chars,
+            default,
+            cache: HashMap::new(),
+            atlas: Atlas::new(ATLAS_SIZE, MaskFormat::Alpha)
+        })
+    }
+
+    pub fn from_bdf(text: &str) -> io::Result<BitmapFont> {
+        let mut ascent = None;
+        let mut descent = None;
+        let mut bounding_box = None;
+        let mut default_char = None;
+        let mut bitmaps = Vec::new();
+        let mut chars = HashMap::new();
+
+        let mut lines = text.lines();
+        while let Some(line) = lines.next() {
+            let mut words = line.split_whitespace();
+            let parse = |w: Option<&str>| -> io::Result<i32> {
+                w.and_then(|w| w.parse().ok()).ok_or_else(|| invalid("malformed bdf line"))
+            };
+            match words.next() {
+                Some("FONT_ASCENT") => ascent = Some(parse(words.next())?),
+                Some("FONT_DESCENT") => descent = Some(parse(words.next())?),
+                Some("DEFAULT_CHAR") => default_char = Some(parse(words.next())?),
+                Some("FONTBOUNDINGBOX") => {
+                    let w = parse(words.next())?;
+                    let h = parse(words.next())?;
+                    let x = parse(words.next())?;
+                    let y = parse(words.next())?;
+                    bounding_box = Some((w, h, x, y));
+                }
+                Some("STARTCHAR") => {
+                    let mut encoding = -1;
+                    let mut advance = None;
+                    let mut bbx = bounding_box;
+                    let mut bits = Vec::new();
+                    while let Some(line) = lines.next() {
+                        let mut words = line.split_whitespace();
+                        match words.next() {
+                            Some("ENCODING") => encoding = parse(words.next())?,
+                            Some("DWIDTH") => advance = Some(parse(words.next())?),
+                            Some("BBX") => {
+                                let w = parse(words.next())?;
+                                let h = parse(words.next())?;
+                                let x = parse(words.next())?;
+                                let y = parse(words.next())?;
+                                bbx = Some((w, h, x, y));
+                            }
+                            Some("BITMAP") => {
+                                for row in lines.by_ref() {
+                                    if row.trim() == "ENDCHAR" {
+                                        break;
+                                    }
+                                    let digit = |b: u8| (b as char).to_digit(16).ok_or_else(|| invalid("malformed bdf bitmap"));
+                                    for pair in row.trim().as_bytes().chunks_exact(2) {
+                                        bits.push((digit(pair[0])? << 4 | digit(pair[1])?) as u8);
+                                    }
+                                }
+                                break;
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    let (w, h, x, y) = bbx.ok_or_else(|| invalid("glyph without bounding box"))?;
+                    let size = 0..=MAX_GLYPH_SIZE;
+                    let offset = -MAX_GLYPH_SIZE..=MAX_GLYPH_SIZE;
+                    if !size.contains(&w) || !size.contains(&h) || !offset.contains(&x) || !offset.contains(&y) {
+                        return Err(invalid("invalid glyph size"));
+                    }
+                    let font_ascent = ascent.unwrap_or(h + y);
+                    bitmaps.push(Bitmap {
+                        width: w as u32,
+                        height: h as u32,
+                        left: x,
+                        top: font_ascent.saturating_sub(h + y),
+                        advance: advance.unwrap_or(w),
+                        bits
+                    });
+                    if encoding >= 0 {
+                        if let Some(c) = ::std::char::from_u32(encoding as u32) {
+                            chars.insert(c, bitmaps.len() - 1);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (_, bbox_height, _, bbox_y) = bounding_box.ok_or_else(|| invalid("missing FONTBOUNDINGBOX"))?;
+        let ascent = ascent.unwrap_or(bbox_height + bbox_y);
+        let descent = descent.unwrap_or(-bbox_y);
+        let default = default_char
+            .and_then(|c| ::std::char::from_u32(c as u32))
+            .and_then(|c| chars.get(&c).cloned())
+            .or_else(|| chars.get(&'?').cloned());
+
+        Ok(BitmapFont {
+            ascent,
+            line_height: ascent + descent,
+            bitmaps,
+            chars,
+            default,
+            cache: HashMap::new(),
+            atlas: Atlas::new(ATLAS_SIZE, MaskFormat::Alpha)
+        })
+    }
+}
+
+impl Font for BitmapFont {
+    fn ascent(&self) -> i32 {
+        self.ascent
+    }
+
+    fn line_height(&self) -> i32 {
+        self.line_height
+    }
+
+    fn glyph(&mut self, c: char) -> Option<Arc<Glyph>> {
+        let index = self.chars.get(&c).cloned().or(self.default)?;
+        if let Some(glyph) = self.cache.get(&index) {
+            return Some(glyph.clone());
+        }
+
+        let glyph = Arc::new(self.bitmaps[index].render(&mut self.atlas));
+        self.cache.insert(index, glyph.clone());
+        Some(glyph)
+    }
+}
+
+/**
+ * Calls `place` with the pen position of every glyph in `text`, starting
+ * at `(x, y)` on the top of the first line.
+ **/
+pub fn layout<F: FnMut(i32, i32, &Arc<Glyph>)>(font: &mut dyn Font, x: i32, y: i32, text: &str, mut place: F) {
+    let (mut pen_x, mut pen_y) = (x, y);
+    let mut previous = None;
+    for c in text.chars() {
+        if c == '\n' {
+            pen_x = x;
+            pen_y += font.line_height();
+            previous = None;
+            continue;
+        }
+        if let Some(p) = previous {
+            pen_x += font.kerning(p, c);
+        }
+        if let Some(glyph) = font.glyph(c) {
+            place(pen_x, pen_y, &glyph);
+            pen_x += glyph.advance;
+        }
+        previous = Some(c);
+    }
+}
+
+/**
+ * Returns the size of the box `text` occupies when drawn at the origin.
+ **/
+pub fn measure(font: &mut dyn Font, text: &str) -> Rect {
+    let mut width = 0;
+    let mut lines = 1;
+    let mut pen = 0;
+    let mut previous = None;
+    for c in text.chars() {
+        if c == '\n' {
+            lines += 1;
+            pen = 0;
+            previous = None;
+            continue;
+        }
+        if let Some(p) = previous {
+            pen += font.kerning(p, c);
+        }
+        if let Some(glyph) = font.glyph(c) {
+            pen += glyph.advance;
+        }
+        width = width.max(pen);
+        previous = Some(c);
+    }
+    Rect::new(0, 0, width.max(0) as u32, (lines * font.line_height()) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_font_glyphs() {
+        let mut font = BitmapFont::default_font();
+        assert_eq!(font.line_height(), 8);
+        assert_eq!(font.ascent(), 7);
+
+        let a = font.glyph('A').unwrap();
+        assert_eq!((a.width, a.height, a.advance), (8, 8, 8));
+        let row: Vec<u8> = (0..8).map(|x| a.mask.coverage(x, 0)[0]).collect();
+        assert_eq!(row, vec![0, 0, 255, 255, 0, 0, 0, 0]);
+
+        // characters missing from the font use the replacement glyph
+        let missing = font.glyph('€').unwrap();
+        assert_eq!(missing, font.glyph('\u{fffd}').unwrap());
+    }
+
+    #[test]
+    fn measures_lines() {
+        let mut font = BitmapFont::default_font();
+        assert_eq!(measure(&mut font, "Hello"), Rect::new(0, 0, 40, 8));
+        assert_eq!(measure(&mut font, "Hi\nthere"), Rect::new(0, 0, 40, 16));
+        assert_eq!(measure(&mut font, ""), Rect::new(0, 0, 0, 8));
+    }
+
+    #[test]
+    fn scales_bitmaps() {
+        let mut font = BitmapFont::default_font().scaled(2);
+        assert_eq!(font.line_height(), 16);
+        assert_eq!(font.ascent(), 14);
+        assert_eq!(measure(&mut font, "Hello"), Rect::new(0, 0, 80, 16));
+
+        let a = font.glyph('A').unwrap();
+        assert_eq!((a.width, a.height, a.advance), (16, 16, 16));
+        for y in 0..2 {
+            let row: Vec<u8> = (0..16).map(|x| a.mask.coverage(x, y)[0]).collect();
+            assert_eq!(row, vec![0, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0]);
+        }
+    }
+
+    fn psf2(glyphs: &[[u8; 2]], table: &[u8]) -> Vec<u8> {
+        let mut data = PSF2_MAGIC.to_vec();
+        for &value in [0, 32, PSF2_HAS_UNICODE_TABLE, glyphs.len() as u32, 2, 2, 3].iter() {
+            data.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+        }
+        for glyph in glyphs {
+            data.extend_from_slice(glyph);
+        }
+        data.extend_from_slice(table);
+        data
+    }
+
+    #[test]
+    fn loads_psf2_with_unicode_table() {
+        let mut table = b"x\xff".to_vec();
+        table.extend_from_slice("é".as_bytes());
+        table.push(PSF2_START_SEQ);
+        table.extend_from_slice("e\u{301}".as_bytes());
+        table.push(PSF2_SEPARATOR);
+        let data = psf2(&[[0xe0, 0x00], [0x20, 0x40]], &table);
+        let mut font = BitmapFont::load(&data).unwrap();
+        assert_eq!(font.line_height(), 2);
+
+        let mask = |glyph: &Glyph| -> Vec<u8> {
+            (0..6).map(|i| glyph.mask.coverage(i % 3, i / 3)[0]).collect()
+        };
+        assert_eq!(mask(&font.glyph('x').unwrap()), vec![255, 255, 255, 0, 0, 0]);
+        assert_eq!(mask(&font.glyph('é').unwrap()), vec![0, 0, 255, 0, 255, 0]);
+    }
+
+    #[test]
+    fn rejects_corrupt_fonts() {
+        let valid = psf2(&[[0xe0, 0x00]], &[]);
+        for &(offset, value) in [(8, u32::MAX), (16, u32::MAX), (28, 0)].iter() {
+            let mut data = valid.clone();
+            data[offset..offset + 4].copy_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+            assert!(BitmapFont::from_psf(&data).is_err());
+        }
+
+        let bdf = "STARTFONT 2.1\nFONTBOUNDINGBOX 8 1 0 0\nSTARTCHAR A\nENCODING 65\nBBX 8 1 0 0\nBITMAP\n0é\nENDCHAR\nENDFONT\n";
+        assert!(BitmapFont::from_bdf(bdf).is_err());
+        assert!(BitmapFont::from_bdf(&bdf.replace("0é", "ff")).is_ok());
+        for bbx in ["BBX -8 1 0 0", "BBX 8 -1 0 0", "BBX 100000 100000 0 0", "BBX 8 1 0 2147483647"].iter() {
+            assert!(BitmapFont::from_bdf(&bdf.replace("0é", "ff").replace("BBX 8 1 0 0", bbx)).is_err());
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::Mutex;
+use std::thread;
+
+/**
+ * One thread per CPU, or `$RENDER_THREADS`.
+ **/
+pub fn default_threads() -> usize {
+    std::env::var("RENDER_THREADS").ok().and_then(|n| n.parse().ok())
+        .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+}
+
+/**
+ * Runs work on a fixed number of threads. The threads are scoped to each
+ * call, so jobs can borrow from the caller.
+ **/
+pub struct ThreadPool {
+    threads: usize
+}
+
+impl ThreadPool {
+    pub fn new(threads: usize) -> ThreadPool {
+        ThreadPool { threads: threads.max(1) }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /**
+     * Calls `f` on every item on the worker threads and waits until all
+     * calls have returned. Panics if one of them did.
+     **/
+    pub fn for_each<T: Send, F: Fn(T) + Sync>(&self, items: Vec<T>, f: F) {
+        let workers = self.threads.min(items.len());
+        let items = Mutex::new(items.into_iter());
+        let (items, f) = (&items, &f);
+        thread::scope(|scope| {
+            for i in 0..workers {
+                thread::Builder::new().name(format!("render-{}", i)).spawn_scoped(scope, move || loop {
+                    let item = match items.lock().unwrap().next() {
+                        Some(item) => item,
+                        None => return
+                    };
+                    f(item);
+                }).unwrap();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn runs_borrowed_jobs() {
+        let pool = ThreadPool::new(3);
+        let mut values = vec![0u32; 100];
+        let sum = AtomicUsize::new(0);
+        for _ in 0..2 {
+            pool.for_each(values.iter_mut().enumerate().collect(), |(i, v): (usize, &mut u32)| {
+                *v += i as u32;
+                sum.fetch_add(i, Ordering::SeqCst);
+            });
+        }
+        assert_eq!(values[99], 198);
+        assert_eq!(sum.load(Ordering::SeqCst), 2 * 4950);
+    }
+
+    #[test]
+    fn survives_panics() {
+        let pool = ThreadPool::new(2);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| pool.for_each(vec![0, 1], |i: i32| assert_eq!(i, 0))));
+        assert!(result.is_err());
+        pool.for_each(vec![1, 2], |_| {});
+    }
+}
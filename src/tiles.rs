@@ -0,0 +1,113 @@
+use super::backend::Backend;
+use super::drm::Rect;
+use super::format::Format;
+
+/**
+ * Height of the tiles the screen is split into.
+ **/
+pub const TILE_HEIGHT: u32 = 64;
+
+/**
+ * A band of full rows of another backend's pixels. Tiles span the whole
+ * width so that each one is a contiguous part of the framebuffer, which
+ * lets several threads draw into the same framebuffer at once.
+ **/
+pub struct Tile<'a> {
+    pixels: &'a mut [u8],
+    width: u32,
+    height: u32,
+    stride: u32,
+    format: Format,
+    area: Rect
+}
+
+/**
+ * Splits the pixels of `backend` into tiles of `height` rows, which borrow
+ * the backend until they are dropped.
+ **/
+pub fn split(backend: &mut dyn Backend, height: u32) -> Vec<Tile<'_>> {
+    let (width, screen_height) = (backend.width(), backend.height());
+    let (stride, format) = (backend.stride(), backend.format());
+    let row_len = (width * format.bytes_per_pixel()) as usize;
+    let pixels = backend.pixels_mut();
+
+    let mut tiles = Vec::new();
+    for (i, band) in pixels.chunks_mut((height * stride) as usize).enumerate() {
+        let y = i as u32 * height;
+        if y >= screen_height {
+            break;
+        }
+        let rows = height.min(screen_height - y);
+        // the last row of the screen may end before the stride does
+        let (pixels, _) = band.split_at_mut((rows - 1) as usize * stride as usize + row_len);
+        tiles.push(Tile {
+            pixels,
+            width,
+            height: screen_height,
+            stride,
+            format,
+            area: Rect::new(0, y as i32, width, rows)
+        });
+    }
+    tiles
+}
+
+impl<'a> Backend for Tile<'a> {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn format(&self) -> Format {
+        self.format
+    }
+
+    fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn pixels(&self) -> &[u8] {
+        self.pixels
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        self.pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::backend::MemoryBackend;
+    use super::super::drm::Color;
+    use super::super::gfx::GFX;
+
+    #[test]
+    fn draws_through_tiles() {
+        let mut backend = MemoryBackend::new(8, 5, Format::Xrgb8888).unwrap();
+        let tiles = split(&mut backend, 2);
+        assert_eq!(tiles.iter().map(|t| t.area()).collect::<Vec<_>>(), vec![
+            Rect::new(0, 0, 8, 2), Rect::new(0, 2, 8, 2), Rect::new(0, 4, 8, 1)
+        ]);
+
+        let red = Color::new(255, 0, 0, 255);
+        for tile in tiles {
+            let mut g = GFX::with_backend(Box::new(tile));
+            assert_eq!((g.width(), g.height()), (8, 5));
+            g.rectangle(1, 1, 3, 3, &red);
+        }
+
+        let mut g = GFX::with_backend(Box::new(backend));
+        assert_eq!(g.get(1, 1), Some(red));
+        assert_eq!(g.get(3, 3), Some(red));
+        assert_eq!(g.get(4, 3), Some(Color::new(0, 0, 0, 255)));
+        assert_eq!(g.get(1, 4), Some(Color::new(0, 0, 0, 255)));
+    }
+}
@@ -0,0 +1,390 @@
+use std::io;
+
+use super::backend::{Backend, MemoryBackend};
+use super::drm::Rect;
+use super::ffi::{DRM_MODE_REFLECT_X, DRM_MODE_ROTATE_0, DRM_MODE_ROTATE_180, DRM_MODE_ROTATE_270, DRM_MODE_ROTATE_90};
+use super::format::Format;
+use super::frame_clock::FrameClock;
+use super::input::InputEvent;
+
+/**
+ * A 2D affine transform, mapping `(x, y)` to
+ * `(xx * x + xy * y + x0, yx * x + yy * y + y0)`.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affine {
+    pub xx: f32,
+    pub yx: f32,
+    pub xy: f32,
+    pub yy: f32,
+    pub x0: f32,
+    pub y0: f32
+}
+
+impl Affine {
+    pub fn identity() -> Affine {
+        Affine { xx: 1.0, yx: 0.0, xy: 0.0, yy: 1.0, x0: 0.0, y0: 0.0 }
+    }
+
+    pub fn translate(x: f32, y: f32) -> Affine {
+        Affine { x0: x, y0: y, ..Affine::identity() }
+    }
+
+    pub fn scale(x: f32, y: f32) -> Affine {
+        Affine { xx: x, yy: y, ..Affine::identity() }
+    }
+
+    /**
+     * Rotates around the origin. As y points down, positive angles turn
+     * clockwise on screen.
+     **/
+    pub fn rotate(radians: f32) -> Affine {
+        let (sin, cos) = radians.sin_cos();
+        Affine { xx: cos, yx: sin, xy: -sin, yy: cos, x0: 0.0, y0: 0.0 }
+    }
+
+    /**
+     * Mirrors left and right around the vertical line at `x = 0`.
+     **/
+    pub fn flip_horizontal() -> Affine {
+        Affine::scale(-1.0, 1.0)
+    }
+
+    pub fn flip_vertical() -> Affine {
+        Affine::scale(1.0, -1.0)
+    }
+
+    /**
+     * The transform that applies `self` first and then `next`.
+     **/
+    pub fn then(&self, next: &Affine) -> Affine {
+        Affine {
+            xx: next.xx * self.xx + next.xy * self.yx,
+            yx: next.yx * self.xx + next.yy * self.yx,
+            xy: next.xx * self.xy + next.xy * self.yy,
+            yy: next.yx * self.xy + next.yy * self.yy,
+            x0: next.xx * self.x0 + next.xy * self.y0 + next.x0,
+            y0: next.yx * self.x0 + next.yy * self.y0 + next.y0
+        }
+    }
+
+    pub fn invert(&self) -> Option<Affine> {
+        let det = self.xx * self.yy - self.xy * self.yx;
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let (xx, yx, xy, yy) = (self.yy / det, -self.yx / det, -self.xy / det, self.xx / det);
+        Some(Affine {
+            xx,
+            yx,
+            xy,
+            yy,
+            x0: -(xx * self.x0 + xy * self.y0),
+            y0: -(yx * self.x0 + yy * self.y0)
+        })
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.xx * x + self.xy * y + self.x0, self.yx * x + self.yy * y + self.y0)
+    }
+
+    /**
+     * The pixels touched by `rect` after transforming it.
+     **/
+    pub fn bounds(&self, rect: &Rect) -> Rect {
+        let (x0, y0, x1, y1) = (rect.x as f32, rect.y as f32, rect.right() as f32, rect.bottom() as f32);
+        let corners = [self.apply(x0, y0), self.apply(x1, y0), self.apply(x0, y1), self.apply(x1, y1)];
+        let min_x = corners.iter().fold(f32::MAX, |m, c| m.min(c.0)).floor() as i32;
+        let min_y = corners.iter().fold(f32::MAX, |m, c| m.min(c.1)).floor() as i32;
+        let max_x = corners.iter().fold(f32::MIN, |m, c| m.max(c.0)).ceil() as i32;
+        let max_y = corners.iter().fold(f32::MIN, |m, c| m.max(c.1)).ceil() as i32;
+        Rect::new(min_x, min_y, (max_x - min_x) as u32, (max_y - min_y) as u32)
+    }
+}
+
+/**
+ * How an output's picture is turned on its way to the panel: optionally
+ * mirrored left to right, then rotated counterclockwise. Named like the
+ * Wayland output transforms.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputTransform {
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    Flipped,
+    Flipped90,
+    Flipped180,
+    Flipped270
+}
+
+impl OutputTransform {
+    /**
+     * Parses `normal`, `90`, `180`, `270`, `flipped` and `flipped-90` etc.
+     **/
+    pub fn parse(s: &str) -> Option<OutputTransform> {
+        match s {
+            "normal" | "0" => Some(OutputTransform::Normal),
+            "90" => Some(OutputTransform::Rotate90),
+            "180" => Some(OutputTransform::Rotate180),
+            "270" => Some(OutputTransform::Rotate270),
+            "flipped" => Some(OutputTransform::Flipped),
+            "flipped-90" => Some(OutputTransform::Flipped90),
+            "flipped-180" => Some(OutputTransform::Flipped180),
+            "flipped-270" => Some(OutputTransform::Flipped270),
+            _ => None
+        }
+    }
+
+    fn parts(&self) -> (bool, u32) {
+        match *self {
+            OutputTransform::Normal => (false, 0),
+            OutputTransform::Rotate90 => (false, 90),
+            OutputTransform::Rotate180 => (false, 180),
+            OutputTransform::Rotate270 => (false, 270),
+            OutputTransform::Flipped => (true, 0),
+            OutputTransform::Flipped90 => (true, 90),
+            OutputTransform::Flipped180 => (true, 180),
+            OutputTransform::Flipped270 => (true, 270)
+        }
+    }
+
+    /**
+     * Whether width and height trade places, as for portrait monitors.
+     **/
+    pub fn swaps_axes(&self) -> bool {
+        self.parts().1 % 180 == 90
+    }
+
+    /**
+     * Size of the picture shown on a panel of the given physical size.
+     **/
+    pub fn logical_size(&self, width: u32, height: u32) -> (u32, u32) {
+        if self.swaps_axes() { (height, width) } else { (width, height) }
+    }
+
+    /**
+     * Maps logical coordinates of a `width` x `height` picture to the
+     * physical coordinates on the panel.
+     **/
+    pub fn affine(&self, width: u32, height: u32) -> Affine {
+        let (w, h) = (width as f32, height as f32);
+        let (flipped, degrees) = self.parts();
+        let flip = if flipped {
+            Affine::flip_horizontal().then(&Affine::translate(w, 0.0))
+        } else {
+            Affine::identity()
+        };
+        let rotation = match degrees {
+            0 => Affine::identity(),
+            90 => Affine { xx: 0.0, yx: -1.0, xy: 1.0, yy: 0.0, x0: 0.0, y0: w },
+            180 => Affine { xx: -1.0, yx: 0.0, xy: 0.0, yy: -1.0, x0: w, y0: h },
+            _ => Affine { xx: 0.0, yx: 1.0, xy: -1.0, yy: 0.0, x0: h, y0: 0.0 }
+        };
+        flip.then(&rotation)
+    }
+
+    /**
+     * Value of the plane `rotation` property with the same effect.
+     **/
+    pub fn drm_rotation(&self) -> u64 {
+        let (flipped, degrees) = self.parts();
+        let rotation = match degrees {
+            0 => DRM_MODE_ROTATE_0,
+            90 => DRM_MODE_ROTATE_90,
+            180 => DRM_MODE_ROTATE_180,
+            _ => DRM_MODE_ROTATE_270
+        };
+        (rotation | if flipped { DRM_MODE_REFLECT_X } else { 0 }) as u64
+    }
+}
+
+/**
+ * Lets GFX draw upright onto a rotated or mirrored output when the display
+ * hardware can't turn the picture itself. Drawing goes to a buffer in
+ * logical coordinates whose damaged parts are copied to the output, turned
+ * around, on every flush.
+ **/
+pub struct TransformedBackend {
+    inner: Box<dyn Backend>,
+    transform: OutputTransform,
+    affine: Affine,
+    buffer: MemoryBackend,
+    damage: Vec<Rect>
+}
+
+impl TransformedBackend {
+    pub fn new(inner: Box<dyn Backend>, transform: OutputTransform) -> io::Result<TransformedBackend> {
+        let (width, height) = transform.logical_size(inner.width(), inner.height());
+        Ok(TransformedBackend {
+            affine: transform.affine(width, height),
+            buffer: MemoryBackend::new(width, height, inner.format())?,
+            inner,
+            transform,
+            damage: Vec::new()
+        })
+    }
+
+    pub fn transform(&self) -> OutputTransform {
+        self.transform
+    }
+
+    /**
+     * Copies `rect` of the logical buffer to the output. The transforms
+     * only ever map pixels to pixels, so each logical row becomes a walk
+     * through the output with a fixed step.
+     **/
+    fn copy(&mut self, rect: Rect) {
+        let format = self.inner.format();
+        let bpp = format.bytes_per_pixel() as isize;
+        let (src_stride, dst_stride) = (self.buffer.stride() as usize, self.inner.stride() as isize);
+        let step = self.affine.xx as isize * bpp + self.affine.yx as isize * dst_stride;
+        let src = self.buffer.pixels();
+        let dst = self.inner.pixels_mut();
+
+        for y in rect.y..rect.bottom() {
+            let (px, py) = self.affine.apply(rect.x as f32 + 0.5, y as f32 + 0.5);
+            let mut d = py.floor() as isize * dst_stride + px.floor() as isize * bpp;
+            let start = y as usize * src_stride + rect.x as usize * bpp as usize;
+            for pixel in src[start..start + rect.width as usize * bpp as usize].chunks(bpp as usize) {
+                dst[d as usize..d as usize + bpp as usize].copy_from_slice(pixel);
+                d += step;
+            }
+        }
+    }
+
+    fn to_logical(&self, x: i32, y: i32) -> (i32, i32) {
+        let (x, y) = self.affine.invert().unwrap().apply(x as f32 + 0.5, y as f32 + 0.5);
+        (x.floor() as i32, y.floor() as i32)
+    }
+}
+
+impl Backend for TransformedBackend {
+    fn width(&self) -> u32 {
+        self.buffer.width()
+    }
+
+    fn height(&self) -> u32 {
+        self.buffer.height()
+    }
+
+    fn format(&self) -> Format {
+        self.buffer.format()
+    }
+
+    fn stride(&self) -> u32 {
+        self.buffer.stride()
+    }
+
+    fn pixels(&self) -> &[u8] {
+        self.buffer.pixels()
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        self.buffer.pixels_mut()
+    }
+
+    fn damage(&mut self, rect: Rect) {
+        let rect = rect.intersect(&Rect::new(0, 0, self.width(), self.height()));
+        if !rect.is_empty() {
+            self.damage.push(rect);
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let damage: Vec<Rect> = self.damage.drain(..).collect();
+        for rect in damage {
+            self.copy(rect);
+            let physical = self.affine.bounds(&rect);
+            self.inner.damage(physical);
+        }
+        self.inner.flush()
+    }
+
+    fn size_mm(&self) -> Option<(u32, u32)> {
+        self.inner.size_mm().map(|(width, height)| self.transform.logical_size(width, height))
+    }
+
+    fn frame_clock(&self) -> io::Result<FrameClock> {
+        self.inner.frame_clock()
+    }
+
+    fn input_events(&mut self) -> Vec<InputEvent> {
+        let events = self.inner.input_events();
+        events.into_iter().map(|event| match event {
+            InputEvent::PointerPosition { x, y } => {
+                let (x, y) = self.to_logical(x, y);
+                InputEvent::PointerPosition { x, y }
+            }
+            event => event
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::drm::Color;
+
+    const ALL: [OutputTransform; 8] = [
+        OutputTransform::Normal, OutputTransform::Rotate90, OutputTransform::Rotate180, OutputTransform::Rotate270,
+        OutputTransform::Flipped, OutputTransform::Flipped90, OutputTransform::Flipped180, OutputTransform::Flipped270
+    ];
+
+    #[test]
+    fn composes_and_inverts() {
+        let t = Affine::scale(2.0, 3.0).then(&Affine::rotate(std::f32::consts::FRAC_PI_2)).then(&Affine::translate(10.0, 0.0));
+        let (x, y) = t.apply(1.0, 1.0);
+        assert!((x - 7.0).abs() < 1e-5 && (y - 2.0).abs() < 1e-5);
+        let (x, y) = t.invert().unwrap().apply(x, y);
+        assert!((x - 1.0).abs() < 1e-5 && (y - 1.0).abs() < 1e-5);
+        assert_eq!(Affine::flip_horizontal().bounds(&Rect::new(1, 2, 3, 4)), Rect::new(-4, 2, 3, 4));
+        assert!(Affine::scale(0.0, 1.0).invert().is_none());
+    }
+
+    #[test]
+    fn output_transforms_map_corners() {
+        // where the logical top left corner of a 4 x 2 picture ends up
+        let corners: Vec<(f32, f32)> = ALL.iter().map(|t| {
+            let (w, h) = t.logical_size(4, 2);
+            t.affine(w, h).apply(0.0, 0.0)
+        }).collect();
+        assert_eq!(corners, vec![
+            (0.0, 0.0), (0.0, 2.0), (4.0, 2.0), (4.0, 0.0),
+            (4.0, 0.0), (0.0, 0.0), (0.0, 2.0), (4.0, 2.0)
+        ]);
+        for t in ALL.iter() {
+            let (w, h) = t.logical_size(4, 2);
+            assert_eq!(t.affine(w, h).bounds(&Rect::new(0, 0, w, h)), Rect::new(0, 0, 4, 2));
+        }
+        assert_eq!(OutputTransform::parse("flipped-270"), Some(OutputTransform::Flipped270));
+        assert_eq!(OutputTransform::Flipped90.drm_rotation(), (DRM_MODE_ROTATE_90 | DRM_MODE_REFLECT_X) as u64);
+    }
+
+    #[test]
+    fn copies_rotated_pixels() {
+        let red = Color::new(255, 0, 0, 255);
+        for &t in ALL.iter() {
+            let output = MemoryBackend::new(4, 2, Format::Xrgb8888).unwrap();
+            let mut backend = TransformedBackend::new(Box::new(output), t).unwrap();
+            let (w, h) = (backend.width(), backend.height());
+            assert_eq!((w, h), t.logical_size(4, 2));
+
+            // mark the logical top left pixel
+            Format::Xrgb8888.write(backend.pixels_mut(), &red);
+            backend.damage(Rect::new(0, 0, w, h));
+            backend.flush().unwrap();
+
+            let (px, py) = t.affine(w, h).apply(0.5, 0.5);
+            let (px, py) = (px.floor() as u32, py.floor() as u32);
+            let inner = &backend.inner;
+            for y in 0..2 {
+                for x in 0..4 {
+                    let c = Format::Xrgb8888.read(&inner.pixels()[(y * inner.stride() + x * 4) as usize..]);
+                    assert_eq!(c == red, (x, y) == (px, py), "{:?} at {} {}", t, x, y);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,813 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use super::atlas::{Atlas, MaskFormat};
+use super::cff::Cff;
+use super::outline::Outline;
+use super::text::{Font, Glyph};
+
+/**
+ * Size of the atlas pages rendered glyphs are cached in.
+ **/
+const ATLAS_SIZE: u32 = 512;
+
+/**
+ * How deep composite glyphs may nest before giving up on them.
+ **/
+const MAX_COMPOSITE_DEPTH: u32 = 8;
+
+const ON_CURVE: u8 = 0x01;
+const X_SHORT: u8 = 0x02;
+const Y_SHORT: u8 = 0x04;
+const REPEAT: u8 = 0x08;
+const X_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+const GPOS_PAIR_ADJUSTMENT: u16 = 2;
+const GPOS_EXTENSION: u16 = 9;
+const VALUE_X_ADVANCE: u16 = 0x0004;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub fn read_u8(data: &[u8], offset: usize) -> io::Result<u8> {
+    data.get(offset).cloned().ok_or_else(|| invalid("truncated font"))
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> io::Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| (b[0] as u16) << 8 | b[1] as u16)
+        .ok_or_else(|| invalid("truncated font"))
+}
+
+pub fn read_i16(data: &[u8], offset: usize) -> io::Result<i16> {
+    read_u16(data, offset).map(|v| v as i16)
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> io::Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| (b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32)
+        .ok_or_else(|| invalid("truncated font"))
+}
+
+/**
+ * Reads a signed 2.14 fixed point number.
+ **/
+fn read_f2dot14(data: &[u8], offset: usize) -> io::Result<f32> {
+    read_i16(data, offset).map(|v| v as f32 / 16384.0)
+}
+
+#[derive(Debug)]
+enum Outlines {
+    /// TrueType quadratic outlines: the `loca` and `glyf` table offsets and
+    /// whether `loca` uses 32 bit entries.
+    Glyf { loca: usize, glyf: usize, long_offsets: bool },
+    /// Cubic outlines from a CFF table.
+    Cff(Cff)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CharMap {
+    SegmentDelta(usize),
+    SegmentedCoverage(usize)
+}
+
+/**
+ * A scalable font loaded from a TrueType or OpenType file. Glyphs are
+ * rasterized at the current pixel size on first use and cached in an atlas.
+ **/
+#[derive(Debug)]
+pub struct TrueTypeFont {
+    data: Vec<u8>,
+    units_per_em: u16,
+    ascender: i16,
+    descender: i16,
+    line_gap: i16,
+    glyph_count: u16,
+    h_metrics: usize,
+    hmtx: usize,
+    outlines: Outlines,
+    cmap: Option<CharMap>,
+    kern_pairs: HashMap<(u16, u16), i32>,
+    gpos_lookups: Vec<usize>,
+    size: f32,
+    antialias: MaskFormat,
+    cache: HashMap<u16, Arc<Glyph>>,
+    kerning_cache: HashMap<(char, char), i32>,
+    atlas: Atlas
+}
+
+impl TrueTypeFont {
+    /**
+     * Parses a TrueType (`glyf`) or OpenType (`CFF `) font, rendering glyphs
+     * `size` pixels per em.
+     **/
+    pub fn load(data: &[u8], size: f32) -> io::Result<TrueTypeFont> {
+        let tag = read_u32(data, 0)?;
+        if tag != 0x00010000 && &data[..4] != b"true" && &data[..4] != b"OTTO" {
+            return Err(invalid("not a TrueType or OpenType font"));
+        }
+
+        let mut tables = HashMap::new();
+        for i in 0..read_u16(data, 4)? as usize {
+            let record = 12 + i * 16;
+            let tag = data.get(record..record + 4).ok_or_else(|| invalid("truncated table directory"))?;
+            let offset = read_u32(data, record + 8)? as usize;
+            let length = read_u32(data, record + 12)? as usize;
+            if offset + length > data.len() {
+                return Err(invalid("table out of bounds"));
+            }
+            tables.insert([tag[0], tag[1], tag[2], tag[3]], offset);
+        }
+        let table = |tag: &[u8; 4]| tables.get(tag).cloned();
+        let required = |tag: &[u8; 4]| table(tag).ok_or_else(|| invalid("missing required table"));
+
+        let head = required(b"head")?;
+        let hhea = required(b"hhea")?;
+        let maxp = required(b"maxp")?;
+        let units_per_em = read_u16(data, head + 18)?;
+        if units_per_em == 0 {
+            return Err(invalid("invalid units per em"));
+        }
+
+        let outlines = if let Some(cff) = table(b"CFF ") {
+            Outlines::Cff(Cff::parse(data, cff)?)
+        } else {
+            Outlines::Glyf {
+                loca: required(b"loca")?,
+                glyf: required(b"glyf")?,
+                long_offsets: read_i16(data, head + 50)? != 0
+            }
+        };
+
+        let mut font = TrueTypeFont {
+            data: data.to_vec(),
+            units_per_em,
+            ascender: read_i16(data, hhea + 4)?,
+            descender: read_i16(data, hhea + 6)?,
+            line_gap: read_i16(data, hhea + 8)?,
+            glyph_count: read_u16(data, maxp + 4)?,
+            h_metrics: read_u16(data, hhea + 34)? as usize,
+            hmtx: required(b"hmtx")?,
+            outlines,
+            cmap: None,
+            kern_pairs: HashMap::new(),
+            gpos_lookups: Vec::new(),
+            size,
+            antialias: MaskFormat::Alpha,
+            cache: HashMap::new(),
+            kerning_cache: HashMap::new(),
+            atlas: Atlas::new(ATLAS_SIZE, MaskFormat::Alpha)
+        };
+        font.cmap = font.find_cmap(required(b"cmap")?)?;
+        if let Some(kern) = table(b"kern") {
+            font.kern_pairs = font.parse_kern(kern)?;
+        }
+        if let Some(gpos) = table(b"GPOS") {
+            font.gpos_lookups = font.kern_lookups(gpos)?;
+        }
+        Ok(font)
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    /**
+     * Changes the pixel size, dropping the glyphs rendered so far.
+     **/
+    pub fn set_size(&mut self, size: f32) {
+        self.size = size;
+        self.reset();
+    }
+
+    pub fn antialias(&self) -> MaskFormat {
+        self.antialias
+    }
+
+    /**
+     * Chooses between grayscale and subpixel antialiasing.
+     **/
+    pub fn set_antialias(&mut self, antialias: MaskFormat) {
+        self.antialias = antialias;
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.cache.clear();
+        self.kerning_cache.clear();
+        self.atlas = Atlas::new(ATLAS_SIZE, self.antialias);
+    }
+
+    fn scale(&self) -> f32 {
+        self.size / self.units_per_em as f32
+    }
+
+    /**
+     * Picks the best Unicode subtable: full repertoire (format 12) if
+     * available, the basic multilingual plane (format 4) otherwise.
+     **/
+    fn find_cmap(&self, cmap: usize) -> io::Result<Option<CharMap>> {
+        let data = &self.data;
+        let mut best = None;
+        for i in 0..read_u16(data, cmap + 2)? as usize {
+            let record = cmap + 4 + i * 8;
+            let platform = read_u16(data, record)?;
+            let encoding = read_u16(data, record + 2)?;
+            let offset = cmap + read_u32(data, record + 4)? as usize;
+            let unicode = platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10));
+            if !unicode {
+                continue;
+            }
+            match read_u16(data, offset)? {
+                12 => return Ok(Some(CharMap::SegmentedCoverage(offset))),
+                4 => best = Some(CharMap::SegmentDelta(offset)),
+                _ => {}
+            }
+        }
+        Ok(best)
+    }
+
+    /**
+     * Maps a character to a glyph index, 0 being the missing glyph.
+     **/
+    pub fn glyph_index(&self, c: char) -> u16 {
+        let c = c as u32;
+        let data = &self.data;
+        let index = match self.cmap {
+            Some(CharMap::SegmentDelta(table)) => (|| -> io::Result<u16> {
+                if c > 0xffff {
+                    return Ok(0);
+                }
+                let segments = read_u16(data, table + 6)? as usize / 2;
+                let ends = table + 14;
+                let starts = ends + segments * 2 + 2;
+                let deltas = starts + segments * 2;
+                let range_offsets = deltas + segments * 2;
+                for i in 0..segments {
+                    if (read_u16(data, ends + i * 2)? as u32) < c {
+                        continue;
+                    }
+                    let start = read_u16(data, starts + i * 2)? as u32;
+                    if start > c {
+                        return Ok(0);
+                    }
+                    let delta = read_u16(data, deltas + i * 2)?;
+                    let range_offset = read_u16(data, range_offsets + i * 2)? as usize;
+                    if range_offset == 0 {
+                        return Ok((c as u16).wrapping_add(delta));
+                    }
+                    let glyph = read_u16(data, range_offsets + i * 2 + range_offset + (c - start) as usize * 2)?;
+                    return Ok(if glyph == 0 { 0 } else { glyph.wrapping_add(delta) });
+                }
+                Ok(0)
+            })(),
+            Some(CharMap::SegmentedCoverage(table)) => (|| -> io::Result<u16> {
+                let (mut lo, mut hi) = (0, read_u32(data, table + 12)? as usize);
+                while lo < hi {
+                    let mid = (lo + hi) / 2;
+                    let group = table + 16 + mid * 12;
+                    let start = read_u32(data, group)?;
+                    let end = read_u32(data, group + 4)?;
+                    if c < start {
+                        hi = mid;
+                    } else if c > end {
+                        lo = mid + 1;
+                    } else {
+                        return Ok((read_u32(data, group + 8)? + c - start) as u16);
+                    }
+                }
+                Ok(0)
+            })(),
+            None => Ok(0)
+        };
+        index.ok().filter(|&i| i < self.glyph_count).unwrap_or(0)
+    }
+
+    /**
+     * Horizontal advance of a glyph in font units.
+     **/
+    pub fn advance(&self, glyph: u16) -> u16 {
+        let metric = (glyph as usize).min(self.h_metrics.saturating_sub(1));
+        read_u16(&self.data, self.hmtx + metric * 4).unwrap_or(0)
+    }
+
+    /**
+     * Returns the outline of a glyph in font units.
+     **/
+    pub fn outline(&self, glyph: u16) -> io::Result<Outline> {
+        match self.outlines {
+            Outlines::Glyf { .. } => self.glyf_outline(glyph, 0),
+            Outlines::Cff(ref cff) => cff.outline(&self.data, glyph)
+        }
+    }
+
+    fn glyf_outline(&self, glyph: u16, depth: u32) -> io::Result<Outline> {
+        let (loca, glyf, long_offsets) = match self.outlines {
+            Outlines::Glyf { loca, glyf, long_offsets } => (loca, glyf, long_offsets),
+            Outlines::Cff(_) => unreachable!()
+        };
+        let data = &self.data;
+        let (start, end) = if long_offsets {
+            (read_u32(data, loca + glyph as usize * 4)? as usize, read_u32(data, loca + glyph as usize * 4 + 4)? as usize)
+        } else {
+            (read_u16(data, loca + glyph as usize * 2)? as usize * 2, read_u16(data, loca + glyph as usize * 2 + 2)? as usize * 2)
+        };
+
+        let mut outline = Outline::new();
+        if start >= end {
+            // glyphs without contours, like the space
+            return Ok(outline);
+        }
+        let offset = glyf + start;
+        let contours = read_i16(data, offset)?;
+        if contours >= 0 {
+            self.simple_glyph(offset, contours as usize, &mut outline)?;
+        } else if depth < MAX_COMPOSITE_DEPTH {
+            self.composite_glyph(offset, depth, &mut outline)?;
+        }
+        Ok(outline)
+    }
+
+    fn simple_glyph(&self, offset: usize, contours: usize, outline: &mut Outline) -> io::Result<()> {
+        let data = &self.data;
+        let mut ends = Vec::with_capacity(contours);
+        for i in 0..contours {
+            ends.push(read_u16(data, offset + 10 + i * 2)? as usize);
+        }
+        let count = ends.last().map_or(0, |&end| end + 1);
+        let instructions = read_u16(data, offset + 10 + contours * 2)? as usize;
+        let mut pos = offset + 12 + contours * 2 + instructions;
+
+        let mut flags = Vec::with_capacity(count);
+        while flags.len() < count {
+            let flag = read_u8(data, pos)?;
+            pos += 1;
+            flags.push(flag);
+            if flag & REPEAT != 0 {
+                let repeat = read_u8(data, pos)?;
+                pos += 1;
+                for _ in 0..repeat {
+                    flags.push(flag);
+                }
+            }
+        }
+        flags.truncate(count);
+
+        let mut coordinates = |short: u8, same_or_positive: u8| -> io::Result<Vec<f32>> {
+            let mut values = Vec::with_capacity(count);
+            let mut value = 0i32;
+            for &flag in flags.iter() {
+                if flag & short != 0 {
+                    let delta = read_u8(data, pos)? as i32;
+                    pos += 1;
+                    value += if flag & same_or_positive != 0 { delta } else { -delta };
+                } else if flag & same_or_positive == 0 {
+                    value += read_i16(data, pos)? as i32;
+                    pos += 2;
+                }
+                values.push(value as f32);
+            }
+            Ok(values)
+        };
+        let xs = coordinates(X_SHORT, X_SAME_OR_POSITIVE)?;
+        let ys = coordinates(Y_SHORT, Y_SAME_OR_POSITIVE)?;
+
+        let mut first = 0;
+        for &end in ends.iter() {
+            if end < first || end >= count {
+                return Err(invalid("invalid contour"));
+            }
+            let points: Vec<(f32, f32, bool)> = (first..=end).map(|i| (xs[i], ys[i], flags[i] & ON_CURVE != 0)).collect();
+            contour(&points, outline);
+            first = end + 1;
+        }
+        Ok(())
+    }
+
+    fn composite_glyph(&self, offset: usize, depth: u32, outline: &mut Outline) -> io::Result<()> {
+        let data = &self.data;
+        let mut pos = offset + 10;
+        loop {
+            let flags = read_u16(data, pos)?;
+            let glyph = read_u16(data, pos + 2)?;
+            pos += 4;
+
+            let (arg1, arg2) = if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                pos += 4;
+                (read_i16(data, pos - 4)? as f32, read_i16(data, pos - 2)? as f32)
+            } else {
+                pos += 2;
+                (read_u8(data, pos - 2)? as i8 as f32, read_u8(data, pos - 1)? as i8 as f32)
+            };
+            // matching points instead of offsets is rare enough to ignore
+            let (dx, dy) = if flags & ARGS_ARE_XY_VALUES != 0 { (arg1, arg2) } else { (0.0, 0.0) };
+
+            let mut m = [1.0, 0.0, 0.0, 1.0, dx, dy];
+            if flags & WE_HAVE_A_SCALE != 0 {
+                m[0] = read_f2dot14(data, pos)?;
+                m[3] = m[0];
+                pos += 2;
+            } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                m[0] = read_f2dot14(data, pos)?;
+                m[3] = read_f2dot14(data, pos + 2)?;
+                pos += 4;
+            } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                m[0] = read_f2dot14(data, pos)?;
+                m[1] = read_f2dot14(data, pos + 2)?;
+                m[2] = read_f2dot14(data, pos + 4)?;
+                m[3] = read_f2dot14(data, pos + 6)?;
+                pos += 8;
+            }
+
+            outline.append(&self.glyf_outline(glyph, depth + 1)?, m);
+            if flags & MORE_COMPONENTS == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_kern(&self, kern: usize) -> io::Result<HashMap<(u16, u16), i32>> {
+        let data = &self.data;
+        let mut pairs = HashMap::new();
+        // only the Microsoft layout, Apple's starts with a 32 bit version
+        if read_u16(data, kern)? != 0 {
+            return Ok(pairs);
+        }
+        let mut subtable = kern + 4;
+        for _ in 0..read_u16(data, kern + 2)? {
+            let length = read_u16(data, subtable + 2)? as usize;
+            let coverage = read_u16(data, subtable + 4)?;
+            let horizontal = coverage & 0x1 != 0;
+            let minimum = coverage & 0x2 != 0;
+            let cross_stream = coverage & 0x4 != 0;
+            if coverage >> 8 == 0 && horizontal && !minimum && !cross_stream {
+                for i in 0..read_u16(data, subtable + 6)? as usize {
+                    let pair = subtable + 14 + i * 6;
+                    let key = (read_u16(data, pair)?, read_u16(data, pair + 2)?);
+                    *pairs.entry(key).or_insert(0) += read_i16(data, pair + 4)? as i32;
+                }
+            }
+            subtable += length;
+        }
+        Ok(pairs)
+    }
+
+    /**
+     * Collects the pair adjustment subtables of the `kern` feature.
+     **/
+    fn kern_lookups(&self, gpos: usize) -> io::Result<Vec<usize>> {
+        let data = &self.data;
+        let features = gpos + read_u16(data, gpos + 6)? as usize;
+        let lookups = gpos + read_u16(data, gpos + 8)? as usize;
+
+        let mut indices = Vec::new();
+        for i in 0..read_u16(data, features)? as usize {
+            let record = features + 2 + i * 6;
+            if data.get(record..record + 4).ok_or_else(|| invalid("truncated font"))? != b"kern" {
+                continue;
+            }
+            let feature = features + read_u16(data, record + 4)? as usize;
+            for j in 0..read_u16(data, feature + 2)? as usize {
+                let index = read_u16(data, feature + 4 + j * 2)?;
+                if !indices.contains(&index) {
+                    indices.push(index);
+                }
+            }
+        }
+        indices.sort();
+
+        let mut subtables = Vec::new();
+        for index in indices {
+            let lookup = lookups + read_u16(data, lookups + 2 + index as usize * 2)? as usize;
+            let kind = read_u16(data, lookup)?;
+            for i in 0..read_u16(data, lookup + 4)? as usize {
+                let subtable = lookup + read_u16(data, lookup + 6 + i * 2)? as usize;
+                if kind == GPOS_PAIR_ADJUSTMENT {
+                    subtables.push(subtable);
+                } else if kind == GPOS_EXTENSION && read_u16(data, subtable + 2)? == GPOS_PAIR_ADJUSTMENT {
+                    subtables.push(subtable + read_u32(data, subtable + 4)? as usize);
+                }
+            }
+        }
+        Ok(subtables)
+    }
+
+    /**
+     * Returns the horizontal adjustment between two glyphs in font units.
+     **/
+    pub fn glyph_kerning(&self, left: u16, right: u16) -> i32 {
+        if let Some(&value) = self.kern_pairs.get(&(left, right)) {
+            return value;
+        }
+        for &subtable in self.gpos_lookups.iter() {
+            if let Ok(Some(value)) = self.pair_adjustment(subtable, left, right) {
+                return value;
+            }
+        }
+        0
+    }
+
+    fn pair_adjustment(&self, subtable: usize, left: u16, right: u16) -> io::Result<Option<i32>> {
+        let data = &self.data;
+        let format = read_u16(data, subtable)?;
+        let coverage_index = match coverage(data, subtable + read_u16(data, subtable + 2)? as usize, left)? {
+            Some(index) => index,
+            None => return Ok(None)
+        };
+        let value_format1 = read_u16(data, subtable + 4)?;
+        let value_format2 = read_u16(data, subtable + 6)?;
+        let record_len = 2 * (value_format1.count_ones() + value_format2.count_ones()) as usize;
+        let x_advance = |record: usize| -> io::Result<i32> {
+            if value_format1 & VALUE_X_ADVANCE == 0 {
+                return Ok(0);
+            }
+            let skip = 2 * (value_format1 & (VALUE_X_ADVANCE - 1)).count_ones() as usize;
+            Ok(read_i16(data, record + skip)? as i32)
+        };
+
+        match format {
+            1 => {
+                let set = subtable + read_u16(data, subtable + 10 + coverage_index * 2)? as usize;
+                let stride = 2 + record_len;
+                let (mut lo, mut hi) = (0, read_u16(data, set)? as usize);
+                while lo < hi {
+                    let mid = (lo + hi) / 2;
+                    let record = set + 2 + mid * stride;
+                    let second = read_u16(data, record)?;
+                    if right < second {
+                        hi = mid;
+                    } else if right > second {
+                        lo = mid + 1;
+                    } else {
+                        return x_advance(record + 2).map(Some);
+                    }
+                }
+                Ok(None)
+            }
+            2 => {
+                let class1 = class(data, subtable + read_u16(data, subtable + 8)? as usize, left)? as usize;
+                let class2 = class(data, subtable + read_u16(data, subtable + 10)? as usize, right)? as usize;
+                let class1_count = read_u16(data, subtable + 12)? as usize;
+                let class2_count = read_u16(data, subtable + 14)? as usize;
+                if class1 >= class1_count || class2 >= class2_count {
+                    return Ok(None);
+                }
+                let record = subtable + 16 + (class1 * class2_count + class2) * record_len;
+                x_advance(record).map(Some)
+            }
+            _ => Ok(None)
+        }
+    }
+
+    fn render(&mut self, index: u16) -> Option<Glyph> {
+        let scale = self.scale();
+        let ascent = self.ascent();
+        let advance = (self.advance(index) as f32 * scale).round() as i32;
+        let outline = self.outline(index).ok()?;
+
+        Some(match outline.rasterize(scale, self.antialias) {
+            Some(coverage) => Glyph {
+                width: coverage.width,
+                height: coverage.height,
+                left: coverage.left,
+                top: ascent + coverage.top,
+                advance,
+                mask: self.atlas.insert(coverage.width, coverage.height, &coverage.data)
+            },
+            None => Glyph {
+                width: 0,
+                height: 0,
+                left: 0,
+                top: 0,
+                advance,
+                mask: self.atlas.insert(0, 0, &[])
+            }
+        })
+    }
+}
+
+impl Font for TrueTypeFont {
+    fn ascent(&self) -> i32 {
+        (self.ascender as f32 * self.scale()).round() as i32
+    }
+
+    fn line_height(&self) -> i32 {
+        ((self.ascender as i32 - self.descender as i32 + self.line_gap as i32) as f32 * self.scale()).round() as i32
+    }
+
+    fn glyph(&mut self, c: char) -> Option<Arc<Glyph>> {
+        let index = self.glyph_index(c);
+        if let Some(glyph) = self.cache.get(&index) {
+            return Some(glyph.clone());
+        }
+        let glyph = Arc::new(self.render(index)?);
+        self.cache.insert(index, glyph.clone());
+        Some(glyph)
+    }
+
+    fn kerning(&mut self, left: char, right: char) -> i32 {
+        if let Some(&value) = self.kerning_cache.get(&(left, right)) {
+            return value;
+        }
+        let units = self.glyph_kerning(self.glyph_index(left), self.glyph_index(right));
+        let value = (units as f32 * self.scale()).round() as i32;
+        self.kerning_cache.insert((left, right), value);
+        value
+    }
+}
+
+/**
+ * Converts a TrueType contour of on- and off-curve points to path commands.
+ * Two consecutive off-curve points imply an on-curve point between them.
+ **/
+fn contour(points: &[(f32, f32, bool)], outline: &mut Outline) {
+    let n = points.len();
+    if n == 0 {
+        return;
+    }
+    let mid = |a: (f32, f32, bool), b: (f32, f32, bool)| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+    let (start, rest) = if points[0].2 {
+        ((points[0].0, points[0].1), 1..n)
+    } else if points[n - 1].2 {
+        ((points[n - 1].0, points[n - 1].1), 0..n - 1)
+    } else {
+        (mid(points[0], points[n - 1]), 0..n)
+    };
+
+    outline.move_to(start.0, start.1);
+    let mut control: Option<(f32, f32)> = None;
+    for &(x, y, on_curve) in points[rest].iter() {
+        match (on_curve, control) {
+            (true, Some(c)) => {
+                outline.quad_to(c.0, c.1, x, y);
+                control = None;
+            }
+            (true, None) => outline.line_to(x, y),
+            (false, Some(c)) => {
+                outline.quad_to(c.0, c.1, (c.0 + x) / 2.0, (c.1 + y) / 2.0);
+                control = Some((x, y));
+            }
+            (false, None) => control = Some((x, y))
+        }
+    }
+    if let Some(c) = control {
+        outline.quad_to(c.0, c.1, start.0, start.1);
+    }
+    outline.close();
+}
+
+/**
+ * Looks up a glyph in an OpenType coverage table.
+ **/
+fn coverage(data: &[u8], table: usize, glyph: u16) -> io::Result<Option<usize>> {
+    match read_u16(data, table)? {
+        1 => {
+            let (mut lo, mut hi) = (0, read_u16(data, table + 2)? as usize);
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                let value = read_u16(data, table + 4 + mid * 2)?;
+                if glyph < value {
+                    hi = mid;
+                } else if glyph > value {
+                    lo = mid + 1;
+                } else {
+                    return Ok(Some(mid));
+                }
+            }
+            Ok(None)
+        }
+        2 => {
+            for i in 0..read_u16(data, table + 2)? as usize {
+                let range = table + 4 + i * 6;
+                let start = read_u16(data, range)?;
+                if glyph >= start && glyph <= read_u16(data, range + 2)? {
+                    return Ok(Some(read_u16(data, range + 4)? as usize + (glyph - start) as usize));
+                }
+            }
+            Ok(None)
+        }
+        _ => Ok(None)
+    }
+}
+
+/**
+ * Looks up a glyph in an OpenType class definition table; glyphs not
+ * listed are in class 0.
+ **/
+fn class(data: &[u8], table: usize, glyph: u16) -> io::Result<u16> {
+    match read_u16(data, table)? {
+        1 => {
+            let start = read_u16(data, table + 2)?;
+            let count = read_u16(data, table + 4)?;
+            if glyph >= start && glyph - start < count {
+                return read_u16(data, table + 6 + (glyph - start) as usize * 2);
+            }
+            Ok(0)
+        }
+        2 => {
+            for i in 0..read_u16(data, table + 2)? as usize {
+                let range = table + 4 + i * 6;
+                if glyph >= read_u16(data, range)? && glyph <= read_u16(data, range + 2)? {
+                    return read_u16(data, range + 4);
+                }
+            }
+            Ok(0)
+        }
+        _ => Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn dejavu(size: f32) -> Option<TrueTypeFont> {
+        // not every system ships DejaVu; skip instead of failing
+        let data = fs::read("/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf").ok()?;
+        Some(TrueTypeFont::load(&data, size).unwrap())
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(TrueTypeFont::load(b"STARTFONT 2.1\n", 12.0).is_err());
+    }
+
+    #[test]
+    fn contours_with_implied_points() {
+        let mut outline = Outline::new();
+        contour(&[(0.0, 0.0, false), (10.0, 0.0, false), (10.0, 10.0, true)], &mut outline);
+        let mut expected = Outline::new();
+        expected.move_to(10.0, 10.0);
+        expected.quad_to(0.0, 0.0, 5.0, 0.0);
+        expected.quad_to(10.0, 0.0, 10.0, 10.0);
+        expected.close();
+        assert_eq!(outline, expected);
+    }
+
+    #[test]
+    fn loads_dejavu() {
+        let mut font = match dejavu(16.0) {
+            Some(font) => font,
+            None => return
+        };
+        assert_eq!(font.units_per_em, 2048);
+        assert!(font.glyph_index('A') != 0);
+        assert_eq!(font.glyph_index('\u{10ffff}'), 0);
+        assert_eq!(font.ascent(), 15);
+        assert_eq!(font.line_height(), 19);
+
+        let space = font.glyph(' ').unwrap();
+        assert_eq!((space.width, space.height), (0, 0));
+        assert!(space.advance > 0);
+
+        // a capital sits on the baseline and reaches about 73% of the em
+        let a = font.glyph('A').unwrap();
+        assert_eq!(a.top + a.height as i32, font.ascent());
+        assert!(a.height >= 11 && a.height <= 13);
+        let covered = (0..a.height).flat_map(|y| (0..a.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| a.mask.coverage(x, y)[0] == 255)
+            .count();
+        assert!(covered > 10);
+
+        // composite glyphs pull in their components
+        let e = font.glyph('é').unwrap();
+        assert!(e.top < font.glyph('e').unwrap().top);
+    }
+
+    #[test]
+    fn dejavu_kerning() {
+        let mut font = match dejavu(32.0) {
+            Some(font) => font,
+            None => return
+        };
+        assert!(font.kerning('A', 'V') < 0);
+        assert_eq!(font.kerning('l', 'l'), 0);
+    }
+
+    #[test]
+    fn dejavu_subpixel() {
+        let mut font = match dejavu(16.0) {
+            Some(font) => font,
+            None => return
+        };
+        font.set_antialias(MaskFormat::Subpixel);
+        let o = font.glyph('o').unwrap();
+        assert_eq!(o.mask.format(), MaskFormat::Subpixel);
+        let fringes = (0..o.height).flat_map(|y| (0..o.width).map(move |x| (x, y)))
+            .map(|(x, y)| o.mask.coverage(x, y))
+            .filter(|c| c[0] != c[2])
+            .count();
+        assert!(fringes > 0);
+    }
+}
@@ -0,0 +1,1987 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use super::keyboard::{Action, GroupChange, Key, KeyGroup, KeyType, Keymap, Modifiers};
+use super::keysym::Keysym;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/**
+ * A keymap described by rules, model, layout, variant and options, the
+ * names users know from `setxkbmap`. Layouts and their variants are comma
+ * separated lists, one entry per group.
+ **/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Names {
+    pub rules: String,
+    pub model: String,
+    pub layout: String,
+    pub variant: String,
+    pub options: String
+}
+
+impl Default for Names {
+    fn default() -> Names {
+        Names {
+            rules: "evdev".to_string(),
+            model: "pc105".to_string(),
+            layout: "us".to_string(),
+            variant: String::new(),
+            options: String::new()
+        }
+    }
+}
+
+impl Names {
+    pub fn new(layout: &str, variant: &str, options: &str) -> Names {
+        Names { layout: layout.to_string(), variant: variant.to_string(), options: options.to_string(), ..Names::default() }
+    }
+
+    /**
+     * The names from `$XKB_DEFAULT_RULES`, `$XKB_DEFAULT_MODEL`,
+     * `$XKB_DEFAULT_LAYOUT`, `$XKB_DEFAULT_VARIANT` and
+     * `$XKB_DEFAULT_OPTIONS`, as libxkbcommon reads them.
+     **/
+    pub fn from_env() -> Names {
+        let mut names = Names::default();
+        for (var, field) in [
+            ("XKB_DEFAULT_RULES", &mut names.rules),
+            ("XKB_DEFAULT_MODEL", &mut names.model),
+            ("XKB_DEFAULT_LAYOUT", &mut names.layout),
+            ("XKB_DEFAULT_VARIANT", &mut names.variant),
+            ("XKB_DEFAULT_OPTIONS", &mut names.options)
+        ] {
+            if let Some(value) = env::var(var).ok().filter(|v| !v.is_empty()) {
+                *field = value;
+            }
+        }
+        names
+    }
+
+    fn layouts(&self) -> Vec<&str> {
+        self.layout.split(',').map(|l| l.trim()).collect()
+    }
+
+    fn variant(&self, index: usize) -> &str {
+        self.variant.split(',').nth(index).map_or("", |v| v.trim())
+    }
+}
+
+/**
+ * The include statements for each part of a keymap, such as
+ * `pc+de(nodeadkeys)+inet(evdev)` for the symbols.
+ **/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Components {
+    pub keycodes: String,
+    pub types: String,
+    pub compat: String,
+    pub symbols: String
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Keycodes,
+    Types,
+    Compat,
+    Symbols
+}
+
+impl Kind {
+    fn dir(self) -> &'static str {
+        match self {
+            Kind::Keycodes => "keycodes",
+            Kind::Types => "types",
+            Kind::Compat => "compat",
+            Kind::Symbols => "symbols"
+        }
+    }
+}
+
+impl Components {
+    fn get_mut(&mut self, kind: Kind) -> &mut String {
+        match kind {
+            Kind::Keycodes => &mut self.keycodes,
+            Kind::Types => &mut self.types,
+            Kind::Compat => &mut self.compat,
+            Kind::Symbols => &mut self.symbols
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Model,
+    Option,
+    Layout(Option<usize>),
+    Variant(Option<usize>)
+}
+
+struct RuleSet {
+    columns: Vec<Column>,
+    target: Option<Kind>,
+    /// Index of the layout the columns refer to.
+    index: usize,
+    applies: bool,
+    matched: bool
+}
+
+/**
+ * Expands `%m`, `%l`, `%v` and `%i`, optionally in parentheses, with a
+ * layout index in brackets or a merge prefix, in the value of a rule.
+ **/
+fn expand(value: &str, names: &Names, index: usize) -> String {
+    let layouts = names.layouts();
+    let mut out = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let prefix = chars.next_if(|&c| c == '+' || c == '|' || c == '_' || c == '-');
+        let paren = chars.next_if_eq(&'(').is_some();
+        let what = chars.next();
+        let mut index = index;
+        if chars.next_if_eq(&'[').is_some() {
+            let digits: String = chars.by_ref().take_while(|&c| c != ']').collect();
+            index = digits.parse().unwrap_or(index);
+        }
+        if paren {
+            chars.next_if_eq(&')');
+        }
+        let expanded = match what {
+            Some('m') => names.model.clone(),
+            Some('l') => layouts.get(index.wrapping_sub(1)).map_or(String::new(), |l| l.to_string()),
+            Some('v') => names.variant(index.wrapping_sub(1)).to_string(),
+            Some('i') => index.to_string(),
+            _ => String::new()
+        };
+        if !expanded.is_empty() {
+            out.extend(prefix);
+            if paren {
+                let _ = write!(out, "({})", expanded);
+            } else {
+                out.push_str(&expanded);
+            }
+        }
+    }
+    out
+}
+
+/**
+ * Adds a rule's value to a component: values starting with `+` or `|` go
+ * after what is there, others before it.
+ **/
+fn append(to: &mut String, value: &str) {
+    let merges = |s: &str| s.starts_with('+') || s.starts_with('|');
+    if merges(value) || to.is_empty() {
+        to.push_str(value);
+    } else if merges(to) {
+        to.insert_str(0, value);
+    }
+}
+
+/**
+ * Resolves names to components with the text of a rules file, such as
+ * `rules/evdev`. Rule sets without a layout index only apply to keymaps
+ * with one layout, and indexed ones only to keymaps with several. The first
+ * matching line of a set wins, except in sets matching options, where
+ * every matching line applies.
+ **/
+fn apply_rules(text: &str, names: &Names) -> io::Result<Components> {
+    let layouts = names.layouts();
+    let options: Vec<&str> = names.options.split(',').map(|o| o.trim()).filter(|o| !o.is_empty()).collect();
+    let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+    let mut components = Components::default();
+    let mut set: Option<RuleSet> = None;
+
+    let text = text.replace("\\\n", " ");
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split("//").next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let words: Vec<&str> = line.trim_start_matches('!').split_whitespace().collect();
+        let equals = words.iter().position(|&w| w == "=");
+        if line.starts_with('!') {
+            let equals = match equals {
+                Some(equals) => equals,
+                None => continue
+            };
+            if words[0].starts_with('$') {
+                groups.insert(words[0].to_string(), words[equals + 1..].iter().map(|w| w.to_string()).collect());
+                continue;
+            }
+            let mut columns = Vec::new();
+            for word in &words[..equals] {
+                let (name, index) = match word.find('[') {
+                    Some(i) => (&word[..i], word[i + 1..].trim_end_matches(']').parse().ok()),
+                    None => (*word, None)
+                };
+                columns.push(match name {
+                    "model" => Column::Model,
+                    "option" | "options" => Column::Option,
+                    "layout" => Column::Layout(index),
+                    "variant" => Column::Variant(index),
+                    _ => return Err(invalid(format!("rules line {}: unknown column {}", number + 1, word)))
+                });
+            }
+            let index = columns
+                .iter()
+                .filter_map(|&c| match c {
+                    Column::Layout(i) | Column::Variant(i) => Some(i),
+                    _ => None
+                })
+                .next();
+            let applies = match index {
+                Some(Some(i)) => layouts.len() > 1 && i >= 1 && i <= layouts.len(),
+                Some(None) => layouts.len() == 1,
+                None => true
+            };
+            let target = match words.get(equals + 1).cloned() {
+                Some("keycodes") => Some(Kind::Keycodes),
+                Some("types") => Some(Kind::Types),
+                Some("compat") => Some(Kind::Compat),
+                Some("symbols") => Some(Kind::Symbols),
+                _ => None
+            };
+            set = Some(RuleSet { columns, target, index: index.flatten().unwrap_or(1), applies, matched: false });
+            continue;
+        }
+
+        let set = match set.as_mut() {
+            Some(set) if set.applies && set.target.is_some() => set,
+            _ => continue
+        };
+        let options_set = set.columns.contains(&Column::Option);
+        let equals = match equals {
+            Some(equals) if equals == set.columns.len() && (!set.matched || options_set) => equals,
+            _ => continue
+        };
+        let matches = |pattern: &str, value: &str| {
+            pattern == "*" || pattern == value || groups.get(pattern).is_some_and(|g| g.iter().any(|v| v == value))
+        };
+        let all = set.columns.iter().zip(&words[..equals]).all(|(&column, &pattern)| match column {
+            Column::Model => matches(pattern, &names.model),
+            Column::Layout(i) => matches(pattern, layouts.get(i.unwrap_or(1) - 1).cloned().unwrap_or("")),
+            Column::Variant(i) => matches(pattern, names.variant(i.unwrap_or(1) - 1)),
+            Column::Option => options.iter().any(|o| matches(pattern, o))
+        });
+        if all {
+            let value = expand(&words[equals + 1..].join(" "), names, set.index);
+            append(components.get_mut(set.target.unwrap()), &value);
+            set.matched = true;
+        }
+    }
+    Ok(components)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    KeyName(String),
+    Str(String),
+    Num(i64),
+    Punct(char)
+}
+
+fn tokenize(text: &str) -> io::Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).cloned();
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '#' || (c == '/' && next == Some('/')) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                line += (chars[i] == '\n') as usize;
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' {
+            let mut s = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(invalid(format!("line {}: unterminated string", line))),
+                    Some('"') => break,
+                    Some('\\') => {
+                        i += 1;
+                        match chars.get(i) {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(&c) => s.push(c),
+                            None => {}
+                        }
+                    }
+                    Some(&c) => {
+                        line += (c == '\n') as usize;
+                        s.push(c);
+                    }
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push((Token::Str(s), line));
+        } else if c == '<' {
+            let end = chars[i..].iter().position(|&c| c == '>' || c == '\n').map(|n| i + n);
+            match end {
+                Some(end) if chars[end] == '>' => {
+                    tokens.push((Token::KeyName(chars[i + 1..end].iter().collect()), line));
+                    i = end + 1;
+                }
+                _ => return Err(invalid(format!("line {}: unterminated key name", line)))
+            }
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let value = match word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                Some(hex) => i64::from_str_radix(hex, 16).ok(),
+                None => word.split('.').next().and_then(|w| w.parse().ok())
+            };
+            match value {
+                Some(value) => tokens.push((Token::Num(value), line)),
+                None => return Err(invalid(format!("line {}: bad number {}", line, word)))
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), line));
+        } else {
+            tokens.push((Token::Punct(c), line));
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Ident(String),
+    KeyName(String),
+    Str(String),
+    Num(i64),
+    Index(String, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Assign(Box<Expr>, Box<Expr>),
+    List(Vec<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Pos(Box<Expr>),
+    Not(Box<Expr>)
+}
+
+/**
+ * How definitions from a statement or include combine with earlier ones.
+ * Plain statements and includes override.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Merge {
+    Override,
+    Augment,
+    Replace
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Stmt {
+    Include(String),
+    Var(Expr, Option<Expr>),
+    KeyCode(String, i64),
+    Alias(String, String),
+    Type(String, Vec<(Merge, Stmt)>),
+    Interpret(Expr, Vec<(Merge, Stmt)>),
+    Key(String, Vec<Expr>),
+    ModMap(String, Vec<Expr>),
+    /// Indicators, virtual modifier declarations and other statements
+    /// without an effect on the keymap.
+    Other
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SectionKind {
+    Part(Kind),
+    Geometry,
+    Keymap
+}
+
+#[derive(Debug, Clone)]
+struct Section {
+    kind: Kind,
+    name: String,
+    default: bool,
+    body: Vec<(Merge, Stmt)>
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize
+}
+
+impl Parser {
+    fn new(text: &str) -> io::Result<Parser> {
+        Ok(Parser { tokens: tokenize(text)?, pos: 0 })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + n).map(|(t, _)| t)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn error(&self, message: &str) -> io::Error {
+        let line = self.tokens.get(self.pos).or_else(|| self.tokens.last()).map_or(0, |&(_, line)| line);
+        invalid(format!("line {}: {}", line, message))
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(&Token::Punct(c)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, c: char) -> io::Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    /**
+     * Skips to the end of the current statement, over any nested blocks.
+     **/
+    fn skip_statement(&mut self) -> io::Result<()> {
+        let mut depth = 0;
+        loop {
+            match self.next() {
+                Some(Token::Punct('{')) => depth += 1,
+                Some(Token::Punct('}')) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.eat(';');
+                        return Ok(());
+                    }
+                }
+                Some(Token::Punct(';')) if depth == 0 => return Ok(()),
+                None => return Err(self.error("unexpected end of file")),
+                _ => {}
+            }
+        }
+    }
+
+    fn sections(&mut self, sections: &mut Vec<Section>) -> io::Result<()> {
+        while self.peek().is_some() && self.peek() != Some(&Token::Punct('}')) {
+            let mut default = false;
+            let kind = loop {
+                let word = match self.next() {
+                    Some(Token::Ident(word)) => word,
+                    _ => return Err(self.error("expected a section"))
+                };
+                match word.as_str() {
+                    "default" => default = true,
+                    "xkb_keymap" | "xkb_semantics" | "xkb_layout" => break SectionKind::Keymap,
+                    "xkb_keycodes" => break SectionKind::Part(Kind::Keycodes),
+                    "xkb_types" => break SectionKind::Part(Kind::Types),
+                    "xkb_compatibility" | "xkb_compat" | "xkb_compatibility_map" => break SectionKind::Part(Kind::Compat),
+                    "xkb_symbols" => break SectionKind::Part(Kind::Symbols),
+                    "xkb_geometry" => break SectionKind::Geometry,
+                    // flags such as `partial` or `alphanumeric_keys`
+                    _ => {}
+                }
+            };
+            let name = match self.peek().cloned() {
+                Some(Token::Str(name)) => {
+                    self.pos += 1;
+                    name
+                }
+                _ => String::new()
+            };
+            match kind {
+                SectionKind::Keymap => {
+                    self.expect('{')?;
+                    self.sections(sections)?;
+                    self.expect('}')?;
+                    self.eat(';');
+                }
+                SectionKind::Geometry => self.skip_statement()?,
+                SectionKind::Part(kind) => {
+                    self.expect('{')?;
+                    let body = self.body()?;
+                    sections.push(Section { kind, name, default, body });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Statements up to and including the closing brace.
+     **/
+    fn body(&mut self) -> io::Result<Vec<(Merge, Stmt)>> {
+        let mut statements = Vec::new();
+        while !self.eat('}') {
+            if self.peek().is_none() {
+                return Err(self.error("unexpected end of file"));
+            }
+            statements.push(self.statement()?);
+        }
+        self.eat(';');
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> io::Result<(Merge, Stmt)> {
+        let mut merge = Merge::Override;
+        if let Some(Token::Ident(word)) = self.peek() {
+            let mode = match word.as_str() {
+                "include" | "override" => Some(Merge::Override),
+                "augment" | "alternate" => Some(Merge::Augment),
+                "replace" => Some(Merge::Replace),
+                _ => None
+            };
+            if let Some(mode) = mode {
+                self.pos += 1;
+                merge = mode;
+                if let Some(Token::Str(spec)) = self.peek().cloned() {
+                    self.pos += 1;
+                    self.eat(';');
+                    return Ok((merge, Stmt::Include(spec)));
+                }
+            }
+        }
+
+        let (first, second) = (self.peek().cloned(), self.peek_at(1).cloned());
+        let keyword = match first {
+            Some(Token::Ident(ref word)) => word.as_str(),
+            _ => ""
+        };
+        let stmt = match (keyword, second) {
+            ("key", Some(Token::KeyName(name))) => {
+                self.pos += 2;
+                self.expect('{')?;
+                let items = self.list('}')?;
+                self.eat(';');
+                Stmt::Key(name, items)
+            }
+            ("type", Some(Token::Str(name))) => {
+                self.pos += 2;
+                self.expect('{')?;
+                Stmt::Type(name, self.body()?)
+            }
+            ("interpret", Some(second)) if second != Token::Punct('.') => {
+                self.pos += 1;
+                let head = self.expr()?;
+                self.expect('{')?;
+                Stmt::Interpret(head, self.body()?)
+            }
+            ("modifier_map", _) | ("modmap", _) | ("mod_map", _) => {
+                self.pos += 1;
+                let name = match self.next() {
+                    Some(Token::Ident(name)) => name,
+                    _ => return Err(self.error("expected a modifier"))
+                };
+                self.expect('{')?;
+                let items = self.list('}')?;
+                self.eat(';');
+                Stmt::ModMap(name, items)
+            }
+            ("alias", Some(Token::KeyName(alias))) => {
+                self.pos += 2;
+                self.expect('=')?;
+                let name = match self.next() {
+                    Some(Token::KeyName(name)) => name,
+                    _ => return Err(self.error("expected a key name"))
+                };
+                self.expect(';')?;
+                Stmt::Alias(alias, name)
+            }
+            ("indicator", Some(Token::Str(_))) | ("indicator", Some(Token::Num(_))) | ("group", Some(Token::Num(_))) | ("virtual_modifiers", _) => {
+                self.skip_statement()?;
+                Stmt::Other
+            }
+            _ => match first {
+                Some(Token::KeyName(name)) => {
+                    self.pos += 1;
+                    self.expect('=')?;
+                    let code = match self.next() {
+                        Some(Token::Num(code)) => code,
+                        _ => return Err(self.error("expected a keycode"))
+                    };
+                    self.expect(';')?;
+                    Stmt::KeyCode(name, code)
+                }
+                _ => {
+                    let lhs = self.expr()?;
+                    let rhs = if self.eat('=') { Some(self.expr()?) } else { None };
+                    self.expect(';')?;
+                    Stmt::Var(lhs, rhs)
+                }
+            }
+        };
+        Ok((merge, stmt))
+    }
+
+    /**
+     * Comma separated items, each an expression or an assignment, up to and
+     * including `end`.
+     **/
+    fn list(&mut self, end: char) -> io::Result<Vec<Expr>> {
+        let mut items = Vec::new();
+        while !self.eat(end) {
+            let item = self.expr()?;
+            items.push(if self.eat('=') { Expr::Assign(Box::new(item), Box::new(self.expr()?)) } else { item });
+            if !self.eat(',') {
+                self.expect(end)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn expr(&mut self) -> io::Result<Expr> {
+        let mut expr = self.unary()?;
+        loop {
+            if self.eat('+') {
+                expr = Expr::Add(Box::new(expr), Box::new(self.unary()?));
+            } else if self.eat('-') {
+                expr = Expr::Add(Box::new(expr), Box::new(Expr::Neg(Box::new(self.unary()?))));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn unary(&mut self) -> io::Result<Expr> {
+        if self.eat('-') {
+            Ok(Expr::Neg(Box::new(self.unary()?)))
+        } else if self.eat('+') {
+            Ok(Expr::Pos(Box::new(self.unary()?)))
+        } else if self.eat('!') || self.eat('~') {
+            Ok(Expr::Not(Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> io::Result<Expr> {
+        match self.next() {
+            Some(Token::Ident(mut name)) => {
+                while self.peek() == Some(&Token::Punct('.')) {
+                    self.pos += 1;
+                    match self.next() {
+                        Some(Token::Ident(field)) => {
+                            name.push('.');
+                            name.push_str(&field);
+                        }
+                        _ => return Err(self.error("expected a field name"))
+                    }
+                }
+                if self.eat('[') {
+                    let index = self.expr()?;
+                    self.expect(']')?;
+                    Ok(Expr::Index(name, Box::new(index)))
+                } else if self.eat('(') {
+                    Ok(Expr::Call(name, self.list(')')?))
+                } else {
+                    Ok(Expr::Ident(name))
+                }
+            }
+            Some(Token::KeyName(name)) => Ok(Expr::KeyName(name)),
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::Punct('[')) => Ok(Expr::List(self.list(']')?)),
+            Some(Token::Punct('{')) => Ok(Expr::List(self.list('}')?)),
+            Some(Token::Punct('(')) => {
+                let expr = self.expr()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.error("expected an expression"))
+            }
+        }
+    }
+}
+
+fn parse(text: &str) -> io::Result<Vec<Section>> {
+    let mut parser = Parser::new(text)?;
+    let mut sections = Vec::new();
+    parser.sections(&mut sections)?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected '}'"));
+    }
+    Ok(sections)
+}
+
+const MODIFIER_NAMES: [&str; 8] = ["Shift", "Lock", "Control", "Mod1", "Mod2", "Mod3", "Mod4", "Mod5"];
+
+/**
+ * A real or virtual modifier by name. Virtual modifiers are bound the way
+ * the usual compat files bind them, which keeps them out of the keymap.
+ **/
+fn modifier(name: &str) -> Option<Modifiers> {
+    if let Some(i) = MODIFIER_NAMES.iter().position(|n| n.eq_ignore_ascii_case(name)) {
+        return Some(Modifiers(1 << i));
+    }
+    let modifiers = match name.to_ascii_lowercase().as_str() {
+        "none" => Modifiers::NONE,
+        "all" => Modifiers::ALL,
+        "ctrl" => Modifiers::CTRL,
+        "caps" => Modifiers::CAPS_LOCK,
+        "numlock" => Modifiers::NUM_LOCK,
+        "alt" | "meta" => Modifiers::ALT,
+        "super" | "hyper" => Modifiers::SUPER,
+        "levelthree" | "altgr" => Modifiers::LEVEL3,
+        "levelfive" => Modifiers::MOD3,
+        _ => return None
+    };
+    Some(modifiers)
+}
+
+fn modifier_terms(expr: &Expr, terms: &mut Vec<Option<Modifiers>>) {
+    match expr {
+        Expr::Add(a, b) => {
+            modifier_terms(a, terms);
+            modifier_terms(b, terms);
+        }
+        Expr::Ident(name) => terms.push(modifier(name)),
+        Expr::Num(n) => terms.push(Some(Modifiers(*n as u32 & 0xff))),
+        _ => terms.push(None)
+    }
+}
+
+/**
+ * A modifier combination, or `None` if it names an unknown modifier.
+ **/
+fn modifiers(expr: &Expr) -> Option<Modifiers> {
+    let mut terms = Vec::new();
+    modifier_terms(expr, &mut terms);
+    terms.into_iter().try_fold(Modifiers::NONE, |all, m| m.map(|m| all | m))
+}
+
+/**
+ * A modifier combination, leaving out unknown modifiers.
+ **/
+fn known_modifiers(expr: &Expr) -> Modifiers {
+    let mut terms = Vec::new();
+    modifier_terms(expr, &mut terms);
+    terms.into_iter().flatten().fold(Modifiers::NONE, |all, m| all | m)
+}
+
+/**
+ * A zero-based index from `Level2`, `Group2` or `2`.
+ **/
+fn index(expr: &Expr, prefix: &str) -> Option<usize> {
+    let n = match expr {
+        Expr::Num(n) => *n as usize,
+        Expr::Ident(name) if name.len() > prefix.len() && name[..prefix.len()].eq_ignore_ascii_case(prefix) => {
+            name[prefix.len()..].parse().ok()?
+        }
+        _ => return None
+    };
+    n.checked_sub(1)
+}
+
+fn string(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Str(s) => Some(s.clone()),
+        _ => None
+    }
+}
+
+/**
+ * A keysym by name; digits stand for themselves and larger numbers for
+ * keysym values. Of several keysyms on one level, the first is used.
+ **/
+fn keysym(expr: &Expr) -> Keysym {
+    match expr {
+        Expr::Ident(name) => Keysym::from_name(name).unwrap_or(Keysym::NO_SYMBOL),
+        Expr::Num(n @ 0..=9) => Keysym::from_char((b'0' + *n as u8) as char),
+        Expr::Num(n) => Keysym(*n as u32),
+        Expr::List(syms) => syms.first().map_or(Keysym::NO_SYMBOL, keysym),
+        _ => Keysym::NO_SYMBOL
+    }
+}
+
+fn keysyms(expr: &Expr) -> Vec<Keysym> {
+    match expr {
+        Expr::List(items) => items.iter().map(keysym).collect(),
+        _ => vec![keysym(expr)]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModsDef {
+    Fixed(Modifiers),
+    /// `modMapMods`: the modifiers the key is bound to.
+    ModMap
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ActionDef {
+    Mods { lock: bool, mods: ModsDef },
+    Fixed(Action),
+    None
+}
+
+impl ActionDef {
+    /**
+     * Modifier and group actions. Latches count as sets; other actions,
+     * such as pointer keys, are dropped.
+     **/
+    fn parse(expr: &Expr) -> ActionDef {
+        let (name, args) = match expr {
+            Expr::Call(name, args) => (name.to_ascii_lowercase(), args),
+            _ => return ActionDef::None
+        };
+        let arg = |names: &[&str]| {
+            args.iter().find_map(|arg| match arg {
+                Expr::Assign(lhs, rhs) => match &**lhs {
+                    Expr::Ident(n) if names.iter().any(|name| n.eq_ignore_ascii_case(name)) => Some(&**rhs),
+                    _ => None
+                },
+                _ => None
+            })
+        };
+        let mods = || match arg(&["modifiers", "mods"]) {
+            Some(Expr::Ident(n)) if n.eq_ignore_ascii_case("modMapMods") => ModsDef::ModMap,
+            Some(expr) => ModsDef::Fixed(known_modifiers(expr)),
+            None => ModsDef::Fixed(Modifiers::NONE)
+        };
+        let group = || match arg(&["group"]) {
+            Some(Expr::Pos(n)) => index(n, "group").map(|g| GroupChange::Relative(g as i32 + 1)),
+            Some(Expr::Neg(n)) => index(n, "group").map(|g| GroupChange::Relative(-(g as i32 + 1))),
+            Some(expr) => index(expr, "group").map(GroupChange::Absolute),
+            None => None
+        };
+        match name.as_str() {
+            "setmods" | "latchmods" => ActionDef::Mods { lock: false, mods: mods() },
+            "lockmods" => ActionDef::Mods { lock: true, mods: mods() },
+            "setgroup" | "latchgroup" => group().map_or(ActionDef::None, |g| ActionDef::Fixed(Action::SetGroup(g))),
+            "lockgroup" => group().map_or(ActionDef::None, |g| ActionDef::Fixed(Action::LockGroup(g))),
+            _ => ActionDef::None
+        }
+    }
+
+    fn resolve(self, modmap: Modifiers) -> Option<Action> {
+        match self {
+            ActionDef::Mods { lock, mods } => {
+                let mods = match mods {
+                    ModsDef::Fixed(mods) => mods,
+                    ModsDef::ModMap => modmap
+                };
+                match (mods.is_empty(), lock) {
+                    (true, _) => None,
+                    (false, true) => Some(Action::LockMods(mods)),
+                    (false, false) => Some(Action::SetMods(mods))
+                }
+            }
+            ActionDef::Fixed(action) => Some(action),
+            ActionDef::None => None
+        }
+    }
+}
+
+#[derive(Default)]
+struct KeycodesInfo {
+    codes: BTreeMap<String, i64>,
+    aliases: BTreeMap<String, String>
+}
+
+#[derive(Default)]
+struct TypesInfo {
+    types: Vec<KeyType>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Predicate {
+    Exactly,
+    AllOf,
+    NoneOf,
+    AnyOf,
+    AnyOfOrNone
+}
+
+impl Predicate {
+    fn matches(self, modmap: Modifiers, mods: Modifiers) -> bool {
+        let common = modmap & mods;
+        match self {
+            Predicate::NoneOf => common.is_empty(),
+            Predicate::AnyOfOrNone => modmap.is_empty() || !common.is_empty(),
+            Predicate::AnyOf => !common.is_empty(),
+            Predicate::AllOf => common == mods,
+            Predicate::Exactly => modmap == mods
+        }
+    }
+}
+
+/**
+ * A compat rule giving keys with a keysym, or any keysym, an action.
+ **/
+#[derive(Debug, Clone)]
+struct Interpret {
+    sym: Option<Keysym>,
+    predicate: Predicate,
+    mods: Modifiers,
+    action: ActionDef,
+    level_one_only: bool
+}
+
+#[derive(Default)]
+struct CompatInfo {
+    interprets: Vec<Interpret>
+}
+
+#[derive(Debug, Clone, Default)]
+struct GroupInfo {
+    key_type: Option<String>,
+    syms: Option<Vec<Keysym>>,
+    actions: Option<Vec<ActionDef>>
+}
+
+#[derive(Debug, Clone, Default)]
+struct KeyInfo {
+    key_type: Option<String>,
+    groups: Vec<GroupInfo>
+}
+
+impl KeyInfo {
+    fn group(&mut self, group: usize) -> &mut GroupInfo {
+        if self.groups.len() <= group {
+            self.groups.resize(group + 1, GroupInfo::default());
+        }
+        &mut self.groups[group]
+    }
+
+    fn merge(&mut self, other: KeyInfo, merge: Merge) {
+        if merge == Merge::Replace {
+            *self = other;
+            return;
+        }
+        fn field<T>(to: &mut Option<T>, from: Option<T>, merge: Merge) {
+            if from.is_some() && (merge != Merge::Augment || to.is_none()) {
+                *to = from;
+            }
+        }
+        field(&mut self.key_type, other.key_type, merge);
+        for (i, group) in other.groups.into_iter().enumerate() {
+            let to = self.group(i);
+            field(&mut to.key_type, group.key_type, merge);
+            to.syms = match (to.syms.take(), group.syms) {
+                // levels left out with NoSymbol keep their keysyms
+                (Some(mut syms), Some(other)) => {
+                    syms.resize(syms.len().max(other.len()), Keysym::NO_SYMBOL);
+                    for (sym, other) in syms.iter_mut().zip(other) {
+                        if other != Keysym::NO_SYMBOL && (merge != Merge::Augment || *sym == Keysym::NO_SYMBOL) {
+                            *sym = other;
+                        }
+                    }
+                    Some(syms)
+                }
+                (syms, other) => other.or(syms)
+            };
+            field(&mut to.actions, group.actions, merge);
+        }
+    }
+}
+
+#[derive(Default)]
+struct SymbolsInfo {
+    keys: Vec<(String, KeyInfo)>,
+    modmap: Vec<(Modifiers, Expr)>,
+    group_names: BTreeMap<usize, String>
+}
+
+impl SymbolsInfo {
+    fn add_key(&mut self, name: String, key: KeyInfo, merge: Merge) {
+        match self.keys.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, existing)) => existing.merge(key, merge),
+            None => self.keys.push((name, key))
+        }
+    }
+}
+
+/**
+ * What a section or include adds up to, for each kind of section.
+ **/
+trait Info: Default {
+    const KIND: Kind;
+
+    fn statement(&mut self, merge: Merge, stmt: &Stmt);
+
+    fn merge(&mut self, other: Self, merge: Merge);
+
+    /**
+     * Moves the first group to `group`, for includes such as `de:2`.
+     **/
+    fn move_to_group(&mut self, _group: usize) {}
+}
+
+impl Info for KeycodesInfo {
+    const KIND: Kind = Kind::Keycodes;
+
+    fn statement(&mut self, merge: Merge, stmt: &Stmt) {
+        let mut other = KeycodesInfo::default();
+        match stmt {
+            Stmt::KeyCode(name, code) => {
+                other.codes.insert(name.clone(), *code);
+            }
+            Stmt::Alias(alias, name) => {
+                other.aliases.insert(alias.clone(), name.clone());
+            }
+            _ => return
+        }
+        self.merge(other, merge);
+    }
+
+    fn merge(&mut self, other: KeycodesInfo, merge: Merge) {
+        for (name, code) in other.codes {
+            if merge != Merge::Augment || !self.codes.contains_key(&name) {
+                self.codes.insert(name, code);
+            }
+        }
+        for (alias, name) in other.aliases {
+            if merge != Merge::Augment || !self.aliases.contains_key(&alias) {
+                self.aliases.insert(alias, name);
+            }
+        }
+    }
+}
+
+impl Info for TypesInfo {
+    const KIND: Kind = Kind::Types;
+
+    fn statement(&mut self, merge: Merge, stmt: &Stmt) {
+        let (name, body) = match stmt {
+            Stmt::Type(name, body) => (name, body),
+            _ => return
+        };
+        let mut key_type = KeyType::new(name, Modifiers::NONE, Vec::new());
+        for (_, stmt) in body {
+            match stmt {
+                Stmt::Var(Expr::Ident(var), Some(value)) if var.eq_ignore_ascii_case("modifiers") => {
+                    key_type.modifiers = known_modifiers(value);
+                }
+                Stmt::Var(Expr::Index(var, mods), Some(level)) if var.eq_ignore_ascii_case("map") => {
+                    if let (Some(mods), Some(level)) = (modifiers(mods), index(level, "level")) {
+                        key_type.map.push((mods, level));
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.merge(TypesInfo { types: vec![key_type] }, merge);
+    }
+
+    fn merge(&mut self, other: TypesInfo, merge: Merge) {
+        for key_type in other.types {
+            match self.types.iter().position(|t| t.name == key_type.name) {
+                Some(i) if merge != Merge::Augment => self.types[i] = key_type,
+                Some(_) => {}
+                None => self.types.push(key_type)
+            }
+        }
+    }
+}
+
+impl Info for CompatInfo {
+    const KIND: Kind = Kind::Compat;
+
+    fn statement(&mut self, merge: Merge, stmt: &Stmt) {
+        let (head, body) = match stmt {
+            Stmt::Interpret(head, body) => (head, body),
+            _ => return
+        };
+        let (sym, predicate) = match head {
+            Expr::Add(sym, predicate) => (&**sym, Some(&**predicate)),
+            sym => (sym, None)
+        };
+        let sym = match sym {
+            Expr::Ident(name) if name.eq_ignore_ascii_case("any") => None,
+            sym => Some(keysym(sym))
+        };
+        let (predicate, mods) = match predicate {
+            None => (Predicate::AnyOfOrNone, Modifiers::ALL),
+            Some(Expr::Ident(name)) if name.eq_ignore_ascii_case("any") => (Predicate::AnyOf, Modifiers::ALL),
+            Some(Expr::Call(name, args)) => {
+                let predicate = match name.to_ascii_lowercase().as_str() {
+                    "noneof" => Predicate::NoneOf,
+                    "anyofornone" => Predicate::AnyOfOrNone,
+                    "anyof" => Predicate::AnyOf,
+                    "allof" => Predicate::AllOf,
+                    _ => Predicate::Exactly
+                };
+                (predicate, args.first().map_or(Modifiers::NONE, known_modifiers))
+            }
+            Some(mods) => (Predicate::Exactly, known_modifiers(mods))
+        };
+        let mut interpret = Interpret { sym, predicate, mods, action: ActionDef::None, level_one_only: false };
+        for (_, stmt) in body {
+            match stmt {
+                Stmt::Var(Expr::Ident(var), Some(value)) if var.eq_ignore_ascii_case("action") => {
+                    interpret.action = ActionDef::parse(value);
+                }
+                Stmt::Var(Expr::Ident(var), Some(Expr::Ident(value)))
+                    if var.eq_ignore_ascii_case("useModMapMods") || var.eq_ignore_ascii_case("useModMap") =>
+                {
+                    interpret.level_one_only = value.eq_ignore_ascii_case("level1") || value.eq_ignore_ascii_case("levelone");
+                }
+                _ => {}
+            }
+        }
+        self.merge(CompatInfo { interprets: vec![interpret] }, merge);
+    }
+
+    fn merge(&mut self, other: CompatInfo, merge: Merge) {
+        for interpret in other.interprets {
+            let same = self
+                .interprets
+                .iter()
+                .position(|i| i.sym == interpret.sym && i.predicate == interpret.predicate && i.mods == interpret.mods);
+            match same {
+                Some(i) if merge != Merge::Augment => self.interprets[i] = interpret,
+                Some(_) => {}
+                None => self.interprets.push(interpret)
+            }
+        }
+    }
+}
+
+impl Info for SymbolsInfo {
+    const KIND: Kind = Kind::Symbols;
+
+    fn statement(&mut self, merge: Merge, stmt: &Stmt) {
+        match stmt {
+            Stmt::Key(name, items) => {
+                let mut key = KeyInfo::default();
+                let mut next = 0;
+                for item in items {
+                    match item {
+                        Expr::List(_) => {
+                            key.group(next).syms = Some(keysyms(item));
+                            next += 1;
+                        }
+                        Expr::Assign(lhs, value) => match &**lhs {
+                            Expr::Ident(var) if var.eq_ignore_ascii_case("type") => key.key_type = string(value),
+                            Expr::Index(var, group) => {
+                                let group = match index(group, "group") {
+                                    Some(group) => group,
+                                    None => continue
+                                };
+                                match var.to_ascii_lowercase().as_str() {
+                                    "type" => key.group(group).key_type = string(value),
+                                    "symbols" => key.group(group).syms = Some(keysyms(value)),
+                                    "actions" => {
+                                        let actions = match &**value {
+                                            Expr::List(actions) => actions.iter().map(ActionDef::parse).collect(),
+                                            action => vec![ActionDef::parse(action)]
+                                        };
+                                        key.group(group).actions = Some(actions);
+                                    }
+                                    _ => {}
+                                }
+                            }
+                            _ => {}
+                        },
+                        _ => {}
+                    }
+                }
+                self.add_key(name.clone(), key, merge);
+            }
+            Stmt::ModMap(name, items) => {
+                if let Some(i) = MODIFIER_NAMES.iter().position(|n| n.eq_ignore_ascii_case(name)) {
+                    self.modmap.extend(items.iter().map(|item| (Modifiers(1 << i), item.clone())));
+                }
+            }
+            Stmt::Var(Expr::Index(var, group), Some(Expr::Str(name))) if var.eq_ignore_ascii_case("name") => {
+                if let Some(group) = index(group, "group") {
+                    let mut other = SymbolsInfo::default();
+                    other.group_names.insert(group, name.clone());
+                    self.merge(other, merge);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn merge(&mut self, other: SymbolsInfo, merge: Merge) {
+        for (name, key) in other.keys {
+            self.add_key(name, key, merge);
+        }
+        self.modmap.extend(other.modmap);
+        for (group, name) in other.group_names {
+            if merge != Merge::Augment || !self.group_names.contains_key(&group) {
+                self.group_names.insert(group, name);
+            }
+        }
+    }
+
+    fn move_to_group(&mut self, group: usize) {
+        for (_, key) in self.keys.iter_mut() {
+            let first = key.groups.drain(..).next().unwrap_or_default();
+            let first = GroupInfo { key_type: first.key_type.or_else(|| key.key_type.take()), ..first };
+            key.key_type = None;
+            *key.group(group) = first;
+        }
+        let name = self.group_names.remove(&0);
+        self.group_names.clear();
+        if let Some(name) = name {
+            self.group_names.insert(group, name);
+        }
+    }
+}
+
+/**
+ * One file of an include statement such as `pc+de(nodeadkeys):2`.
+ **/
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Include {
+    merge: Merge,
+    file: String,
+    map: Option<String>,
+    group: Option<usize>
+}
+
+fn includes(spec: &str) -> io::Result<Vec<Include>> {
+    let mut includes = Vec::new();
+    let mut merge = Merge::Override;
+    let mut rest = spec;
+    while !rest.is_empty() {
+        let end = rest.char_indices().skip(1).find(|&(_, c)| c == '+' || c == '|').map_or(rest.len(), |(i, _)| i);
+        let mut item = &rest[..end];
+        rest = &rest[end..];
+        if let Some(stripped) = item.strip_prefix('+') {
+            merge = Merge::Override;
+            item = stripped;
+        } else if let Some(stripped) = item.strip_prefix('|') {
+            merge = Merge::Augment;
+            item = stripped;
+        }
+        let (item, group) = match item.rfind(':') {
+            Some(i) => match item[i + 1..].parse::<usize>() {
+                Ok(group) if group >= 1 => (&item[..i], Some(group - 1)),
+                _ => return Err(invalid(format!("bad group in include {}", spec)))
+            },
+            None => (item, None)
+        };
+        let (file, map) = match item.find('(') {
+            Some(i) if item.ends_with(')') => (&item[..i], Some(item[i + 1..item.len() - 1].to_string())),
+            Some(_) => return Err(invalid(format!("bad include {}", spec))),
+            None => (item, None)
+        };
+        // files are looked up in the keymap directory only
+        if file.is_empty() || file.starts_with('/') || file.contains("..") {
+            return Err(invalid(format!("bad include {}", spec)));
+        }
+        includes.push(Include { merge, file: file.to_string(), map, group });
+    }
+    Ok(includes)
+}
+
+/**
+ * Built-in definitions of the types keys get automatically, for keymaps
+ * whose types section lacks them.
+ **/
+fn builtin_type(name: &str) -> Option<KeyType> {
+    let (shift, lock, num, level3) = (Modifiers::SHIFT, Modifiers::CAPS_LOCK, Modifiers::NUM_LOCK, Modifiers::LEVEL3);
+    let key_type = match name {
+        "ONE_LEVEL" => KeyType::one_level(),
+        "TWO_LEVEL" => KeyType::two_level(),
+        "ALPHABETIC" => KeyType::alphabetic(),
+        "KEYPAD" => KeyType::keypad(),
+        "FOUR_LEVEL" => KeyType::new(name, shift | level3, vec![(shift, 1), (level3, 2), (shift | level3, 3)]),
+        "FOUR_LEVEL_ALPHABETIC" => KeyType::new(
+            name,
+            shift | lock | level3,
+            vec![(shift, 1), (lock, 1), (level3, 2), (shift | level3, 3), (lock | level3, 3)]
+        ),
+        "FOUR_LEVEL_SEMIALPHABETIC" => KeyType::new(
+            name,
+            shift | lock | level3,
+            vec![(shift, 1), (lock, 1), (level3, 2), (shift | level3, 3), (lock | level3, 2), (shift | lock | level3, 3)]
+        ),
+        "FOUR_LEVEL_KEYPAD" => KeyType::new(
+            name,
+            shift | num | level3,
+            vec![(shift, 1), (num, 1), (level3, 2), (shift | level3, 3), (num | level3, 3), (shift | num | level3, 2)]
+        ),
+        _ => return None
+    };
+    Some(key_type)
+}
+
+/**
+ * The type XKB picks for a group without one, from its keysyms.
+ **/
+fn automatic_type(syms: &[Keysym]) -> &'static str {
+    let lower = |i: usize| {
+        syms.get(i).and_then(|s| s.to_char()).is_some_and(|c| c.is_lowercase() && c.to_uppercase().next() != Some(c))
+    };
+    let upper = |i: usize| syms.get(i).and_then(|s| s.to_char()).is_some_and(|c| c.is_uppercase());
+    let keypad = |i: usize| syms.get(i).is_some_and(|s| s.is_keypad());
+    match syms.len() {
+        0 | 1 => "ONE_LEVEL",
+        2 if lower(0) && upper(1) => "ALPHABETIC",
+        2 if keypad(0) || keypad(1) => "KEYPAD",
+        2 => "TWO_LEVEL",
+        _ if lower(0) && upper(1) && lower(2) && upper(3) => "FOUR_LEVEL_ALPHABETIC",
+        _ if lower(0) && upper(1) => "FOUR_LEVEL_SEMIALPHABETIC",
+        _ if keypad(0) || keypad(1) => "FOUR_LEVEL_KEYPAD",
+        _ => "FOUR_LEVEL"
+    }
+}
+
+/**
+ * Finds the type named `name` in the keymap, adding a built-in one if the
+ * types section lacks it.
+ **/
+fn find_type(keymap: &mut Keymap, name: &str) -> Option<usize> {
+    match keymap.types.iter().position(|t| t.name == name) {
+        Some(i) => Some(i),
+        None => builtin_type(name).map(|t| keymap.add_type(t))
+    }
+}
+
+/**
+ * Puts the parts of a keymap together. Keys get actions from the compat
+ * rules unless they list their own.
+ **/
+fn build(keycodes: KeycodesInfo, types: TypesInfo, compat: CompatInfo, symbols: SymbolsInfo) -> Keymap {
+    let mut keymap = Keymap::new();
+    for key_type in types.types {
+        keymap.add_type(key_type);
+    }
+    // key names, after following aliases, and Linux keycodes
+    let resolve = |name: &str| -> Option<(String, u16)> {
+        let mut name = name;
+        for _ in 0..4 {
+            if let Some(&code) = keycodes.codes.get(name) {
+                return if (8..=0xffff + 8).contains(&code) { Some((name.to_string(), (code - 8) as u16)) } else { None };
+            }
+            name = keycodes.aliases.get(name)?.as_str();
+        }
+        None
+    };
+
+    let mut explicit: BTreeMap<u16, Vec<Option<Vec<ActionDef>>>> = BTreeMap::new();
+    for (name, info) in symbols.keys {
+        let (name, code) = match resolve(&name) {
+            Some(key) => key,
+            None => continue
+        };
+        let (key_type, mut groups) = (info.key_type, info.groups);
+        while groups.last().is_some_and(|g| g.syms.is_none() && g.actions.is_none()) {
+            groups.pop();
+        }
+        let mut key = Key::default();
+        let mut actions = Vec::new();
+        for group in groups {
+            let syms = group.syms.unwrap_or_default();
+            let automatic = automatic_type(&syms);
+            let key_type = group
+                .key_type
+                .or_else(|| key_type.clone())
+                .and_then(|name| find_type(&mut keymap, &name))
+                .or_else(|| find_type(&mut keymap, automatic))
+                .or_else(|| find_type(&mut keymap, "FOUR_LEVEL"))
+                .unwrap_or(0);
+            key.groups.push(KeyGroup { key_type, syms, actions: Vec::new() });
+            actions.push(group.actions);
+        }
+        keymap.set_name(code, &name);
+        keymap.set_key(code, key);
+        explicit.insert(code, actions);
+    }
+
+    for (mods, target) in symbols.modmap {
+        let code = match target {
+            Expr::KeyName(name) => resolve(&name).map(|(_, code)| code),
+            target => {
+                let sym = keysym(&target);
+                keymap
+                    .keys()
+                    .find(|(_, key)| key.groups.iter().any(|g| g.syms.contains(&sym)))
+                    .map(|(code, _)| code)
+            }
+        };
+        if let Some(code) = code {
+            let modmap = keymap.modmap(code) | mods;
+            keymap.set_modmap(code, modmap);
+        }
+    }
+
+    let mut interprets = compat.interprets;
+    interprets.sort_by_key(|i| (i.sym.is_none(), i.predicate));
+    for (code, actions) in explicit {
+        let modmap = keymap.modmap(code);
+        let mut key = keymap.key(code).cloned().unwrap_or_default();
+        for (group, actions) in key.groups.iter_mut().zip(actions) {
+            group.actions = match actions {
+                Some(actions) => actions.iter().map(|a| a.resolve(modmap)).collect(),
+                None => group
+                    .syms
+                    .iter()
+                    .enumerate()
+                    .map(|(level, &sym)| {
+                        if sym == Keysym::NO_SYMBOL {
+                            return None;
+                        }
+                        let interpret = interprets.iter().find(|i| {
+                            let mods = if level == 0 || !i.level_one_only { modmap } else { Modifiers::NONE };
+                            i.sym.is_none_or(|s| s == sym) && i.predicate.matches(mods, i.mods)
+                        })?;
+                        interpret.action.resolve(modmap)
+                    })
+                    .collect()
+            };
+            while group.actions.last() == Some(&None) {
+                group.actions.pop();
+            }
+        }
+        keymap.set_key(code, key);
+    }
+
+    let group_names = symbols.group_names;
+    if let Some(&last) = group_names.keys().last() {
+        let names = (0..=last).map(|g| group_names.get(&g).cloned().unwrap_or_default()).collect();
+        keymap.set_group_names(names);
+    }
+    keymap
+}
+
+/**
+ * Compiles XKB keymaps from the files of an xkb data directory, usually
+ * `/usr/share/X11/xkb`. Only what the keyboard state uses is kept: key
+ * types, keysyms, modifier and group actions, the modifier map and group
+ * names.
+ **/
+pub struct Xkb {
+    root: PathBuf
+}
+
+/// Includes nested deeper than this are taken to be a loop.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+impl Xkb {
+    pub fn new(root: PathBuf) -> Xkb {
+        Xkb { root }
+    }
+
+    /**
+     * The system's xkb data, or `$XKB_CONFIG_ROOT` if set.
+     **/
+    pub fn system() -> Xkb {
+        let root = env::var_os("XKB_CONFIG_ROOT").map_or_else(|| PathBuf::from("/usr/share/X11/xkb"), PathBuf::from);
+        Xkb::new(root)
+    }
+
+    /**
+     * Resolves names to components with the rules file `names.rules`.
+     **/
+    pub fn components(&self, names: &Names) -> io::Result<Components> {
+        if names.rules.is_empty() || names.rules.contains('/') {
+            return Err(invalid(format!("bad rules name {}", names.rules)));
+        }
+        let path = self.root.join("rules").join(&names.rules);
+        let text = fs::read_to_string(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        let components = apply_rules(&text, names)?;
+        if components.keycodes.is_empty() || components.symbols.is_empty() {
+            return Err(invalid(format!("no keymap for layout {} in {}", names.layout, path.display())));
+        }
+        Ok(components)
+    }
+
+    pub fn compile(&self, names: &Names) -> io::Result<Keymap> {
+        self.compile_components(&self.components(names)?)
+    }
+
+    pub fn compile_components(&self, components: &Components) -> io::Result<Keymap> {
+        let body = |spec: &str| vec![(Merge::Override, Stmt::Include(spec.to_string()))];
+        let types = if components.types.is_empty() { Vec::new() } else { body(&components.types) };
+        let compat = if components.compat.is_empty() { Vec::new() } else { body(&components.compat) };
+        Ok(build(
+            self.process(&body(&components.keycodes), 0)?,
+            self.process(&types, 0)?,
+            self.process(&compat, 0)?,
+            self.process(&body(&components.symbols), 0)?
+        ))
+    }
+
+    /**
+     * Compiles a complete `xkb_keymap`, as sent to clients. Its sections may
+     * include files from the data directory.
+     **/
+    pub fn compile_text(&self, text: &str) -> io::Result<Keymap> {
+        let sections = parse(text)?;
+        let body = |kind: Kind| -> Vec<(Merge, Stmt)> {
+            sections.iter().filter(|s| s.kind == kind).flat_map(|s| s.body.iter().cloned()).collect()
+        };
+        Ok(build(
+            self.process(&body(Kind::Keycodes), 0)?,
+            self.process(&body(Kind::Types), 0)?,
+            self.process(&body(Kind::Compat), 0)?,
+            self.process(&body(Kind::Symbols), 0)?
+        ))
+    }
+
+    /**
+     * The section `map` of a file, or the file's default section.
+     **/
+    fn load(&self, kind: Kind, file: &str, map: Option<&str>) -> io::Result<Section> {
+        let path = self.root.join(kind.dir()).join(file);
+        let text = fs::read_to_string(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        let sections: Vec<Section> = parse(&text)
+            .map_err(|e| invalid(format!("{}: {}", path.display(), e)))?
+            .into_iter()
+            .filter(|s| s.kind == kind)
+            .collect();
+        let found = match map {
+            Some(map) => sections.iter().position(|s| s.name == map),
+            None => sections.iter().position(|s| s.default).or(if sections.is_empty() { None } else { Some(0) })
+        };
+        match found {
+            Some(i) => Ok(sections.into_iter().nth(i).unwrap()),
+            None => Err(invalid(format!("{}: no map {}", path.display(), map.unwrap_or("")))),
+        }
+    }
+
+    fn process<I: Info>(&self, body: &[(Merge, Stmt)], depth: usize) -> io::Result<I> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(invalid("includes nested too deeply".to_string()));
+        }
+        let mut info = I::default();
+        for (merge, stmt) in body {
+            let spec = match stmt {
+                Stmt::Include(spec) => spec,
+                stmt => {
+                    info.statement(*merge, stmt);
+                    continue;
+                }
+            };
+            let mut included: Option<I> = None;
+            for include in includes(spec)? {
+                let section = self.load(I::KIND, &include.file, include.map.as_deref())?;
+                let mut other: I = self.process(&section.body, depth + 1)?;
+                if let Some(group) = include.group {
+                    other.move_to_group(group);
+                }
+                match included.as_mut() {
+                    Some(included) => included.merge(other, include.merge),
+                    None => included = Some(other)
+                }
+            }
+            if let Some(included) = included {
+                info.merge(included, *merge);
+            }
+        }
+        Ok(info)
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn modifier_names(modifiers: Modifiers) -> String {
+    if modifiers.is_empty() {
+        return "none".to_string();
+    }
+    let names: Vec<&str> = (0..8).filter(|&i| modifiers.0 & (1 << i) != 0).map(|i| MODIFIER_NAMES[i]).collect();
+    names.join("+")
+}
+
+fn action_text(action: Option<Action>) -> String {
+    let group = |change: GroupChange| match change {
+        GroupChange::Relative(n) => format!("{:+}", n),
+        GroupChange::Absolute(g) => (g + 1).to_string()
+    };
+    match action {
+        None => "NoAction()".to_string(),
+        Some(Action::SetMods(mods)) => format!("SetMods(modifiers={})", modifier_names(mods)),
+        Some(Action::LockMods(mods)) => format!("LockMods(modifiers={})", modifier_names(mods)),
+        Some(Action::SetGroup(change)) => format!("SetGroup(group={})", group(change)),
+        Some(Action::LockGroup(change)) => format!("LockGroup(group={})", group(change))
+    }
+}
+
+fn keysym_text(sym: Keysym) -> String {
+    if sym == Keysym::NO_SYMBOL {
+        "NoSymbol".to_string()
+    } else {
+        sym.name()
+    }
+}
+
+/**
+ * Writes a keymap out as a complete `xkb_keymap`, the text format clients
+ * compile with libxkbcommon. Actions are written out per key, so the
+ * compat section stays empty, and virtual modifiers are written as the
+ * real ones they are bound to.
+ **/
+pub fn serialize(keymap: &Keymap) -> String {
+    let key_name = |code: u16| keymap.name(code).map_or_else(|| format!("I{}", code as u32 + 8), |n| n.to_string());
+    let mut out = String::new();
+
+    out.push_str("xkb_keymap {\n");
+    let maximum = keymap.keys().map(|(code, _)| code as u32 + 8).max().unwrap_or(0).max(255);
+    let _ = writeln!(out, "    xkb_keycodes \"(unnamed)\" {{\n        minimum = 8;\n        maximum = {};", maximum);
+    for (code, _) in keymap.keys() {
+        let _ = writeln!(out, "        <{}> = {};", key_name(code), code as u32 + 8);
+    }
+    out.push_str("    };\n\n");
+
+    out.push_str("    xkb_types \"(unnamed)\" {\n");
+    for key_type in keymap.types.iter() {
+        let _ = writeln!(out, "        type {} {{", quote(&key_type.name));
+        let _ = writeln!(out, "            modifiers = {};", modifier_names(key_type.modifiers));
+        for &(mods, level) in key_type.map.iter() {
+            let _ = writeln!(out, "            map[{}] = Level{};", modifier_names(mods), level + 1);
+        }
+        out.push_str("        };\n");
+    }
+    out.push_str("    };\n\n");
+
+    out.push_str("    xkb_compatibility \"(unnamed)\" {\n    };\n\n");
+
+    out.push_str("    xkb_symbols \"(unnamed)\" {\n");
+    for (i, name) in keymap.group_names().iter().enumerate() {
+        let _ = writeln!(out, "        name[Group{}] = {};", i + 1, quote(name));
+    }
+    for (code, key) in keymap.keys() {
+        let mut items = Vec::new();
+        for (i, group) in key.groups.iter().enumerate() {
+            if let Some(key_type) = keymap.types.get(group.key_type) {
+                items.push(format!("type[Group{}] = {}", i + 1, quote(&key_type.name)));
+            }
+            let syms: Vec<String> = group.syms.iter().map(|&s| keysym_text(s)).collect();
+            items.push(format!("symbols[Group{}] = [ {} ]", i + 1, syms.join(", ")));
+            if group.actions.iter().any(|a| a.is_some()) {
+                let actions: Vec<String> = group.actions.iter().map(|&a| action_text(a)).collect();
+                items.push(format!("actions[Group{}] = [ {} ]", i + 1, actions.join(", ")));
+            }
+        }
+        let _ = writeln!(out, "        key <{}> {{\n            {}\n        }};", key_name(code), items.join(",\n            "));
+    }
+    for (i, name) in MODIFIER_NAMES.iter().enumerate() {
+        let keys: Vec<String> = keymap
+            .keys()
+            .filter(|&(code, _)| keymap.modmap(code).0 & (1 << i) != 0)
+            .map(|(code, _)| format!("<{}>", key_name(code)))
+            .collect();
+        if !keys.is_empty() {
+            let _ = writeln!(out, "        modifier_map {} {{ {} }};", name, keys.join(", "));
+        }
+    }
+    out.push_str("    };\n};\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = "
+// a small part of rules/evdev
+! $dvoraklayouts = de fr us
+
+! model = keycodes
+  *             = evdev
+
+! model = types
+  *             = complete
+
+! model layout = compat
+  *     *       = complete
+
+! model layout[1] = compat
+  *     *       = complete
+
+! model layout variant = symbols
+  *     dvorak  $dvoraklayouts = pc+%v(dvorak)
+
+! model layout = symbols
+  *     *       = pc+%l%(v)
+
+! model layout[1] = symbols
+  *     *       = pc+%l[1]%(v[1])
+
+! model layout[2] = symbols
+  *     *       = +%l[2]%(v[2]):2
+
+! option = symbols
+  grp:alt_shift_toggle = +group(alt_shift_toggle)
+";
+
+    const KEYCODES: &str = r#"
+default xkb_keycodes "evdev" {
+    minimum = 8;
+    maximum = 255;
+    <AE01> = 10; <AD01> = 24; <AD02> = 25; <AD06> = 29;
+    <AC01> = 38; <AB01> = 52;
+    <LFSH> = 50; <LALT> = 64; <CAPS> = 66; <RALT> = 108;
+    alias <LatY> = <AD06>;
+    indicator 1 = "Caps Lock";
+};
+"#;
+
+    const TYPES: &str = r#"
+default xkb_types "complete" {
+    virtual_modifiers LevelThree, Alt;
+    type "ONE_LEVEL" {
+        modifiers = none;
+        map[None] = Level1;
+    };
+    type "TWO_LEVEL" {
+        modifiers = Shift;
+        map[Shift] = Level2;
+    };
+    type "ALPHABETIC" {
+        modifiers = Shift+Lock;
+        map[Shift] = Level2;
+        map[Lock] = Level2;
+    };
+    type "FOUR_LEVEL_SEMIALPHABETIC" {
+        modifiers = Shift+Lock+LevelThree;
+        map[None] = Level1;
+        map[Shift] = Level2;
+        map[Lock] = Level2;
+        map[LevelThree] = Level3;
+        map[Shift+LevelThree] = Level4;
+        map[Lock+LevelThree] = Level3;
+        preserve[Lock+LevelThree] = Lock;
+    };
+    type "PC_ALT_LEVEL2" {
+        modifiers = Alt;
+        map[Alt] = Level2;
+    };
+};
+"#;
+
+    const COMPAT: &str = r#"
+default xkb_compatibility "complete" {
+    interpret.useModMapMods = AnyLevel;
+    interpret Shift_L { action = SetMods(modifiers=Shift); };
+    interpret ISO_Level3_Shift+Any {
+        useModMapMods = level1;
+        action = SetMods(modifiers=LevelThree);
+    };
+    interpret Caps_Lock+AnyOfOrNone(all) { action = LockMods(modifiers=Lock); };
+    interpret ISO_Next_Group {
+        useModMapMods = level1;
+        action = LockGroup(group=+1);
+    };
+    interpret Any+AnyOf(all) { action = SetMods(modifiers=modMapMods); };
+    indicator "Caps Lock" { !allowExplicit; modifiers = Lock; };
+};
+"#;
+
+    const SYMBOLS_PC: &str = r#"
+default partial xkb_symbols "pc105" {
+    key <LFSH> { [ Shift_L ] };
+    key <LALT> { [ Alt_L, Meta_L ] };
+    key <CAPS> { [ Caps_Lock ] };
+    modifier_map Shift { Shift_L };
+    modifier_map Lock { Caps_Lock };
+    modifier_map Mod1 { <LALT> };
+};
+"#;
+
+    const SYMBOLS_US: &str = r#"
+default xkb_symbols "basic" {
+    name[Group1] = "English (US)";
+    key <AE01> { [ 1, exclam ] };
+    key <AD01> { [ q, Q ] };
+    key <AD06> { [ y, Y ] };
+    key <AB01> { [ z, Z ] };
+};
+
+xkb_symbols "dvorak" {
+    include "us(basic)"
+    name[Group1] = "English (Dvorak)";
+    key <AD01> { [ apostrophe, quotedbl ] };
+    key <AB01> { [ semicolon, colon ] };
+};
+"#;
+
+    const SYMBOLS_DE: &str = r#"
+default xkb_symbols "basic" {
+    include "us(basic)"
+    name[Group1] = "German";
+    key <AD01> { [ q, Q, at ] };
+    key <LatY> { [ z, Z ] };
+    key <AB01> { [ y, Y, guillemotright ] };
+    include "level3(ralt_switch)"
+};
+"#;
+
+    const SYMBOLS_LEVEL3: &str = r#"
+partial modifier_keys
+xkb_symbols "ralt_switch" {
+    key <RALT> { type[Group1] = "ONE_LEVEL", symbols[Group1] = [ ISO_Level3_Shift ] };
+    modifier_map Mod5 { ISO_Level3_Shift };
+};
+"#;
+
+    const SYMBOLS_GROUP: &str = r#"
+partial modifier_keys
+xkb_symbols "alt_shift_toggle" {
+    key <LALT> { symbols[Group1] = [ NoSymbol, ISO_Next_Group ] };
+    key <LFSH> { type[Group1] = "PC_ALT_LEVEL2", symbols[Group1] = [ Shift_L, ISO_Next_Group ] };
+};
+"#;
+
+    fn fixture(name: &str) -> Xkb {
+        let root = env::temp_dir().join(format!("drmtest-xkb-{}-{}", name, std::process::id()));
+        let files = [
+            ("rules/evdev", RULES),
+            ("keycodes/evdev", KEYCODES),
+            ("types/complete", TYPES),
+            ("compat/complete", COMPAT),
+            ("symbols/pc", SYMBOLS_PC),
+            ("symbols/us", SYMBOLS_US),
+            ("symbols/de", SYMBOLS_DE),
+            ("symbols/level3", SYMBOLS_LEVEL3),
+            ("symbols/group", SYMBOLS_GROUP)
+        ];
+        for &(path, text) in files.iter() {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+        Xkb::new(root)
+    }
+
+    fn sym(name: &str) -> Keysym {
+        Keysym::from_name(name).unwrap()
+    }
+
+    #[test]
+    fn applies_rules() {
+        let components = apply_rules(RULES, &Names::new("de,us", "nodeadkeys,dvorak", "grp:alt_shift_toggle")).unwrap();
+        assert_eq!(components.keycodes, "evdev");
+        assert_eq!(components.types, "complete");
+        assert_eq!(components.compat, "complete");
+        assert_eq!(components.symbols, "pc+de(nodeadkeys)+us(dvorak):2+group(alt_shift_toggle)");
+
+        let components = apply_rules(RULES, &Names::new("us", "dvorak", "")).unwrap();
+        assert_eq!(components.compat, "complete");
+        assert_eq!(components.symbols, "pc+us(dvorak)");
+        let components = apply_rules(RULES, &Names::new("fr", "", "")).unwrap();
+        assert_eq!(components.symbols, "pc+fr");
+
+        assert_eq!(
+            includes("pc+de(nodeadkeys):2|inet").unwrap(),
+            vec![
+                Include { merge: Merge::Override, file: "pc".to_string(), map: None, group: None },
+                Include { merge: Merge::Override, file: "de".to_string(), map: Some("nodeadkeys".to_string()), group: Some(1) },
+                Include { merge: Merge::Augment, file: "inet".to_string(), map: None, group: None }
+            ]
+        );
+        assert_eq!(
+            includes("é+ß").unwrap(),
+            vec![
+                Include { merge: Merge::Override, file: "é".to_string(), map: None, group: None },
+                Include { merge: Merge::Override, file: "ß".to_string(), map: None, group: None }
+            ]
+        );
+        assert!(includes("pc+/etc/passwd").is_err());
+        assert!(includes("../us").is_err());
+        assert!(includes("pc+").is_err());
+    }
+
+    #[test]
+    fn compiles_layouts() {
+        let xkb = fixture("layouts");
+        let keymap = xkb.compile(&Names::new("de", "", "")).unwrap();
+        assert_eq!(keymap.group_names(), ["German"]);
+        // <AD06> is reached through the alias <LatY>
+        assert_eq!(keymap.keysym(21, 0, Modifiers::NONE), sym("z"));
+        assert_eq!(keymap.keysym(44, 0, Modifiers::SHIFT), sym("Y"));
+        assert_eq!(keymap.keysym(16, 0, Modifiers::LEVEL3), sym("at"));
+        assert_eq!(keymap.keysym(16, 0, Modifiers::CAPS_LOCK), sym("Q"));
+        assert_eq!(keymap.modmap(100), Modifiers::LEVEL3);
+        assert_eq!(keymap.action(100, 0, Modifiers::NONE), Some(Action::SetMods(Modifiers::LEVEL3)));
+        assert_eq!(keymap.action(58, 0, Modifiers::NONE), Some(Action::LockMods(Modifiers::CAPS_LOCK)));
+        assert_eq!(keymap.action(56, 0, Modifiers::NONE), Some(Action::SetMods(Modifiers::ALT)));
+
+        let keymap = xkb.compile(&Names::new("us", "dvorak", "")).unwrap();
+        assert_eq!(keymap.group_names(), ["English (Dvorak)"]);
+        assert_eq!(keymap.keysym(16, 0, Modifiers::SHIFT), sym("quotedbl"));
+        assert_eq!(keymap.keysym(2, 0, Modifiers::SHIFT), sym("exclam"));
+    }
+
+    #[test]
+    fn switches_groups() {
+        let xkb = fixture("groups");
+        let keymap = xkb.compile(&Names::new("de,us", ",dvorak", "grp:alt_shift_toggle")).unwrap();
+        assert_eq!(keymap.groups(), 2);
+        assert_eq!(keymap.group_names(), ["German", "English (Dvorak)"]);
+
+        let mut keyboard = super::super::keyboard::Keyboard::new(keymap);
+        assert_eq!(keyboard.key(44, true).keysym, sym("y"));
+        keyboard.key(44, false);
+        keyboard.key(56, true);
+        keyboard.key(42, true);
+        keyboard.key(42, false);
+        keyboard.key(56, false);
+        assert_eq!(keyboard.group(), 1);
+        assert_eq!(keyboard.key(44, true).keysym, sym("semicolon"));
+        keyboard.key(44, false);
+        // keys only in the first layout keep working in the second
+        assert_eq!(keyboard.key(2, true).keysym, sym("1"));
+    }
+
+    #[test]
+    fn serializes_keymaps() {
+        let xkb = fixture("serialize");
+        let keymap = xkb.compile(&Names::new("de,us", ",dvorak", "grp:alt_shift_toggle")).unwrap();
+        let text = serialize(&keymap);
+        assert!(text.contains("name[Group2] = \"English (Dvorak)\";"));
+        assert!(text.contains("modifier_map Mod5 { <RALT> };"));
+        assert_eq!(xkb.compile_text(&text).unwrap(), keymap);
+        let us = serialize(&Keymap::us());
+        assert_eq!(serialize(&Xkb::new(PathBuf::new()).compile_text(&us).unwrap()), us);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let xkb = fixture("errors");
+        assert!(xkb.compile(&Names::new("xx", "", "")).is_err());
+        assert!(xkb.compile_text("xkb_keymap { xkb_symbols { key <AE01> { [ 1 ] } };").is_err());
+        assert!(xkb.compile_text("xkb_keymap { xkb_symbols { include \"nothere\" }; };").is_err());
+    }
+}
@@ -0,0 +1,341 @@
+use std::io;
+
+use super::drm::{Color, Rect};
+use super::drm_const::{DRM_FORMAT_NV12, DRM_FORMAT_YUV420, DRM_FORMAT_YUYV};
+use super::format::Format;
+use super::image::Image;
+
+/**
+ * Layouts of YUV frames as produced by video decoders. The 4:2:0 formats
+ * have one chroma sample for every 2 x 2 pixels, YUYV one for every pair
+ * of pixels in a row.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YuvFormat {
+    /// A luma plane followed by a plane of interleaved U and V samples.
+    Nv12,
+    /// Separate luma, U and V planes.
+    Yuv420,
+    /// A single plane of Y0 U Y1 V pixel pairs.
+    Yuyv
+}
+
+impl YuvFormat {
+    pub fn from_fourcc(fourcc: u32) -> Option<YuvFormat> {
+        match fourcc {
+            DRM_FORMAT_NV12 => Some(YuvFormat::Nv12),
+            DRM_FORMAT_YUV420 => Some(YuvFormat::Yuv420),
+            DRM_FORMAT_YUYV => Some(YuvFormat::Yuyv),
+            _ => None
+        }
+    }
+
+    pub fn fourcc(&self) -> u32 {
+        match *self {
+            YuvFormat::Nv12 => DRM_FORMAT_NV12,
+            YuvFormat::Yuv420 => DRM_FORMAT_YUV420,
+            YuvFormat::Yuyv => DRM_FORMAT_YUYV
+        }
+    }
+
+    pub fn planes(&self) -> usize {
+        match *self {
+            YuvFormat::Nv12 => 2,
+            YuvFormat::Yuv420 => 3,
+            YuvFormat::Yuyv => 1
+        }
+    }
+
+    /**
+     * Bytes per row and number of rows of plane `i` of a frame.
+     **/
+    pub fn plane_size(&self, i: usize, width: u32, height: u32) -> (u32, u32) {
+        let (half_width, half_height) = (width.div_ceil(2), height.div_ceil(2));
+        match (*self, i) {
+            (YuvFormat::Yuyv, _) => (2 * 2 * half_width, height),
+            (_, 0) => (width, height),
+            (YuvFormat::Nv12, _) => (2 * half_width, half_height),
+            (YuvFormat::Yuv420, _) => (half_width, half_height)
+        }
+    }
+}
+
+/**
+ * The weights of red, green and blue in the luma of a YUV color.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Matrix {
+    /// Standard definition video.
+    Bt601,
+    /// High definition video.
+    Bt709
+}
+
+impl Matrix {
+    /**
+     * The red and blue luma weights, Kr and Kb.
+     **/
+    fn weights(&self) -> (f32, f32) {
+        match *self {
+            Matrix::Bt601 => (0.299, 0.114),
+            Matrix::Bt709 => (0.2126, 0.0722)
+        }
+    }
+}
+
+/**
+ * Which values of the samples stand for black and white.
+ **/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Range {
+    /// Luma from 16 to 235 and chroma from 16 to 240, as in most video.
+    Limited,
+    /// All values from 0 to 255, as in JPEG.
+    Full
+}
+
+/**
+ * Fixed point factors for turning YUV samples into RGB, with 16 fraction
+ * bits.
+ **/
+#[derive(Debug, Clone, Copy)]
+struct Coefficients {
+    y_offset: i32,
+    y: i32,
+    r_v: i32,
+    g_u: i32,
+    g_v: i32,
+    b_u: i32
+}
+
+impl Coefficients {
+    fn new(matrix: Matrix, range: Range) -> Coefficients {
+        let (kr, kb) = matrix.weights();
+        let kg = 1.0 - kr - kb;
+        let (y_offset, y_scale, c_scale) = match range {
+            Range::Limited => (16, 255.0 / 219.0, 255.0 / 224.0),
+            Range::Full => (0, 1.0, 1.0)
+        };
+        let fixed = |v: f32| (v * 65536.0).round() as i32;
+        Coefficients {
+            y_offset,
+            y: fixed(y_scale),
+            r_v: fixed(2.0 * (1.0 - kr) * c_scale),
+            g_u: fixed(2.0 * kb * (1.0 - kb) / kg * c_scale),
+            g_v: fixed(2.0 * kr * (1.0 - kr) / kg * c_scale),
+            b_u: fixed(2.0 * (1.0 - kb) * c_scale)
+        }
+    }
+
+    fn rgb(&self, y: u8, u: u8, v: u8) -> Color {
+        let y = (y as i32 - self.y_offset) * self.y + (1 << 15);
+        let (u, v) = (u as i32 - 128, v as i32 - 128);
+        let channel = |c: i32| (c >> 16).clamp(0, 255) as u8;
+        Color::new(
+            channel(y + self.r_v * v),
+            channel(y - self.g_u * u - self.g_v * v),
+            channel(y + self.b_u * u),
+            255
+        )
+    }
+}
+
+/**
+ * A video frame in one of the YUV formats. Each plane is stored on its
+ * own, with rows `stride` bytes apart.
+ **/
+#[derive(Debug, Clone, PartialEq)]
+pub struct YuvImage {
+    width: u32,
+    height: u32,
+    format: YuvFormat,
+    matrix: Matrix,
+    range: Range,
+    planes: Vec<(u32, Vec<u8>)>
+}
+
+impl YuvImage {
+    /**
+     * A black BT.709 limited range frame.
+     **/
+    pub fn new(width: u32, height: u32, format: YuvFormat) -> YuvImage {
+        let planes = (0..format.planes()).map(|i| {
+            let (row_len, rows) = format.plane_size(i, width, height);
+            let len = (row_len * rows) as usize;
+            let data = match (format, i) {
+                (YuvFormat::Yuyv, _) => [16, 128].iter().cycle().cloned().take(len).collect(),
+                (_, 0) => vec![16; len],
+                _ => vec![128; len]
+            };
+            (row_len, data)
+        }).collect();
+        YuvImage { width, height, format, matrix: Matrix::Bt709, range: Range::Limited, planes }
+    }
+
+    /**
+     * Wraps existing planes, given as `(stride, data)`. Returns `None` if
+     * the number of planes is wrong or one of them is too small.
+     **/
+    pub fn from_planes(width: u32, height: u32, format: YuvFormat, planes: Vec<(u32, Vec<u8>)>) -> Option<YuvImage> {
+        if planes.len() != format.planes() {
+            return None;
+        }
+        for (i, &(stride, ref data)) in planes.iter().enumerate() {
+            let (row_len, rows) = format.plane_size(i, width, height);
+            if stride < row_len || (stride as usize) * rows.saturating_sub(1) as usize + row_len as usize > data.len() {
+                return None;
+            }
+        }
+        Some(YuvImage { width, height, format, matrix: Matrix::Bt709, range: Range::Limited, planes })
+    }
+
+    /**
+     * Sets how the samples translate to colors, as signalled by the video
+     * stream.
+     **/
+    pub fn set_color_space(&mut self, matrix: Matrix, range: Range) {
+        self.matrix = matrix;
+        self.range = range;
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> YuvFormat {
+        self.format
+    }
+
+    pub fn matrix(&self) -> Matrix {
+        self.matrix
+    }
+
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    pub fn stride(&self, plane: usize) -> u32 {
+        self.planes[plane].0
+    }
+
+    pub fn plane(&self, plane: usize) -> &[u8] {
+        &self.planes[plane].1
+    }
+
+    pub fn plane_mut(&mut self, plane: usize) -> &mut [u8] {
+        &mut self.planes[plane].1
+    }
+
+    /**
+     * The Y, U and V samples of pixel `(x, y)`.
+     **/
+    pub fn sample(&self, x: u32, y: u32) -> (u8, u8, u8) {
+        let at = |plane: usize, x: u32, y: u32| {
+            let (stride, ref data) = self.planes[plane];
+            data[(y * stride + x) as usize]
+        };
+        match self.format {
+            YuvFormat::Nv12 => (at(0, x, y), at(1, x / 2 * 2, y / 2), at(1, x / 2 * 2 + 1, y / 2)),
+            YuvFormat::Yuv420 => (at(0, x, y), at(1, x / 2, y / 2), at(2, x / 2, y / 2)),
+            YuvFormat::Yuyv => {
+                let pair = x / 2 * 4;
+                (at(0, pair + x % 2 * 2, y), at(0, pair + 1, y), at(0, pair + 3, y))
+            }
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Color {
+        let (y, u, v) = self.sample(x, y);
+        Coefficients::new(self.matrix, self.range).rgb(y, u, v)
+    }
+
+    /**
+     * Converts the `rect` part of the frame to RGB.
+     **/
+    pub fn to_image(&self, rect: Rect) -> io::Result<Image> {
+        let rect = rect.intersect(&self.bounds());
+        let coefficients = Coefficients::new(self.matrix, self.range);
+        let format = Format::Xrgb8888;
+        let mut image = Image::new(rect.width, rect.height, format)?;
+        for y in 0..rect.height {
+            let row = image.row_mut(y);
+            for (x, pixel) in row.chunks_mut(4).enumerate() {
+                let (sy, su, sv) = self.sample(rect.x as u32 + x as u32, rect.y as u32 + y);
+                format.write(pixel, &coefficients.rgb(sy, su, sv));
+            }
+        }
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Color, b: Color) -> bool {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).abs() <= 1;
+        d(a.r, b.r) && d(a.g, b.g) && d(a.b, b.b)
+    }
+
+    #[test]
+    fn converts_colors() {
+        let bt601 = Coefficients::new(Matrix::Bt601, Range::Limited);
+        assert_eq!(bt601.rgb(16, 128, 128), Color::new(0, 0, 0, 255));
+        assert_eq!(bt601.rgb(235, 128, 128), Color::new(255, 255, 255, 255));
+        // values below black and above white are clamped
+        assert_eq!(bt601.rgb(0, 128, 128), Color::new(0, 0, 0, 255));
+        assert!(close(bt601.rgb(81, 90, 240), Color::new(255, 0, 0, 255)));
+        assert!(close(bt601.rgb(41, 240, 110), Color::new(0, 0, 255, 255)));
+
+        let bt709 = Coefficients::new(Matrix::Bt709, Range::Limited);
+        assert!(close(bt709.rgb(63, 102, 240), Color::new(255, 0, 0, 255)));
+        assert!(close(bt709.rgb(173, 42, 26), Color::new(0, 255, 0, 255)));
+
+        let full = Coefficients::new(Matrix::Bt601, Range::Full);
+        assert_eq!(full.rgb(0, 128, 128), Color::new(0, 0, 0, 255));
+        assert_eq!(full.rgb(255, 128, 128), Color::new(255, 255, 255, 255));
+        assert!(close(full.rgb(76, 85, 255), Color::new(255, 0, 0, 255)));
+    }
+
+    #[test]
+    fn reads_all_layouts() {
+        // a 4 x 2 frame: gray, except for a red left half in full range
+        let gray = (128, 128, 128);
+        let red = (76, 85, 255);
+        let nv12 = YuvImage::from_planes(4, 2, YuvFormat::Nv12, vec![
+            (4, vec![76, 76, 128, 128, 76, 76, 128, 128]),
+            (4, vec![85, 255, 128, 128])
+        ]).unwrap();
+        let yuv420 = YuvImage::from_planes(4, 2, YuvFormat::Yuv420, vec![
+            (4, vec![76, 76, 128, 128, 76, 76, 128, 128]),
+            (2, vec![85, 128]),
+            (2, vec![255, 128])
+        ]).unwrap();
+        let yuyv = YuvImage::from_planes(4, 2, YuvFormat::Yuyv, vec![
+            (8, vec![76, 85, 76, 255, 128, 128, 128, 128, 76, 85, 76, 255, 128, 128, 128, 128])
+        ]).unwrap();
+
+        for mut frame in [nv12, yuv420, yuyv] {
+            assert_eq!(frame.sample(1, 1), red);
+            assert_eq!(frame.sample(2, 0), gray);
+            frame.set_color_space(Matrix::Bt601, Range::Full);
+            let image = frame.to_image(Rect::new(1, 0, 3, 2)).unwrap();
+            assert_eq!((image.width(), image.height()), (3, 2));
+            assert!(close(image.get(0, 1), Color::new(255, 0, 0, 255)));
+            assert_eq!(image.get(2, 0), Color::new(128, 128, 128, 255));
+        }
+
+        assert!(YuvImage::from_planes(4, 2, YuvFormat::Nv12, vec![(4, vec![0; 8])]).is_none());
+        assert!(YuvImage::from_planes(4, 2, YuvFormat::Yuyv, vec![(4, vec![0; 16])]).is_none());
+        assert_eq!(YuvImage::new(3, 3, YuvFormat::Yuv420).get(2, 2), Color::new(0, 0, 0, 255));
+        assert_eq!(YuvImage::new(3, 1, YuvFormat::Yuyv).plane(0), &[16, 128, 16, 128, 16, 128, 16, 128]);
+    }
+}